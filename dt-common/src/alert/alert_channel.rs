@@ -0,0 +1,125 @@
+use anyhow::bail;
+use lettre::{message::Mailbox, AsyncSmtpTransport, AsyncTransport, Message, Tokio1Executor};
+use reqwest::{header, Client};
+use serde_json::json;
+
+use super::alert_manager::Alert;
+use crate::{config::alert_config::AlertConfig, error::Error};
+
+pub enum AlertChannel {
+    // posts the alert in json
+    Webhook {
+        client: Client,
+        url: String,
+    },
+    // posts {"text": "..."}, supported by slack incoming webhooks and most chat tools
+    Slack {
+        client: Client,
+        url: String,
+    },
+    Email {
+        transport: Box<AsyncSmtpTransport<Tokio1Executor>>,
+        from: Mailbox,
+        to: Vec<Mailbox>,
+    },
+}
+
+impl AlertChannel {
+    pub fn from_config(config: &AlertConfig) -> anyhow::Result<Vec<Self>> {
+        let mut channels = Vec::new();
+        if !config.webhook_url.is_empty() {
+            channels.push(Self::Webhook {
+                client: Client::new(),
+                url: config.webhook_url.clone(),
+            });
+        }
+
+        if !config.slack_webhook_url.is_empty() {
+            channels.push(Self::Slack {
+                client: Client::new(),
+                url: config.slack_webhook_url.clone(),
+            });
+        }
+
+        if !config.smtp_url.is_empty() {
+            let parse_mailbox = |address: &str| -> anyhow::Result<Mailbox> {
+                address.parse().map_err(|err| {
+                    Error::ConfigError(format!("invalid alert email: {}, error: {}", address, err))
+                        .into()
+                })
+            };
+            let transport = AsyncSmtpTransport::<Tokio1Executor>::from_url(&config.smtp_url)
+                .map_err(|err| {
+                    Error::ConfigError(format!("invalid alert smtp_url, error: {}", err))
+                })?
+                .build();
+            if config.email_to.is_empty() {
+                bail! {Error::ConfigError("alert email_to is empty".into())}
+            }
+            channels.push(Self::Email {
+                transport: Box::new(transport),
+                from: parse_mailbox(&config.email_from)?,
+                to: config
+                    .email_to
+                    .iter()
+                    .map(|address| parse_mailbox(address))
+                    .collect::<anyhow::Result<_>>()?,
+            });
+        }
+        Ok(channels)
+    }
+
+    pub fn get_name(&self) -> &'static str {
+        match self {
+            Self::Webhook { .. } => "webhook",
+            Self::Slack { .. } => "slack",
+            Self::Email { .. } => "email",
+        }
+    }
+
+    pub async fn send(&self, alert: &Alert) -> anyhow::Result<()> {
+        match self {
+            Self::Webhook { client, url } => {
+                Self::post_json(client, url, serde_json::to_string(alert)?).await
+            }
+
+            Self::Slack { client, url } => {
+                let body = json!({ "text": alert.to_text() }).to_string();
+                Self::post_json(client, url, body).await
+            }
+
+            Self::Email {
+                transport,
+                from,
+                to,
+            } => {
+                let mut builder = Message::builder()
+                    .from(from.clone())
+                    .subject(format!("[ape-dts] {} alert: {}", alert.task_id, alert.rule));
+                for to in to.iter() {
+                    builder = builder.to(to.clone());
+                }
+                transport.send(builder.body(alert.to_text())?).await?;
+                Ok(())
+            }
+        }
+    }
+
+    async fn post_json(client: &Client, url: &str, body: String) -> anyhow::Result<()> {
+        let response = client
+            .post(url)
+            .header(header::CONTENT_TYPE, "application/json")
+            .body(body)
+            .send()
+            .await?;
+        let status_code = response.status();
+        if !status_code.is_success() {
+            bail! {Error::HttpError(format!(
+                "alert request failed, status_code: {}, response_text: {:?}",
+                status_code,
+                response.text().await?
+            ))}
+        }
+        Ok(())
+    }
+}
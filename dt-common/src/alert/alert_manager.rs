@@ -0,0 +1,277 @@
+use std::collections::BTreeMap;
+#[cfg(feature = "alert")]
+use std::sync::Arc;
+
+use chrono::{SecondsFormat, Utc};
+use dashmap::DashMap;
+use serde::Serialize;
+
+use crate::{
+    config::alert_config::{AlertConfig, AlertRule},
+    monitor::task_metrics::TaskMetricsType,
+};
+
+#[cfg(feature = "alert")]
+use super::alert_channel::AlertChannel;
+#[cfg(not(feature = "alert"))]
+use crate::error::Error;
+#[cfg(feature = "alert")]
+use crate::{log_info, log_warn};
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Alert {
+    pub ts: String,
+    pub task_id: String,
+    pub rule: String,
+    pub message: String,
+}
+
+impl Alert {
+    pub fn new(task_id: &str, rule: &AlertRule, message: String) -> Self {
+        Self {
+            ts: Utc::now().to_rfc3339_opts(SecondsFormat::Millis, true),
+            task_id: task_id.into(),
+            rule: rule.to_string(),
+            message,
+        }
+    }
+
+    pub fn to_text(&self) -> String {
+        format!(
+            "[ape-dts] task: {}, rule: {}, {}, time: {}",
+            self.task_id, self.rule, self.message, self.ts
+        )
+    }
+}
+
+/// Checks alert rules and sends alerts to all configured channels,
+/// sending failures are only logged so they never break the task.
+/// Configured alerts fail the task to start if the alert feature is disabled
+pub struct AlertManager {
+    task_id: String,
+    rules: Vec<AlertRule>,
+    #[cfg(feature = "alert")]
+    channels: Arc<Vec<AlertChannel>>,
+    silence_secs: u64,
+    // rule -> the time in secs when it was last sent
+    last_sent: DashMap<String, i64>,
+}
+
+impl AlertManager {
+    pub fn new(config: &AlertConfig, task_id: &str) -> anyhow::Result<Self> {
+        #[cfg(not(feature = "alert"))]
+        if !config.rules.is_empty()
+            || !config.webhook_url.is_empty()
+            || !config.slack_webhook_url.is_empty()
+            || !config.smtp_url.is_empty()
+        {
+            anyhow::bail! {Error::ConfigError(
+                "[alert] requires ape-dts built with feature: alert".into()
+            )}
+        }
+
+        Ok(Self {
+            task_id: task_id.into(),
+            rules: config.rules.clone(),
+            #[cfg(feature = "alert")]
+            channels: Arc::new(AlertChannel::from_config(config)?),
+            silence_secs: config.silence_secs,
+            last_sent: DashMap::new(),
+        })
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        #[cfg(feature = "alert")]
+        let has_channels = !self.channels.is_empty();
+        #[cfg(not(feature = "alert"))]
+        let has_channels = false;
+        !self.rules.is_empty() && has_channels
+    }
+
+    /// Called by each flush of task metrics, alerts are sent in background
+    pub fn check_metrics(&self, metrics: &BTreeMap<TaskMetricsType, u64>) {
+        if !self.is_enabled() {
+            return;
+        }
+
+        for rule in self.rules.iter() {
+            let AlertRule::Metrics {
+                metrics_type,
+                op,
+                threshold,
+            } = rule
+            else {
+                continue;
+            };
+            let Some(value) = metrics.get(metrics_type) else {
+                continue;
+            };
+            if !op.matches(*value, *threshold) || self.is_silenced(rule) {
+                continue;
+            }
+
+            let alert = Alert::new(&self.task_id, rule, format!("current value: {}", value));
+            #[cfg(feature = "alert")]
+            {
+                let channels = self.channels.clone();
+                tokio::spawn(async move { Self::send(&channels, &alert).await });
+            }
+            #[cfg(not(feature = "alert"))]
+            let _ = alert;
+        }
+    }
+
+    /// Called once the task exits, waits until alerts are sent
+    pub async fn notify_task_exit(&self, result: &anyhow::Result<()>) {
+        if !self.is_enabled() {
+            return;
+        }
+
+        let (rule, message) = match result {
+            Ok(_) => (AlertRule::TaskFinished, "task finished".to_string()),
+            Err(err) => (
+                AlertRule::TaskFailed,
+                format!("task failed, error: {}", err),
+            ),
+        };
+        if self.rules.contains(&rule) {
+            let alert = Alert::new(&self.task_id, &rule, message);
+            #[cfg(feature = "alert")]
+            Self::send(&self.channels, &alert).await;
+            #[cfg(not(feature = "alert"))]
+            let _ = alert;
+        }
+    }
+
+    fn is_silenced(&self, rule: &AlertRule) -> bool {
+        let now = Utc::now().timestamp();
+        let mut last_sent = self.last_sent.entry(rule.to_string()).or_insert(i64::MIN);
+        if now.saturating_sub(*last_sent) < self.silence_secs as i64 {
+            return true;
+        }
+        *last_sent = now;
+        false
+    }
+
+    #[cfg(feature = "alert")]
+    async fn send(channels: &[AlertChannel], alert: &Alert) {
+        for channel in channels.iter() {
+            match channel.send(alert).await {
+                Ok(_) => log_info!("alert sent by {}: {}", channel.get_name(), alert.to_text()),
+                Err(err) => log_warn!(
+                    "failed to send alert by {}, error: {}",
+                    channel.get_name(),
+                    err
+                ),
+            }
+        }
+    }
+}
+
+#[cfg(all(test, feature = "alert"))]
+mod tests {
+    use std::{str::FromStr, time::Duration};
+
+    use super::*;
+    use crate::alert::alert_stub::AlertStub;
+
+    fn build_manager(stub: &AlertStub, rules: &str) -> AlertManager {
+        let config = AlertConfig {
+            webhook_url: stub.get_url(),
+            slack_webhook_url: stub.get_url(),
+            rules: rules
+                .split(',')
+                .map(|rule| AlertRule::from_str(rule).unwrap())
+                .collect(),
+            silence_secs: 300,
+            ..Default::default()
+        };
+        AlertManager::new(&config, "task_1").unwrap()
+    }
+
+    async fn wait_received(stub: &AlertStub, count: usize) -> Vec<serde_json::Value> {
+        for _ in 0..50 {
+            if stub.get_received().len() >= count {
+                break;
+            }
+            tokio::time::sleep(Duration::from_millis(100)).await;
+        }
+        stub.get_received()
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_notify_task_exit() {
+        let stub = AlertStub::start("127.0.0.1", 0).unwrap();
+        let manager = build_manager(&stub, "task_failed");
+
+        // task_finished is not configured
+        manager.notify_task_exit(&Ok(())).await;
+        assert!(stub.get_received().is_empty());
+
+        manager
+            .notify_task_exit(&Err(anyhow::anyhow!("connection refused")))
+            .await;
+        let received = stub.get_received();
+        assert_eq!(received.len(), 2);
+        // webhook
+        assert_eq!(received[0]["task_id"], "task_1");
+        assert_eq!(received[0]["rule"], "task_failed");
+        assert_eq!(
+            received[0]["message"],
+            "task failed, error: connection refused"
+        );
+        // slack
+        assert!(received[1]["text"]
+            .as_str()
+            .unwrap()
+            .starts_with("[ape-dts] task: task_1, rule: task_failed, task failed"));
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_check_metrics() {
+        let stub = AlertStub::start("127.0.0.1", 0).unwrap();
+        let manager = build_manager(&stub, "delay_to_now>60000,checker_diff_count>0");
+
+        let mut metrics = BTreeMap::new();
+        metrics.insert(TaskMetricsType::DelayToNow, 1000);
+        metrics.insert(TaskMetricsType::CheckerDiffCount, 0);
+        manager.check_metrics(&metrics);
+
+        metrics.insert(TaskMetricsType::DelayToNow, 70000);
+        manager.check_metrics(&metrics);
+        // silenced
+        manager.check_metrics(&metrics);
+
+        let received = wait_received(&stub, 2).await;
+        tokio::time::sleep(Duration::from_millis(500)).await;
+        assert_eq!(stub.get_received().len(), 2);
+        let alert = received.iter().find(|i| i.get("rule").is_some()).unwrap();
+        assert_eq!(alert["rule"], "delay_to_now>60000");
+        assert_eq!(alert["message"], "current value: 70000");
+    }
+}
+
+#[cfg(all(test, not(feature = "alert")))]
+mod tests {
+    use std::str::FromStr;
+
+    use super::*;
+
+    #[test]
+    fn test_new_without_feature() {
+        let manager = AlertManager::new(&AlertConfig::default(), "task_1").unwrap();
+        assert!(!manager.is_enabled());
+
+        let config = AlertConfig {
+            rules: vec![AlertRule::from_str("task_failed").unwrap()],
+            ..Default::default()
+        };
+        assert!(AlertManager::new(&config, "task_1").is_err());
+
+        let config = AlertConfig {
+            webhook_url: "http://127.0.0.1:8080".into(),
+            ..Default::default()
+        };
+        assert!(AlertManager::new(&config, "task_1").is_err());
+    }
+}
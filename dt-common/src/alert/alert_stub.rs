@@ -0,0 +1,56 @@
+use std::{
+    net::SocketAddr,
+    sync::{Arc, Mutex},
+};
+
+use actix_web::{web, App, HttpResponse, HttpServer, Responder};
+use serde_json::Value;
+
+type Received = Arc<Mutex<Vec<Value>>>;
+
+/// A local endpoint to verify alert channels without external services,
+/// POST /alert keeps the received json bodies, GET /alert lists them
+pub struct AlertStub {
+    addr: SocketAddr,
+    received: Received,
+}
+
+impl AlertStub {
+    /// Starts the stub in background, a free port is picked if port is 0
+    pub fn start(host: &str, port: u16) -> anyhow::Result<Self> {
+        let received = Received::default();
+        let app_received = received.clone();
+        let server = HttpServer::new(move || {
+            App::new()
+                .app_data(web::Data::new(app_received.clone()))
+                .service(
+                    web::resource("/alert")
+                        .route(web::post().to(receive))
+                        .route(web::get().to(list)),
+                )
+        })
+        .workers(1)
+        .bind((host, port))?;
+        let addr = server.addrs()[0];
+        tokio::spawn(server.run());
+
+        Ok(Self { addr, received })
+    }
+
+    pub fn get_url(&self) -> String {
+        format!("http://{}/alert", self.addr)
+    }
+
+    pub fn get_received(&self) -> Vec<Value> {
+        self.received.lock().unwrap().clone()
+    }
+}
+
+async fn receive(body: web::Json<Value>, received: web::Data<Received>) -> impl Responder {
+    received.lock().unwrap().push(body.into_inner());
+    HttpResponse::Ok().finish()
+}
+
+async fn list(received: web::Data<Received>) -> impl Responder {
+    HttpResponse::Ok().json(received.lock().unwrap().clone())
+}
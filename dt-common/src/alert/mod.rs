@@ -0,0 +1,4 @@
+#[cfg(feature = "alert")]
+pub mod alert_channel;
+pub mod alert_manager;
+pub mod alert_stub;
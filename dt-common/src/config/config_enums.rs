@@ -26,6 +26,8 @@ pub enum DbType {
     Foxlake,
     #[strum(serialize = "tidb")]
     Tidb,
+    #[strum(serialize = "file")]
+    File,
 }
 
 #[derive(Display, EnumString, IntoStaticStr, Debug, Clone)]
@@ -110,6 +112,62 @@ pub enum ConflictPolicyEnum {
     Interrupt,
 }
 
+#[derive(Clone, Debug, Display, EnumString, IntoStaticStr, PartialEq, Default)]
+pub enum KafkaPartitionStrategy {
+    // decided by kafka producer: hash of message key if exists, otherwise round robin
+    #[default]
+    #[strum(serialize = "default")]
+    Default,
+    #[strum(serialize = "pk_hash")]
+    PkHash,
+    #[strum(serialize = "table")]
+    Table,
+    #[strum(serialize = "column")]
+    Column,
+    #[strum(serialize = "round_robin")]
+    RoundRobin,
+}
+
+#[derive(Clone, Debug, Display, EnumString, IntoStaticStr, PartialEq, Default)]
+pub enum FileFormat {
+    #[default]
+    #[strum(serialize = "parquet")]
+    Parquet,
+    #[strum(serialize = "orc")]
+    Orc,
+    #[strum(serialize = "csv")]
+    Csv,
+    #[strum(serialize = "jsonl")]
+    Jsonl,
+}
+
+#[derive(Clone, Debug, Display, EnumString, IntoStaticStr, PartialEq, Default)]
+pub enum FilePartitionBy {
+    #[default]
+    #[strum(serialize = "none")]
+    None,
+    // hive style partition paths by write time, e.g. dt=2024-01-01
+    #[strum(serialize = "day")]
+    Day,
+    // e.g. dt=2024-01-01/hr=08
+    #[strum(serialize = "hour")]
+    Hour,
+}
+
+#[derive(Debug, Clone, PartialEq, Display, EnumString, IntoStaticStr)]
+pub enum ProcessorType {
+    #[strum(serialize = "filter")]
+    Filter,
+    #[strum(serialize = "router")]
+    Router,
+    #[strum(serialize = "lua")]
+    Lua,
+    #[strum(serialize = "transform")]
+    Transform,
+    #[strum(serialize = "wasm")]
+    Wasm,
+}
+
 #[derive(Display, EnumString, IntoStaticStr, PartialEq)]
 pub enum MetaCenterType {
     #[strum(serialize = "basic")]
@@ -130,6 +188,16 @@ pub enum TaskType {
     Check,
 }
 
+#[derive(Debug, Clone, PartialEq, Eq, Default, Display, EnumString, IntoStaticStr)]
+pub enum LogFormat {
+    #[default]
+    #[strum(serialize = "text")]
+    Text,
+    // every log record is a json object, refer to LogEvent
+    #[strum(serialize = "json")]
+    Json,
+}
+
 pub fn build_task_type(extract_type: &ExtractType, sink_type: &SinkType) -> Option<TaskType> {
     match (extract_type, sink_type) {
         (ExtractType::Struct, SinkType::Struct) => Some(TaskType::Struct),
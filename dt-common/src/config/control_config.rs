@@ -0,0 +1,8 @@
+#[derive(Clone, Default)]
+pub struct ControlConfig {
+    pub http_host: String,
+    // the control api is disabled if http_port is 0
+    pub http_port: u64,
+    // if set, requests must carry the header: Authorization: Bearer {token}
+    pub token: String,
+}
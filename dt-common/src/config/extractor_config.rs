@@ -1,5 +1,5 @@
 use super::{
-    config_enums::{DbType, ExtractType},
+    config_enums::{DbType, ExtractType, FileFormat},
     s3_config::S3Config,
 };
 
@@ -79,6 +79,7 @@ pub enum ExtractorConfig {
         app_name: String,
         db: String,
         tb: String,
+        parallel_size: usize,
     },
 
     MongoCdc {
@@ -102,6 +103,7 @@ pub enum ExtractorConfig {
     RedisSnapshot {
         url: String,
         repl_port: u64,
+        is_cluster: bool,
     },
 
     RedisCdc {
@@ -113,6 +115,7 @@ pub enum ExtractorConfig {
         heartbeat_interval_secs: u64,
         heartbeat_key: String,
         now_db_id: i64,
+        is_cluster: bool,
     },
 
     RedisSnapshotAndCdc {
@@ -122,6 +125,7 @@ pub enum ExtractorConfig {
         keepalive_interval_secs: u64,
         heartbeat_interval_secs: u64,
         heartbeat_key: String,
+        is_cluster: bool,
     },
 
     RedisSnapshotFile {
@@ -138,13 +142,23 @@ pub enum ExtractorConfig {
         url: String,
     },
 
+    RedisCheck {
+        url: String,
+        check_log_dir: String,
+        batch_size: usize,
+    },
+
     Kafka {
         url: String,
         group: String,
-        topic: String,
+        // topic names, or regex patterns starting with ^
+        topics: Vec<String>,
+        // -1: subscribe all partitions of topics by consumer group,
+        // others(default 0): assign the partition of the only topic
         partition: i32,
         offset: i64,
         ack_interval_secs: u64,
+        auto_offset_reset: String,
     },
 
     FoxlakeS3 {
@@ -154,6 +168,18 @@ pub enum ExtractorConfig {
         s3_config: S3Config,
         batch_size: usize,
     },
+
+    File {
+        // local file path, or key in s3 bucket if s3_config.bucket is set
+        file_path: String,
+        file_format: FileFormat,
+        // rows are loaded into schema.tb, which can be further routed by [router]
+        schema: String,
+        tb: String,
+        csv_delimiter: char,
+        csv_has_header: bool,
+        s3_config: S3Config,
+    },
 }
 
 #[derive(Clone, Debug)]
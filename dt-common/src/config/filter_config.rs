@@ -30,6 +30,12 @@ pub struct FilterConfig {
     pub do_dcls: String,
     pub ignore_cmds: String,
 
+    // Redis key filtering, globs separated by ',' or a regex
+    pub do_keys: String,
+    pub ignore_keys: String,
+    pub do_keys_regex: String,
+    pub ignore_keys_regex: String,
+
     // WHERE clause filtering (table-level conditions)
     pub where_conditions: String,
 
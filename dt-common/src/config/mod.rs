@@ -1,5 +1,7 @@
+pub mod alert_config;
 pub mod config_enums;
 pub mod config_token_parser;
+pub mod control_config;
 pub mod data_marker_config;
 pub mod extractor_config;
 pub mod filter_config;
@@ -15,6 +17,9 @@ pub mod runtime_config;
 pub mod s3_config;
 pub mod sinker_config;
 pub mod task_config;
+pub mod transform_config;
 
 #[cfg(feature = "metrics")]
 pub mod metrics_config;
+#[cfg(feature = "otel")]
+pub mod otel_config;
@@ -0,0 +1,8 @@
+#[derive(Clone)]
+#[cfg(feature = "otel")]
+pub struct OtelConfig {
+    // otlp grpc endpoint, such as: http://127.0.0.1:4317, the exporter is disabled if empty
+    pub endpoint: String,
+    pub service_name: String,
+    pub export_interval_secs: u64,
+}
@@ -1,5 +1,45 @@
+use super::{
+    filter_config::FilterConfig, router_config::RouterConfig, transform_config::TransformConfig,
+};
+
 #[derive(Clone)]
 pub struct ProcessorConfig {
     pub lua_code_file: String,
     pub lua_code: String,
+    // executed in order on dml and ddl data in pipeline,
+    // [lua_code] only if processors are not set, for compatibility
+    pub processors: Vec<ProcessorItemConfig>,
+}
+
+#[derive(Clone)]
+pub enum ProcessorItemConfig {
+    Filter {
+        name: String,
+        filter: FilterConfig,
+    },
+
+    Router {
+        name: String,
+        router: RouterConfig,
+    },
+
+    Lua {
+        name: String,
+        lua_code_file: String,
+        lua_code: String,
+    },
+
+    Transform {
+        name: String,
+        transform: TransformConfig,
+    },
+
+    // requires feature: wasm
+    Wasm {
+        name: String,
+        wasm_file: String,
+        // fuel of each call, 0 means unlimited
+        fuel: u64,
+        max_memory_mb: u64,
+    },
 }
@@ -11,6 +11,15 @@ pub enum RouterConfig {
         // Content-based routing rules
         // Format: json:[{"db":"test_db","tb":"tb_1","routes":[{"condition":{"column":"region","operator":"eq","value":"us"},"target_db":"us_db","target_tb":"users"}]}]
         content_routes: String,
+        // Redis key renaming by prefix
+        // Format: json:[{"src_prefix":"order:","dst_prefix":"tenant_a:order:"}]
+        key_map: String,
+        // Rdb table <-> redis key mapping
+        // Format: json:[{"db":"test_db","tb":"tb_1","key":"user:{tenant_id}:{id}","value_format":"hash"}]
+        redis_map: String,
+        // Mongo document -> rdb rows flattening
+        // Format: json:[{"db":"test_db","tb":"users","id_col":"id","cols":[{"path":"profile.name","col":"name"}],"child_tbs":[{"path":"orders","tb":"user_orders"}]}]
+        mongo_map: String,
     },
 }
 
@@ -526,6 +535,9 @@ mod tests {
             col_map: "".to_string(),
             topic_map: "".to_string(),
             content_routes: serde_json::to_string(&vec![content_route]).unwrap(),
+            key_map: "".to_string(),
+            redis_map: "".to_string(),
+            mongo_map: "".to_string(),
         };
 
         let routes = config.parse_content_routes().unwrap();
@@ -541,6 +553,9 @@ mod tests {
             col_map: "".to_string(),
             topic_map: "".to_string(),
             content_routes: "".to_string(),
+            key_map: "".to_string(),
+            redis_map: "".to_string(),
+            mongo_map: "".to_string(),
         };
 
         let map = config.get_schema_map();
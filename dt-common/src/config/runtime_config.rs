@@ -1,7 +1,17 @@
+use super::config_enums::LogFormat;
+
 #[derive(Clone)]
 pub struct RuntimeConfig {
     pub log_level: String,
     pub log_dir: String,
     pub log4rs_file: String,
+    pub log_format: LogFormat,
+    // written to every log record in json log format
+    pub task_id: String,
     pub tb_parallel_size: usize,
+    // whether to write delays of each table to task.log
+    pub log_tb_metrics: bool,
+    // progress of each table in snapshot tasks, written every checkpoint_interval_secs
+    pub write_progress_file: bool,
+    pub progress_file: String,
 }
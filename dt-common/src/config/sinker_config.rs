@@ -1,5 +1,7 @@
 use super::{
-    config_enums::{ConflictPolicyEnum, DbType},
+    config_enums::{
+        ConflictPolicyEnum, DbType, FileFormat, FilePartitionBy, KafkaPartitionStrategy,
+    },
     s3_config::S3Config,
 };
 use crate::config::config_enums::SinkType;
@@ -63,6 +65,13 @@ pub enum SinkerConfig {
         ack_timeout_secs: u64,
         required_acks: String,
         with_field_defs: bool,
+        partition_strategy: KafkaPartitionStrategy,
+        // for partition_strategy=column, e.g. *.*:id,db_1.tb_1:f_1
+        partition_cols: String,
+        // create topics in [router] topic_map if not exist
+        create_topics: bool,
+        topic_partitions: i32,
+        topic_replication_factor: i32,
     },
 
     Redis {
@@ -72,11 +81,24 @@ pub enum SinkerConfig {
         is_cluster: bool,
     },
 
+    RedisCheck {
+        url: String,
+        batch_size: usize,
+        is_cluster: bool,
+        check_log_dir: String,
+        // compare DUMP payloads besides values
+        compare_digest: bool,
+    },
+
     RedisStatistic {
         statistic_type: String,
         data_size_threshold: usize,
         freq_threshold: i64,
         statistic_log_dir: String,
+        // keys kept in each top list of the report
+        top_n: usize,
+        // keys are grouped by the part before the delimiter in the report
+        prefix_delimiter: String,
     },
 
     StarRocks {
@@ -143,6 +165,20 @@ pub enum SinkerConfig {
     Sql {
         reverse: bool,
     },
+
+    File {
+        // local dir, or key prefix in s3 bucket if s3_config.bucket is set
+        root_dir: String,
+        file_format: FileFormat,
+        partition_by: FilePartitionBy,
+        // rotate to a new file if exceeded, 0 means no limit
+        file_max_rows: usize,
+        file_max_mb: usize,
+        // finish files opened in previous windows of this length at checkpoints,
+        // 0 means finishing at every checkpoint
+        file_max_secs: u64,
+        s3_config: S3Config,
+    },
 }
 
 #[derive(Clone, Debug, Default)]
@@ -0,0 +1,10 @@
+use serde::{Deserialize, Serialize};
+
+#[derive(Clone, Default, Serialize, Deserialize)]
+pub struct TransformConfig {
+    // transforms=json:[{"db":"test_db","tb":"users","col":"phone","type":"mask","keep_prefix":3,"keep_suffix":4}]
+    // rules are applied in order, db / tb may be "*" to match all
+    pub transforms: String,
+    // secret salt of "hash" rules, rows hashed with the same salt are joinable
+    pub hash_salt: String,
+}
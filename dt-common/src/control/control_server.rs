@@ -0,0 +1,165 @@
+use std::{collections::BTreeMap, sync::Arc};
+
+use actix_web::{
+    body::MessageBody,
+    dev::{ServiceRequest, ServiceResponse},
+    http::header,
+    middleware::{from_fn, Logger, Next},
+    web, App, HttpResponse, HttpServer, Responder,
+};
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+
+use super::task_control::{TaskControl, TaskPosition, TaskState};
+use crate::config::control_config::ControlConfig;
+
+#[derive(Serialize)]
+struct StatusResp {
+    state: TaskState,
+    max_rps: u64,
+    positions: BTreeMap<String, TaskPosition>,
+}
+
+#[derive(Deserialize)]
+struct MaxRpsReq {
+    max_rps: u64,
+}
+
+struct ControlToken(String);
+
+pub struct ControlServer {}
+
+impl ControlServer {
+    pub fn start(
+        config: &ControlConfig,
+        task_control: Arc<TaskControl>,
+    ) -> anyhow::Result<tokio::task::JoinHandle<Result<(), std::io::Error>>> {
+        let addr = format!("{}:{}", config.http_host, config.http_port);
+        let token = web::Data::new(ControlToken(config.token.clone()));
+        let server = HttpServer::new(move || {
+            App::new()
+                .wrap(from_fn(check_token))
+                .wrap(Logger::default())
+                .app_data(token.clone())
+                .app_data(web::Data::new(task_control.clone()))
+                .service(web::resource("/status").route(web::get().to(status)))
+                .service(web::resource("/pause").route(web::post().to(pause)))
+                .service(web::resource("/resume").route(web::post().to(resume)))
+                .service(web::resource("/max_rps").route(web::post().to(set_max_rps)))
+                .service(web::resource("/checkpoint").route(web::post().to(checkpoint)))
+                .service(web::resource("/stop").route(web::post().to(stop)))
+        })
+        .workers(1)
+        .shutdown_timeout(10)
+        .bind(&addr)?
+        .run();
+
+        Ok(tokio::spawn(server))
+    }
+}
+
+async fn check_token(
+    req: ServiceRequest,
+    next: Next<impl MessageBody + 'static>,
+) -> Result<ServiceResponse<impl MessageBody>, actix_web::Error> {
+    let authorized = match req.app_data::<web::Data<ControlToken>>() {
+        Some(token) if !token.0.is_empty() => req
+            .headers()
+            .get(header::AUTHORIZATION)
+            .and_then(|value| value.to_str().ok())
+            .and_then(|value| value.strip_prefix("Bearer "))
+            .is_some_and(|value| value == token.0),
+        _ => true,
+    };
+
+    if !authorized {
+        let resp = HttpResponse::Unauthorized().json(json!({ "error": "invalid token" }));
+        return Ok(req.into_response(resp).map_into_right_body());
+    }
+    next.call(req)
+        .await
+        .map(ServiceResponse::map_into_left_body)
+}
+
+async fn status(task_control: web::Data<Arc<TaskControl>>) -> impl Responder {
+    HttpResponse::Ok().json(StatusResp {
+        state: task_control.get_state(),
+        max_rps: task_control.get_max_rps(),
+        positions: task_control.get_positions().await,
+    })
+}
+
+async fn pause(task_control: web::Data<Arc<TaskControl>>) -> impl Responder {
+    task_control.pause();
+    state_response(&task_control)
+}
+
+async fn resume(task_control: web::Data<Arc<TaskControl>>) -> impl Responder {
+    task_control.resume();
+    state_response(&task_control)
+}
+
+async fn set_max_rps(
+    req: web::Json<MaxRpsReq>,
+    task_control: web::Data<Arc<TaskControl>>,
+) -> impl Responder {
+    task_control.set_max_rps(req.max_rps);
+    HttpResponse::Ok().json(json!({ "max_rps": task_control.get_max_rps() }))
+}
+
+async fn checkpoint(task_control: web::Data<Arc<TaskControl>>) -> impl Responder {
+    // the checkpoint is recorded by pipelines asynchronously, query /status for the new positions
+    task_control.request_checkpoint();
+    state_response(&task_control)
+}
+
+async fn stop(task_control: web::Data<Arc<TaskControl>>) -> impl Responder {
+    task_control.stop();
+    state_response(&task_control)
+}
+
+fn state_response(task_control: &TaskControl) -> HttpResponse {
+    HttpResponse::Ok().json(json!({ "state": task_control.get_state() }))
+}
+
+#[cfg(test)]
+mod tests {
+    use actix_web::{http::StatusCode, test};
+
+    use super::*;
+
+    async fn get_status(token: &str, auth_header: Option<&str>) -> StatusCode {
+        let app = test::init_service(
+            App::new()
+                .wrap(from_fn(check_token))
+                .app_data(web::Data::new(ControlToken(token.into())))
+                .app_data(web::Data::new(Arc::new(TaskControl::new(0))))
+                .service(web::resource("/status").route(web::get().to(status))),
+        )
+        .await;
+
+        let mut req = test::TestRequest::get().uri("/status");
+        if let Some(auth_header) = auth_header {
+            req = req.insert_header((header::AUTHORIZATION, auth_header));
+        }
+        test::call_service(&app, req.to_request()).await.status()
+    }
+
+    #[tokio::test]
+    async fn test_check_token() {
+        // no token configured
+        assert_eq!(get_status("", None).await, StatusCode::OK);
+        assert_eq!(get_status("", Some("Bearer abc")).await, StatusCode::OK);
+
+        assert_eq!(get_status("abc", None).await, StatusCode::UNAUTHORIZED);
+        assert_eq!(
+            get_status("abc", Some("abc")).await,
+            StatusCode::UNAUTHORIZED
+        );
+        assert_eq!(
+            get_status("abc", Some("Bearer abcd")).await,
+            StatusCode::UNAUTHORIZED
+        );
+        assert_eq!(get_status("abc", Some("Bearer abc")).await, StatusCode::OK);
+    }
+}
@@ -0,0 +1,2 @@
+pub mod control_server;
+pub mod task_control;
@@ -0,0 +1,180 @@
+use std::{
+    collections::BTreeMap,
+    sync::{
+        atomic::{AtomicBool, AtomicU64, Ordering},
+        Arc,
+    },
+};
+
+use dashmap::DashMap;
+use serde::Serialize;
+use tokio::sync::Mutex;
+
+use crate::{
+    log_info,
+    meta::{position::Position, syncer::Syncer},
+    utils::time_util::TimeUtil,
+};
+
+const WAIT_INTERVAL_MILLIS: u64 = 100;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TaskState {
+    Running,
+    Paused,
+    Stopping,
+}
+
+#[derive(Serialize)]
+pub struct TaskPosition {
+    pub current_position: Position,
+    pub checkpoint_position: Position,
+}
+
+/// Shared by the task runner, extractors, pipelines and the control api,
+/// all changes take effect on running tasks without restarting
+#[derive(Default)]
+pub struct TaskControl {
+    paused: AtomicBool,
+    stopping: AtomicBool,
+    // 0 means no limit
+    max_rps: AtomicU64,
+    // increased by each checkpoint request, pipelines record checkpoint once it changes
+    checkpoint_seq: AtomicU64,
+    // syncers of running single tasks, keyed by single task id
+    syncers: DashMap<String, Arc<Mutex<Syncer>>>,
+}
+
+impl TaskControl {
+    pub fn new(max_rps: u64) -> Self {
+        Self {
+            max_rps: AtomicU64::new(max_rps),
+            ..Default::default()
+        }
+    }
+
+    pub fn get_state(&self) -> TaskState {
+        if self.is_stopping() {
+            TaskState::Stopping
+        } else if self.is_paused() {
+            TaskState::Paused
+        } else {
+            TaskState::Running
+        }
+    }
+
+    pub fn pause(&self) {
+        if !self.paused.swap(true, Ordering::AcqRel) {
+            log_info!("task paused");
+        }
+    }
+
+    pub fn resume(&self) {
+        if self.paused.swap(false, Ordering::AcqRel) {
+            log_info!("task resumed");
+        }
+    }
+
+    pub fn is_paused(&self) -> bool {
+        self.paused.load(Ordering::Acquire)
+    }
+
+    pub async fn wait_if_paused(&self) {
+        while self.is_paused() && !self.is_stopping() {
+            TimeUtil::sleep_millis(WAIT_INTERVAL_MILLIS).await;
+        }
+    }
+
+    pub fn stop(&self) {
+        if !self.stopping.swap(true, Ordering::AcqRel) {
+            log_info!("task stopping");
+        }
+    }
+
+    pub fn is_stopping(&self) -> bool {
+        self.stopping.load(Ordering::Acquire)
+    }
+
+    pub async fn wait_stopping(&self) {
+        while !self.is_stopping() {
+            TimeUtil::sleep_millis(WAIT_INTERVAL_MILLIS).await;
+        }
+    }
+
+    pub fn set_max_rps(&self, max_rps: u64) {
+        let old = self.max_rps.swap(max_rps, Ordering::AcqRel);
+        log_info!("max_rps changed from {} to {}", old, max_rps);
+    }
+
+    pub fn get_max_rps(&self) -> u64 {
+        self.max_rps.load(Ordering::Acquire)
+    }
+
+    pub fn request_checkpoint(&self) {
+        self.checkpoint_seq.fetch_add(1, Ordering::AcqRel);
+    }
+
+    pub fn get_checkpoint_seq(&self) -> u64 {
+        self.checkpoint_seq.load(Ordering::Acquire)
+    }
+
+    pub fn register_syncer(&self, task_id: &str, syncer: Arc<Mutex<Syncer>>) {
+        self.syncers.insert(task_id.to_string(), syncer);
+    }
+
+    pub fn unregister_syncer(&self, task_id: &str) {
+        self.syncers.remove(task_id);
+    }
+
+    pub async fn get_positions(&self) -> BTreeMap<String, TaskPosition> {
+        let syncers: Vec<(String, Arc<Mutex<Syncer>>)> = self
+            .syncers
+            .iter()
+            .map(|item| (item.key().clone(), item.value().clone()))
+            .collect();
+
+        let mut positions = BTreeMap::new();
+        for (task_id, syncer) in syncers {
+            let syncer = syncer.lock().await;
+            positions.insert(
+                task_id,
+                TaskPosition {
+                    current_position: syncer.received_position.clone(),
+                    checkpoint_position: syncer.committed_position.clone(),
+                },
+            );
+        }
+        positions
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_task_state() {
+        let task_control = TaskControl::new(100);
+        assert_eq!(task_control.get_state(), TaskState::Running);
+        assert_eq!(task_control.get_max_rps(), 100);
+
+        task_control.pause();
+        assert_eq!(task_control.get_state(), TaskState::Paused);
+        task_control.resume();
+        assert_eq!(task_control.get_state(), TaskState::Running);
+
+        // stopping takes precedence over paused
+        task_control.pause();
+        task_control.stop();
+        assert_eq!(task_control.get_state(), TaskState::Stopping);
+    }
+
+    #[test]
+    fn test_request_checkpoint() {
+        let task_control = TaskControl::default();
+        let seq = task_control.get_checkpoint_seq();
+        task_control.request_checkpoint();
+        assert_ne!(task_control.get_checkpoint_seq(), seq);
+    }
+}
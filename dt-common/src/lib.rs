@@ -1,5 +1,8 @@
+pub mod alert;
 pub mod config;
+pub mod control;
 pub mod error;
+pub mod log_event;
 pub mod logger;
 pub mod meta;
 pub mod monitor;
@@ -0,0 +1,104 @@
+use chrono::{SecondsFormat, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+
+// keys of structured values attached to log records, such as:
+// log_task!(schema = "db_1", tb = "tb_1", payload:serde = metrics; "{}", ...)
+pub const SCHEMA_KEY: &str = "schema";
+pub const TB_KEY: &str = "tb";
+pub const PAYLOAD_KEY: &str = "payload";
+
+/// A log record in json log format, fields are stable and always present:
+/// {"ts":"2024-04-01T03:25:18.701725Z","level":"INFO","task_id":"task_1","kind":"position","schema":"","tb":"","payload":{...}}
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct LogEvent {
+    pub ts: String,
+    pub level: String,
+    pub task_id: String,
+    // the logger of the record without "_logger", such as: task, position, finished, miss, diff, default
+    pub kind: String,
+    pub schema: String,
+    pub tb: String,
+    pub payload: Value,
+}
+
+impl LogEvent {
+    pub fn new(level: &str, task_id: &str, target: &str) -> Self {
+        Self {
+            ts: Utc::now().to_rfc3339_opts(SecondsFormat::Micros, true),
+            level: level.into(),
+            task_id: task_id.into(),
+            kind: Self::get_kind(target).into(),
+            schema: String::new(),
+            tb: String::new(),
+            payload: Value::Null,
+        }
+    }
+
+    /// Parses a line written in json log format, returns None for lines in text log format
+    pub fn from_log(log: &str) -> Option<Self> {
+        let log = log.trim();
+        if !log.starts_with('{') {
+            return None;
+        }
+        serde_json::from_str(log).ok()
+    }
+
+    fn get_kind(target: &str) -> &str {
+        // logs of dependencies, such as sqlx, are written by their own targets
+        target.strip_suffix("_logger").unwrap_or("default")
+    }
+
+    /// The payload of records without a structured payload value:
+    /// messages in json object are used directly, others are wrapped as {"message": "..."}
+    pub fn build_payload(message: &str) -> Value {
+        if message.starts_with('{') {
+            if let Ok(value) = serde_json::from_str::<Value>(message) {
+                if value.is_object() {
+                    return value;
+                }
+            }
+        }
+        json!({ "message": message })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_from_log() {
+        let log = r#"{"ts":"2024-04-01T03:25:18.701725Z","level":"INFO","task_id":"task_1","kind":"finished","schema":"test_db_1","tb":"one_pk_no_uk","payload":{"type":"RdbSnapshotFinished","db_type":"mysql","schema":"test_db_1","tb":"one_pk_no_uk"}}"#;
+        let event = LogEvent::from_log(log).unwrap();
+        assert_eq!(event.kind, "finished");
+        assert_eq!(event.schema, "test_db_1");
+        assert_eq!(event.payload["type"], "RdbSnapshotFinished");
+
+        // text log format
+        let log = r#"2024-04-01 03:25:18.701725 | {"type":"RdbSnapshotFinished","db_type":"mysql","schema":"test_db_1","tb":"one_pk_no_uk"}"#;
+        assert!(LogEvent::from_log(log).is_none());
+        // json logs which are not log events, such as check logs in text log format
+        let log = r#"{"log_type":"Diff","schema":"test_db_1","tb":"one_pk_no_uk"}"#;
+        assert!(LogEvent::from_log(log).is_none());
+    }
+
+    #[test]
+    fn test_build_payload() {
+        assert_eq!(
+            LogEvent::build_payload(r#"{"sinker_rps_avg":10}"#),
+            json!({"sinker_rps_avg": 10})
+        );
+        assert_eq!(
+            LogEvent::build_payload("task finished"),
+            json!({"message": "task finished"})
+        );
+        assert_eq!(LogEvent::build_payload("{}}"), json!({"message": "{}}"}));
+    }
+
+    #[test]
+    fn test_get_kind() {
+        assert_eq!(LogEvent::get_kind("position_logger"), "position");
+        assert_eq!(LogEvent::get_kind("sqlx::query"), "default");
+    }
+}
@@ -1,4 +1,5 @@
 use serde::{Deserialize, Serialize};
+use serde_json::json;
 
 use crate::{config::config_enums::DbType, utils::sql_util::SqlUtil};
 
@@ -34,6 +35,12 @@ pub enum DdlStatement {
     RenameTable(RenameTableStatement),
     PgDropIndex(PgDropIndexStatement),
 
+    MongoCreateCollection(MongoCommandStatement),
+    MongoCollMod(MongoCommandStatement),
+    MongoCreateIndexes(MongoCommandStatement),
+    MongoDropIndexes(MongoCommandStatement),
+    MongoRenameCollection(MongoRenameCollectionStatement),
+
     #[default]
     Unknown,
 }
@@ -113,6 +120,12 @@ impl DdlStatement {
             DdlStatement::PgAlterTableRename(s) => (s.schema.clone(), s.tb.clone()),
             DdlStatement::PgAlterTableSetSchema(s) => (s.schema.clone(), s.tb.clone()),
 
+            DdlStatement::MongoCreateCollection(s)
+            | DdlStatement::MongoCollMod(s)
+            | DdlStatement::MongoCreateIndexes(s)
+            | DdlStatement::MongoDropIndexes(s) => (s.db.clone(), s.tb.clone()),
+            DdlStatement::MongoRenameCollection(s) => (s.db.clone(), s.tb.clone()),
+
             DdlStatement::PgDropIndex(_)
             | DdlStatement::PgDropMultiIndex(_)
             | DdlStatement::DropMultiTable(_)
@@ -126,6 +139,7 @@ impl DdlStatement {
             DdlStatement::RenameTable(s) => (s.new_schema.clone(), s.new_tb.clone()),
             DdlStatement::MysqlAlterTableRename(s) => (s.new_db.clone(), s.new_tb.clone()),
             DdlStatement::PgAlterTableRename(s) => (s.new_schema.clone(), s.new_tb.clone()),
+            DdlStatement::MongoRenameCollection(s) => (s.new_db.clone(), s.new_tb.clone()),
             _ => (String::new(), String::new()),
         }
     }
@@ -171,6 +185,13 @@ impl DdlStatement {
                 s.new_tb = dst_new_tb;
             }
 
+            DdlStatement::MongoRenameCollection(s) => {
+                s.db = dst_schema;
+                s.tb = dst_tb;
+                s.new_db = dst_new_schema;
+                s.new_tb = dst_new_tb;
+            }
+
             _ => {}
         }
     }
@@ -260,8 +281,17 @@ impl DdlStatement {
                 s.tb = dst_tb;
             }
 
+            DdlStatement::MongoCreateCollection(s)
+            | DdlStatement::MongoCollMod(s)
+            | DdlStatement::MongoCreateIndexes(s)
+            | DdlStatement::MongoDropIndexes(s) => {
+                s.db = dst_schema;
+                s.tb = dst_tb;
+            }
+
             // not supported
             DdlStatement::RenameTable(_)
+            | DdlStatement::MongoRenameCollection(_)
             | DdlStatement::MysqlAlterTableRename(_)
             | DdlStatement::PgAlterTableRename(_)
             | DdlStatement::PgAlterTableSetSchema(_)
@@ -471,6 +501,24 @@ pub struct PgDropIndexStatement {
     pub unparsed: String,
 }
 
+#[derive(Debug, Clone, Serialize, Deserialize, Default, PartialEq, Eq)]
+pub struct MongoCommandStatement {
+    pub db: String,
+    pub tb: String,
+    // db command in canonical extended json, the first field is the command name with tb as value,
+    // e.g. {"createIndexes":"tb_1","indexes":[{"key":{"f_1":{"$numberInt":"1"}},"name":"f_1_1"}]}
+    pub command: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Default, PartialEq, Eq)]
+pub struct MongoRenameCollectionStatement {
+    pub db: String,
+    pub tb: String,
+    pub new_db: String,
+    pub new_tb: String,
+    pub drop_target: bool,
+}
+
 impl DdlStatement {
     pub fn to_sql(&self, db_type: &DbType) -> String {
         match self {
@@ -692,6 +740,19 @@ impl DdlStatement {
                 multi_s.to_sql(db_type)
             }
 
+            // mongo commands are not sql, return them in json for logging
+            DdlStatement::MongoCreateCollection(s)
+            | DdlStatement::MongoCollMod(s)
+            | DdlStatement::MongoCreateIndexes(s)
+            | DdlStatement::MongoDropIndexes(s) => s.command.clone(),
+
+            DdlStatement::MongoRenameCollection(s) => json!({
+                "renameCollection": format!("{}.{}", s.db, s.tb),
+                "to": format!("{}.{}", s.new_db, s.new_tb),
+                "dropTarget": s.drop_target,
+            })
+            .to_string(),
+
             _ => String::new(),
         }
     }
@@ -878,6 +939,21 @@ impl DdlStatement {
                 size += mysql_drop_index_statement.index_name.len() as u64;
                 size += mysql_drop_index_statement.unparsed.len() as u64;
             }
+            DdlStatement::MongoCreateCollection(mongo_command_statement)
+            | DdlStatement::MongoCollMod(mongo_command_statement)
+            | DdlStatement::MongoCreateIndexes(mongo_command_statement)
+            | DdlStatement::MongoDropIndexes(mongo_command_statement) => {
+                size += mongo_command_statement.db.len() as u64;
+                size += mongo_command_statement.tb.len() as u64;
+                size += mongo_command_statement.command.len() as u64;
+            }
+            DdlStatement::MongoRenameCollection(mongo_rename_collection_statement) => {
+                size += mongo_rename_collection_statement.db.len() as u64;
+                size += mongo_rename_collection_statement.tb.len() as u64;
+                size += mongo_rename_collection_statement.new_db.len() as u64;
+                size += mongo_rename_collection_statement.new_tb.len() as u64;
+                size += 1;
+            }
             DdlStatement::Unknown => {}
         }
         size
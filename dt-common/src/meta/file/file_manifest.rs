@@ -0,0 +1,34 @@
+use std::str::FromStr;
+
+use anyhow::Context;
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+
+use crate::meta::position::Position;
+
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+pub struct FileManifest {
+    pub schema: String,
+    pub tb: String,
+    pub file_format: String,
+    pub data_file_name: String,
+    pub data_size: usize,
+    pub row_count: usize,
+    pub first_position: Position,
+    pub last_position: Position,
+}
+
+impl std::fmt::Display for FileManifest {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", json!(self))
+    }
+}
+
+impl FromStr for FileManifest {
+    type Err = anyhow::Error;
+    fn from_str(str: &str) -> anyhow::Result<Self, anyhow::Error> {
+        let me: Self = serde_json::from_str(str)
+            .with_context(|| format!("invalid file manifest str: [{}]", str))?;
+        Ok(me)
+    }
+}
@@ -7,6 +7,7 @@ pub mod dcl_meta;
 pub mod ddl_meta;
 pub mod dt_data;
 pub mod dt_queue;
+pub mod file;
 pub mod foreign_key;
 pub mod foxlake;
 pub mod kafka;
@@ -1,3 +1,5 @@
 pub mod mongo_cdc_source;
 pub mod mongo_constant;
+pub mod mongo_ddl;
 pub mod mongo_key;
+pub mod mongo_rdb_mapping;
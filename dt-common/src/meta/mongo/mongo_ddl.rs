@@ -0,0 +1,451 @@
+use anyhow::{bail, Context};
+use mongodb::bson::{doc, Bson, Document};
+
+use crate::{
+    config::config_enums::DbType,
+    error::Error,
+    meta::ddl_meta::{
+        ddl_data::DdlData,
+        ddl_statement::{
+            DdlStatement, DropDatabaseStatement, DropTableStatement, MongoCommandStatement,
+            MongoRenameCollectionStatement,
+        },
+        ddl_type::DdlType,
+    },
+};
+
+const ADMIN_DB: &str = "admin";
+const SYSTEM_TB_PREFIX: &str = "system.";
+
+/// Translates command oplog entries and change stream events into DdlData,
+/// and DdlData back into db commands to run in the target
+pub struct MongoDdl {}
+
+impl MongoDdl {
+    /// Parses the "o" of a command oplog entry (op: "c"), ns is like "db_1.$cmd".
+    /// Returns None if the command is not a supported ddl
+    pub fn from_oplog(ns: &str, o: &Document) -> Option<DdlData> {
+        let db = ns.split('.').next().unwrap_or_default();
+        let (cmd_name, cmd_value) = o.iter().next()?;
+        let tb = cmd_value.as_str().unwrap_or_default();
+        let query = Self::to_json(o);
+
+        let (ddl_type, statement) = match cmd_name.as_str() {
+            "create" => {
+                let mut command = o.clone();
+                command.remove("idIndex");
+                (
+                    DdlType::CreateTable,
+                    DdlStatement::MongoCreateCollection(Self::command_statement(db, tb, &command)),
+                )
+            }
+
+            "drop" => (DdlType::DropTable, Self::drop_statement(db, tb)),
+
+            "dropDatabase" => (DdlType::DropDatabase, Self::drop_database_statement(db)),
+
+            // {"renameCollection": "db_1.tb_1", "to": "db_2.tb_2", "stayTemp": false, "dropTarget": false}
+            // dropTarget is the uuid of the dropped target collection if it existed
+            "renameCollection" => {
+                let (db, tb) = Self::split_ns(cmd_value.as_str()?)?;
+                let (new_db, new_tb) = Self::split_ns(o.get_str("to").ok()?)?;
+                let drop_target = !matches!(o.get("dropTarget"), None | Some(Bson::Boolean(false)));
+                let statement = MongoRenameCollectionStatement {
+                    db,
+                    tb,
+                    new_db,
+                    new_tb,
+                    drop_target,
+                };
+                (
+                    DdlType::RenameTable,
+                    DdlStatement::MongoRenameCollection(statement),
+                )
+            }
+
+            // before 4.4: {"createIndexes": "tb_1", "v": 2, "key": {"f_1": 1}, "name": "f_1_1"}
+            "createIndexes" => {
+                let mut spec = o.clone();
+                spec.remove(cmd_name);
+                let command = doc! {"createIndexes": tb, "indexes": [spec]};
+                (
+                    DdlType::CreateIndex,
+                    DdlStatement::MongoCreateIndexes(Self::command_statement(db, tb, &command)),
+                )
+            }
+
+            // since 4.4, indexes are built by startIndexBuild + commitIndexBuild,
+            // {"commitIndexBuild": "tb_1", "indexBuildUUID": ..., "indexes": [{"v": 2, "key": {"f_1": 1}, "name": "f_1_1"}]}
+            "commitIndexBuild" => {
+                let indexes = o.get_array("indexes").ok()?.clone();
+                let command = doc! {"createIndexes": tb, "indexes": indexes};
+                (
+                    DdlType::CreateIndex,
+                    DdlStatement::MongoCreateIndexes(Self::command_statement(db, tb, &command)),
+                )
+            }
+
+            // {"dropIndexes": "tb_1", "index": "f_1_1"}
+            "dropIndexes" | "deleteIndexes" => {
+                let index = o.get("index")?.clone();
+                let command = doc! {"dropIndexes": tb, "index": index};
+                (
+                    DdlType::DropIndex,
+                    DdlStatement::MongoDropIndexes(Self::command_statement(db, tb, &command)),
+                )
+            }
+
+            "collMod" => (
+                DdlType::AlterTable,
+                DdlStatement::MongoCollMod(Self::command_statement(db, tb, o)),
+            ),
+
+            _ => return None,
+        };
+
+        Self::build_ddl_data(ddl_type, statement, query)
+    }
+
+    /// Parses a change stream event, events other than drop / rename / dropDatabase
+    /// are only generated with showExpandedEvents (since 6.0).
+    /// Returns None if the event is not a supported ddl
+    pub fn from_change_event(event: &Document) -> Option<DdlData> {
+        let operation_type = event.get_str("operationType").ok()?;
+        let ns = event.get_document("ns").ok()?;
+        let db = ns.get_str("db").ok()?;
+        let tb = ns.get_str("coll").unwrap_or_default();
+        let empty_desc = Document::new();
+        let desc = event
+            .get_document("operationDescription")
+            .unwrap_or(&empty_desc);
+        let query = Self::to_json(event);
+
+        let (ddl_type, statement) = match operation_type {
+            "create" => {
+                let mut command = doc! {"create": tb};
+                for (k, v) in desc.iter().filter(|(k, _)| k.as_str() != "idIndex") {
+                    command.insert(k, v.clone());
+                }
+                (
+                    DdlType::CreateTable,
+                    DdlStatement::MongoCreateCollection(Self::command_statement(db, tb, &command)),
+                )
+            }
+
+            "drop" => (DdlType::DropTable, Self::drop_statement(db, tb)),
+
+            "dropDatabase" => (DdlType::DropDatabase, Self::drop_database_statement(db)),
+
+            "rename" => {
+                let to = event
+                    .get_document("to")
+                    .or_else(|_| desc.get_document("to"))
+                    .ok()?;
+                let statement = MongoRenameCollectionStatement {
+                    db: db.into(),
+                    tb: tb.into(),
+                    new_db: to.get_str("db").ok()?.into(),
+                    new_tb: to.get_str("coll").ok()?.into(),
+                    drop_target: desc.contains_key("dropTarget"),
+                };
+                (
+                    DdlType::RenameTable,
+                    DdlStatement::MongoRenameCollection(statement),
+                )
+            }
+
+            "createIndexes" => {
+                let indexes = desc.get_array("indexes").ok()?.clone();
+                let command = doc! {"createIndexes": tb, "indexes": indexes};
+                (
+                    DdlType::CreateIndex,
+                    DdlStatement::MongoCreateIndexes(Self::command_statement(db, tb, &command)),
+                )
+            }
+
+            "dropIndexes" => {
+                let names: Vec<Bson> = desc
+                    .get_array("indexes")
+                    .ok()?
+                    .iter()
+                    .filter_map(|i| i.as_document()?.get("name").cloned())
+                    .collect();
+                let command = doc! {"dropIndexes": tb, "index": names};
+                (
+                    DdlType::DropIndex,
+                    DdlStatement::MongoDropIndexes(Self::command_statement(db, tb, &command)),
+                )
+            }
+
+            "modify" => {
+                let mut command = doc! {"collMod": tb};
+                for (k, v) in desc.iter() {
+                    command.insert(k, v.clone());
+                }
+                (
+                    DdlType::AlterTable,
+                    DdlStatement::MongoCollMod(Self::command_statement(db, tb, &command)),
+                )
+            }
+
+            _ => return None,
+        };
+
+        Self::build_ddl_data(ddl_type, statement, query)
+    }
+
+    /// Builds the db command of a mongo ddl, returns (db to run the command, command),
+    /// the collection in command is replaced by the routed one
+    pub fn to_command(ddl_data: &DdlData) -> anyhow::Result<(String, Document)> {
+        let res = match &ddl_data.statement {
+            DdlStatement::MongoCreateCollection(s)
+            | DdlStatement::MongoCollMod(s)
+            | DdlStatement::MongoCreateIndexes(s)
+            | DdlStatement::MongoDropIndexes(s) => {
+                let json: serde_json::Value = serde_json::from_str(&s.command)
+                    .with_context(|| format!("invalid mongo command: [{}]", s.command))?;
+                let mut command = match Bson::try_from(json)? {
+                    Bson::Document(command) => command,
+                    _ => {
+                        bail! {Error::Unexpected(format!("invalid mongo command: [{}]", s.command))}
+                    }
+                };
+                // insert keeps the position of an existing key, the command name stays first
+                if let Some(cmd_name) = command.keys().next().cloned() {
+                    command.insert(cmd_name, s.tb.clone());
+                }
+                (s.db.clone(), command)
+            }
+
+            DdlStatement::MongoRenameCollection(s) => (
+                ADMIN_DB.to_string(),
+                doc! {
+                    "renameCollection": format!("{}.{}", s.db, s.tb),
+                    "to": format!("{}.{}", s.new_db, s.new_tb),
+                    "dropTarget": s.drop_target,
+                },
+            ),
+
+            DdlStatement::DropTable(s) => (s.schema.clone(), doc! {"drop": s.tb.clone()}),
+
+            DdlStatement::DropDatabase(s) => (s.db.clone(), doc! {"dropDatabase": 1}),
+
+            _ => bail! {Error::Unexpected(format!(
+                "unsupported mongo ddl: {}",
+                ddl_data
+            ))},
+        };
+        Ok(res)
+    }
+
+    fn build_ddl_data(
+        ddl_type: DdlType,
+        statement: DdlStatement,
+        query: String,
+    ) -> Option<DdlData> {
+        let (db, tb) = statement.get_schema_tb();
+        let (_, new_tb) = statement.get_rename_to_schema_tb();
+        // ddls of system collections (system.views, system.buckets.*, ...) are generated by the server
+        if tb.starts_with(SYSTEM_TB_PREFIX) || new_tb.starts_with(SYSTEM_TB_PREFIX) {
+            return None;
+        }
+
+        Some(DdlData {
+            default_schema: db,
+            query,
+            ddl_type,
+            db_type: DbType::Mongo,
+            statement,
+        })
+    }
+
+    fn command_statement(db: &str, tb: &str, command: &Document) -> MongoCommandStatement {
+        MongoCommandStatement {
+            db: db.into(),
+            tb: tb.into(),
+            command: Self::to_json(command),
+        }
+    }
+
+    fn drop_statement(db: &str, tb: &str) -> DdlStatement {
+        DdlStatement::DropTable(DropTableStatement {
+            schema: db.into(),
+            tb: tb.into(),
+            ..Default::default()
+        })
+    }
+
+    fn drop_database_statement(db: &str) -> DdlStatement {
+        DdlStatement::DropDatabase(DropDatabaseStatement {
+            db: db.into(),
+            ..Default::default()
+        })
+    }
+
+    fn split_ns(ns: &str) -> Option<(String, String)> {
+        let (db, tb) = ns.split_once('.')?;
+        Some((db.into(), tb.into()))
+    }
+
+    fn to_json(doc: &Document) -> String {
+        Bson::Document(doc.clone())
+            .into_canonical_extjson()
+            .to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use mongodb::bson::{spec::BinarySubtype, Binary};
+
+    use super::*;
+
+    #[test]
+    fn test_from_oplog() {
+        // create
+        let o = doc! {"create": "tb_1", "idIndex": {"v": 2, "key": {"_id": 1}, "name": "_id_"}};
+        let ddl_data = MongoDdl::from_oplog("db_1.$cmd", &o).unwrap();
+        assert_eq!(ddl_data.ddl_type, DdlType::CreateTable);
+        assert_eq!(ddl_data.db_type, DbType::Mongo);
+        assert_eq!(
+            ddl_data.get_schema_tb(),
+            ("db_1".to_string(), "tb_1".to_string())
+        );
+        assert_eq!(
+            MongoDdl::to_command(&ddl_data).unwrap(),
+            ("db_1".to_string(), doc! {"create": "tb_1"})
+        );
+
+        // createIndexes before 4.4
+        let o = doc! {"createIndexes": "tb_1", "v": 2, "key": {"f_1": 1}, "name": "f_1_1"};
+        let ddl_data = MongoDdl::from_oplog("db_1.$cmd", &o).unwrap();
+        assert_eq!(ddl_data.ddl_type, DdlType::CreateIndex);
+        assert_eq!(
+            MongoDdl::to_command(&ddl_data).unwrap().1,
+            doc! {"createIndexes": "tb_1", "indexes": [{"v": 2, "key": {"f_1": 1}, "name": "f_1_1"}]}
+        );
+
+        // commitIndexBuild
+        let o = doc! {
+            "commitIndexBuild": "tb_1",
+            "indexBuildUUID": Binary { subtype: BinarySubtype::Uuid, bytes: vec![1; 16] },
+            "indexes": [{"v": 2, "key": {"f_1": 1}, "name": "f_1_1"}]
+        };
+        let ddl_data = MongoDdl::from_oplog("db_1.$cmd", &o).unwrap();
+        assert_eq!(ddl_data.ddl_type, DdlType::CreateIndex);
+        assert_eq!(
+            MongoDdl::to_command(&ddl_data).unwrap().1,
+            doc! {"createIndexes": "tb_1", "indexes": [{"v": 2, "key": {"f_1": 1}, "name": "f_1_1"}]}
+        );
+
+        // dropIndexes
+        let o = doc! {"dropIndexes": "tb_1", "index": "f_1_1"};
+        let ddl_data = MongoDdl::from_oplog("db_1.$cmd", &o).unwrap();
+        assert_eq!(ddl_data.ddl_type, DdlType::DropIndex);
+
+        // renameCollection
+        let o = doc! {"renameCollection": "db_1.tb_1", "to": "db_2.tb_2", "stayTemp": false, "dropTarget": false};
+        let ddl_data = MongoDdl::from_oplog("admin.$cmd", &o).unwrap();
+        assert_eq!(ddl_data.ddl_type, DdlType::RenameTable);
+        assert_eq!(
+            ddl_data.get_schema_tb(),
+            ("db_1".to_string(), "tb_1".to_string())
+        );
+        assert_eq!(
+            ddl_data.get_rename_to_schema_tb(),
+            ("db_2".to_string(), "tb_2".to_string())
+        );
+        assert_eq!(
+            MongoDdl::to_command(&ddl_data).unwrap(),
+            (
+                "admin".to_string(),
+                doc! {"renameCollection": "db_1.tb_1", "to": "db_2.tb_2", "dropTarget": false}
+            )
+        );
+
+        // drop & dropDatabase
+        let ddl_data = MongoDdl::from_oplog("db_1.$cmd", &doc! {"drop": "tb_1"}).unwrap();
+        assert_eq!(ddl_data.ddl_type, DdlType::DropTable);
+        assert_eq!(
+            MongoDdl::to_command(&ddl_data).unwrap(),
+            ("db_1".to_string(), doc! {"drop": "tb_1"})
+        );
+        let ddl_data = MongoDdl::from_oplog("db_1.$cmd", &doc! {"dropDatabase": 1}).unwrap();
+        assert_eq!(ddl_data.ddl_type, DdlType::DropDatabase);
+        assert_eq!(
+            MongoDdl::to_command(&ddl_data).unwrap(),
+            ("db_1".to_string(), doc! {"dropDatabase": 1})
+        );
+
+        // not ddl
+        assert!(MongoDdl::from_oplog("admin.$cmd", &doc! {"applyOps": []}).is_none());
+        assert!(MongoDdl::from_oplog("db_1.$cmd", &doc! {"create": "system.views"}).is_none());
+    }
+
+    #[test]
+    fn test_from_change_event() {
+        let event = doc! {
+            "operationType": "createIndexes",
+            "ns": {"db": "db_1", "coll": "tb_1"},
+            "operationDescription": {"indexes": [{"v": 2, "key": {"f_1": 1}, "name": "f_1_1"}]}
+        };
+        let ddl_data = MongoDdl::from_change_event(&event).unwrap();
+        assert_eq!(ddl_data.ddl_type, DdlType::CreateIndex);
+        assert_eq!(
+            MongoDdl::to_command(&ddl_data).unwrap().1,
+            doc! {"createIndexes": "tb_1", "indexes": [{"v": 2, "key": {"f_1": 1}, "name": "f_1_1"}]}
+        );
+
+        let event = doc! {
+            "operationType": "dropIndexes",
+            "ns": {"db": "db_1", "coll": "tb_1"},
+            "operationDescription": {"indexes": [{"v": 2, "key": {"f_1": 1}, "name": "f_1_1"}]}
+        };
+        let ddl_data = MongoDdl::from_change_event(&event).unwrap();
+        assert_eq!(
+            MongoDdl::to_command(&ddl_data).unwrap().1,
+            doc! {"dropIndexes": "tb_1", "index": ["f_1_1"]}
+        );
+
+        let event = doc! {
+            "operationType": "rename",
+            "ns": {"db": "db_1", "coll": "tb_1"},
+            "to": {"db": "db_1", "coll": "tb_2"},
+            "operationDescription": {"to": {"db": "db_1", "coll": "tb_2"}}
+        };
+        let ddl_data = MongoDdl::from_change_event(&event).unwrap();
+        assert_eq!(ddl_data.ddl_type, DdlType::RenameTable);
+        assert_eq!(
+            MongoDdl::to_command(&ddl_data).unwrap().1,
+            doc! {"renameCollection": "db_1.tb_1", "to": "db_1.tb_2", "dropTarget": false}
+        );
+
+        let event = doc! {
+            "operationType": "modify",
+            "ns": {"db": "db_1", "coll": "tb_1"},
+            "operationDescription": {"index": {"name": "f_1_1", "hidden": true}}
+        };
+        let ddl_data = MongoDdl::from_change_event(&event).unwrap();
+        assert_eq!(ddl_data.ddl_type, DdlType::AlterTable);
+        assert_eq!(
+            MongoDdl::to_command(&ddl_data).unwrap().1,
+            doc! {"collMod": "tb_1", "index": {"name": "f_1_1", "hidden": true}}
+        );
+
+        let event = doc! {"operationType": "insert", "ns": {"db": "db_1", "coll": "tb_1"}};
+        assert!(MongoDdl::from_change_event(&event).is_none());
+    }
+
+    #[test]
+    fn test_to_command_routed() {
+        let o = doc! {"createIndexes": "tb_1", "v": 2, "key": {"f_1": 1}, "name": "f_1_1"};
+        let mut ddl_data = MongoDdl::from_oplog("db_1.$cmd", &o).unwrap();
+        ddl_data
+            .statement
+            .route("dst_db_1".to_string(), "dst_tb_1".to_string());
+        let (db, command) = MongoDdl::to_command(&ddl_data).unwrap();
+        assert_eq!(db, "dst_db_1");
+        assert_eq!(command.keys().next().unwrap(), "createIndexes");
+        assert_eq!(command.get_str("createIndexes").unwrap(), "dst_tb_1");
+    }
+}
@@ -0,0 +1,623 @@
+use std::collections::HashMap;
+
+use anyhow::bail;
+use chrono::DateTime;
+use mongodb::bson::{Bson, Document};
+use serde::{Deserialize, Serialize};
+
+use super::mongo_constant::MongoConstants;
+use crate::{
+    error::Error,
+    log_warn,
+    meta::{col_value::ColValue, row_data::RowData, row_type::RowType},
+};
+
+const DATETIME_FORMAT: &str = "%Y-%m-%d %H:%M:%S%.3f";
+// column of scalar array elements in child tables
+const VALUE_COL: &str = "value";
+
+/// Maps a document field to a column, nested fields are separated by '.'
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MongoColMapping {
+    pub path: String,
+    // path with '.' replaced by '_' if empty
+    #[serde(default)]
+    pub col: String,
+}
+
+/// Writes elements of an array field into a child table
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MongoChildTbMapping {
+    pub path: String,
+    pub tb: String,
+    // column holding _id of the parent document
+    #[serde(default = "default_parent_col")]
+    pub parent_col: String,
+    // column holding the index of the element in array
+    #[serde(default = "default_index_col")]
+    pub index_col: String,
+    // columns of document elements, all top level fields if empty,
+    // scalar elements are written to column: value
+    #[serde(default)]
+    pub cols: Vec<MongoColMapping>,
+}
+
+fn default_id_col() -> String {
+    MongoConstants::ID.to_string()
+}
+
+fn default_parent_col() -> String {
+    "parent_id".to_string()
+}
+
+fn default_index_col() -> String {
+    "idx".to_string()
+}
+
+/// Flattens documents of a collection into rows of relational tables.
+/// mongo_map=json:[{"db":"test_db","tb":"users","id_col":"id","cols":[{"path":"profile.name","col":"name"}],"child_tbs":[{"path":"orders","tb":"user_orders"}]}]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MongoRdbMapping {
+    pub db: String,
+    pub tb: String,
+    // column of _id
+    #[serde(default = "default_id_col")]
+    pub id_col: String,
+    // all top level fields are mapped to columns with the same names if empty,
+    // sub documents and arrays not in child_tbs are written as json
+    #[serde(default)]
+    pub cols: Vec<MongoColMapping>,
+    #[serde(default)]
+    pub child_tbs: Vec<MongoChildTbMapping>,
+}
+
+impl MongoRdbMapping {
+    /// the default mapping: _id and all top level fields to columns with the same names
+    pub fn new(db: &str, tb: &str) -> Self {
+        Self {
+            db: db.to_string(),
+            tb: tb.to_string(),
+            id_col: default_id_col(),
+            cols: Vec::new(),
+            child_tbs: Vec::new(),
+        }
+    }
+
+    pub fn parse(&mut self) -> anyhow::Result<()> {
+        Self::parse_cols(&mut self.cols);
+        for child_tb in self.child_tbs.iter_mut() {
+            Self::parse_cols(&mut child_tb.cols);
+        }
+
+        for col in self.cols.iter() {
+            if col.path == MongoConstants::ID {
+                bail! {Error::ConfigError(format!(
+                    "_id of {}.{} is mapped by id_col, should not be in cols",
+                    self.db, self.tb
+                ))}
+            }
+        }
+        Ok(())
+    }
+
+    fn parse_cols(cols: &mut [MongoColMapping]) {
+        for col in cols.iter_mut() {
+            if col.col.is_empty() {
+                col.col = col.path.replace('.', "_");
+            }
+        }
+    }
+
+    /// whether the row is from a mongo source, in which the whole document is in column: doc / diff_doc
+    pub fn is_mongo_row(row_data: &RowData) -> bool {
+        let has_doc = |col_values: &Option<HashMap<String, ColValue>>| {
+            col_values.as_ref().is_some_and(|i| {
+                matches!(i.get(MongoConstants::DOC), Some(ColValue::MongoDoc(_)))
+                    || matches!(i.get(MongoConstants::DIFF_DOC), Some(ColValue::MongoDoc(_)))
+            })
+        };
+        has_doc(&row_data.before) || has_doc(&row_data.after)
+    }
+
+    /// whether the row is a $set / $unset diff from oplog, which is flattened into a partial update
+    pub fn is_diff_row(row_data: &RowData) -> bool {
+        row_data
+            .after
+            .as_ref()
+            .is_some_and(|i| matches!(i.get(MongoConstants::DIFF_DOC), Some(ColValue::MongoDoc(_))))
+    }
+
+    /// Converts a mongo row (documents in column: doc / diff_doc) into rows of the parent table,
+    /// followed by rows of child tables, existing child rows of the document are deleted
+    /// by parent_col before the array is written again, or when the document is deleted
+    pub fn to_rows(&self, row_data: &RowData) -> anyhow::Result<Vec<RowData>> {
+        let get_doc = |col_values: &Option<HashMap<String, ColValue>>, key: &str| match col_values
+            .as_ref()
+            .and_then(|i| i.get(key))
+        {
+            Some(ColValue::MongoDoc(doc)) => Some(doc.clone()),
+            _ => None,
+        };
+
+        let schema = &row_data.schema;
+        let mut rows = Vec::new();
+        match row_data.row_type {
+            RowType::Insert => {
+                if let Some(doc) = get_doc(&row_data.after, MongoConstants::DOC) {
+                    let id = Self::get_id(&doc)?;
+                    let after = self.doc_to_col_values(&doc);
+                    rows.push(self.new_row(schema, RowType::Insert, None, Some(after)));
+                    rows.extend(self.doc_to_child_rows(schema, id, &doc, false));
+                }
+            }
+
+            RowType::Delete => {
+                if let Some(doc) = get_doc(&row_data.before, MongoConstants::DOC) {
+                    let id = Self::get_id(&doc)?;
+                    let before = self.id_col_values(id);
+                    rows.push(self.new_row(schema, RowType::Delete, Some(before), None));
+                    for child_tb in self.child_tbs.iter() {
+                        rows.push(child_tb.to_delete_row(schema, id));
+                    }
+                }
+            }
+
+            RowType::Update => {
+                let Some(before_doc) = get_doc(&row_data.before, MongoConstants::DOC) else {
+                    return Ok(rows);
+                };
+                let id = Self::get_id(&before_doc)?;
+                let before = self.id_col_values(id);
+
+                if let Some(doc) = get_doc(&row_data.after, MongoConstants::DOC) {
+                    // full document from change stream
+                    let after = self.doc_to_col_values(&doc);
+                    rows.push(self.new_row(schema, RowType::Update, Some(before), Some(after)));
+                    rows.extend(self.doc_to_child_rows(schema, id, &doc, true));
+                } else if let Some(diff_doc) = get_doc(&row_data.after, MongoConstants::DIFF_DOC) {
+                    // $set / $unset from oplog, applied as partial update
+                    let mut after = HashMap::new();
+                    let mut child_rows = Vec::new();
+                    if let Some(Bson::Document(set_doc)) = diff_doc.get(MongoConstants::SET) {
+                        for (key, value) in set_doc {
+                            self.apply_diff(
+                                schema,
+                                id,
+                                key,
+                                Some(value),
+                                &mut after,
+                                &mut child_rows,
+                            );
+                        }
+                    }
+                    if let Some(Bson::Document(unset_doc)) = diff_doc.get(MongoConstants::UNSET) {
+                        for key in unset_doc.keys() {
+                            self.apply_diff(schema, id, key, None, &mut after, &mut child_rows);
+                        }
+                    }
+
+                    if !after.is_empty() {
+                        rows.push(self.new_row(schema, RowType::Update, Some(before), Some(after)));
+                    }
+                    rows.extend(child_rows);
+                }
+            }
+        }
+        Ok(rows)
+    }
+
+    /// whether the row deletes rows of a child table by parent_col
+    pub fn is_child_delete_row(&self, row_data: &RowData) -> bool {
+        row_data.row_type == RowType::Delete && self.child_tbs.iter().any(|i| i.tb == row_data.tb)
+    }
+
+    fn apply_diff(
+        &self,
+        schema: &str,
+        id: &Bson,
+        key: &str,
+        value: Option<&Bson>,
+        after: &mut HashMap<String, ColValue>,
+        child_rows: &mut Vec<RowData>,
+    ) {
+        let to_col_value =
+            |v: Option<&Bson>| v.map(Self::bson_to_col_value).unwrap_or(ColValue::None);
+
+        if let Some(child_tb) = self.child_tbs.iter().find(|i| i.path == key) {
+            // the array is set / unset as a whole
+            child_rows.push(child_tb.to_delete_row(schema, id));
+            if let Some(Bson::Array(array)) = value {
+                child_rows.extend(child_tb.to_rows(schema, id, array));
+            }
+            return;
+        }
+
+        if self.cols.is_empty() {
+            if key.contains('.') {
+                log_warn!(
+                    "partial update of nested field: {} in {}.{} is ignored, since the top level field is written as json",
+                    key,
+                    self.db,
+                    self.tb
+                );
+                return;
+            }
+            after.insert(key.to_string(), to_col_value(value));
+            return;
+        }
+
+        for col in self.cols.iter() {
+            if col.path == key {
+                after.insert(col.col.clone(), to_col_value(value));
+            } else if let Some(sub_path) =
+                col.path.strip_prefix(key).and_then(|i| i.strip_prefix('.'))
+            {
+                // the parent field of path is set / unset
+                let sub_value = match value {
+                    Some(Bson::Document(doc)) => Self::get_path(doc, sub_path),
+                    _ => None,
+                };
+                after.insert(col.col.clone(), to_col_value(sub_value));
+            } else if key.starts_with(&format!("{}.", col.path)) {
+                log_warn!(
+                    "partial update of nested field: {} in {}.{} is ignored, since column: {} is written as json",
+                    key,
+                    self.db,
+                    self.tb,
+                    col.col
+                );
+            }
+        }
+    }
+
+    fn new_row(
+        &self,
+        schema: &str,
+        row_type: RowType,
+        before: Option<HashMap<String, ColValue>>,
+        after: Option<HashMap<String, ColValue>>,
+    ) -> RowData {
+        RowData::new(schema.into(), self.tb.clone(), row_type, before, after)
+    }
+
+    fn get_id(doc: &Document) -> anyhow::Result<&Bson> {
+        match doc.get(MongoConstants::ID) {
+            Some(id) => Ok(id),
+            None => bail! {Error::Unexpected(format!(
+                "document without _id: {}",
+                doc
+            ))},
+        }
+    }
+
+    fn id_col_values(&self, id: &Bson) -> HashMap<String, ColValue> {
+        HashMap::from([(self.id_col.clone(), Self::bson_to_col_value(id))])
+    }
+
+    fn doc_to_col_values(&self, doc: &Document) -> HashMap<String, ColValue> {
+        let mut col_values = HashMap::new();
+        if self.cols.is_empty() {
+            for (key, value) in doc {
+                if self.child_tbs.iter().any(|i| i.path == *key) {
+                    continue;
+                }
+                let col = if key == MongoConstants::ID {
+                    self.id_col.clone()
+                } else {
+                    key.clone()
+                };
+                col_values.insert(col, Self::bson_to_col_value(value));
+            }
+        } else {
+            if let Some(id) = doc.get(MongoConstants::ID) {
+                col_values.insert(self.id_col.clone(), Self::bson_to_col_value(id));
+            }
+            Self::map_cols(doc, &self.cols, &mut col_values);
+        }
+        col_values
+    }
+
+    fn doc_to_child_rows(
+        &self,
+        schema: &str,
+        id: &Bson,
+        doc: &Document,
+        delete_existing: bool,
+    ) -> Vec<RowData> {
+        let mut rows = Vec::new();
+        for child_tb in self.child_tbs.iter() {
+            if delete_existing {
+                rows.push(child_tb.to_delete_row(schema, id));
+            }
+            if let Some(Bson::Array(array)) = Self::get_path(doc, &child_tb.path) {
+                rows.extend(child_tb.to_rows(schema, id, array));
+            }
+        }
+        rows
+    }
+
+    fn map_cols(
+        doc: &Document,
+        cols: &[MongoColMapping],
+        col_values: &mut HashMap<String, ColValue>,
+    ) {
+        for col in cols.iter() {
+            let col_value = Self::get_path(doc, &col.path)
+                .map(Self::bson_to_col_value)
+                .unwrap_or(ColValue::None);
+            col_values.insert(col.col.clone(), col_value);
+        }
+    }
+
+    fn get_path<'a>(doc: &'a Document, path: &str) -> Option<&'a Bson> {
+        let mut parts = path.split('.');
+        let mut value = doc.get(parts.next()?)?;
+        for part in parts {
+            value = value.as_document()?.get(part)?;
+        }
+        Some(value)
+    }
+
+    pub fn bson_to_col_value(value: &Bson) -> ColValue {
+        match value {
+            Bson::Null | Bson::Undefined => ColValue::None,
+            Bson::Boolean(v) => ColValue::Bool(*v),
+            Bson::Int32(v) => ColValue::Long(*v),
+            Bson::Int64(v) => ColValue::LongLong(*v),
+            Bson::Double(v) => ColValue::Double(*v),
+            Bson::String(v) => ColValue::String(v.clone()),
+            Bson::ObjectId(v) => ColValue::String(v.to_hex()),
+            Bson::Binary(v) => ColValue::Blob(v.bytes.clone()),
+            Bson::Timestamp(v) => ColValue::LongLong(v.time as i64),
+            Bson::DateTime(v) => match DateTime::from_timestamp_millis(v.timestamp_millis()) {
+                Some(datetime) => ColValue::DateTime(datetime.format(DATETIME_FORMAT).to_string()),
+                None => ColValue::None,
+            },
+            Bson::Decimal128(_) => {
+                // {"$numberDecimal":"1.23"}
+                match value.clone().into_relaxed_extjson() {
+                    serde_json::Value::Object(map) => match map.get("$numberDecimal") {
+                        Some(serde_json::Value::String(v)) => ColValue::Decimal(v.clone()),
+                        _ => ColValue::None,
+                    },
+                    _ => ColValue::None,
+                }
+            }
+            // sub documents, arrays and others
+            _ => ColValue::Json2(value.clone().into_relaxed_extjson().to_string()),
+        }
+    }
+}
+
+impl MongoChildTbMapping {
+    /// deletes all rows of the parent document, only parent_col is in before
+    fn to_delete_row(&self, schema: &str, parent_id: &Bson) -> RowData {
+        let before = HashMap::from([(
+            self.parent_col.clone(),
+            MongoRdbMapping::bson_to_col_value(parent_id),
+        )]);
+        RowData::new(
+            schema.into(),
+            self.tb.clone(),
+            RowType::Delete,
+            Some(before),
+            None,
+        )
+    }
+
+    fn to_rows(&self, schema: &str, parent_id: &Bson, array: &[Bson]) -> Vec<RowData> {
+        let mut rows = Vec::new();
+        for (i, element) in array.iter().enumerate() {
+            let mut after = HashMap::new();
+            match element {
+                Bson::Document(doc) if self.cols.is_empty() => {
+                    for (key, value) in doc {
+                        after.insert(key.clone(), MongoRdbMapping::bson_to_col_value(value));
+                    }
+                }
+                Bson::Document(doc) => MongoRdbMapping::map_cols(doc, &self.cols, &mut after),
+                _ => {
+                    after.insert(
+                        VALUE_COL.to_string(),
+                        MongoRdbMapping::bson_to_col_value(element),
+                    );
+                }
+            }
+            after.insert(
+                self.parent_col.clone(),
+                MongoRdbMapping::bson_to_col_value(parent_id),
+            );
+            after.insert(self.index_col.clone(), ColValue::LongLong(i as i64));
+            rows.push(RowData::new(
+                schema.into(),
+                self.tb.clone(),
+                RowType::Insert,
+                None,
+                Some(after),
+            ));
+        }
+        rows
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use mongodb::bson::{doc, oid::ObjectId};
+
+    use super::*;
+
+    fn new_mongo_row(
+        row_type: RowType,
+        before: Option<Document>,
+        after: Option<(&str, Document)>,
+    ) -> RowData {
+        let before = before
+            .map(|doc| HashMap::from([(MongoConstants::DOC.to_string(), ColValue::MongoDoc(doc))]));
+        let after =
+            after.map(|(key, doc)| HashMap::from([(key.to_string(), ColValue::MongoDoc(doc))]));
+        RowData::new("db_1".into(), "users".into(), row_type, before, after)
+    }
+
+    #[test]
+    fn test_default_mapping() {
+        let mapping = MongoRdbMapping::new("db_1", "users");
+        let oid = ObjectId::new();
+        let doc = doc! {"_id": oid, "name": "a", "age": 10, "tags": ["x", "y"], "profile": {"city": "bj"}};
+
+        let rows = mapping
+            .to_rows(&new_mongo_row(
+                RowType::Insert,
+                None,
+                Some((MongoConstants::DOC, doc)),
+            ))
+            .unwrap();
+        assert_eq!(rows.len(), 1);
+        let after = rows[0].after.as_ref().unwrap();
+        assert_eq!(after["_id"], ColValue::String(oid.to_hex()));
+        assert_eq!(after["name"], ColValue::String("a".into()));
+        assert_eq!(after["age"], ColValue::Long(10));
+        assert_eq!(after["tags"], ColValue::Json2(r#"["x","y"]"#.into()));
+        assert_eq!(after["profile"], ColValue::Json2(r#"{"city":"bj"}"#.into()));
+
+        // $set / $unset as partial update
+        let diff = doc! {"$set": {"name": "b", "profile.city": "sh"}, "$unset": {"age": true}};
+        let row_data = new_mongo_row(
+            RowType::Update,
+            Some(doc! {"_id": oid}),
+            Some((MongoConstants::DIFF_DOC, diff)),
+        );
+        assert!(MongoRdbMapping::is_mongo_row(&row_data));
+        assert!(MongoRdbMapping::is_diff_row(&row_data));
+        let rows = mapping.to_rows(&row_data).unwrap();
+        assert!(!MongoRdbMapping::is_mongo_row(&rows[0]));
+        assert_eq!(rows.len(), 1);
+        assert_eq!(rows[0].row_type, RowType::Update);
+        assert_eq!(
+            rows[0].before,
+            Some(HashMap::from([(
+                "_id".to_string(),
+                ColValue::String(oid.to_hex())
+            )]))
+        );
+        let after = rows[0].after.as_ref().unwrap();
+        assert_eq!(after.len(), 2);
+        assert_eq!(after["name"], ColValue::String("b".into()));
+        assert_eq!(after["age"], ColValue::None);
+
+        let rows = mapping
+            .to_rows(&new_mongo_row(RowType::Delete, Some(doc! {"_id": 1}), None))
+            .unwrap();
+        assert_eq!(rows[0].row_type, RowType::Delete);
+        assert_eq!(rows[0].before.as_ref().unwrap()["_id"], ColValue::Long(1));
+    }
+
+    #[test]
+    fn test_custom_mapping() {
+        let mut mapping: MongoRdbMapping = serde_json::from_str(
+            r#"{"db":"db_1","tb":"users","id_col":"id",
+            "cols":[{"path":"name"},{"path":"profile.city","col":"city"},{"path":"profile.zip"}],
+            "child_tbs":[{"path":"orders","tb":"user_orders","cols":[{"path":"sku"}]},{"path":"tags","tb":"user_tags","parent_col":"user_id"}]}"#,
+        )
+        .unwrap();
+        mapping.parse().unwrap();
+
+        let doc = doc! {
+            "_id": 1,
+            "name": "a",
+            "ignored": "x",
+            "profile": {"city": "bj"},
+            "orders": [{"sku": "s1", "price": 1}, {"sku": "s2"}],
+            "tags": ["t1"]
+        };
+        let rows = mapping
+            .to_rows(&new_mongo_row(
+                RowType::Insert,
+                None,
+                Some((MongoConstants::DOC, doc)),
+            ))
+            .unwrap();
+        assert_eq!(rows.len(), 4);
+
+        let after = rows[0].after.as_ref().unwrap();
+        assert_eq!(rows[0].tb, "users");
+        assert_eq!(after.len(), 4);
+        assert_eq!(after["id"], ColValue::Long(1));
+        assert_eq!(after["city"], ColValue::String("bj".into()));
+        assert_eq!(after["profile_zip"], ColValue::None);
+
+        assert_eq!(rows[2].tb, "user_orders");
+        let after = rows[2].after.as_ref().unwrap();
+        assert_eq!(after.len(), 3);
+        assert_eq!(after["sku"], ColValue::String("s2".into()));
+        assert_eq!(after["parent_id"], ColValue::Long(1));
+        assert_eq!(after["idx"], ColValue::LongLong(1));
+
+        assert_eq!(rows[3].tb, "user_tags");
+        let after = rows[3].after.as_ref().unwrap();
+        assert_eq!(after["value"], ColValue::String("t1".into()));
+        assert_eq!(after["user_id"], ColValue::Long(1));
+
+        // set the parent of a mapped path, set an array in child table
+        let diff = doc! {"$set": {"profile": {"city": "sh", "zip": "200000"}, "tags": ["t2"]}};
+        let rows = mapping
+            .to_rows(&new_mongo_row(
+                RowType::Update,
+                Some(doc! {"_id": 1}),
+                Some((MongoConstants::DIFF_DOC, diff)),
+            ))
+            .unwrap();
+        assert_eq!(rows.len(), 3);
+        let after = rows[0].after.as_ref().unwrap();
+        assert_eq!(after.len(), 2);
+        assert_eq!(after["city"], ColValue::String("sh".into()));
+        assert_eq!(after["profile_zip"], ColValue::String("200000".into()));
+        // existing children are deleted before the array is written again
+        assert_eq!(rows[1].tb, "user_tags");
+        assert_eq!(rows[1].row_type, RowType::Delete);
+        assert_eq!(
+            rows[1].before,
+            Some(HashMap::from([("user_id".to_string(), ColValue::Long(1))]))
+        );
+        assert!(mapping.is_child_delete_row(&rows[1]));
+        assert_eq!(rows[2].tb, "user_tags");
+        assert_eq!(rows[2].row_type, RowType::Insert);
+
+        // children are deleted with the document
+        let rows = mapping
+            .to_rows(&new_mongo_row(RowType::Delete, Some(doc! {"_id": 1}), None))
+            .unwrap();
+        assert_eq!(rows.len(), 3);
+        assert_eq!(rows[0].tb, "users");
+        assert!(!mapping.is_child_delete_row(&rows[0]));
+        assert_eq!(rows[1].tb, "user_orders");
+        assert_eq!(
+            rows[1].before,
+            Some(HashMap::from([(
+                "parent_id".to_string(),
+                ColValue::Long(1)
+            )]))
+        );
+        assert_eq!(rows[2].tb, "user_tags");
+        assert_eq!(rows[2].row_type, RowType::Delete);
+
+        // full document from change stream
+        let rows = mapping
+            .to_rows(&new_mongo_row(
+                RowType::Update,
+                Some(doc! {"_id": 1}),
+                Some((
+                    MongoConstants::DOC,
+                    doc! {"_id": 1, "name": "b", "tags": []},
+                )),
+            ))
+            .unwrap();
+        assert_eq!(rows.len(), 3);
+        assert_eq!(rows[0].row_type, RowType::Update);
+        assert!(rows[1..].iter().all(|i| mapping.is_child_delete_row(i)));
+
+        // _id should not be in cols
+        let mut mapping: MongoRdbMapping =
+            serde_json::from_str(r#"{"db":"db_1","tb":"users","cols":[{"path":"_id"}]}"#).unwrap();
+        assert!(mapping.parse().is_err());
+    }
+}
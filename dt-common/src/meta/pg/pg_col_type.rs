@@ -12,6 +12,8 @@ pub struct PgColType {
     pub element_oid: i32,
     pub category: String,
     pub enum_values: Option<Vec<String>>,
+    // atttypmod of the column, such as precision and scale of numeric(p, s), -1 if not set
+    pub typmod: i32,
 }
 
 impl std::fmt::Display for PgColType {
@@ -33,4 +35,56 @@ impl PgColType {
     pub fn is_user_defined(&self) -> bool {
         "U" == self.category
     }
+
+    /// Returns (precision, scale) of numeric(p, s), None for numeric without precision
+    pub fn get_numeric_precision_scale(&self) -> Option<(u32, u32)> {
+        // refer to: numeric_typmod_precision / numeric_typmod_scale in postgres source
+        const VARHDRSZ: i32 = 4;
+        if self.value_type != PgValueType::Numeric || self.typmod < VARHDRSZ {
+            return None;
+        }
+        let typmod = self.typmod - VARHDRSZ;
+        let precision = (typmod >> 16) & 0xffff;
+        // scale is 11 bits signed since pg 15, negative scales are not supported
+        let scale = ((typmod & 0x7ff) ^ 1024) - 1024;
+        if scale < 0 {
+            return None;
+        }
+        Some((precision as u32, scale as u32))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_get_numeric_precision_scale() {
+        let mut col_type = PgColType {
+            value_type: PgValueType::Numeric,
+            name: "numeric".into(),
+            alias: "numeric".into(),
+            oid: 1700,
+            parent_oid: 0,
+            element_oid: 0,
+            category: "N".into(),
+            enum_values: None,
+            typmod: -1,
+        };
+        assert_eq!(col_type.get_numeric_precision_scale(), None);
+
+        // numeric(10, 2)
+        col_type.typmod = (10 << 16) + 2 + 4;
+        assert_eq!(col_type.get_numeric_precision_scale(), Some((10, 2)));
+        // numeric(38, 0)
+        col_type.typmod = (38 << 16) + 4;
+        assert_eq!(col_type.get_numeric_precision_scale(), Some((38, 0)));
+        // numeric(5, -2)
+        col_type.typmod = (5 << 16) + 0x7fe + 4;
+        assert_eq!(col_type.get_numeric_precision_scale(), None);
+
+        col_type.value_type = PgValueType::Int32;
+        col_type.typmod = (10 << 16) + 2 + 4;
+        assert_eq!(col_type.get_numeric_precision_scale(), None);
+    }
 }
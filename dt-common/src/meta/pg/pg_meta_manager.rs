@@ -154,7 +154,7 @@ impl PgMetaManager {
 
         // get col_type_oid of the table
         let sql = format!(
-            "SELECT a.attname AS col_name, a.atttypid as col_type_oid
+            "SELECT a.attname AS col_name, a.atttypid as col_type_oid, a.atttypmod as col_typmod
             FROM pg_class t, pg_attribute a
             WHERE a.attrelid = t.oid
                 AND t.relname = '{}'
@@ -170,11 +170,12 @@ impl PgMetaManager {
             }
 
             let col_type_oid: i32 = row.try_get_unchecked("col_type_oid")?;
-            let col_type = type_registry
+            let mut col_type = type_registry
                 .oid_to_type
                 .get(&col_type_oid)
                 .unwrap()
                 .clone();
+            col_type.typmod = row.try_get_unchecked("col_typmod")?;
             col_origin_type_map.insert(col.clone(), col_type.alias.clone());
             col_type_map.insert(col, col_type);
         }
@@ -52,6 +52,7 @@ impl TypeRegistry {
         let element_oid: i32 = row.get_unchecked("element");
         let parent_oid: i32 = row.get_unchecked("parentoid");
         let category: String = row.get_unchecked("category");
+        let typmod: i32 = row.get_unchecked("modifiers");
         let enum_values: Option<Vec<u8>> = row.get_unchecked("enum_values");
         let enum_values = if enum_values.is_none() {
             None
@@ -69,6 +70,7 @@ impl TypeRegistry {
             parent_oid,
             category,
             enum_values,
+            typmod,
         })
     }
 
@@ -1,11 +1,11 @@
-use std::str::FromStr;
+use std::{collections::BTreeMap, str::FromStr};
 
 use anyhow::Context;
 use chrono::{DateTime, NaiveDateTime};
 use serde::{Deserialize, Serialize};
 use serde_json::json;
 
-use crate::log_error;
+use crate::{log_error, log_event::LogEvent};
 
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq, Default)]
 #[serde(tag = "type")]
@@ -16,6 +16,9 @@ pub enum Position {
         topic: String,
         partition: i32,
         offset: i64,
+        // offsets of all consumed partitions, key: topic:partition
+        #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+        partition_offsets: BTreeMap<String, i64>,
     },
     RdbSnapshot {
         db_type: String,
@@ -51,12 +54,29 @@ pub enum Position {
         repl_offset: u64,
         now_db_id: i64,
         timestamp: String,
+        // positions of all master nodes when extracting from a cluster, key: node address
+        #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+        node_positions: BTreeMap<String, RedisNodePosition>,
     },
     FoxlakeS3 {
         schema: String,
         tb: String,
         s3_meta_file: String,
     },
+    File {
+        schema: String,
+        tb: String,
+        file_path: String,
+        // csv/jsonl: byte offset after the last extracted row, parquet: count of extracted rows
+        offset: u64,
+    },
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq, Default)]
+pub struct RedisNodePosition {
+    pub repl_id: String,
+    pub repl_offset: u64,
+    pub now_db_id: i64,
 }
 
 impl Position {
@@ -67,6 +87,51 @@ impl Position {
             String::new()
         }
     }
+
+    pub fn to_kafka_partition_key(topic: &str, partition: i32) -> String {
+        format!("{}:{}", topic, partition)
+    }
+
+    pub fn from_kafka_partition_key(key: &str) -> Option<(String, i32)> {
+        // kafka topic names can not contain ':'
+        let (topic, partition) = key.rsplit_once(':')?;
+        let partition = partition.parse::<i32>().ok()?;
+        Some((topic.to_string(), partition))
+    }
+
+    pub fn get_kafka_partition_offsets(&self) -> BTreeMap<String, i64> {
+        match self {
+            Position::Kafka {
+                topic,
+                partition,
+                offset,
+                partition_offsets,
+            } => {
+                if partition_offsets.is_empty() && !topic.is_empty() {
+                    // position logs generated by old versions only contain one partition
+                    let key = Self::to_kafka_partition_key(topic, *partition);
+                    BTreeMap::from([(key, *offset)])
+                } else {
+                    partition_offsets.clone()
+                }
+            }
+            _ => BTreeMap::new(),
+        }
+    }
+
+    /// checkpoint_position contains offsets of all partitions,
+    /// current_position may contain a newer offset of a single partition
+    pub fn merge_kafka_partition_offsets(
+        checkpoint_position: &Position,
+        current_position: &Position,
+    ) -> BTreeMap<String, i64> {
+        let mut offsets = checkpoint_position.get_kafka_partition_offsets();
+        for (key, offset) in current_position.get_kafka_partition_offsets() {
+            let merged = offsets.entry(key).or_insert(offset);
+            *merged = (*merged).max(offset);
+        }
+        offsets
+    }
 }
 
 impl std::fmt::Display for Position {
@@ -92,6 +157,23 @@ impl Position {
             return Position::None;
         }
 
+        // json log format:
+        // {"ts":"2024-03-29T07:02:24.463776Z",...,"kind":"position",...,"payload":{"current_position":{"type":"RdbSnapshot",...}}}
+        // {"ts":"2024-04-01T03:25:18.701725Z",...,"kind":"finished",...,"payload":{"type":"RdbSnapshotFinished",...}}
+        if let Some(event) = LogEvent::from_log(log) {
+            let payload = match event.payload.as_object() {
+                Some(obj) if obj.len() == 1 && !obj.contains_key("type") => {
+                    obj.values().next().cloned().unwrap_or_default()
+                }
+                _ => event.payload,
+            };
+            if let Ok(position) = serde_json::from_value(payload) {
+                return position;
+            }
+            log_error!("invalid position log: {}", log);
+            return Position::None;
+        }
+
         let left = log.find('{');
         let right = log.rfind('}');
         if left.is_some() && right.is_some() {
@@ -196,4 +278,53 @@ mod test {
 
         assert_eq!(Position::from_log(log3), Position::None);
     }
+
+    #[test]
+    fn test_kafka_partition_offsets() {
+        let log1 = r#"2024-04-01 03:25:18.701725 | current_position | {"type":"Kafka","topic":"test","partition":0,"offset":10}"#;
+        let log2 = r#"2024-04-01 03:25:18.701725 | current_position | {"type":"Kafka","topic":"test_2","partition":1,"offset":7,"partition_offsets":{"test:0":10,"test_2:0":3,"test_2:1":7}}"#;
+
+        let offsets = Position::from_log(log1).get_kafka_partition_offsets();
+        assert_eq!(offsets, BTreeMap::from([("test:0".to_string(), 10)]));
+
+        let offsets = Position::from_log(log2).get_kafka_partition_offsets();
+        assert_eq!(offsets.len(), 3);
+        assert_eq!(offsets.get("test_2:0"), Some(&3));
+        assert_eq!(
+            Position::from_kafka_partition_key("test_2:1"),
+            Some(("test_2".to_string(), 1))
+        );
+        assert_eq!(Position::from_kafka_partition_key("test_2"), None);
+    }
+
+    #[test]
+    fn test_merge_kafka_partition_offsets() {
+        let checkpoint = r#"2024-04-01 03:25:18.701725 | checkpoint_position | {"type":"Kafka","topic":"test_2","partition":1,"offset":7,"partition_offsets":{"test:0":10,"test_2:0":3,"test_2:1":7}}"#;
+        let current = r#"2024-04-01 03:25:19.701725 | current_position | {"type":"Kafka","topic":"test_2","partition":0,"offset":5}"#;
+        let offsets = Position::merge_kafka_partition_offsets(
+            &Position::from_log(checkpoint),
+            &Position::from_log(current),
+        );
+        assert_eq!(
+            offsets,
+            BTreeMap::from([
+                ("test:0".to_string(), 10),
+                ("test_2:0".to_string(), 5),
+                ("test_2:1".to_string(), 7),
+            ])
+        );
+
+        // no checkpoint_position
+        let offsets =
+            Position::merge_kafka_partition_offsets(&Position::None, &Position::from_log(current));
+        assert_eq!(offsets, BTreeMap::from([("test_2:0".to_string(), 5)]));
+
+        // current_position older than checkpoint_position
+        let current = r#"2024-04-01 03:25:17.701725 | current_position | {"type":"Kafka","topic":"test","partition":0,"offset":8}"#;
+        let offsets = Position::merge_kafka_partition_offsets(
+            &Position::from_log(checkpoint),
+            &Position::from_log(current),
+        );
+        assert_eq!(offsets.get("test:0"), Some(&10));
+    }
 }
@@ -0,0 +1,11 @@
+pub struct RedisConstants {}
+
+impl RedisConstants {
+    // id col of redis check logs
+    pub const KEY: &'static str = "key";
+    // diff cols of redis check logs
+    pub const TYPE: &'static str = "type";
+    pub const TTL: &'static str = "ttl";
+    pub const VALUE: &'static str = "value";
+    pub const DIGEST: &'static str = "digest";
+}
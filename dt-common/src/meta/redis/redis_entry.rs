@@ -14,6 +14,8 @@ pub struct RedisEntry {
     pub timestamp_ms: u64,
 
     pub expire_ms: i64,
+    // expired but still present in rdb
+    pub is_expired: bool,
     pub key: RedisString,
     pub value: RedisObject,
     pub value_type_byte: u8,
@@ -23,6 +25,8 @@ pub struct RedisEntry {
     pub data_size: usize,
     pub slot: i32,
     pub freq: i64,
+    // set by scan, since values are not fetched
+    pub element_count: usize,
 }
 
 impl RedisEntry {
@@ -34,6 +38,7 @@ impl RedisEntry {
             timestamp_ms: 0,
 
             expire_ms: 0,
+            is_expired: false,
             key: RedisString::new(),
             value: RedisObject::Unknown,
             raw_bytes: Vec::new(),
@@ -43,6 +48,7 @@ impl RedisEntry {
             data_size: 0,
             slot: 0,
             freq: -1,
+            element_count: 0,
         }
     }
 
@@ -38,6 +38,36 @@ impl RedisObject {
         .to_string()
     }
 
+    pub fn get_element_count(&self) -> usize {
+        match self {
+            Self::String(_) => 1,
+            Self::List(v) => v.elements.len(),
+            Self::Hash(v) => v.value.len(),
+            Self::Set(v) => v.elements.len(),
+            Self::Zset(v) => v.elements.len(),
+            // stream entries are rebuilt by XADD
+            Self::Stream(v) => v
+                .cmds
+                .iter()
+                .filter(|i| i.get_name().eq_ignore_ascii_case("xadd"))
+                .count(),
+            Self::Module(_) | Self::Unknown => 0,
+        }
+    }
+
+    pub fn set_key(&mut self, key: RedisString) {
+        match self {
+            Self::String(v) => v.key = key,
+            Self::List(v) => v.key = key,
+            Self::Hash(v) => v.key = key,
+            Self::Set(v) => v.key = key,
+            Self::Zset(v) => v.key = key,
+            Self::Module(v) => v.key = key,
+            Self::Stream(v) => v.key = key,
+            Self::Unknown => {}
+        }
+    }
+
     pub fn new(key_type: &str) -> Self {
         match key_type {
             TYPE_STRING => Self::String(StringObject::new()),
@@ -83,11 +113,21 @@ impl ListObject {
 }
 
 #[derive(Debug, Clone)]
-pub struct ModuleObject {}
+pub struct ModuleObject {
+    pub key: RedisString,
+    // module type name, such as: ReJSON-RL, MBbloom--
+    pub module_name: String,
+    // commands to rebuild the value, empty if the module type is not supported to decode
+    pub cmds: Vec<RedisCmd>,
+}
 
 impl ModuleObject {
     pub fn new() -> Self {
-        Self {}
+        Self {
+            key: RedisString::new(),
+            module_name: String::new(),
+            cmds: vec![],
+        }
     }
 }
 
@@ -334,7 +374,15 @@ impl RedisObject {
                 }
                 size + v.key.bytes.len()
             }
-            RedisObject::Module(_) => 0,
+            RedisObject::Module(v) => {
+                let mut size = 0;
+                for cmd in v.cmds.iter() {
+                    for arg in cmd.args.iter() {
+                        size += arg.len();
+                    }
+                }
+                size + v.key.bytes.len()
+            }
             RedisObject::Unknown => 0,
         }
     }
@@ -0,0 +1,556 @@
+use std::{
+    collections::HashMap,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use anyhow::bail;
+use serde::{Deserialize, Serialize};
+
+use super::{
+    redis_entry::RedisEntry,
+    redis_object::{RedisCmd, RedisObject},
+};
+use crate::{
+    error::Error,
+    meta::{col_value::ColValue, row_data::RowData, row_type::RowType},
+};
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RedisValueFormat {
+    // HSET key col_1 v_1 col_2 v_2
+    #[default]
+    Hash,
+    // SET key {"col_1":v_1,"col_2":v_2}
+    Json,
+    // JSON.SET key $ {"col_1":v_1,"col_2":v_2}, requires RedisJSON module
+    Rejson,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum KeyPart {
+    Text(String),
+    Col(String),
+}
+
+/// Maps rows of a relational table to redis keys and back.
+/// redis_map=json:[{"db":"test_db","tb":"tb_1","key":"user:{tenant_id}:{id}","value_format":"hash","ttl_col":"ttl","cols":["name","age"]}]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RedisRdbMapping {
+    pub db: String,
+    pub tb: String,
+    // key template, {col} is replaced by the column value
+    pub key: String,
+    #[serde(default)]
+    pub value_format: RedisValueFormat,
+    // column holding the remaining ttl in seconds, no ttl if empty
+    #[serde(default)]
+    pub ttl_col: String,
+    // columns stored in redis value, all columns if empty
+    #[serde(default)]
+    pub cols: Vec<String>,
+    #[serde(default)]
+    pub key_parts: Vec<KeyPart>,
+}
+
+impl RedisRdbMapping {
+    pub fn parse(&mut self) -> anyhow::Result<()> {
+        let mut parts = Vec::new();
+        let mut text = String::new();
+        let mut chars = self.key.chars();
+        while let Some(c) = chars.next() {
+            if c != '{' {
+                text.push(c);
+                continue;
+            }
+
+            let mut col = String::new();
+            let mut closed = false;
+            for c in chars.by_ref() {
+                if c == '}' {
+                    closed = true;
+                    break;
+                }
+                col.push(c);
+            }
+            if !closed || col.is_empty() {
+                bail! {Error::ConfigError(format!(
+                    "invalid redis key template: {}",
+                    self.key
+                ))}
+            }
+
+            if !text.is_empty() {
+                parts.push(KeyPart::Text(std::mem::take(&mut text)));
+            } else if matches!(parts.last(), Some(KeyPart::Col(_))) {
+                // the boundary of adjacent columns can not be found when parsing keys back
+                bail! {Error::ConfigError(format!(
+                    "columns in redis key template should be separated, got: {}",
+                    self.key
+                ))}
+            }
+            parts.push(KeyPart::Col(col));
+        }
+        if !text.is_empty() {
+            parts.push(KeyPart::Text(text));
+        }
+
+        if !parts.iter().any(|i| matches!(i, KeyPart::Col(_))) {
+            bail! {Error::ConfigError(format!(
+                "redis key template should contain at least one {{col}}, got: {}",
+                self.key
+            ))}
+        }
+        self.key_parts = parts;
+        Ok(())
+    }
+
+    pub fn build_key(&self, col_values: &HashMap<String, ColValue>) -> Option<String> {
+        let mut key = String::new();
+        for part in self.key_parts.iter() {
+            match part {
+                KeyPart::Text(text) => key.push_str(text),
+                KeyPart::Col(col) => key.push_str(&col_values.get(col)?.to_option_string()?),
+            }
+        }
+        Some(key)
+    }
+
+    /// extract values of the key columns, None if the key does not match the template
+    pub fn parse_key(&self, key: &str) -> Option<HashMap<String, String>> {
+        let mut key_cols = HashMap::new();
+        let mut rest = key;
+        for (i, part) in self.key_parts.iter().enumerate() {
+            match part {
+                KeyPart::Text(text) => rest = rest.strip_prefix(text.as_str())?,
+                KeyPart::Col(col) => {
+                    let end = match self.key_parts.get(i + 1) {
+                        Some(KeyPart::Text(next)) => rest.find(next.as_str())?,
+                        _ => rest.len(),
+                    };
+                    if end == 0 {
+                        return None;
+                    }
+                    key_cols.insert(col.clone(), rest[..end].to_string());
+                    rest = &rest[end..];
+                }
+            }
+        }
+
+        if rest.is_empty() {
+            Some(key_cols)
+        } else {
+            None
+        }
+    }
+
+    pub fn to_redis_cmds(&self, row_data: &RowData) -> Vec<RedisCmd> {
+        let mut cmds = Vec::new();
+        let before_key = row_data.before.as_ref().and_then(|i| self.build_key(i));
+        let after_key = row_data.after.as_ref().and_then(|i| self.build_key(i));
+
+        match row_data.row_type {
+            RowType::Insert | RowType::Update => {
+                let Some(key) = after_key else {
+                    return cmds;
+                };
+                // key columns changed, remove the old key
+                if let Some(before_key) = before_key {
+                    if before_key != key {
+                        cmds.push(RedisCmd::from_str_args(&["DEL", &before_key]));
+                    }
+                }
+                cmds.extend(self.build_set_cmds(&key, row_data.after.as_ref().unwrap()));
+            }
+
+            RowType::Delete => {
+                if let Some(key) = before_key {
+                    cmds.push(RedisCmd::from_str_args(&["DEL", &key]));
+                }
+            }
+        }
+        cmds
+    }
+
+    fn build_set_cmds(&self, key: &str, after: &HashMap<String, ColValue>) -> Vec<RedisCmd> {
+        let mut cmds = Vec::new();
+        let mut value_cols: Vec<&String> = if self.cols.is_empty() {
+            after.keys().collect()
+        } else {
+            self.cols
+                .iter()
+                .filter(|i| after.contains_key(*i))
+                .collect()
+        };
+        value_cols.sort();
+
+        let ttl = if self.ttl_col.is_empty() {
+            None
+        } else {
+            Some(
+                after
+                    .get(&self.ttl_col)
+                    .and_then(|i| i.to_option_string())
+                    .and_then(|i| i.parse::<i64>().ok())
+                    .filter(|i| *i > 0),
+            )
+        };
+
+        match self.value_format {
+            RedisValueFormat::Hash => {
+                if value_cols.is_empty() {
+                    return cmds;
+                }
+                let mut cmd = RedisCmd::from_str_args(&["HSET", key]);
+                for col in value_cols {
+                    cmd.add_str_arg(col);
+                    cmd.add_str_arg(&after[col].to_option_string().unwrap_or_default());
+                }
+                cmds.push(cmd);
+            }
+
+            RedisValueFormat::Json | RedisValueFormat::Rejson => {
+                let mut json = serde_json::Map::new();
+                for col in value_cols {
+                    let value = serde_json::to_value(&after[col]).unwrap_or_default();
+                    json.insert(col.clone(), value);
+                }
+                let json = serde_json::Value::Object(json).to_string();
+
+                if self.value_format == RedisValueFormat::Json {
+                    // SET also clears the previous ttl
+                    let mut cmd = RedisCmd::from_str_args(&["SET", key, &json]);
+                    if let Some(Some(ttl)) = ttl {
+                        cmd.add_str_arg("EX");
+                        cmd.add_str_arg(&ttl.to_string());
+                    }
+                    return vec![cmd];
+                }
+                cmds.push(RedisCmd::from_str_args(&["JSON.SET", key, "$", &json]));
+            }
+        }
+
+        match ttl {
+            Some(Some(ttl)) => {
+                cmds.push(RedisCmd::from_str_args(&["EXPIRE", key, &ttl.to_string()]));
+            }
+            Some(None) => cmds.push(RedisCmd::from_str_args(&["PERSIST", key])),
+            None => {}
+        }
+        cmds
+    }
+
+    /// convert a redis entry of the mapped key into a row,
+    /// entries decoded from rdb are full rows, commands from aof may only carry some columns
+    pub fn to_row_data(
+        &self,
+        key_cols: HashMap<String, String>,
+        entry: &RedisEntry,
+    ) -> Option<RowData> {
+        let key_values: HashMap<String, ColValue> = key_cols
+            .into_iter()
+            .map(|(col, v)| (col, ColValue::String(v)))
+            .collect();
+
+        if entry.is_base {
+            let mut after = match (&self.value_format, &entry.value) {
+                (RedisValueFormat::Hash, RedisObject::Hash(obj)) => {
+                    let mut after = HashMap::new();
+                    for (field, (value, _)) in obj.value.iter() {
+                        after.insert(field.to_string(), ColValue::String(value.to_string()));
+                    }
+                    after
+                }
+                (RedisValueFormat::Json, RedisObject::String(obj)) => {
+                    Self::parse_json(obj.value.as_bytes())?
+                }
+                (RedisValueFormat::Rejson, RedisObject::Module(obj)) => {
+                    let cmd = obj.cmds.iter().find(|i| i.get_name() == "JSON.SET")?;
+                    Self::parse_json(cmd.args.get(3)?)?
+                }
+                _ => return None,
+            };
+            self.retain_cols(&mut after);
+
+            if !self.ttl_col.is_empty() {
+                after.insert(self.ttl_col.clone(), Self::get_ttl(entry));
+            }
+            after.extend(key_values);
+            return Some(self.new_row(RowType::Insert, None, Some(after)));
+        }
+
+        let cmd = &entry.cmd;
+        match cmd.get_name().to_ascii_uppercase().as_str() {
+            "DEL" | "UNLINK" => Some(self.new_row(RowType::Delete, Some(key_values), None)),
+
+            "HSET" | "HMSET" if self.value_format == RedisValueFormat::Hash => {
+                let mut after = HashMap::new();
+                for pair in cmd.args[2..].chunks_exact(2) {
+                    let field = String::from_utf8_lossy(&pair[0]).to_string();
+                    let value = String::from_utf8_lossy(&pair[1]).to_string();
+                    after.insert(field, ColValue::String(value));
+                }
+                // the hash may be created by this command, so the row is upserted,
+                // sinkers only insert / update the given columns
+                self.retain_cols(&mut after);
+                if after.is_empty() {
+                    return None;
+                }
+                after.extend(key_values);
+                Some(self.new_row(RowType::Insert, None, Some(after)))
+            }
+
+            "HDEL" if self.value_format == RedisValueFormat::Hash => {
+                let mut after = HashMap::new();
+                for field in cmd.args[2..].iter() {
+                    after.insert(String::from_utf8_lossy(field).to_string(), ColValue::None);
+                }
+                self.retain_cols(&mut after);
+                if after.is_empty() {
+                    return None;
+                }
+                after.extend(key_values.clone());
+                Some(self.new_row(RowType::Update, Some(key_values), Some(after)))
+            }
+
+            "SET" if self.value_format == RedisValueFormat::Json => {
+                let mut after = Self::parse_json(cmd.args.get(2)?)?;
+                self.retain_cols(&mut after);
+                after.extend(key_values);
+                Some(self.new_row(RowType::Insert, None, Some(after)))
+            }
+
+            // only full document writes can be converted into rows
+            "JSON.SET"
+                if self.value_format == RedisValueFormat::Rejson
+                    && matches!(cmd.get_str_arg(2).as_str(), "$" | ".") =>
+            {
+                let mut after = Self::parse_json(cmd.args.get(3)?)?;
+                self.retain_cols(&mut after);
+                after.extend(key_values);
+                Some(self.new_row(RowType::Insert, None, Some(after)))
+            }
+
+            _ => None,
+        }
+    }
+
+    fn new_row(
+        &self,
+        row_type: RowType,
+        before: Option<HashMap<String, ColValue>>,
+        after: Option<HashMap<String, ColValue>>,
+    ) -> RowData {
+        RowData::new(self.db.clone(), self.tb.clone(), row_type, before, after)
+    }
+
+    fn retain_cols(&self, col_values: &mut HashMap<String, ColValue>) {
+        if !self.cols.is_empty() {
+            col_values.retain(|col, _| self.cols.contains(col));
+        }
+    }
+
+    fn get_ttl(entry: &RedisEntry) -> ColValue {
+        if entry.expire_ms <= 0 {
+            return ColValue::None;
+        }
+        let now_ms = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap()
+            .as_millis() as i64;
+        ColValue::LongLong(((entry.expire_ms - now_ms) / 1000).max(1))
+    }
+
+    fn parse_json(bytes: &[u8]) -> Option<HashMap<String, ColValue>> {
+        let serde_json::Value::Object(json) = serde_json::from_slice(bytes).ok()? else {
+            return None;
+        };
+
+        let mut col_values = HashMap::new();
+        for (col, value) in json {
+            let col_value = match value {
+                serde_json::Value::Null => ColValue::None,
+                serde_json::Value::Bool(v) => ColValue::Bool(v),
+                serde_json::Value::Number(v) => {
+                    if let Some(v) = v.as_i64() {
+                        ColValue::LongLong(v)
+                    } else if let Some(v) = v.as_u64() {
+                        ColValue::UnsignedLongLong(v)
+                    } else {
+                        ColValue::Double(v.as_f64().unwrap_or_default())
+                    }
+                }
+                serde_json::Value::String(v) => ColValue::String(v),
+                v => ColValue::String(v.to_string()),
+            };
+            col_values.insert(col, col_value);
+        }
+        Some(col_values)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::meta::redis::redis_object::{HashObject, RedisString};
+
+    fn new_mapping(key: &str, value_format: RedisValueFormat, ttl_col: &str) -> RedisRdbMapping {
+        let mut mapping = RedisRdbMapping {
+            db: "test_db".into(),
+            tb: "tb_1".into(),
+            key: key.into(),
+            value_format,
+            ttl_col: ttl_col.into(),
+            cols: vec![],
+            key_parts: vec![],
+        };
+        mapping.parse().unwrap();
+        mapping
+    }
+
+    fn new_row(row_type: RowType, before: &[(&str, &str)], after: &[(&str, &str)]) -> RowData {
+        let to_map = |cols: &[(&str, &str)]| {
+            if cols.is_empty() {
+                return None;
+            }
+            Some(
+                cols.iter()
+                    .map(|(k, v)| (k.to_string(), ColValue::String(v.to_string())))
+                    .collect::<HashMap<_, _>>(),
+            )
+        };
+        RowData::new(
+            "test_db".into(),
+            "tb_1".into(),
+            row_type,
+            to_map(before),
+            to_map(after),
+        )
+    }
+
+    #[test]
+    fn test_parse_key_template() {
+        let mapping = new_mapping("user:{tenant_id}:{id}", RedisValueFormat::Hash, "");
+        assert_eq!(
+            mapping.key_parts,
+            vec![
+                KeyPart::Text("user:".into()),
+                KeyPart::Col("tenant_id".into()),
+                KeyPart::Text(":".into()),
+                KeyPart::Col("id".into()),
+            ]
+        );
+
+        let key_cols = mapping.parse_key("user:t1:100").unwrap();
+        assert_eq!(key_cols["tenant_id"], "t1");
+        assert_eq!(key_cols["id"], "100");
+        assert!(mapping.parse_key("order:t1:100").is_none());
+        assert!(mapping.parse_key("user::100").is_none());
+
+        for key in ["user:{id}{name}", "user:{id", "user:{}", "user"] {
+            let mut mapping = mapping.clone();
+            mapping.key = key.into();
+            assert!(mapping.parse().is_err());
+        }
+    }
+
+    #[test]
+    fn test_to_redis_cmds() {
+        let mapping = new_mapping("user:{tenant_id}:{id}", RedisValueFormat::Hash, "ttl");
+        let row = new_row(
+            RowType::Insert,
+            &[],
+            &[
+                ("tenant_id", "t1"),
+                ("id", "1"),
+                ("name", "a"),
+                ("ttl", "60"),
+            ],
+        );
+        let cmds: Vec<String> = mapping
+            .to_redis_cmds(&row)
+            .iter()
+            .map(|i| i.to_string())
+            .collect();
+        assert_eq!(
+            cmds,
+            vec![
+                "HSET user:t1:1 id 1 name a tenant_id t1 ttl 60",
+                "EXPIRE user:t1:1 60"
+            ]
+        );
+
+        // key changed
+        let mut mapping = new_mapping("user:{tenant_id}:{id}", RedisValueFormat::Json, "");
+        mapping.cols = vec!["name".into()];
+        let row = new_row(
+            RowType::Update,
+            &[("tenant_id", "t1"), ("id", "1"), ("name", "a")],
+            &[("tenant_id", "t2"), ("id", "1"), ("name", "b")],
+        );
+        let cmds: Vec<String> = mapping
+            .to_redis_cmds(&row)
+            .iter()
+            .map(|i| i.to_string())
+            .collect();
+        assert_eq!(cmds, vec!["DEL user:t1:1", r#"SET user:t2:1 {"name":"b"}"#]);
+
+        let row = new_row(RowType::Delete, &[("tenant_id", "t1"), ("id", "1")], &[]);
+        let cmds = mapping.to_redis_cmds(&row);
+        assert_eq!(cmds[0].to_string(), "DEL user:t1:1");
+
+        // key column is null
+        let row = new_row(RowType::Delete, &[("id", "1")], &[]);
+        assert!(mapping.to_redis_cmds(&row).is_empty());
+    }
+
+    #[test]
+    fn test_to_row_data() {
+        let mapping = new_mapping("user:{id}", RedisValueFormat::Hash, "");
+        let key_cols = mapping.parse_key("user:1").unwrap();
+
+        let mut hash = HashObject::new();
+        hash.value.insert(
+            RedisString::from("name".to_string()),
+            (RedisString::from("a".to_string()), None),
+        );
+        let mut entry = RedisEntry::new();
+        entry.is_base = true;
+        entry.value = RedisObject::Hash(hash);
+        let row = mapping.to_row_data(key_cols.clone(), &entry).unwrap();
+        assert_eq!(row.row_type, RowType::Insert);
+        let after = row.after.unwrap();
+        assert_eq!(after["id"], ColValue::String("1".into()));
+        assert_eq!(after["name"], ColValue::String("a".into()));
+
+        let mut entry = RedisEntry::new();
+        entry.cmd = RedisCmd::from_str_args(&["hset", "user:1", "name", "b"]);
+        let row = mapping.to_row_data(key_cols.clone(), &entry).unwrap();
+        assert_eq!(row.row_type, RowType::Insert);
+        assert!(row.before.is_none());
+        let after = row.after.unwrap();
+        assert_eq!(after.len(), 2);
+        assert_eq!(after["id"], ColValue::String("1".into()));
+        assert_eq!(after["name"], ColValue::String("b".into()));
+
+        entry.cmd = RedisCmd::from_str_args(&["hdel", "user:1", "name"]);
+        let row = mapping.to_row_data(key_cols.clone(), &entry).unwrap();
+        assert_eq!(row.row_type, RowType::Update);
+        assert_eq!(row.before.unwrap()["id"], ColValue::String("1".into()));
+        assert_eq!(row.after.unwrap()["name"], ColValue::None);
+
+        entry.cmd = RedisCmd::from_str_args(&["del", "user:1"]);
+        let row = mapping.to_row_data(key_cols.clone(), &entry).unwrap();
+        assert_eq!(row.row_type, RowType::Delete);
+
+        // value format mismatch
+        entry.cmd = RedisCmd::from_str_args(&["set", "user:1", r#"{"name":"c"}"#]);
+        assert!(mapping.to_row_data(key_cols.clone(), &entry).is_none());
+
+        let mapping = new_mapping("user:{id}", RedisValueFormat::Json, "");
+        let row = mapping.to_row_data(key_cols, &entry).unwrap();
+        let after = row.after.unwrap();
+        assert_eq!(after["name"], ColValue::String("c".into()));
+        assert_eq!(after["id"], ColValue::String("1".into()));
+    }
+}
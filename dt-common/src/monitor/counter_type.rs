@@ -37,6 +37,10 @@ pub enum CounterType {
     QueuedRecordCurrent,
     #[strum(serialize = "queued_bytes")]
     QueuedByteCurrent,
+    #[strum(serialize = "pushed_records")]
+    PushedRecordTotal,
+    #[strum(serialize = "pushed_bytes")]
+    PushedByteTotal,
 
     #[strum(serialize = "sinked_records")]
     SinkedRecordTotal,
@@ -44,8 +48,20 @@ pub enum CounterType {
     SinkedByteTotal,
     #[strum(serialize = "ddl_records")]
     DDLRecordTotal,
+    // source commit time of the last sinked position, in millis
     #[strum(serialize = "timestamp")]
     Timestamp,
+    // sink time - source commit time of the last sinked position, in millis
+    #[strum(serialize = "delay")]
+    Delay,
+    // now - source commit time of the committed position, updated by heartbeat, in millis
+    #[strum(serialize = "heartbeat_delay")]
+    HeartbeatDelay,
+    // records missing or different in target, found by checkers
+    #[strum(serialize = "miss_records")]
+    CheckerMissTotal,
+    #[strum(serialize = "diff_records")]
+    CheckerDiffTotal,
 }
 
 #[derive(EnumString, IntoStaticStr, Display, PartialEq, Eq, Hash, Clone)]
@@ -94,8 +110,14 @@ impl CounterType {
             | Self::SinkedByteTotal
             | Self::QueuedRecordCurrent
             | Self::QueuedByteCurrent
+            | Self::PushedRecordTotal
+            | Self::PushedByteTotal
             | Self::DDLRecordTotal
-            | Self::Timestamp => WindowType::NoWindow,
+            | Self::Timestamp
+            | Self::Delay
+            | Self::HeartbeatDelay
+            | Self::CheckerMissTotal
+            | Self::CheckerDiffTotal => WindowType::NoWindow,
         }
     }
 
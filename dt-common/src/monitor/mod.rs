@@ -5,11 +5,15 @@ pub mod counter_type;
 pub mod group_monitor;
 pub mod task_metrics;
 pub mod task_monitor;
+pub mod tb_progress;
+pub mod trace_span;
 
 #[allow(clippy::module_inception)]
 pub mod monitor;
 pub mod time_window_counter;
 
+#[cfg(feature = "otel")]
+pub mod otel_exporter;
 #[cfg(feature = "metrics")]
 pub mod prometheus_metrics;
 
@@ -15,6 +15,8 @@ pub struct Monitor {
     pub description: String,
     pub no_window_counters: DashMap<CounterType, Counter>,
     pub time_window_counters: DashMap<CounterType, TimeWindowCounter>,
+    // no window counters of each (schema, tb)
+    pub tb_no_window_counters: DashMap<(String, String), DashMap<CounterType, Counter>>,
     pub time_window_secs: u64,
     pub max_sub_count: u64,
     pub count_window: u64,
@@ -40,6 +42,7 @@ impl Monitor {
             description: description.into(),
             no_window_counters: DashMap::new(),
             time_window_counters: DashMap::new(),
+            tb_no_window_counters: DashMap::new(),
             time_window_secs,
             max_sub_count,
             count_window,
@@ -102,6 +105,30 @@ impl Monitor {
         self
     }
 
+    pub fn set_tb_counter(
+        &self,
+        schema: &str,
+        tb: &str,
+        counter_type: CounterType,
+        value: u64,
+    ) -> &Self {
+        if let WindowType::NoWindow = counter_type.get_window_type() {
+            self.tb_no_window_counters
+                .entry((schema.to_string(), tb.to_string()))
+                .or_default()
+                .entry(counter_type)
+                .and_modify(|counter| counter.set(value, 1))
+                .or_insert_with(|| Counter::new(value, 1));
+        }
+        self
+    }
+
+    /// Removes counters of a dropped table, or all tables of the schema if tb is empty
+    pub fn remove_tb_counters(&self, schema: &str, tb: &str) {
+        self.tb_no_window_counters
+            .retain(|(i_schema, i_tb), _| !(i_schema == schema && (tb.is_empty() || i_tb == tb)));
+    }
+
     pub fn add_multi_counter(
         &self,
         counter_type: CounterType,
@@ -0,0 +1,184 @@
+use std::{collections::BTreeMap, sync::Arc, time::Duration};
+
+use dashmap::DashMap;
+use opentelemetry::{global, metrics::Gauge, KeyValue};
+use opentelemetry_otlp::{MetricExporter, SpanExporter, WithExportConfig};
+use opentelemetry_sdk::{
+    metrics::{PeriodicReader, SdkMeterProvider},
+    runtime,
+    trace::TracerProvider,
+    Resource,
+};
+
+use super::{task_metrics::TaskMetricsType, trace_span::TRACER_NAME};
+use crate::{config::otel_config::OtelConfig, log_info, log_warn};
+
+/// Exports spans and task metrics to an OTLP collector by grpc
+pub struct OtelExporter {
+    tracer_provider: TracerProvider,
+    meter_provider: SdkMeterProvider,
+}
+
+impl OtelExporter {
+    pub fn init(config: &OtelConfig) -> anyhow::Result<Self> {
+        let resource = Resource::new(vec![KeyValue::new(
+            "service.name",
+            config.service_name.clone(),
+        )]);
+
+        let span_exporter = SpanExporter::builder()
+            .with_tonic()
+            .with_endpoint(&config.endpoint)
+            .build()?;
+        let tracer_provider = TracerProvider::builder()
+            .with_batch_exporter(span_exporter, runtime::Tokio)
+            .with_resource(resource.clone())
+            .build();
+
+        let metric_exporter = MetricExporter::builder()
+            .with_tonic()
+            .with_endpoint(&config.endpoint)
+            .build()?;
+        let reader = PeriodicReader::builder(metric_exporter, runtime::Tokio)
+            .with_interval(Duration::from_secs(config.export_interval_secs))
+            .build();
+        let meter_provider = SdkMeterProvider::builder()
+            .with_reader(reader)
+            .with_resource(resource)
+            .build();
+
+        global::set_tracer_provider(tracer_provider.clone());
+        global::set_meter_provider(meter_provider.clone());
+        log_info!("otel exporter started, endpoint: {}", config.endpoint);
+
+        Ok(Self {
+            tracer_provider,
+            meter_provider,
+        })
+    }
+
+    /// Flushes the pending spans and metrics before the task exits
+    pub fn shutdown(&self) {
+        if let Err(err) = self.tracer_provider.shutdown() {
+            log_warn!("failed to shutdown otel tracer provider, error: {}", err);
+        }
+        if let Err(err) = self.meter_provider.shutdown() {
+            log_warn!("failed to shutdown otel meter provider, error: {}", err);
+        }
+    }
+}
+
+/// Task metrics recorded by otel gauges, named the same as prometheus metrics,
+/// does nothing if OtelExporter is not initialized
+#[derive(Clone, Default)]
+pub struct OtelMetrics {
+    gauges: Arc<DashMap<TaskMetricsType, Gauge<f64>>>,
+    // metrics of each table, with attributes: schema, tb
+    tb_gauges: Arc<DashMap<TaskMetricsType, Gauge<f64>>>,
+}
+
+impl OtelMetrics {
+    pub fn set_metrics(&self, metrics: &BTreeMap<TaskMetricsType, u64>) {
+        for (metrics_type, value) in metrics.iter() {
+            self.gauges
+                .entry(*metrics_type)
+                .or_insert_with(|| Self::build_gauge(Self::get_name(metrics_type)))
+                .record(*value as f64, &[]);
+        }
+    }
+
+    pub fn set_tb_metrics(
+        &self,
+        tb_metrics: &BTreeMap<(String, String), BTreeMap<TaskMetricsType, u64>>,
+    ) {
+        for ((schema, tb), metrics) in tb_metrics.iter() {
+            let attributes = [
+                KeyValue::new("schema", schema.clone()),
+                KeyValue::new("tb", tb.clone()),
+            ];
+            for (metrics_type, value) in metrics.iter() {
+                self.tb_gauges
+                    .entry(*metrics_type)
+                    .or_insert_with(|| Self::build_gauge(Self::get_tb_name(metrics_type)))
+                    .record(*value as f64, &attributes);
+            }
+        }
+    }
+
+    fn build_gauge(name: String) -> Gauge<f64> {
+        global::meter(TRACER_NAME).f64_gauge(name).build()
+    }
+
+    fn get_name(metrics_type: &TaskMetricsType) -> String {
+        // the serialized name, such as: sinker_rps_avg
+        serde_json::to_value(metrics_type)
+            .ok()
+            .and_then(|v| v.as_str().map(|s| s.to_string()))
+            .unwrap_or_else(|| metrics_type.to_string())
+    }
+
+    fn get_tb_name(metrics_type: &TaskMetricsType) -> String {
+        let name = match metrics_type {
+            TaskMetricsType::ExtractorPlanRecords => "plan_records".to_string(),
+            TaskMetricsType::ExtractorPushedRecords => "extracted_records".to_string(),
+            TaskMetricsType::ExtractorPushedBytes => "extracted_bytes".to_string(),
+            TaskMetricsType::SinkerSinkedRecords => "sinked_records".to_string(),
+            TaskMetricsType::SinkerSinkedBytes => "sinked_bytes".to_string(),
+            _ => Self::get_name(metrics_type),
+        };
+        format!("tb_{}", name)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_get_name() {
+        assert_eq!(
+            OtelMetrics::get_name(&TaskMetricsType::SinkerRpsAvg),
+            "sinker_rps_avg"
+        );
+        assert_eq!(
+            OtelMetrics::get_name(&TaskMetricsType::DelayToNow),
+            "delay_to_now"
+        );
+    }
+
+    #[test]
+    fn test_get_tb_name() {
+        // the same as the table metrics of prometheus
+        let cases = [
+            (TaskMetricsType::ExtractorPlanRecords, "tb_plan_records"),
+            (
+                TaskMetricsType::ExtractorPushedRecords,
+                "tb_extracted_records",
+            ),
+            (TaskMetricsType::ExtractorPushedBytes, "tb_extracted_bytes"),
+            (TaskMetricsType::SinkerSinkedRecords, "tb_sinked_records"),
+            (TaskMetricsType::SinkerSinkedBytes, "tb_sinked_bytes"),
+            (TaskMetricsType::StartTime, "tb_start_time"),
+            (TaskMetricsType::FinishTime, "tb_finish_time"),
+            (TaskMetricsType::Eta, "tb_eta"),
+            (TaskMetricsType::Timestamp, "tb_timestamp"),
+            (TaskMetricsType::Delay, "tb_delay"),
+            (TaskMetricsType::DelayToNow, "tb_delay_to_now"),
+        ];
+        for (metrics_type, name) in cases {
+            assert_eq!(OtelMetrics::get_tb_name(&metrics_type), name);
+        }
+    }
+
+    #[test]
+    fn test_set_metrics_without_exporter() {
+        let otel_metrics = OtelMetrics::default();
+        otel_metrics.set_metrics(&BTreeMap::from([(TaskMetricsType::Delay, 10)]));
+        otel_metrics.set_tb_metrics(&BTreeMap::from([(
+            ("db_1".to_string(), "tb_1".to_string()),
+            BTreeMap::from([(TaskMetricsType::Delay, 10), (TaskMetricsType::Eta, 5)]),
+        )]));
+        assert_eq!(otel_metrics.gauges.len(), 1);
+        assert_eq!(otel_metrics.tb_gauges.len(), 2);
+    }
+}
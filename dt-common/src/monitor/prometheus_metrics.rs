@@ -3,7 +3,7 @@ use std::{collections::BTreeMap, sync::Arc};
 
 use actix_web::{middleware::Logger, web, App, HttpResponse, HttpServer, Responder, Result};
 use dashmap::DashMap;
-use prometheus::{Gauge, Opts, Registry, TextEncoder};
+use prometheus::{Gauge, GaugeVec, Opts, Registry, TextEncoder};
 
 use crate::config::config_enums::TaskType;
 use crate::config::metrics_config::MetricsConfig;
@@ -12,6 +12,8 @@ use crate::monitor::task_metrics::TaskMetricsType;
 pub struct PrometheusMetrics {
     registry: Arc<Registry>,
     metrics: DashMap<TaskMetricsType, Gauge>,
+    // metrics of each table, labeled by schema and tb
+    tb_metrics: DashMap<TaskMetricsType, GaugeVec>,
     task_type: Option<TaskType>,
     config: MetricsConfig,
 }
@@ -21,6 +23,7 @@ impl PrometheusMetrics {
         Self {
             registry: Arc::new(Registry::new()),
             metrics: DashMap::new(),
+            tb_metrics: DashMap::new(),
             task_type,
             config,
         }
@@ -38,6 +41,18 @@ impl PrometheusMetrics {
                 self.registry.register(Box::new(metrics.clone())).unwrap();
                 self.metrics.insert(metrics_type, metrics);
             };
+        let register_tb_handler =
+            |metrics_name: &str, metrics_desc: &str, metrics_type: TaskMetricsType| {
+                let metrics = GaugeVec::new(
+                    Opts::new(metrics_name, metrics_desc)
+                        .const_labels(self.config.metrics_labels.to_owned()),
+                    &["schema", "tb"],
+                )
+                .unwrap();
+
+                self.registry.register(Box::new(metrics.clone())).unwrap();
+                self.tb_metrics.insert(metrics_type, metrics);
+            };
 
         // TODO: support these metrics:
         // register_handler(
@@ -179,20 +194,102 @@ impl PrometheusMetrics {
                         "the records estimated by extractor plan",
                         TaskMetricsType::ExtractorPlanRecords,
                     );
+                    register_tb_handler(
+                        "tb_plan_records",
+                        "the records of table estimated by extractor plan",
+                        TaskMetricsType::ExtractorPlanRecords,
+                    );
+                    register_tb_handler(
+                        "tb_extracted_records",
+                        "the records of table pushed by extractor",
+                        TaskMetricsType::ExtractorPushedRecords,
+                    );
+                    register_tb_handler(
+                        "tb_extracted_bytes",
+                        "the bytes of table pushed by extractor",
+                        TaskMetricsType::ExtractorPushedBytes,
+                    );
+                    register_tb_handler(
+                        "tb_sinked_records",
+                        "the records of table sinked",
+                        TaskMetricsType::SinkerSinkedRecords,
+                    );
+                    register_tb_handler(
+                        "tb_sinked_bytes",
+                        "the bytes of table sinked",
+                        TaskMetricsType::SinkerSinkedBytes,
+                    );
+                    register_tb_handler(
+                        "tb_start_time",
+                        "the time when the table started, the unit is millisecond",
+                        TaskMetricsType::StartTime,
+                    );
+                    register_tb_handler(
+                        "tb_finish_time",
+                        "the time when the table finished, 0 if not finished, the unit is millisecond",
+                        TaskMetricsType::FinishTime,
+                    );
+                    register_tb_handler(
+                        "tb_eta",
+                        "the estimated time left of table, the unit is second",
+                        TaskMetricsType::Eta,
+                    );
                 }
                 TaskType::Cdc => {
                     register_handler(
                         "timestamp",
-                        "the timestamp of task",
+                        "the source commit time of the last sinked position, the unit is millisecond",
+                        TaskMetricsType::Timestamp,
+                    );
+                    register_handler(
+                        "delay",
+                        "the sink time - source commit time of the last sinked position, the unit is millisecond",
+                        TaskMetricsType::Delay,
+                    );
+                    register_handler(
+                        "delay_to_now",
+                        "now - source commit time of the last sinked position, the unit is millisecond",
+                        TaskMetricsType::DelayToNow,
+                    );
+                    register_handler(
+                        "heartbeat_delay",
+                        "now - source commit time of the committed position, updated by heartbeat, the unit is millisecond",
+                        TaskMetricsType::HeartbeatDelay,
+                    );
+                    register_tb_handler(
+                        "tb_timestamp",
+                        "the source commit time of the last sinked record of table, the unit is millisecond",
                         TaskMetricsType::Timestamp,
                     );
+                    register_tb_handler(
+                        "tb_delay",
+                        "the sink time - source commit time of the last sinked record of table, the unit is millisecond",
+                        TaskMetricsType::Delay,
+                    );
+                    register_tb_handler(
+                        "tb_delay_to_now",
+                        "now - source commit time of the last sinked record of table, the unit is millisecond",
+                        TaskMetricsType::DelayToNow,
+                    );
                     register_handler(
                         "sinker_ddl_count",
                         "the count of DDL operations",
                         TaskMetricsType::SinkerDdlCount,
                     );
                 }
-                TaskType::Struct | TaskType::Check => {}
+                TaskType::Check => {
+                    register_handler(
+                        "checker_miss_count",
+                        "the count of records missing in target, found by checker",
+                        TaskMetricsType::CheckerMissCount,
+                    );
+                    register_handler(
+                        "checker_diff_count",
+                        "the count of records different in target, found by checker",
+                        TaskMetricsType::CheckerDiffCount,
+                    );
+                }
+                TaskType::Struct => {}
             }
         }
         self
@@ -206,6 +303,29 @@ impl PrometheusMetrics {
         }
     }
 
+    pub fn set_tb_metrics(
+        &self,
+        tb_metrics: &BTreeMap<(String, String), BTreeMap<TaskMetricsType, u64>>,
+    ) {
+        for ((schema, tb), metrics) in tb_metrics.iter() {
+            for (metrics_type, value) in metrics.iter() {
+                if let Some(metrics) = self.tb_metrics.get(metrics_type) {
+                    metrics
+                        .with_label_values(&[schema.as_str(), tb.as_str()])
+                        .set(*value as f64);
+                }
+            }
+        }
+    }
+
+    /// Removes label sets of a table which no longer reports metrics
+    pub fn remove_tb_metrics(&self, schema: &str, tb: &str) {
+        for metrics in self.tb_metrics.iter() {
+            // not found if the metrics was never set for the table
+            let _ = metrics.remove_label_values(&[schema, tb]);
+        }
+    }
+
     pub async fn start_metrics(&self) -> tokio::task::JoinHandle<Result<(), std::io::Error>> {
         let registry = self.registry.clone();
         let addr = format!("{}:{}", self.config.http_host, self.config.http_port);
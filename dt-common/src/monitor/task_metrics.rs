@@ -1,4 +1,4 @@
-use serde::Serialize;
+use serde::{Deserialize, Serialize};
 use strum::{Display, EnumString, IntoStaticStr};
 
 #[derive(
@@ -14,12 +14,21 @@ use strum::{Display, EnumString, IntoStaticStr};
     Copy,
     Debug,
     Serialize,
+    Deserialize,
 )]
 #[serde(rename_all = "snake_case")]
 pub enum TaskMetricsType {
-    // TODO:
+    // all in millis, see CounterType for details
     Delay,
     Timestamp,
+    // now - Timestamp, keeps growing if nothing is received from the source
+    DelayToNow,
+    HeartbeatDelay,
+
+    // snapshot progress of each table, times in millis, eta in seconds
+    StartTime,
+    FinishTime,
+    Eta,
 
     // TODO: These metrics describe the records and bytes pulled by extractor, different from ExtractorPushed*, which describe the overall traffic before filtering
     ExtractorRpsMax,
@@ -30,6 +39,8 @@ pub enum TaskMetricsType {
     ExtractorBpsAvg,
 
     ExtractorPlanRecords,
+    ExtractorPushedRecords,
+    ExtractorPushedBytes,
 
     ExtractorPushedRpsMax,
     ExtractorPushedRpsMin,
@@ -58,4 +69,8 @@ pub enum TaskMetricsType {
     SinkerSinkedBytes,
 
     SinkerDdlCount,
+
+    // records found missing or different by checkers, accumulated over all tables
+    CheckerMissCount,
+    CheckerDiffCount,
 }
@@ -1,15 +1,25 @@
-use std::{collections::BTreeMap, sync::Arc};
+use std::{collections::BTreeMap, fs, sync::Arc};
 
 use async_trait::async_trait;
-use dashmap::DashMap;
+use chrono::Utc;
+use dashmap::{DashMap, DashSet};
+use serde_json::json;
 
 use super::monitor::Monitor;
+#[cfg(feature = "otel")]
+use crate::monitor::otel_exporter::OtelMetrics;
 #[cfg(feature = "metrics")]
 use crate::monitor::prometheus_metrics::PrometheusMetrics;
 use crate::{
+    alert::alert_manager::AlertManager,
     config::config_enums::TaskType,
-    log_task,
-    monitor::{counter_type::CounterType, task_metrics::TaskMetricsType, FlushableMonitor},
+    log_task, log_warn,
+    monitor::{
+        counter_type::CounterType,
+        task_metrics::TaskMetricsType,
+        tb_progress::{TbProgress, TbProgressStatus},
+        FlushableMonitor,
+    },
 };
 
 #[derive(Clone)]
@@ -21,6 +31,15 @@ pub struct TaskMonitor {
     sinkers: DashMap<String, Arc<Monitor>>,
 
     no_window_metrics_map: DashMap<TaskMetricsType, u64>,
+    // snapshot progress of each table, keyed by single task id
+    tb_progresses: DashMap<String, TbProgress>,
+    progress_file: Option<String>,
+    log_tb_metrics: bool,
+    // tables reported by the last flush of table metrics
+    reported_tbs: DashSet<(String, String)>,
+    alert_manager: Arc<AlertManager>,
+    #[cfg(feature = "otel")]
+    otel_metrics: OtelMetrics,
     #[cfg(feature = "metrics")]
     pub prometheus_metrics: Arc<PrometheusMetrics>,
 }
@@ -53,30 +72,76 @@ impl FlushableMonitor for TaskMonitor {
             log_task!("{}", serde_json::to_string(&metrics).unwrap());
             #[cfg(feature = "metrics")]
             self.prometheus_metrics.set_metrics(&metrics);
+            #[cfg(feature = "otel")]
+            self.otel_metrics.set_metrics(&metrics);
+            self.alert_manager.check_metrics(&metrics);
         }
+
+        let tb_metrics = self.calc_tb_metrics();
+        if self.log_tb_metrics {
+            for ((schema, tb), metrics) in tb_metrics.iter() {
+                log_task!(
+                    schema = schema.as_str(), tb = tb.as_str(), payload:serde = metrics;
+                    "{}",
+                    json!({"schema": schema, "tb": tb, "metrics": metrics})
+                );
+            }
+        }
+        self.remove_finished_tbs(&tb_metrics);
+        #[cfg(feature = "metrics")]
+        self.prometheus_metrics.set_tb_metrics(&tb_metrics);
+        #[cfg(feature = "otel")]
+        self.otel_metrics.set_tb_metrics(&tb_metrics);
+
+        self.flush_tb_progresses();
     }
 }
 
 impl TaskMonitor {
     #[cfg(not(feature = "metrics"))]
-    pub fn new(task_type: Option<TaskType>) -> Self {
+    pub fn new(
+        task_type: Option<TaskType>,
+        progress_file: Option<String>,
+        log_tb_metrics: bool,
+        alert_manager: Arc<AlertManager>,
+    ) -> Self {
         Self {
             task_type,
             extractors: DashMap::new(),
             pipelines: DashMap::new(),
             sinkers: DashMap::new(),
             no_window_metrics_map: DashMap::new(),
+            tb_progresses: DashMap::new(),
+            progress_file,
+            log_tb_metrics,
+            reported_tbs: DashSet::new(),
+            alert_manager,
+            #[cfg(feature = "otel")]
+            otel_metrics: OtelMetrics::default(),
         }
     }
 
     #[cfg(feature = "metrics")]
-    pub fn new(task_type: Option<TaskType>, prometheus_metrics: Arc<PrometheusMetrics>) -> Self {
+    pub fn new(
+        task_type: Option<TaskType>,
+        progress_file: Option<String>,
+        log_tb_metrics: bool,
+        alert_manager: Arc<AlertManager>,
+        prometheus_metrics: Arc<PrometheusMetrics>,
+    ) -> Self {
         Self {
             task_type,
             extractors: DashMap::new(),
             pipelines: DashMap::new(),
             sinkers: DashMap::new(),
             no_window_metrics_map: DashMap::new(),
+            tb_progresses: DashMap::new(),
+            progress_file,
+            log_tb_metrics,
+            reported_tbs: DashSet::new(),
+            alert_manager,
+            #[cfg(feature = "otel")]
+            otel_metrics: OtelMetrics::default(),
             prometheus_metrics,
         }
     }
@@ -120,10 +185,20 @@ impl TaskMonitor {
                 }
                 MonitorType::Sinker => {
                     if let Some((_, monitor)) = self.sinkers.remove(task_id) {
+                        // checker counts of finished single tasks are kept and accumulated
+                        for (metrics_type, value) in get_checker_metrics(&monitor) {
+                            self.add_no_window_metrics(metrics_type, value);
+                        }
                         calc_monitors.push((MonitorType::Sinker, monitor.clone()));
                     };
                 }
-                _ => {}
+                MonitorType::Pipeline => {
+                    // pipelines are kept since sinked counts are accumulated from them,
+                    // only the table metrics of the finished single task are removed
+                    if let Some(monitor) = self.pipelines.get(task_id) {
+                        monitor.tb_no_window_counters.clear();
+                    }
+                }
             }
         }
         calc_nowindow_metrics(&self.no_window_metrics_map, calc_monitors);
@@ -136,6 +211,127 @@ impl TaskMonitor {
             .or_insert(value);
     }
 
+    pub fn init_tb_progress(&self, task_id: &str, schema: &str, tb: &str, plan_records: u64) {
+        if self.task_type.is_none() {
+            return;
+        }
+        self.tb_progresses.insert(
+            task_id.to_string(),
+            TbProgress::new(schema, tb, plan_records),
+        );
+    }
+
+    pub fn start_tb_progress(&self, task_id: &str) {
+        if let Some(mut progress) = self.tb_progresses.get_mut(task_id) {
+            progress.start();
+        }
+    }
+
+    /// Should be called before the monitors of the single task are unregistered
+    pub fn finish_tb_progress(&self, task_id: &str) {
+        self.update_tb_progress(task_id);
+        if let Some(mut progress) = self.tb_progresses.get_mut(task_id) {
+            progress.finish();
+        }
+    }
+
+    /// Called when the single task errors, the table will be extracted again when resuming
+    pub fn fail_tb_progress(&self, task_id: &str) {
+        self.update_tb_progress(task_id);
+        if let Some(mut progress) = self.tb_progresses.get_mut(task_id) {
+            progress.fail();
+        }
+    }
+
+    fn update_tb_progress(&self, task_id: &str) {
+        let get_counter = |monitors: &DashMap<String, Arc<Monitor>>, counter_type: CounterType| {
+            monitors
+                .get(task_id)
+                .and_then(|monitor| {
+                    monitor
+                        .no_window_counters
+                        .get(&counter_type)
+                        .map(|counter| counter.value)
+                })
+                .unwrap_or_default()
+        };
+        let extracted_records = get_counter(&self.extractors, CounterType::PushedRecordTotal);
+        let extracted_bytes = get_counter(&self.extractors, CounterType::PushedByteTotal);
+        let sinked_records = get_counter(&self.pipelines, CounterType::SinkedRecordTotal);
+        let sinked_bytes = get_counter(&self.pipelines, CounterType::SinkedByteTotal);
+
+        if let Some(mut progress) = self.tb_progresses.get_mut(task_id) {
+            progress.update(
+                extracted_records,
+                extracted_bytes,
+                sinked_records,
+                sinked_bytes,
+            );
+        }
+    }
+
+    fn flush_tb_progresses(&self) {
+        if self.tb_progresses.is_empty() {
+            return;
+        }
+
+        let running_task_ids: Vec<String> = self
+            .tb_progresses
+            .iter()
+            .filter(|item| item.value().status == TbProgressStatus::Running)
+            .map(|item| item.key().clone())
+            .collect();
+        for task_id in running_task_ids.iter() {
+            self.update_tb_progress(task_id);
+        }
+
+        let mut progresses: Vec<TbProgress> = self
+            .tb_progresses
+            .iter()
+            .map(|item| item.value().clone())
+            .collect();
+        progresses.sort_by(|a, b| (&a.schema, &a.tb).cmp(&(&b.schema, &b.tb)));
+
+        #[cfg(any(feature = "metrics", feature = "otel"))]
+        {
+            let tb_metrics: BTreeMap<_, _> = progresses
+                .iter()
+                .map(|p| ((p.schema.clone(), p.tb.clone()), p.to_metrics()))
+                .collect();
+            #[cfg(feature = "metrics")]
+            self.prometheus_metrics.set_tb_metrics(&tb_metrics);
+            #[cfg(feature = "otel")]
+            self.otel_metrics.set_tb_metrics(&tb_metrics);
+        }
+
+        if let Some(progress_file) = &self.progress_file {
+            let count_status =
+                |status: TbProgressStatus| progresses.iter().filter(|p| p.status == status).count();
+            let progress = json!({
+                "update_time": Utc::now().timestamp_millis(),
+                "tb_count": progresses.len(),
+                "pending_tb_count": count_status(TbProgressStatus::Pending),
+                "running_tb_count": count_status(TbProgressStatus::Running),
+                "finished_tb_count": count_status(TbProgressStatus::Finished),
+                "failed_tb_count": count_status(TbProgressStatus::Failed),
+                "plan_records": progresses.iter().map(|p| p.plan_records).sum::<u64>(),
+                "sinked_records": progresses.iter().map(|p| p.sinked_records).sum::<u64>(),
+                "tbs": progresses,
+            });
+            // write to a temp file and rename it, so readers never see a partial file
+            let tmp_file = format!("{}.tmp", progress_file);
+            if let Err(err) = fs::write(&tmp_file, progress.to_string())
+                .and_then(|_| fs::rename(&tmp_file, progress_file))
+            {
+                log_warn!(
+                    "failed to write progress file: {}, error: {}",
+                    progress_file,
+                    err
+                );
+            }
+        }
+    }
+
     fn calc(&self) -> Option<BTreeMap<TaskMetricsType, u64>> {
         self.task_type.as_ref()?;
         let mut metrics: BTreeMap<TaskMetricsType, u64> = BTreeMap::new();
@@ -341,17 +537,118 @@ impl TaskMonitor {
             self.prometheus_metrics.set_metrics(&metrics);
         }
 
+        for item in self.sinkers.iter() {
+            for (metrics_type, value) in get_checker_metrics(item.value()) {
+                *metrics.entry(metrics_type).or_default() += value;
+            }
+        }
+
+        if let Some(delay_to_now) = calc_delay_to_now(&metrics) {
+            metrics.insert(TaskMetricsType::DelayToNow, delay_to_now);
+        }
+
         Some(metrics)
     }
 
+    /// Delays of each table, only for cdc tasks whose positions carry source commit time
+    fn calc_tb_metrics(&self) -> BTreeMap<(String, String), BTreeMap<TaskMetricsType, u64>> {
+        let mut tb_metrics: BTreeMap<(String, String), BTreeMap<TaskMetricsType, u64>> =
+            BTreeMap::new();
+        if self.task_type.is_none() {
+            return tb_metrics;
+        }
+
+        for item in self.pipelines.iter() {
+            for tb_item in item.value().tb_no_window_counters.iter() {
+                let metrics = tb_metrics.entry(tb_item.key().clone()).or_default();
+                for counter in tb_item.value().iter() {
+                    let metrics_type = match counter.key() {
+                        CounterType::Timestamp => TaskMetricsType::Timestamp,
+                        CounterType::Delay => TaskMetricsType::Delay,
+                        _ => continue,
+                    };
+                    let value = counter.value().value;
+                    metrics
+                        .entry(metrics_type)
+                        .and_modify(|v| *v = (*v).max(value))
+                        .or_insert(value);
+                }
+            }
+        }
+
+        for metrics in tb_metrics.values_mut() {
+            if let Some(delay_to_now) = calc_delay_to_now(metrics) {
+                metrics.insert(TaskMetricsType::DelayToNow, delay_to_now);
+            }
+        }
+        tb_metrics
+    }
+
+    /// Tables not reported any more, e.g. dropped, or their single tasks finished
+    fn remove_finished_tbs(
+        &self,
+        tb_metrics: &BTreeMap<(String, String), BTreeMap<TaskMetricsType, u64>>,
+    ) {
+        let finished_tbs: Vec<(String, String)> = self
+            .reported_tbs
+            .iter()
+            .filter(|item| !tb_metrics.contains_key(item.key()))
+            .map(|item| item.key().clone())
+            .collect();
+        for schema_tb in finished_tbs {
+            #[cfg(feature = "metrics")]
+            self.prometheus_metrics
+                .remove_tb_metrics(&schema_tb.0, &schema_tb.1);
+            self.reported_tbs.remove(&schema_tb);
+        }
+
+        for schema_tb in tb_metrics.keys() {
+            self.reported_tbs.insert(schema_tb.clone());
+        }
+    }
+
     fn reset_before_calc(&self) {
         self.no_window_metrics_map
             .remove(&TaskMetricsType::PipelineQueueSize);
         self.no_window_metrics_map
             .remove(&TaskMetricsType::PipelineQueueBytes);
+        self.no_window_metrics_map.remove(&TaskMetricsType::Delay);
+        self.no_window_metrics_map
+            .remove(&TaskMetricsType::HeartbeatDelay);
     }
 }
 
+fn calc_delay_to_now(metrics: &BTreeMap<TaskMetricsType, u64>) -> Option<u64> {
+    match metrics.get(&TaskMetricsType::Timestamp) {
+        Some(timestamp) if *timestamp > 0 => {
+            let now = Utc::now().timestamp_millis() as u64;
+            Some(now.saturating_sub(*timestamp))
+        }
+        _ => None,
+    }
+}
+
+fn get_checker_metrics(monitor: &Monitor) -> Vec<(TaskMetricsType, u64)> {
+    [
+        (
+            CounterType::CheckerMissTotal,
+            TaskMetricsType::CheckerMissCount,
+        ),
+        (
+            CounterType::CheckerDiffTotal,
+            TaskMetricsType::CheckerDiffCount,
+        ),
+    ]
+    .into_iter()
+    .filter_map(|(counter_type, metrics_type)| {
+        monitor
+            .no_window_counters
+            .get(&counter_type)
+            .map(|counter| (metrics_type, counter.value))
+    })
+    .collect()
+}
+
 fn calc_nowindow_metrics(
     result_map: &DashMap<TaskMetricsType, u64>,
     calc_monitors: Vec<(MonitorType, Arc<Monitor>)>,
@@ -397,7 +694,14 @@ fn calc_nowindow_metrics(
 
     for (monitor_type, monitor) in calc_monitors {
         match monitor_type {
-            MonitorType::Extractor => {}
+            MonitorType::Extractor => {
+                metric_handler(
+                    &monitor,
+                    CounterType::HeartbeatDelay,
+                    TaskMetricsType::HeartbeatDelay,
+                    CalcType::Max,
+                );
+            }
             MonitorType::Sinker => {}
             MonitorType::Pipeline => {
                 metric_handler(
@@ -406,6 +710,12 @@ fn calc_nowindow_metrics(
                     TaskMetricsType::Timestamp,
                     CalcType::Max,
                 );
+                metric_handler(
+                    &monitor,
+                    CounterType::Delay,
+                    TaskMetricsType::Delay,
+                    CalcType::Max,
+                );
                 metric_handler(
                     &monitor,
                     CounterType::QueuedRecordCurrent,
@@ -443,3 +753,171 @@ fn calc_nowindow_metrics(
             .or_insert(value);
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::alert_config::AlertConfig;
+    #[cfg(feature = "metrics")]
+    use crate::config::metrics_config::MetricsConfig;
+
+    fn new_alert_manager() -> Arc<AlertManager> {
+        Arc::new(AlertManager::new(&AlertConfig::default(), "task_1").unwrap())
+    }
+
+    #[cfg(not(feature = "metrics"))]
+    fn new_task_monitor(task_type: TaskType, progress_file: Option<String>) -> TaskMonitor {
+        TaskMonitor::new(Some(task_type), progress_file, false, new_alert_manager())
+    }
+
+    #[cfg(feature = "metrics")]
+    fn new_task_monitor(task_type: TaskType, progress_file: Option<String>) -> TaskMonitor {
+        let prometheus_metrics = PrometheusMetrics::new(
+            Some(task_type.clone()),
+            MetricsConfig {
+                http_host: "127.0.0.1".into(),
+                http_port: 0,
+                workers: 1,
+                metrics_labels: Default::default(),
+            },
+        );
+        prometheus_metrics.initialization();
+        TaskMonitor::new(
+            Some(task_type),
+            progress_file,
+            false,
+            new_alert_manager(),
+            Arc::new(prometheus_metrics),
+        )
+    }
+
+    fn register_monitor(
+        task_monitor: &TaskMonitor,
+        task_id: &str,
+        monitor_type: MonitorType,
+    ) -> Arc<Monitor> {
+        let monitor = Arc::new(Monitor::new("monitor", task_id, 10, 100, 10));
+        task_monitor.register(task_id, vec![(monitor_type, monitor.clone())]);
+        monitor
+    }
+
+    fn register_pipeline(task_monitor: &TaskMonitor, task_id: &str) -> Arc<Monitor> {
+        register_monitor(task_monitor, task_id, MonitorType::Pipeline)
+    }
+
+    #[test]
+    fn test_calc_tb_metrics() {
+        let task_monitor = new_task_monitor(TaskType::Cdc, None);
+        let pipeline_1 = register_pipeline(&task_monitor, "task_1");
+        let pipeline_2 = register_pipeline(&task_monitor, "task_2");
+
+        pipeline_1
+            .set_tb_counter("db_1", "tb_1", CounterType::Timestamp, 1000)
+            .set_tb_counter("db_1", "tb_1", CounterType::Delay, 30);
+        pipeline_2
+            .set_tb_counter("db_1", "tb_1", CounterType::Timestamp, 2000)
+            .set_tb_counter("db_1", "tb_1", CounterType::Delay, 20)
+            .set_tb_counter("db_1", "tb_2", CounterType::Timestamp, 3000);
+        // only no window counters are kept for tables
+        pipeline_2.set_tb_counter("db_1", "tb_2", CounterType::RecordCount, 1);
+
+        let tb_metrics = task_monitor.calc_tb_metrics();
+        assert_eq!(tb_metrics.len(), 2);
+
+        // max of all pipelines
+        let metrics = &tb_metrics[&("db_1".to_string(), "tb_1".to_string())];
+        assert_eq!(metrics[&TaskMetricsType::Timestamp], 2000);
+        assert_eq!(metrics[&TaskMetricsType::Delay], 30);
+        let now = Utc::now().timestamp_millis() as u64;
+        assert!(metrics[&TaskMetricsType::DelayToNow] <= now - 2000);
+
+        let metrics = &tb_metrics[&("db_1".to_string(), "tb_2".to_string())];
+        assert_eq!(metrics.len(), 2);
+        assert!(!metrics.contains_key(&TaskMetricsType::Delay));
+    }
+
+    #[test]
+    fn test_remove_finished_tbs() {
+        let task_monitor = new_task_monitor(TaskType::Cdc, None);
+        let pipeline_1 = register_pipeline(&task_monitor, "task_1");
+        let pipeline_2 = register_pipeline(&task_monitor, "task_2");
+        for tb in ["tb_1", "tb_2"] {
+            pipeline_1.set_tb_counter("db_1", tb, CounterType::Timestamp, 1000);
+        }
+        pipeline_1.set_tb_counter("db_2", "tb_1", CounterType::Timestamp, 1000);
+        pipeline_2.set_tb_counter("db_3", "tb_1", CounterType::Timestamp, 1000);
+
+        let tb_metrics = task_monitor.calc_tb_metrics();
+        task_monitor.remove_finished_tbs(&tb_metrics);
+        assert_eq!(task_monitor.reported_tbs.len(), 4);
+
+        // dropped table / database
+        pipeline_1.remove_tb_counters("db_1", "tb_1");
+        pipeline_1.remove_tb_counters("db_2", "");
+        // the single task finished
+        task_monitor.unregister("task_2", vec![MonitorType::Pipeline]);
+
+        let tb_metrics = task_monitor.calc_tb_metrics();
+        assert_eq!(tb_metrics.len(), 1);
+        task_monitor.remove_finished_tbs(&tb_metrics);
+        assert_eq!(task_monitor.reported_tbs.len(), 1);
+        assert!(task_monitor
+            .reported_tbs
+            .contains(&("db_1".to_string(), "tb_2".to_string())));
+    }
+
+    #[test]
+    fn test_tb_progress() {
+        let progress_file = std::env::temp_dir()
+            .join(format!("progress_{}.json", std::process::id()))
+            .to_string_lossy()
+            .to_string();
+        let task_monitor = new_task_monitor(TaskType::Snapshot, Some(progress_file.clone()));
+        for (task_id, tb) in [
+            ("db_1.tb_1", "tb_1"),
+            ("db_1.tb_2", "tb_2"),
+            ("db_1.tb_3", "tb_3"),
+        ] {
+            task_monitor.init_tb_progress(task_id, "db_1", tb, 100);
+        }
+
+        for task_id in ["db_1.tb_1", "db_1.tb_2"] {
+            register_monitor(&task_monitor, task_id, MonitorType::Extractor)
+                .set_counter(CounterType::PushedRecordTotal, 100);
+            register_pipeline(&task_monitor, task_id)
+                .set_counter(CounterType::SinkedRecordTotal, 50);
+            task_monitor.start_tb_progress(task_id);
+        }
+        task_monitor.finish_tb_progress("db_1.tb_1");
+        task_monitor.fail_tb_progress("db_1.tb_2");
+
+        let progress = &task_monitor.tb_progresses;
+        assert_eq!(
+            progress.get("db_1.tb_1").unwrap().status,
+            TbProgressStatus::Finished
+        );
+        assert_eq!(progress.get("db_1.tb_1").unwrap().sinked_records, 50);
+        assert_eq!(
+            progress.get("db_1.tb_2").unwrap().status,
+            TbProgressStatus::Failed
+        );
+        assert_eq!(progress.get("db_1.tb_2").unwrap().extracted_records, 100);
+        assert_eq!(
+            progress.get("db_1.tb_3").unwrap().status,
+            TbProgressStatus::Pending
+        );
+
+        task_monitor.flush_tb_progresses();
+        let content = fs::read_to_string(&progress_file).unwrap();
+        fs::remove_file(&progress_file).unwrap();
+        let progress: serde_json::Value = serde_json::from_str(&content).unwrap();
+        assert_eq!(progress["tb_count"], 3);
+        assert_eq!(progress["pending_tb_count"], 1);
+        assert_eq!(progress["running_tb_count"], 0);
+        assert_eq!(progress["finished_tb_count"], 1);
+        assert_eq!(progress["failed_tb_count"], 1);
+        assert_eq!(progress["plan_records"], 300);
+        assert_eq!(progress["sinked_records"], 100);
+        assert_eq!(progress["tbs"][1]["status"], "failed");
+    }
+}
@@ -0,0 +1,159 @@
+use std::collections::BTreeMap;
+
+use chrono::Utc;
+use serde::Serialize;
+
+use super::task_metrics::TaskMetricsType;
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TbProgressStatus {
+    #[default]
+    Pending,
+    Running,
+    Finished,
+    Failed,
+}
+
+/// Progress of a single table in snapshot tasks, times are in millis
+#[derive(Clone, Debug, Default, Serialize)]
+pub struct TbProgress {
+    pub schema: String,
+    pub tb: String,
+    pub status: TbProgressStatus,
+    // estimated by table statistics, may differ from the actual count
+    pub plan_records: u64,
+    pub extracted_records: u64,
+    pub extracted_bytes: u64,
+    pub sinked_records: u64,
+    pub sinked_bytes: u64,
+    pub start_time: u64,
+    pub finish_time: u64,
+    // seconds left, estimated by the average sink rate since the table started
+    pub eta: Option<u64>,
+}
+
+impl TbProgress {
+    pub fn new(schema: &str, tb: &str, plan_records: u64) -> Self {
+        Self {
+            schema: schema.into(),
+            tb: tb.into(),
+            plan_records,
+            ..Default::default()
+        }
+    }
+
+    pub fn start(&mut self) {
+        self.status = TbProgressStatus::Running;
+        self.start_time = Utc::now().timestamp_millis() as u64;
+    }
+
+    pub fn finish(&mut self) {
+        self.status = TbProgressStatus::Finished;
+        self.finish_time = Utc::now().timestamp_millis() as u64;
+        self.eta = Some(0);
+    }
+
+    pub fn fail(&mut self) {
+        self.status = TbProgressStatus::Failed;
+        self.finish_time = Utc::now().timestamp_millis() as u64;
+        self.eta = None;
+    }
+
+    pub fn update(
+        &mut self,
+        extracted_records: u64,
+        extracted_bytes: u64,
+        sinked_records: u64,
+        sinked_bytes: u64,
+    ) {
+        self.extracted_records = extracted_records;
+        self.extracted_bytes = extracted_bytes;
+        self.sinked_records = sinked_records;
+        self.sinked_bytes = sinked_bytes;
+        if self.status == TbProgressStatus::Running {
+            let elapsed_millis = (Utc::now().timestamp_millis() as u64)
+                .saturating_sub(self.start_time)
+                .max(1);
+            self.eta = Self::calc_eta(self.plan_records, self.sinked_records, elapsed_millis);
+        }
+    }
+
+    fn calc_eta(plan_records: u64, sinked_records: u64, elapsed_millis: u64) -> Option<u64> {
+        if plan_records == 0 || sinked_records == 0 {
+            return None;
+        }
+        // plan records are estimated, the table may be larger than planned
+        let left_records = plan_records.saturating_sub(sinked_records);
+        Some(left_records * elapsed_millis / sinked_records / 1000)
+    }
+
+    pub fn to_metrics(&self) -> BTreeMap<TaskMetricsType, u64> {
+        let mut metrics = BTreeMap::from([
+            (TaskMetricsType::ExtractorPlanRecords, self.plan_records),
+            (
+                TaskMetricsType::ExtractorPushedRecords,
+                self.extracted_records,
+            ),
+            (TaskMetricsType::ExtractorPushedBytes, self.extracted_bytes),
+            (TaskMetricsType::SinkerSinkedRecords, self.sinked_records),
+            (TaskMetricsType::SinkerSinkedBytes, self.sinked_bytes),
+            (TaskMetricsType::StartTime, self.start_time),
+            (TaskMetricsType::FinishTime, self.finish_time),
+        ]);
+        if let Some(eta) = self.eta {
+            metrics.insert(TaskMetricsType::Eta, eta);
+        }
+        metrics
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_calc_eta() {
+        // unknown without plan or sinked records
+        assert_eq!(TbProgress::calc_eta(0, 100, 1000), None);
+        assert_eq!(TbProgress::calc_eta(1000, 0, 1000), None);
+        // 100 records in 2s, 900 records left
+        assert_eq!(TbProgress::calc_eta(1000, 100, 2000), Some(18));
+        // less than 1s left
+        assert_eq!(TbProgress::calc_eta(1000, 999, 1000), Some(0));
+        // the table is larger than planned
+        assert_eq!(TbProgress::calc_eta(1000, 2000, 1000), Some(0));
+    }
+
+    #[test]
+    fn test_progress_status() {
+        let mut progress = TbProgress::new("db_1", "tb_1", 1000);
+        assert_eq!(progress.status, TbProgressStatus::Pending);
+
+        // eta is only estimated for running tables
+        progress.update(10, 100, 10, 100);
+        assert_eq!(progress.eta, None);
+        assert_eq!(progress.sinked_records, 10);
+
+        progress.start();
+        assert_eq!(progress.status, TbProgressStatus::Running);
+        assert!(progress.start_time > 0);
+        progress.update(200, 2000, 100, 1000);
+        assert!(progress.eta.is_some());
+
+        let mut failed = progress.clone();
+        failed.fail();
+        assert_eq!(failed.status, TbProgressStatus::Failed);
+        assert!(failed.finish_time >= failed.start_time);
+        assert_eq!(failed.eta, None);
+        assert!(!failed.to_metrics().contains_key(&TaskMetricsType::Eta));
+
+        progress.finish();
+        assert_eq!(progress.status, TbProgressStatus::Finished);
+        assert!(progress.finish_time >= progress.start_time);
+        let metrics = progress.to_metrics();
+        assert_eq!(metrics[&TaskMetricsType::Eta], 0);
+        assert_eq!(metrics[&TaskMetricsType::ExtractorPlanRecords], 1000);
+        assert_eq!(metrics[&TaskMetricsType::SinkerSinkedRecords], 100);
+    }
+}
@@ -0,0 +1,165 @@
+use std::future::Future;
+
+#[cfg(feature = "otel")]
+use opentelemetry::{
+    global,
+    trace::{FutureExt, Status, TraceContextExt, Tracer},
+    Context, KeyValue,
+};
+
+#[cfg(feature = "otel")]
+pub const TRACER_NAME: &str = "ape-dts";
+
+/// A span exported by OpenTelemetry, does nothing if the otel feature is disabled.
+/// Spans started inside `in_scope` become children of this span,
+/// the span ends when dropped.
+pub struct TraceSpan {
+    #[cfg(feature = "otel")]
+    cx: Context,
+}
+
+impl TraceSpan {
+    pub fn start(name: &'static str) -> Self {
+        #[cfg(feature = "otel")]
+        {
+            // the parent is the span of the current context, if any
+            let span = global::tracer(TRACER_NAME).start(name);
+            Self {
+                cx: Context::current_with_span(span),
+            }
+        }
+
+        #[cfg(not(feature = "otel"))]
+        {
+            let _ = name;
+            Self {}
+        }
+    }
+
+    /// A span that is never exported, used to skip tracing idle loops
+    pub fn empty() -> Self {
+        Self {
+            #[cfg(feature = "otel")]
+            cx: Context::new(),
+        }
+    }
+
+    pub fn start_child(&self, name: &'static str) -> Self {
+        #[cfg(feature = "otel")]
+        {
+            let span = global::tracer(TRACER_NAME).start_with_context(name, &self.cx);
+            Self {
+                cx: self.cx.with_span(span),
+            }
+        }
+
+        #[cfg(not(feature = "otel"))]
+        {
+            let _ = name;
+            Self {}
+        }
+    }
+
+    pub fn set_tb(&self, schema: &str, tb: &str) -> &Self {
+        #[cfg(feature = "otel")]
+        {
+            let span = self.cx.span();
+            span.set_attribute(KeyValue::new("schema", schema.to_string()));
+            span.set_attribute(KeyValue::new("tb", tb.to_string()));
+        }
+
+        #[cfg(not(feature = "otel"))]
+        let _ = (schema, tb);
+        self
+    }
+
+    pub fn set_count(&self, key: &'static str, count: u64) -> &Self {
+        #[cfg(feature = "otel")]
+        self.cx
+            .span()
+            .set_attribute(KeyValue::new(key, count as i64));
+
+        #[cfg(not(feature = "otel"))]
+        let _ = (key, count);
+        self
+    }
+
+    pub fn set_error(&self, error: &anyhow::Error) -> &Self {
+        #[cfg(feature = "otel")]
+        self.cx.span().set_status(Status::error(error.to_string()));
+
+        #[cfg(not(feature = "otel"))]
+        let _ = error;
+        self
+    }
+
+    /// Runs the future with this span as the parent of spans started inside,
+    /// the span is marked as error if the future fails
+    pub async fn in_scope<T, F>(&self, future: F) -> anyhow::Result<T>
+    where
+        F: Future<Output = anyhow::Result<T>>,
+    {
+        #[cfg(feature = "otel")]
+        let result = future.with_context(self.cx.clone()).await;
+
+        #[cfg(not(feature = "otel"))]
+        let result = future.await;
+
+        if let Err(error) = &result {
+            self.set_error(error);
+        }
+        result
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use anyhow::bail;
+
+    use super::*;
+
+    #[tokio::test]
+    async fn test_in_scope() {
+        let span = TraceSpan::start("test");
+        span.set_tb("db_1", "tb_1").set_count("count", 10);
+
+        let result = span.in_scope(async { Ok(1) }).await;
+        assert_eq!(result.unwrap(), 1);
+
+        // errors are returned as is
+        let result: anyhow::Result<()> = span.in_scope(async { bail!("failed") }).await;
+        assert_eq!(result.unwrap_err().to_string(), "failed");
+
+        let child = span.start_child("child");
+        assert!(child.in_scope(async { Ok(()) }).await.is_ok());
+        assert!(TraceSpan::empty().in_scope(async { Ok(()) }).await.is_ok());
+    }
+
+    #[cfg(feature = "otel")]
+    #[tokio::test]
+    async fn test_span_context() {
+        global::set_tracer_provider(opentelemetry_sdk::trace::TracerProvider::builder().build());
+        let get_span_context = |span: &TraceSpan| span.cx.span().span_context().clone();
+
+        let span = TraceSpan::start("parent");
+        let parent = get_span_context(&span);
+        assert!(parent.is_valid());
+
+        let child = get_span_context(&span.start_child("child"));
+        assert_eq!(child.trace_id(), parent.trace_id());
+        assert_ne!(child.span_id(), parent.span_id());
+
+        // spans started inside in_scope are children
+        let inner = span
+            .in_scope(async { Ok(get_span_context(&TraceSpan::start("inner"))) })
+            .await
+            .unwrap();
+        assert_eq!(inner.trace_id(), parent.trace_id());
+
+        // spans started outside are roots of new traces
+        let other = get_span_context(&TraceSpan::start("other"));
+        assert_ne!(other.trace_id(), parent.trace_id());
+
+        assert!(!get_span_context(&TraceSpan::empty()).is_valid());
+    }
+}
@@ -41,6 +41,8 @@ pub struct RdbFilter {
     pub do_ddls: HashSet<String>,
     pub do_dcls: HashSet<String>,
     pub ignore_cmds: HashSet<String>,
+    pub do_keys: Vec<Regex>,
+    pub ignore_keys: Vec<Regex>,
     pub where_conditions: WhereConditions,
     pub content_filters: ContentFilters,
     pub cache: DashMap<(String, String), bool>,
@@ -61,6 +63,8 @@ impl RdbFilter {
             do_ddls: Self::parse_single_tokens(&config.do_ddls, db_type)?,
             do_dcls: Self::parse_single_tokens(&config.do_dcls, db_type)?,
             ignore_cmds: Self::parse_single_tokens(&config.ignore_cmds, db_type)?,
+            do_keys: Self::parse_key_patterns(&config.do_keys, &config.do_keys_regex)?,
+            ignore_keys: Self::parse_key_patterns(&config.ignore_keys, &config.ignore_keys_regex)?,
             where_conditions: Self::parse_where_conditions(&config.where_conditions)?,
             content_filters: Self::parse_content_filters(&config.content_filters)?,
             cache: DashMap::new(),
@@ -141,6 +145,18 @@ impl RdbFilter {
         self.ignore_cmds.contains(cmd)
     }
 
+    pub fn has_key_filter(&self) -> bool {
+        !self.do_keys.is_empty() || !self.ignore_keys.is_empty()
+    }
+
+    /// redis keys are kept if do_keys is empty or any of them matches
+    pub fn filter_key(&self, key: &str) -> bool {
+        if self.ignore_keys.iter().any(|i| i.is_match(key)) {
+            return true;
+        }
+        !self.do_keys.is_empty() && !self.do_keys.iter().any(|i| i.is_match(key))
+    }
+
     pub fn get_ignore_cols(&self, schema: &str, tb: &str) -> Option<&HashSet<String>> {
         self.ignore_cols.get(&(schema.to_string(), tb.to_string()))
     }
@@ -244,6 +260,63 @@ impl RdbFilter {
         ConfigTokenParser::parse_config(config_str, db_type, &delimiters)
     }
 
+    fn parse_key_patterns(globs: &str, regex: &str) -> anyhow::Result<Vec<Regex>> {
+        // do_keys=user:*,order:?:*
+        let mut results = Vec::new();
+        for glob in globs.split(',').map(|i| i.trim()).filter(|i| !i.is_empty()) {
+            let pattern = Self::glob_to_regex(glob);
+            results.push(
+                Regex::new(&pattern).with_context(|| format!("invalid key pattern: [{}]", glob))?,
+            );
+        }
+        // do_keys_regex=^(user|order):\d+$
+        if !regex.trim().is_empty() {
+            results.push(
+                Regex::new(regex.trim())
+                    .with_context(|| format!("invalid key regex: [{}]", regex))?,
+            );
+        }
+        Ok(results)
+    }
+
+    /// same as glob of redis KEYS / SCAN:
+    /// * : matching any chars
+    /// ? : matching a single char
+    /// [abc], [^abc], [a-z] : matching chars in brackets
+    /// \x : escaping x
+    fn glob_to_regex(glob: &str) -> String {
+        let mut pattern = String::from("(?s)^");
+        let mut in_brackets = false;
+        let mut chars = glob.chars().peekable();
+        while let Some(c) = chars.next() {
+            match c {
+                '\\' => {
+                    if let Some(next) = chars.next() {
+                        pattern.push_str(&regex::escape(&next.to_string()));
+                    }
+                }
+                '*' if !in_brackets => pattern.push_str(".*"),
+                '?' if !in_brackets => pattern.push('.'),
+                '[' if !in_brackets => {
+                    in_brackets = true;
+                    pattern.push('[');
+                    if chars.peek() == Some(&'^') {
+                        chars.next();
+                        pattern.push('^');
+                    }
+                }
+                ']' if in_brackets => {
+                    in_brackets = false;
+                    pattern.push(']');
+                }
+                '-' if in_brackets => pattern.push('-'),
+                _ => pattern.push_str(&regex::escape(&c.to_string())),
+            }
+        }
+        pattern.push('$');
+        pattern
+    }
+
     fn parse_ignore_cols(config_str: &str) -> anyhow::Result<IgnoreCols> {
         let mut results = IgnoreCols::new();
         if config_str.trim().is_empty() {
@@ -1029,4 +1102,37 @@ mod tests {
         assert!(rdb_fitler.filter_event("test_db_1", "aaaa", &RowType::Update));
         assert!(rdb_fitler.filter_event("test_db_1", "aaaa", &RowType::Delete));
     }
+
+    #[test]
+    fn test_rdb_filter_keys() {
+        let db_type = DbType::Redis;
+        let config = FilterConfig {
+            do_keys: "user:*,order:?,tag:[ab]-[^0-9]".to_string(),
+            ignore_keys: "user:tmp:*".to_string(),
+            ignore_keys_regex: r"^order:\d$".to_string(),
+            ..Default::default()
+        };
+        let rdb_fitler = RdbFilter::from_config(&config, &db_type).unwrap();
+        assert!(rdb_fitler.has_key_filter());
+        assert!(!rdb_fitler.filter_key("user:1"));
+        assert!(!rdb_fitler.filter_key("user:\n1"));
+        assert!(rdb_fitler.filter_key("user:tmp:1"));
+        assert!(!rdb_fitler.filter_key("order:a"));
+        assert!(rdb_fitler.filter_key("order:1"));
+        assert!(rdb_fitler.filter_key("order:ab"));
+        assert!(!rdb_fitler.filter_key("tag:a-x"));
+        assert!(rdb_fitler.filter_key("tag:c-x"));
+        assert!(rdb_fitler.filter_key("tag:a-1"));
+        assert!(rdb_fitler.filter_key("other"));
+
+        // keep all keys if do_keys is empty
+        let config = FilterConfig {
+            ignore_keys: "a\\*".to_string(),
+            ..Default::default()
+        };
+        let rdb_fitler = RdbFilter::from_config(&config, &db_type).unwrap();
+        assert!(rdb_fitler.filter_key("a*"));
+        assert!(!rdb_fitler.filter_key("ab"));
+        assert!(!rdb_fitler.filter_key("b"));
+    }
 }
@@ -1,5 +1,6 @@
 pub mod file_util;
 pub mod limit_queue;
+pub mod mongo_util;
 pub mod redis_util;
 pub mod sql_util;
 pub mod time_util;
@@ -0,0 +1,179 @@
+use anyhow::Context;
+use mongodb::bson::{doc, oid::ObjectId, Bson, Document};
+
+use crate::meta::mongo::mongo_constant::MongoConstants;
+
+pub struct MongoUtil {}
+
+// $type aliases grouped by bson comparison order, values of different groups are NOT
+// matched by $gt / $lt, refer to: https://www.mongodb.com/docs/manual/reference/bson-type-comparison-order/
+const TYPE_ORDER: [&[&str]; 13] = [
+    &["minKey"],
+    &["null", "undefined"],
+    &["int", "long", "double", "decimal"],
+    &["string", "symbol"],
+    &["object"],
+    &["array"],
+    &["binData"],
+    &["objectId"],
+    &["bool"],
+    &["date"],
+    &["timestamp"],
+    &["regex"],
+    &["javascript", "javascriptWithScope", "maxKey"],
+];
+
+impl MongoUtil {
+    /// Encodes _id as canonical extended json, which keeps the bson type, e.g.
+    /// {"$oid":"65f1..."}, "abc", {"$numberInt":"1"}, {"$binary":{"base64":"...","subType":"04"}}
+    pub fn encode_id(id: &Bson) -> String {
+        id.clone().into_canonical_extjson().to_string()
+    }
+
+    /// Decodes _id encoded by encode_id, a plain hex string is decoded as ObjectId
+    /// for positions written by older versions
+    pub fn decode_id(value: &str) -> anyhow::Result<Bson> {
+        if let Ok(oid) = ObjectId::parse_str(value) {
+            return Ok(Bson::ObjectId(oid));
+        }
+
+        let json: serde_json::Value =
+            serde_json::from_str(value).with_context(|| format!("invalid _id: [{}]", value))?;
+        Bson::try_from(json).with_context(|| format!("invalid _id: [{}]", value))
+    }
+
+    /// Filter of _id > start and _id <= end in bson comparison order, either bound can be None.
+    /// Since $gt / $lte only match values of the same type group, values of the other
+    /// type groups in range are matched by $type
+    pub fn build_id_range_filter(start: Option<&Bson>, end: Option<&Bson>) -> Option<Document> {
+        let id = MongoConstants::ID;
+        let with_types = |filter: Document, types: Vec<&str>| {
+            if types.is_empty() {
+                filter
+            } else {
+                doc! {"$or": [filter, {id: {"$type": types}}]}
+            }
+        };
+        let gt_filter = start.map(|start| {
+            let types = Self::get_types(Self::get_type_order(start) + 1, TYPE_ORDER.len());
+            with_types(doc! {id: {"$gt": start}}, types)
+        });
+        let lte_filter = end.map(|end| {
+            let types = Self::get_types(0, Self::get_type_order(end));
+            with_types(doc! {id: {"$lte": end}}, types)
+        });
+
+        match (gt_filter, lte_filter) {
+            (Some(gt_filter), Some(lte_filter)) => Some(doc! {"$and": [gt_filter, lte_filter]}),
+            (Some(filter), None) | (None, Some(filter)) => Some(filter),
+            (None, None) => None,
+        }
+    }
+
+    fn get_types(start_order: usize, end_order: usize) -> Vec<&'static str> {
+        TYPE_ORDER
+            .iter()
+            .take(end_order)
+            .skip(start_order)
+            .flat_map(|i| i.iter().copied())
+            .collect()
+    }
+
+    fn get_type_order(value: &Bson) -> usize {
+        match value {
+            Bson::MinKey => 0,
+            Bson::Null | Bson::Undefined => 1,
+            Bson::Int32(_) | Bson::Int64(_) | Bson::Double(_) | Bson::Decimal128(_) => 2,
+            Bson::String(_) | Bson::Symbol(_) => 3,
+            Bson::Document(_) => 4,
+            Bson::Array(_) => 5,
+            Bson::Binary(_) => 6,
+            Bson::ObjectId(_) => 7,
+            Bson::Boolean(_) => 8,
+            Bson::DateTime(_) => 9,
+            Bson::Timestamp(_) => 10,
+            Bson::RegularExpression(_) => 11,
+            // JavaScriptCode, JavaScriptCodeWithScope, DbPointer and MaxKey
+            _ => 12,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use mongodb::bson::{spec::BinarySubtype, Binary};
+
+    use super::*;
+
+    #[test]
+    fn test_encode_decode_id() {
+        let oid = ObjectId::new();
+        let ids = vec![
+            Bson::ObjectId(oid),
+            Bson::String("abc".into()),
+            Bson::String("{\"a\":1}".into()),
+            Bson::Int32(1),
+            Bson::Int64(1),
+            Bson::Double(1.5),
+            Bson::Binary(Binary {
+                subtype: BinarySubtype::Uuid,
+                bytes: vec![1; 16],
+            }),
+            Bson::Document(doc! {"a": 1, "b": "x"}),
+        ];
+        for id in ids {
+            let encoded = MongoUtil::encode_id(&id);
+            assert_eq!(MongoUtil::decode_id(&encoded).unwrap(), id);
+        }
+
+        // legacy positions
+        assert_eq!(
+            MongoUtil::decode_id(&oid.to_hex()).unwrap(),
+            Bson::ObjectId(oid)
+        );
+        assert_eq!(
+            MongoUtil::encode_id(&Bson::Int32(1)),
+            r#"{"$numberInt":"1"}"#
+        );
+        assert!(MongoUtil::decode_id("abc").is_err());
+    }
+
+    #[test]
+    fn test_build_id_range_filter() {
+        assert_eq!(MongoUtil::build_id_range_filter(None, None), None);
+        assert_eq!(
+            MongoUtil::build_id_range_filter(None, Some(&Bson::MinKey)),
+            Some(doc! {"_id": {"$lte": Bson::MinKey}})
+        );
+
+        let filter = MongoUtil::build_id_range_filter(Some(&Bson::Int32(1)), None).unwrap();
+        assert_eq!(
+            filter,
+            doc! {"$or": [
+                {"_id": {"$gt": 1}},
+                {"_id": {"$type": ["string", "symbol", "object", "array", "binData", "objectId", "bool",
+                    "date", "timestamp", "regex", "javascript", "javascriptWithScope", "maxKey"]}}
+            ]}
+        );
+
+        let filter = MongoUtil::build_id_range_filter(
+            Some(&Bson::String("a".into())),
+            Some(&Bson::String("b".into())),
+        )
+        .unwrap();
+        assert_eq!(
+            filter,
+            doc! {"$and": [
+                {"$or": [
+                    {"_id": {"$gt": "a"}},
+                    {"_id": {"$type": ["object", "array", "binData", "objectId", "bool", "date",
+                        "timestamp", "regex", "javascript", "javascriptWithScope", "maxKey"]}}
+                ]},
+                {"$or": [
+                    {"_id": {"$lte": "b"}},
+                    {"_id": {"$type": ["minKey", "null", "undefined", "int", "long", "double", "decimal"]}}
+                ]}
+            ]}
+        );
+    }
+}
@@ -89,6 +89,26 @@ impl RedisUtil {
         )}
     }
 
+    pub fn get_repl_ids(conn: &mut redis::Connection) -> anyhow::Result<Vec<String>> {
+        // after a failover, master_replid2 of the new master is the master_replid of the old master
+        let cmd = RedisCmd::from_str_args(&["INFO", "replication"]);
+        let value = conn.req_packed_command(&CmdEncoder::encode(&cmd))?;
+        if let redis::Value::BulkString(data) = value {
+            let info = String::from_utf8(data)?;
+            let re = Regex::new(r"master_replid2?:(\w+)").unwrap();
+            let repl_ids = re
+                .captures_iter(&info)
+                .map(|cap| cap[1].to_string())
+                // master_replid2 is all zeros if there was no failover
+                .filter(|i| i.chars().any(|c| c != '0'))
+                .collect();
+            return Ok(repl_ids);
+        }
+        bail! {Error::RedisResultError(
+            "can not get redis replication ids by INFO".into(),
+        )}
+    }
+
     pub fn parse_result_as_string(value: Value) -> anyhow::Result<Vec<String>> {
         let mut results = Vec::new();
         match value {
@@ -4,7 +4,7 @@ use std::{
 };
 
 use anyhow::Context;
-use dt_common::error::Error;
+use dt_common::{error::Error, log_event::LogEvent};
 use serde::{Deserialize, Serialize, Serializer};
 use serde_json::json;
 
@@ -36,6 +36,14 @@ impl std::fmt::Display for CheckLog {
 impl FromStr for CheckLog {
     type Err = Error;
     fn from_str(str: &str) -> Result<Self, Self::Err> {
+        // check logs in json log format are wrapped in payload
+        if let Some(event) = LogEvent::from_log(str) {
+            let me: Self = serde_json::from_value(event.payload)
+                .with_context(|| format!("invalid check log: [{}]", str))
+                .unwrap();
+            return Ok(me);
+        }
+
         let me: Self = serde_json::from_str(str)
             .with_context(|| format!("invalid check log: [{}]", str))
             .unwrap();
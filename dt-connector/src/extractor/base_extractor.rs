@@ -4,9 +4,12 @@ use std::sync::{
 };
 
 use anyhow::bail;
+use chrono::Utc;
+use tokio::sync::Mutex;
 
 use dt_common::{
     config::{config_enums::DbType, config_token_parser::ConfigTokenParser},
+    control::task_control::TaskControl,
     error::Error,
     log_debug, log_error, log_info, log_warn,
     meta::{
@@ -14,7 +17,9 @@ use dt_common::{
         ddl_meta::ddl_data::DdlData,
         dt_queue::DtQueue,
         struct_meta::struct_data::StructData,
+        syncer::Syncer,
     },
+    monitor::{counter_type::CounterType, monitor::Monitor},
     rdb_filter::RdbFilter,
     utils::{sql_util::SqlUtil, time_util::TimeUtil},
 };
@@ -39,6 +44,7 @@ pub struct BaseExtractor {
     pub monitor: ExtractorMonitor,
     pub data_marker: Option<DataMarker>,
     pub time_filter: TimeFilter,
+    pub task_control: Arc<TaskControl>,
 }
 
 impl BaseExtractor {
@@ -62,6 +68,14 @@ impl BaseExtractor {
             return Ok(());
         }
 
+        // block extracting if paused by the control api
+        self.task_control.wait_if_paused().await;
+        // once stopped by the control api, extracting exits at the next push, including
+        // workers spawned by the extractor, the task runner ignores this error
+        if self.task_control.is_stopping() {
+            bail! {Error::ExtractorError("task stopped by the control api".into())}
+        }
+
         self.monitor.counters.pushed_record_count += 1;
         self.monitor.counters.pushed_data_size += dt_data.get_data_size();
         self.monitor.try_flush(false).await;
@@ -235,6 +249,16 @@ impl BaseExtractor {
         schema_tb
     }
 
+    /// Heartbeat records keep the committed position moving forward even if the source is idle,
+    /// so now - source commit time of the committed position is the delay of the task
+    pub async fn update_heartbeat_delay(monitor: &Monitor, syncer: &Mutex<Syncer>) {
+        let timestamp = syncer.lock().await.committed_position.to_timestamp();
+        if timestamp > 0 {
+            let now = Utc::now().timestamp_millis() as u64;
+            monitor.set_counter(CounterType::HeartbeatDelay, now.saturating_sub(timestamp));
+        }
+    }
+
     pub fn update_time_filter(time_filter: &mut TimeFilter, timestamp: u32, position: &Position) {
         if !time_filter.started && timestamp >= time_filter.start_timestamp {
             time_filter.started = true;
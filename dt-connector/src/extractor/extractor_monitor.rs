@@ -54,7 +54,9 @@ impl ExtractorMonitor {
         {
             self.monitor
                 .add_counter(CounterType::RecordCount, pushed_record_count)
-                .add_counter(CounterType::DataBytes, pushed_record_size);
+                .add_counter(CounterType::DataBytes, pushed_record_size)
+                .add_counter(CounterType::PushedRecordTotal, pushed_record_count)
+                .add_counter(CounterType::PushedByteTotal, pushed_record_size);
 
             self.last_flush_time = Instant::now();
             self.flushed_counters = self.counters.clone();
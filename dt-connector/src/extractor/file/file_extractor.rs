@@ -0,0 +1,359 @@
+use std::{collections::HashMap, io::SeekFrom};
+
+use anyhow::{bail, Context};
+use async_trait::async_trait;
+use parquet::file::{reader::FileReader, serialized_reader::SerializedFileReader};
+use rusoto_s3::{GetObjectRequest, S3Client, S3};
+use tokio::io::{AsyncBufRead, AsyncSeekExt, AsyncWriteExt, BufReader};
+
+use super::file_record_reader::{FileRecord, FileValue, ParquetRecordReader, TextRecordReader};
+use crate::{
+    extractor::{base_extractor::BaseExtractor, resumer::snapshot_resumer::SnapshotResumer},
+    Extractor,
+};
+use dt_common::{
+    config::{config_enums::FileFormat, s3_config::S3Config},
+    error::Error,
+    log_info,
+    meta::{
+        adaptor::{
+            mysql_col_value_convertor::MysqlColValueConvertor,
+            pg_col_value_convertor::PgColValueConvertor,
+        },
+        col_value::ColValue,
+        mysql::mysql_col_type::MysqlColType,
+        pg::pg_value_type::PgValueType,
+        position::Position,
+        rdb_meta_manager::RdbMetaManager,
+        row_data::RowData,
+        row_type::RowType,
+    },
+};
+
+pub struct FileExtractor {
+    pub base_extractor: BaseExtractor,
+    pub resumer: SnapshotResumer,
+    pub file_path: String,
+    pub file_format: FileFormat,
+    pub schema: String,
+    pub tb: String,
+    pub csv_delimiter: char,
+    pub csv_has_header: bool,
+    // read from s3 if set, otherwise read from local file_path
+    pub s3_client: Option<S3Client>,
+    pub s3_config: S3Config,
+    // meta manager of target, used to convert file values by target column types
+    pub meta_manager: Option<RdbMetaManager>,
+}
+
+#[async_trait]
+impl Extractor for FileExtractor {
+    async fn extract(&mut self) -> anyhow::Result<()> {
+        log_info!(
+            "FileExtractor starts, file_path: {}, file_format: {}, schema: `{}`, tb: `{}`",
+            self.file_path,
+            self.file_format,
+            self.schema,
+            self.tb
+        );
+
+        if self.resumer.check_finished(&self.schema, &self.tb) {
+            log_info!("`{}`.`{}` has finished", self.schema, self.tb);
+        } else {
+            let offset = self
+                .resumer
+                .get_resume_value(&self.schema, &self.tb, &self.file_path, false)
+                .map(|i| i.parse::<u64>())
+                .transpose()?
+                .unwrap_or(0);
+            match self.file_format {
+                FileFormat::Csv | FileFormat::Jsonl => self.extract_text(offset).await?,
+                FileFormat::Parquet => self.extract_parquet(offset).await?,
+                FileFormat::Orc => {
+                    bail! {Error::ConfigError("file extractor does not support orc".into())}
+                }
+            }
+        }
+
+        self.base_extractor.wait_task_finish().await
+    }
+
+    async fn close(&mut self) -> anyhow::Result<()> {
+        if let Some(meta_manager) = &self.meta_manager {
+            meta_manager.close().await?;
+        }
+        Ok(())
+    }
+}
+
+impl FileExtractor {
+    async fn extract_text(&mut self, offset: u64) -> anyhow::Result<()> {
+        let mut reader = TextRecordReader {
+            reader: self.open_text_reader(0).await?,
+            file_format: self.file_format.clone(),
+            csv_delimiter: self.csv_delimiter,
+            offset: 0,
+            header: Vec::new(),
+        };
+
+        if self.file_format == FileFormat::Csv {
+            reader.header = if self.csv_has_header {
+                reader.read_header().await?
+            } else {
+                self.get_target_cols().await?
+            };
+        }
+
+        // resume from the offset after the last extracted row
+        if offset > reader.offset {
+            reader.reader = self.open_text_reader(offset).await?;
+            reader.offset = offset;
+        }
+        log_info!("start extracting from offset: {}", reader.offset);
+
+        while let Some(record) = reader.next_record().await? {
+            let position = self.get_position(reader.offset);
+            self.push_record(record, position).await?;
+        }
+        Ok(())
+    }
+
+    async fn extract_parquet(&mut self, offset: u64) -> anyhow::Result<()> {
+        let file = if let Some(s3_client) = &self.s3_client {
+            Self::download_s3_object(s3_client, &self.s3_config, &self.file_path).await?
+        } else {
+            std::fs::File::open(&self.file_path)
+                .with_context(|| format!("failed to open: {}", self.file_path))?
+        };
+        let reader = SerializedFileReader::new(file)?;
+        log_info!("start extracting from row: {}", offset);
+
+        // for parquet, offset is the count of extracted rows
+        let mut row_count = 0;
+        for i in 0..ParquetRecordReader::get_row_group_count(&reader) {
+            let row_group_rows = reader.metadata().row_group(i).num_rows() as u64;
+            if row_count + row_group_rows <= offset {
+                row_count += row_group_rows;
+                continue;
+            }
+
+            for record in ParquetRecordReader::read_row_group(&reader, i)? {
+                row_count += 1;
+                if row_count <= offset {
+                    continue;
+                }
+                let position = self.get_position(row_count);
+                self.push_record(record, position).await?;
+            }
+        }
+        Ok(())
+    }
+
+    async fn push_record(&mut self, record: FileRecord, position: Position) -> anyhow::Result<()> {
+        // the file is extracted as self.schema.self.tb, columns are routed by [router] later,
+        // convert values by types of routed target columns
+        let (dst_schema, dst_tb) = self
+            .base_extractor
+            .router
+            .get_tb_map(&self.schema, &self.tb);
+        let (dst_schema, dst_tb) = (dst_schema.to_string(), dst_tb.to_string());
+        let col_map = self
+            .base_extractor
+            .router
+            .get_col_map(&self.schema, &self.tb)
+            .cloned();
+
+        let mut after = HashMap::new();
+        for (col, value) in record {
+            let dst_col = col_map.as_ref().and_then(|i| i.get(&col)).unwrap_or(&col);
+            let col_value = self
+                .to_col_value(&dst_schema, &dst_tb, dst_col, value)
+                .await
+                .with_context(|| format!("failed to convert value of column: `{}`", col))?;
+            after.insert(col, col_value);
+        }
+
+        let row_data = RowData::new(
+            self.schema.clone(),
+            self.tb.clone(),
+            RowType::Insert,
+            None,
+            Some(after),
+        );
+        self.base_extractor.push_row(row_data, position).await
+    }
+
+    async fn to_col_value(
+        &mut self,
+        schema: &str,
+        tb: &str,
+        col: &str,
+        value: FileValue,
+    ) -> anyhow::Result<ColValue> {
+        if value == FileValue::Null {
+            return Ok(ColValue::None);
+        }
+
+        let meta_manager = match self.meta_manager.as_mut() {
+            Some(meta_manager) => meta_manager,
+            None => {
+                return Ok(match value {
+                    FileValue::Bytes(v) => ColValue::Blob(v),
+                    _ => ColValue::String(value.into_string()?),
+                })
+            }
+        };
+
+        let col_not_found_err = || {
+            Error::Unexpected(format!(
+                "column `{}` not found in target table `{}`.`{}`",
+                col, schema, tb
+            ))
+        };
+
+        if let Some(mysql_meta_manager) = meta_manager.mysql_meta_manager.as_mut() {
+            let tb_meta = mysql_meta_manager.get_tb_meta(schema, tb).await?;
+            let col_type = tb_meta
+                .col_type_map
+                .get(col)
+                .ok_or_else(col_not_found_err)?;
+            return match col_type {
+                MysqlColType::Binary { .. }
+                | MysqlColType::VarBinary { .. }
+                | MysqlColType::TinyBlob
+                | MysqlColType::MediumBlob
+                | MysqlColType::Blob
+                | MysqlColType::LongBlob => Ok(ColValue::Blob(value.into_bytes())),
+                // mysql has no bool type, bools are stored in numeric columns such as tinyint(1)
+                _ => match value {
+                    FileValue::Json(serde_json::Value::Bool(v)) if !col_type.is_string() => {
+                        MysqlColValueConvertor::from_str(col_type, if v { "1" } else { "0" })
+                    }
+                    _ => MysqlColValueConvertor::from_str(col_type, &value.into_string()?),
+                },
+            };
+        }
+
+        if let Some(pg_meta_manager) = meta_manager.pg_meta_manager.as_mut() {
+            let tb_meta = pg_meta_manager.get_tb_meta(schema, tb).await?;
+            let col_type = tb_meta
+                .col_type_map
+                .get(col)
+                .ok_or_else(col_not_found_err)?
+                .clone();
+            return match (&col_type.value_type, value) {
+                (PgValueType::Boolean, value) => {
+                    let value = value.into_string()?.to_lowercase();
+                    Ok(ColValue::Bool(matches!(
+                        value.as_str(),
+                        "t" | "true" | "1" | "y" | "yes" | "on"
+                    )))
+                }
+                (PgValueType::Bytes, FileValue::Bytes(v)) => Ok(ColValue::Blob(v)),
+                (_, value) => {
+                    PgColValueConvertor::from_str(&col_type, &value.into_string()?, pg_meta_manager)
+                }
+            };
+        }
+
+        Ok(ColValue::String(value.into_string()?))
+    }
+
+    async fn get_target_cols(&mut self) -> anyhow::Result<Vec<String>> {
+        // csv without header, columns are in the same order as the target table
+        let meta_manager = match self.meta_manager.as_mut() {
+            Some(meta_manager) => meta_manager,
+            None => {
+                bail! {Error::ConfigError(
+                    "csv without header is only supported for mysql/pg targets".into()
+                )}
+            }
+        };
+
+        let router = &self.base_extractor.router;
+        let (dst_schema, dst_tb) = router.get_tb_map(&self.schema, &self.tb);
+        let tb_meta = meta_manager.get_tb_meta(dst_schema, dst_tb).await?;
+        // map target columns back to source columns
+        let col_map = router.get_col_map(&self.schema, &self.tb);
+        let cols = tb_meta
+            .cols
+            .iter()
+            .map(|dst_col| {
+                col_map
+                    .and_then(|i| i.iter().find(|(_, v)| *v == dst_col))
+                    .map(|(k, _)| k.to_owned())
+                    .unwrap_or_else(|| dst_col.to_owned())
+            })
+            .collect();
+        Ok(cols)
+    }
+
+    async fn open_text_reader(
+        &self,
+        offset: u64,
+    ) -> anyhow::Result<Box<dyn AsyncBufRead + Unpin + Send>> {
+        if let Some(s3_client) = &self.s3_client {
+            let request = GetObjectRequest {
+                bucket: self.s3_config.bucket.clone(),
+                key: self.file_path.clone(),
+                range: Some(format!("bytes={}-", offset)),
+                ..Default::default()
+            };
+            let output = s3_client
+                .get_object(request)
+                .await
+                .with_context(|| format!("failed to get s3 object, key: {}", self.file_path))?;
+            let body = match output.body {
+                Some(body) => body,
+                None => bail! {Error::Unexpected(format!(
+                    "empty s3 object, key: {}",
+                    self.file_path
+                ))},
+            };
+            return Ok(Box::new(BufReader::new(Box::pin(body.into_async_read()))));
+        }
+
+        let mut file = tokio::fs::File::open(&self.file_path)
+            .await
+            .with_context(|| format!("failed to open: {}", self.file_path))?;
+        file.seek(SeekFrom::Start(offset)).await?;
+        Ok(Box::new(BufReader::new(file)))
+    }
+
+    async fn download_s3_object(
+        s3_client: &S3Client,
+        s3_config: &S3Config,
+        key: &str,
+    ) -> anyhow::Result<std::fs::File> {
+        // parquet footer is at the end of file, the object is streamed into an anonymous
+        // temp file instead of memory, the temp file is removed once closed
+        let request = GetObjectRequest {
+            bucket: s3_config.bucket.clone(),
+            key: key.to_string(),
+            ..Default::default()
+        };
+        let output = s3_client
+            .get_object(request)
+            .await
+            .with_context(|| format!("failed to get s3 object, key: {}", key))?;
+
+        let mut file = tokio::fs::File::from_std(tempfile::tempfile()?);
+        if let Some(body) = output.body {
+            let mut body = Box::pin(body.into_async_read());
+            tokio::io::copy(&mut body, &mut file)
+                .await
+                .with_context(|| format!("failed to download s3 object, key: {}", key))?;
+        }
+        file.flush().await?;
+        Ok(file.into_std().await)
+    }
+
+    fn get_position(&self, offset: u64) -> Position {
+        Position::File {
+            schema: self.schema.clone(),
+            tb: self.tb.clone(),
+            file_path: self.file_path.clone(),
+            offset,
+        }
+    }
+}
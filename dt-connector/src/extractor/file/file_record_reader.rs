@@ -0,0 +1,344 @@
+use anyhow::{bail, Context};
+use parquet::{
+    file::{reader::FileReader, serialized_reader::SerializedFileReader},
+    record::Field,
+};
+use tokio::io::{AsyncBufRead, AsyncBufReadExt};
+
+use dt_common::{config::config_enums::FileFormat, error::Error};
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum FileValue {
+    Null,
+    Str(String),
+    Bytes(Vec<u8>),
+    // numbers, booleans, objects and arrays in jsonl / parquet
+    Json(serde_json::Value),
+}
+
+impl FileValue {
+    pub fn into_string(self) -> anyhow::Result<String> {
+        let str = match self {
+            FileValue::Null => String::new(),
+            FileValue::Str(v) => v,
+            FileValue::Bytes(v) => String::from_utf8(v).context("bytes value is not utf8")?,
+            FileValue::Json(v) => v.to_string(),
+        };
+        Ok(str)
+    }
+
+    pub fn into_bytes(self) -> Vec<u8> {
+        match self {
+            FileValue::Null => Vec::new(),
+            FileValue::Str(v) => v.into_bytes(),
+            FileValue::Bytes(v) => v,
+            FileValue::Json(v) => v.to_string().into_bytes(),
+        }
+    }
+
+    fn from_json(value: serde_json::Value) -> Self {
+        match value {
+            serde_json::Value::Null => FileValue::Null,
+            serde_json::Value::String(v) => FileValue::Str(v),
+            _ => FileValue::Json(value),
+        }
+    }
+}
+
+pub type FileRecord = Vec<(String, FileValue)>;
+
+/// reads csv / jsonl records line by line, and tracks the byte offset for resuming
+pub struct TextRecordReader {
+    pub reader: Box<dyn AsyncBufRead + Unpin + Send>,
+    pub file_format: FileFormat,
+    pub csv_delimiter: char,
+    // byte offset after the last read record
+    pub offset: u64,
+    pub header: Vec<String>,
+}
+
+impl TextRecordReader {
+    pub async fn read_header(&mut self) -> anyhow::Result<Vec<String>> {
+        let header = match self.read_csv_fields().await? {
+            Some(fields) => fields
+                .into_iter()
+                .map(|i| i.into_string())
+                .collect::<anyhow::Result<_>>()?,
+            None => Vec::new(),
+        };
+        Ok(header)
+    }
+
+    pub async fn next_record(&mut self) -> anyhow::Result<Option<FileRecord>> {
+        match self.file_format {
+            FileFormat::Csv => {
+                let fields = match self.read_csv_fields().await? {
+                    Some(fields) => fields,
+                    None => return Ok(None),
+                };
+                if fields.len() != self.header.len() {
+                    bail! {Error::Unexpected(format!(
+                        "csv field count: {} not match column count: {}, offset: {}",
+                        fields.len(),
+                        self.header.len(),
+                        self.offset
+                    ))}
+                }
+                Ok(Some(self.header.iter().cloned().zip(fields).collect()))
+            }
+
+            FileFormat::Jsonl => {
+                let line = match self.read_line().await? {
+                    Some(line) => line,
+                    None => return Ok(None),
+                };
+                let value: serde_json::Value = serde_json::from_str(&line)
+                    .with_context(|| format!("invalid jsonl line, offset: {}", self.offset))?;
+                let serde_json::Value::Object(map) = value else {
+                    bail! {Error::Unexpected(format!(
+                        "jsonl line is not an object, offset: {}",
+                        self.offset
+                    ))}
+                };
+                Ok(Some(
+                    map.into_iter()
+                        .map(|(k, v)| (k, FileValue::from_json(v)))
+                        .collect(),
+                ))
+            }
+
+            _ => bail! {Error::Unexpected(format!(
+                "not a text file format: {}",
+                self.file_format
+            ))},
+        }
+    }
+
+    async fn read_line(&mut self) -> anyhow::Result<Option<String>> {
+        loop {
+            let mut buf = Vec::new();
+            let n = self.reader.read_until(b'\n', &mut buf).await?;
+            if n == 0 {
+                return Ok(None);
+            }
+            let line = self.decode_line(buf)?;
+            self.offset += n as u64;
+
+            let line = line.trim_end_matches(['\r', '\n']);
+            // skip blank lines
+            if !line.trim().is_empty() {
+                return Ok(Some(line.to_string()));
+            }
+        }
+    }
+
+    async fn read_csv_fields(&mut self) -> anyhow::Result<Option<Vec<FileValue>>> {
+        let mut record = match self.read_line().await? {
+            Some(line) => line,
+            None => return Ok(None),
+        };
+
+        // a quoted field may contain line breaks
+        loop {
+            if let Some(fields) = Self::parse_csv_record(&record, self.csv_delimiter) {
+                return Ok(Some(fields));
+            }
+
+            let mut buf = Vec::new();
+            let n = self.reader.read_until(b'\n', &mut buf).await?;
+            if n == 0 {
+                bail! {Error::Unexpected(format!(
+                    "unclosed quote in csv record, offset: {}",
+                    self.offset
+                ))}
+            }
+            let line = self.decode_line(buf)?;
+            self.offset += n as u64;
+            record.push('\n');
+            record.push_str(line.trim_end_matches(['\r', '\n']));
+        }
+    }
+
+    fn decode_line(&self, buf: Vec<u8>) -> anyhow::Result<String> {
+        // never replace invalid bytes silently, which corrupts data
+        String::from_utf8(buf).with_context(|| {
+            format!(
+                "line is not utf8, file format: {}, offset: {}",
+                self.file_format, self.offset
+            )
+        })
+    }
+
+    /// returns None if the record is not complete (in quotes)
+    fn parse_csv_record(record: &str, delimiter: char) -> Option<Vec<FileValue>> {
+        let mut fields = Vec::new();
+        let mut field = String::new();
+        let mut quoted = false;
+        let mut in_quotes = false;
+
+        let mut chars = record.chars().peekable();
+        while let Some(c) = chars.next() {
+            if in_quotes {
+                if c == '"' {
+                    if chars.peek() == Some(&'"') {
+                        field.push('"');
+                        chars.next();
+                    } else {
+                        in_quotes = false;
+                    }
+                } else {
+                    field.push(c);
+                }
+            } else if c == '"' && field.is_empty() && !quoted {
+                in_quotes = true;
+                quoted = true;
+            } else if c == delimiter {
+                fields.push(Self::to_csv_value(field, quoted));
+                field = String::new();
+                quoted = false;
+            } else {
+                field.push(c);
+            }
+        }
+
+        if in_quotes {
+            return None;
+        }
+        fields.push(Self::to_csv_value(field, quoted));
+        Some(fields)
+    }
+
+    fn to_csv_value(field: String, quoted: bool) -> FileValue {
+        // unquoted empty field is NULL, "" is empty string
+        if field.is_empty() && !quoted {
+            FileValue::Null
+        } else {
+            FileValue::Str(field)
+        }
+    }
+}
+
+pub struct ParquetRecordReader;
+
+impl ParquetRecordReader {
+    pub fn get_row_group_count<R: parquet::file::reader::ChunkReader + 'static>(
+        reader: &SerializedFileReader<R>,
+    ) -> usize {
+        reader.metadata().num_row_groups()
+    }
+
+    /// read all records of a row group, rows are kept in memory by row groups
+    pub fn read_row_group<R: parquet::file::reader::ChunkReader + 'static>(
+        reader: &SerializedFileReader<R>,
+        index: usize,
+    ) -> anyhow::Result<Vec<FileRecord>> {
+        let row_group_reader = reader.get_row_group(index)?;
+        let mut records = Vec::new();
+        for row in row_group_reader.get_row_iter(None)? {
+            let row = row?;
+            let record = row
+                .get_column_iter()
+                .map(|(col, field)| (col.to_owned(), Self::to_file_value(field)))
+                .collect();
+            records.push(record);
+        }
+        Ok(records)
+    }
+
+    fn to_file_value(field: &Field) -> FileValue {
+        match field {
+            Field::Null => FileValue::Null,
+            Field::Str(v) => FileValue::Str(v.clone()),
+            Field::Bytes(v) => FileValue::Bytes(v.data().to_vec()),
+            _ => FileValue::from_json(field.to_json_value()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn new_reader(content: &str, file_format: FileFormat) -> TextRecordReader {
+        let reader = tokio::io::BufReader::new(std::io::Cursor::new(content.as_bytes().to_vec()));
+        TextRecordReader {
+            reader: Box::new(reader),
+            file_format,
+            csv_delimiter: ',',
+            offset: 0,
+            header: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn test_parse_csv_record() {
+        let fields = TextRecordReader::parse_csv_record(r#"1,"a,""b""",,"""#, ',').unwrap();
+        assert_eq!(
+            fields,
+            vec![
+                FileValue::Str("1".into()),
+                FileValue::Str("a,\"b\"".into()),
+                FileValue::Null,
+                FileValue::Str(String::new()),
+            ]
+        );
+        assert!(TextRecordReader::parse_csv_record(r#"1,"a"#, ',').is_none());
+    }
+
+    #[tokio::test]
+    async fn test_read_csv() {
+        let content = "id,name\n1,\"line1\nline2\"\n\n2,b\n";
+        let mut reader = new_reader(content, FileFormat::Csv);
+        reader.header = reader.read_header().await.unwrap();
+        assert_eq!(reader.header, vec!["id", "name"]);
+        let header_offset = reader.offset;
+
+        let record = reader.next_record().await.unwrap().unwrap();
+        assert_eq!(
+            record[1],
+            ("name".into(), FileValue::Str("line1\nline2".into()))
+        );
+        assert_eq!(reader.offset, header_offset + 16);
+
+        let record = reader.next_record().await.unwrap().unwrap();
+        assert_eq!(record[0], ("id".into(), FileValue::Str("2".into())));
+        assert_eq!(reader.offset, content.len() as u64);
+        assert!(reader.next_record().await.unwrap().is_none());
+    }
+
+    #[tokio::test]
+    async fn test_read_jsonl() {
+        let content = "{\"id\":1,\"name\":\"a\",\"tags\":[1,2],\"f\":null}\n";
+        let mut reader = new_reader(content, FileFormat::Jsonl);
+        let record = reader.next_record().await.unwrap().unwrap();
+        let record: std::collections::HashMap<String, FileValue> = record.into_iter().collect();
+        assert_eq!(record["id"].clone().into_string().unwrap(), "1");
+        assert_eq!(record["name"], FileValue::Str("a".into()));
+        assert_eq!(record["tags"].clone().into_string().unwrap(), "[1,2]");
+        assert_eq!(record["f"], FileValue::Null);
+        assert_eq!(reader.offset, content.len() as u64);
+    }
+
+    #[tokio::test]
+    async fn test_read_invalid_utf8() {
+        let mut reader = new_reader("", FileFormat::Csv);
+        let content = [b"id,name\n".to_vec(), vec![b'1', b',', 0xff, b'\n']].concat();
+        reader.reader = Box::new(tokio::io::BufReader::new(std::io::Cursor::new(content)));
+        reader.header = reader.read_header().await.unwrap();
+        let err = reader.next_record().await.unwrap_err();
+        assert!(err.to_string().contains("offset: 8"));
+
+        assert!(FileValue::Bytes(vec![0xff]).into_string().is_err());
+        assert_eq!(FileValue::Bytes(vec![0xff]).into_bytes(), vec![0xff]);
+    }
+
+    #[test]
+    fn test_bool_value() {
+        assert_eq!(
+            FileValue::Json(serde_json::Value::Bool(true))
+                .into_string()
+                .unwrap(),
+            "true"
+        );
+    }
+}
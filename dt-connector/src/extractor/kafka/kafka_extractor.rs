@@ -1,88 +1,336 @@
-use std::sync::Arc;
+use std::{
+    collections::{BTreeMap, HashSet},
+    sync::Arc,
+};
 
-use anyhow::Context;
+use anyhow::{bail, Context};
 use async_trait::async_trait;
-use tokio::sync::Mutex;
+use tokio::{
+    sync::Mutex,
+    time::{timeout, Duration, Instant},
+};
 
 use rdkafka::{
-    consumer::{Consumer, StreamConsumer},
-    ClientConfig, Message, Offset, TopicPartitionList,
+    consumer::{CommitMode, Consumer, ConsumerContext, Rebalance, StreamConsumer},
+    ClientConfig, ClientContext, Message, Offset, TopicPartitionList,
 };
 
 use crate::extractor::resumer::cdc_resumer::CdcResumer;
 use crate::{extractor::base_extractor::BaseExtractor, Extractor};
-use dt_common::log_info;
-use dt_common::meta::{avro::avro_converter::AvroConverter, position::Position, syncer::Syncer};
+use dt_common::meta::{
+    avro::avro_converter::AvroConverter, dt_data::DtData, position::Position, syncer::Syncer,
+};
+use dt_common::{error::Error, log_error, log_info, log_warn};
+
+const CHECKPOINT_INTERVAL_SECS: u64 = 1;
+const SEEK_TIMEOUT_SECS: u64 = 10;
 
 pub struct KafkaExtractor {
     pub base_extractor: BaseExtractor,
     pub url: String,
     pub group: String,
-    pub topic: String,
+    pub topics: Vec<String>,
     pub partition: i32,
     pub offset: i64,
     pub ack_interval_secs: u64,
+    pub auto_offset_reset: String,
+    // key: topic:partition, value: offset of the last extracted message
+    pub partition_offsets: BTreeMap<String, i64>,
     pub avro_converter: AvroConverter,
     pub syncer: Arc<Mutex<Syncer>>,
     pub resumer: CdcResumer,
 }
 
+#[derive(Default)]
+pub struct KafkaConsumerContext {
+    // partitions assigned by group rebalancing, they should start from resumed offsets
+    assigned: std::sync::Mutex<Vec<(String, i32)>>,
+    // partitions revoked by group rebalancing, their offsets should no longer be tracked
+    revoked: std::sync::Mutex<Vec<(String, i32)>>,
+}
+
+impl ClientContext for KafkaConsumerContext {}
+
+impl ConsumerContext for KafkaConsumerContext {
+    fn pre_rebalance(&self, rebalance: &Rebalance) {
+        match rebalance {
+            Rebalance::Assign(tpl) => {
+                log_info!("kafka partitions assigned: {:?}", Self::to_partitions(tpl));
+            }
+            Rebalance::Revoke(tpl) => {
+                let partitions = Self::to_partitions(tpl);
+                log_info!("kafka partitions revoked: {:?}", partitions);
+                self.revoked.lock().unwrap().extend(partitions);
+            }
+            Rebalance::Error(err) => {
+                log_error!("kafka rebalance failed, error: {}", err);
+            }
+        }
+    }
+
+    fn post_rebalance(&self, rebalance: &Rebalance) {
+        // partitions can only be seeked after they are assigned
+        if let Rebalance::Assign(tpl) = rebalance {
+            self.assigned
+                .lock()
+                .unwrap()
+                .extend(Self::to_partitions(tpl));
+        }
+    }
+}
+
+impl KafkaConsumerContext {
+    fn to_partitions(tpl: &TopicPartitionList) -> Vec<(String, i32)> {
+        tpl.elements()
+            .iter()
+            .map(|i| (i.topic().to_string(), i.partition()))
+            .collect()
+    }
+
+    fn take_assigned(&self) -> Vec<(String, i32)> {
+        self.assigned.lock().unwrap().drain(..).collect()
+    }
+
+    fn take_revoked(&self) -> Vec<(String, i32)> {
+        self.revoked.lock().unwrap().drain(..).collect()
+    }
+}
+
 #[async_trait]
 impl Extractor for KafkaExtractor {
     async fn extract(&mut self) -> anyhow::Result<()> {
-        if let Position::Kafka { offset, .. } = &self.resumer.current_position {
-            self.offset = offset.to_owned();
-        };
+        let resumed_offsets = Position::merge_kafka_partition_offsets(
+            &self.resumer.checkpoint_position,
+            &self.resumer.current_position,
+        );
+        if self.is_assign_mode() {
+            if let Position::Kafka { offset, .. } = &self.resumer.current_position {
+                self.offset = offset.to_owned();
+            };
+        }
 
         log_info!(
-            "KafkaCdcExtractor starts, topic: {}, partition: {}, offset: {}",
-            self.topic,
+            "KafkaCdcExtractor starts, topics: {:?}, partition: {}, offset: {}, resumed offsets: {:?}",
+            self.topics,
             self.partition,
-            self.offset
+            self.offset,
+            resumed_offsets
         );
-        let consumer = self.create_consumer();
-        self.extract_avro(consumer).await
+        let consumer = self.create_consumer()?;
+        self.extract_avro(consumer, resumed_offsets).await
     }
 }
 
 impl KafkaExtractor {
-    async fn extract_avro(&mut self, consumer: StreamConsumer) -> anyhow::Result<()> {
+    async fn extract_avro(
+        &mut self,
+        consumer: StreamConsumer<KafkaConsumerContext>,
+        mut resumed_offsets: BTreeMap<String, i64>,
+    ) -> anyhow::Result<()> {
+        let mut last_ack_time = Instant::now();
+        let mut last_checkpoint_time = Instant::now();
+        // the last extracted message whose offset is not pushed by checkpoint yet
+        let mut last_position = Position::None;
         loop {
-            let msg = consumer
-                .recv()
-                .await
-                .with_context(|| format!("KafkaCdcExtractor failed, topic: {}", self.topic))?;
+            if self.ack_interval_secs > 0
+                && last_ack_time.elapsed().as_secs() >= self.ack_interval_secs
+            {
+                self.ack_offsets(&consumer).await;
+                last_ack_time = Instant::now();
+            }
+
+            if last_position != Position::None
+                && last_checkpoint_time.elapsed().as_secs() >= CHECKPOINT_INTERVAL_SECS
+            {
+                self.push_checkpoint(last_position).await?;
+                last_position = Position::None;
+                last_checkpoint_time = Instant::now();
+            }
+
+            for (topic, partition) in consumer.context().take_revoked() {
+                let key = Position::to_kafka_partition_key(&topic, partition);
+                self.partition_offsets.remove(&key);
+                // consumed by other members of the group since then, start from committed offsets
+                resumed_offsets.remove(&key);
+            }
+
+            // wake up periodically to ack offsets even if no new messages
+            let result = timeout(Duration::from_secs(1), consumer.recv()).await;
+            // rebalancing happens while polling
+            let seeked = Self::seek_assigned(&consumer, &resumed_offsets);
+            let msg = match result {
+                Ok(msg) => msg.with_context(|| {
+                    format!("KafkaCdcExtractor failed, topics: {:?}", self.topics)
+                })?,
+                Err(_) => continue,
+            };
+
+            let key = Position::to_kafka_partition_key(msg.topic(), msg.partition());
+            // fetched before seeking, it will be consumed again from the resumed offset
+            if seeked.contains(&key) {
+                continue;
+            }
+            // messages may be consumed again if offsets committed to kafka fall behind position.log
+            if let Some(resumed_offset) = resumed_offsets.get(&key) {
+                if msg.offset() <= *resumed_offset {
+                    continue;
+                }
+                resumed_offsets.remove(&key);
+            }
+            self.partition_offsets.insert(key, msg.offset());
+
+            // positions of messages only contain their own partitions,
+            // offsets of all partitions are pushed by checkpoints
+            let position = Position::Kafka {
+                topic: msg.topic().to_string(),
+                partition: msg.partition(),
+                offset: msg.offset(),
+                partition_offsets: BTreeMap::new(),
+            };
             if let Some(payload) = msg.payload() {
                 let dt_data = self
                     .avro_converter
                     .avro_value_to_dt_data(payload.to_vec())?;
-                let position = Position::Kafka {
-                    topic: self.topic.clone(),
-                    partition: self.partition,
-                    offset: msg.offset(),
-                };
-                self.base_extractor.push_dt_data(dt_data, position).await?;
+                self.base_extractor
+                    .push_dt_data(dt_data, position.clone())
+                    .await?;
+            }
+            last_position = position;
+        }
+    }
+
+    async fn push_checkpoint(&mut self, last_position: Position) -> anyhow::Result<()> {
+        if let Position::Kafka {
+            topic,
+            partition,
+            offset,
+            ..
+        } = last_position
+        {
+            let position = Position::Kafka {
+                topic,
+                partition,
+                offset,
+                partition_offsets: self.partition_offsets.clone(),
+            };
+            self.base_extractor
+                .push_dt_data(DtData::Heartbeat {}, position)
+                .await?;
+        }
+        Ok(())
+    }
+
+    async fn ack_offsets(&self, consumer: &StreamConsumer<KafkaConsumerContext>) {
+        // committed_position is updated by pipeline after data sinked
+        let position = self.syncer.lock().await.committed_position.clone();
+        let tpl = match Self::build_commit_tpl(&position.get_kafka_partition_offsets()) {
+            Ok(tpl) if tpl.count() > 0 => tpl,
+            Ok(_) => return,
+            Err(err) => {
+                log_warn!("failed to build kafka offsets to commit, error: {}", err);
+                return;
+            }
+        };
+
+        if let Err(err) = consumer.commit(&tpl, CommitMode::Async) {
+            log_warn!("failed to commit kafka offsets, error: {}", err);
+        }
+    }
+
+    fn build_commit_tpl(offsets: &BTreeMap<String, i64>) -> anyhow::Result<TopicPartitionList> {
+        let mut tpl = TopicPartitionList::new();
+        for (key, offset) in offsets.iter() {
+            if let Some((topic, partition)) = Position::from_kafka_partition_key(key) {
+                // committed offset is the offset of the next message to consume
+                tpl.add_partition_offset(&topic, partition, Offset::Offset(offset + 1))?;
+            }
+        }
+        Ok(tpl)
+    }
+
+    /// Newly assigned partitions start from offsets in position.log instead of committed offsets,
+    /// partitions not in position.log start from committed offsets or auto.offset.reset
+    fn seek_assigned(
+        consumer: &StreamConsumer<KafkaConsumerContext>,
+        resumed_offsets: &BTreeMap<String, i64>,
+    ) -> HashSet<String> {
+        let mut seeked = HashSet::new();
+        for (topic, partition) in consumer.context().take_assigned() {
+            let key = Position::to_kafka_partition_key(&topic, partition);
+            let Some(offset) = resumed_offsets.get(&key) else {
+                continue;
+            };
+            match consumer.seek(
+                &topic,
+                partition,
+                Offset::Offset(offset + 1),
+                Duration::from_secs(SEEK_TIMEOUT_SECS),
+            ) {
+                Ok(_) => {
+                    log_info!("kafka partition: {} seeked to offset: {}", key, offset + 1);
+                    seeked.insert(key);
+                }
+                // messages before the resumed offset are still skipped
+                Err(err) => log_warn!(
+                    "failed to seek kafka partition: {} to resumed offset: {}, error: {}",
+                    key,
+                    offset + 1,
+                    err
+                ),
             }
         }
+        seeked
+    }
+
+    fn is_assign_mode(&self) -> bool {
+        self.partition >= 0
     }
 
-    fn create_consumer(&self) -> StreamConsumer {
+    fn create_consumer(&self) -> anyhow::Result<StreamConsumer<KafkaConsumerContext>> {
+        if self.topics.is_empty() {
+            bail! {Error::ConfigError("no kafka topic configured".into())}
+        }
+
         let mut config = ClientConfig::new();
         config.set("bootstrap.servers", &self.url);
         config.set("group.id", &self.group);
-        config.set("auto.offset.reset", "latest");
+        config.set("auto.offset.reset", &self.auto_offset_reset);
         config.set("session.timeout.ms", "10000");
+        // offsets are committed only after data sinked, refer to: ack_offsets
+        config.set("enable.auto.commit", "false");
 
-        let consumer: StreamConsumer = config.create().unwrap();
-        // only support extract data from one topic, one partition
-        let mut tpl = TopicPartitionList::new();
-        if self.offset >= 0 {
-            tpl.add_partition_offset(&self.topic, self.partition, Offset::Offset(self.offset))
-                .unwrap();
-        } else {
-            tpl.add_partition(&self.topic, self.partition);
+        let consumer: StreamConsumer<KafkaConsumerContext> = config
+            .create_with_context(KafkaConsumerContext::default())
+            .with_context(|| format!("failed to create kafka consumer, url: [{}]", self.url))?;
+
+        if self.is_assign_mode() {
+            // extract data from one topic, one partition
+            if self.topics.len() > 1 || self.topics[0].starts_with('^') {
+                bail! {Error::ConfigError(format!(
+                    "only one topic name is allowed when partition is specified, topics: {:?}",
+                    self.topics
+                ))}
+            }
+
+            let mut tpl = TopicPartitionList::new();
+            if self.offset >= 0 {
+                tpl.add_partition_offset(
+                    &self.topics[0],
+                    self.partition,
+                    Offset::Offset(self.offset),
+                )?;
+            } else {
+                tpl.add_partition(&self.topics[0], self.partition);
+            }
+            consumer.assign(&tpl)?;
+            return Ok(consumer);
         }
-        consumer.assign(&tpl).unwrap();
+
+        // offsets in position.log are applied once partitions are assigned, refer to: seek_assigned
+        let topics: Vec<&str> = self.topics.iter().map(|i| i.as_str()).collect();
         consumer
+            .subscribe(&topics)
+            .with_context(|| format!("failed to subscribe kafka topics: {:?}", self.topics))?;
+        Ok(consumer)
     }
 }
@@ -6,10 +6,14 @@ use std::{
     },
 };
 
+use anyhow::bail;
 use async_trait::async_trait;
 use chrono::Utc;
 use serde_json::json;
-use tokio::{sync::Mutex, time::Instant};
+use tokio::{
+    sync::{mpsc, Mutex},
+    time::Instant,
+};
 
 use crate::{
     extractor::{base_extractor::BaseExtractor, resumer::cdc_resumer::CdcResumer},
@@ -17,10 +21,14 @@ use crate::{
 };
 use dt_common::{
     config::config_enums::DbType,
-    log_error, log_info,
+    error::Error,
+    log_error, log_info, log_warn,
     meta::col_value::ColValue,
+    meta::ddl_meta::ddl_data::DdlData,
     meta::dt_data::DtData,
-    meta::mongo::{mongo_cdc_source::MongoCdcSource, mongo_constant::MongoConstants},
+    meta::mongo::{
+        mongo_cdc_source::MongoCdcSource, mongo_constant::MongoConstants, mongo_ddl::MongoDdl,
+    },
     meta::position::Position,
     meta::row_data::RowData,
     meta::row_type::RowType,
@@ -30,12 +38,28 @@ use dt_common::{
 };
 use mongodb::{
     bson::{doc, Bson, Document, Timestamp},
-    change_stream::event::{OperationType, ResumeToken},
-    options::{ChangeStreamOptions, FullDocumentBeforeChangeType, FullDocumentType, UpdateOptions},
+    change_stream::{
+        event::{ChangeStreamEvent, OperationType, ResumeToken},
+        ChangeStream,
+    },
+    error::ErrorKind,
+    options::{
+        ChangeStreamOptions, ClientOptions, FullDocumentBeforeChangeType, FullDocumentType,
+        ServerAddress, UpdateOptions,
+    },
     Client,
 };
 
 const SYSTEM_DBS: [&str; 3] = ["admin", "config", "local"];
+const OPLOG_CHANNEL_SIZE: usize = 10000;
+// ddl oplogs of the same command from other shards within the window are duplicates
+const SHARD_DDL_WINDOW_SECS: u32 = 60;
+// https://github.com/mongodb/mongo/blob/master/src/mongo/base/error_codes.yml
+// returned when the resume token is not found in the oplog
+const CHANGE_STREAM_FATAL_ERROR: i32 = 280;
+const CHANGE_STREAM_HISTORY_LOST: i32 = 286;
+// showExpandedEvents is supported since 6.0
+const EXPANDED_EVENTS_MIN_MAJOR_VERSION: u32 = 6;
 
 pub struct MongoCdcExtractor {
     pub base_extractor: BaseExtractor,
@@ -43,6 +67,7 @@ pub struct MongoCdcExtractor {
     pub resume_token: String,
     pub start_timestamp: u32,
     pub source: MongoCdcSource,
+    pub url: String,
     pub mongo_client: Client,
     pub app_name: String,
     pub heartbeat_interval_secs: u64,
@@ -97,6 +122,120 @@ impl Extractor for MongoCdcExtractor {
 impl MongoCdcExtractor {
     async fn extract_oplog(&mut self) -> anyhow::Result<()> {
         let start_timestamp = self.parse_start_timestamp();
+        let mut clients = self.get_shard_clients().await?;
+        if clients.is_empty() {
+            clients.push(self.mongo_client.clone());
+        }
+
+        let (tx, mut rx) = mpsc::channel(OPLOG_CHANNEL_SIZE);
+        for (shard_index, client) in clients.iter().cloned().enumerate() {
+            let tx = tx.clone();
+            tokio::spawn(async move {
+                if let Err(err) = Self::tail_oplog(shard_index, &client, start_timestamp, &tx).await
+                {
+                    let _ = tx.send((shard_index, Err(err))).await;
+                }
+            });
+        }
+        drop(tx);
+
+        // oplogs of shards are tailed separately, the position only moves forward to
+        // the min ts of all shards, so no oplog of any shard is missed when resuming
+        let mut shard_timestamps = vec![start_timestamp; clients.len()];
+        let mut shard_ddls = HashMap::new();
+        while let Some((shard_index, doc)) = rx.recv().await {
+            let doc = doc?;
+            let position_ts =
+                Self::update_shard_timestamp(&mut shard_timestamps, shard_index, &doc);
+            let position = Self::build_oplog_position(&position_ts);
+            if clients.len() > 1
+                && Self::is_dup_shard_ddl(
+                    &mut shard_ddls,
+                    clients.len(),
+                    shard_index,
+                    &doc,
+                    &position_ts,
+                )
+            {
+                self.base_extractor
+                    .push_dt_data(DtData::Heartbeat {}, position)
+                    .await?;
+                continue;
+            }
+            self.parse_oplog(doc, position).await?;
+        }
+        Ok(())
+    }
+
+    /// Ddl of a sharded collection / database is executed by each shard, a "c" oplog of the
+    /// same command is generated in every shard, only the first of them is synced.
+    /// shard_ddls, key: ns + command, value: (ts of the first oplog, shards generated the oplog)
+    fn is_dup_shard_ddl(
+        shard_ddls: &mut HashMap<String, (Timestamp, Vec<usize>)>,
+        shard_count: usize,
+        shard_index: usize,
+        doc: &Document,
+        position_ts: &Timestamp,
+    ) -> bool {
+        // ddl NOT executed by all shards (e.g. of unsharded collections) is kept until expired
+        shard_ddls.retain(|_, (ts, _)| ts.time + SHARD_DDL_WINDOW_SECS >= position_ts.time);
+
+        if Self::get_op(doc) != "c" {
+            return false;
+        }
+        let (Some(Bson::String(ns)), Some(Bson::Document(o)), Some(Bson::Timestamp(ts))) =
+            (doc.get("ns"), doc.get("o"), doc.get("ts"))
+        else {
+            return false;
+        };
+        if MongoDdl::from_oplog(ns, o).is_none() {
+            return false;
+        }
+
+        let key = format!("{}:{}", ns, o);
+        match shard_ddls.get_mut(&key) {
+            Some((_, shards)) if !shards.contains(&shard_index) => {
+                shards.push(shard_index);
+                if shards.len() >= shard_count {
+                    shard_ddls.remove(&key);
+                }
+                true
+            }
+            // executed again by the same shard, it is a new ddl
+            _ => {
+                shard_ddls.insert(key, (*ts, vec![shard_index]));
+                false
+            }
+        }
+    }
+
+    /// Records ts of the latest oplog of the shard, returns the min ts of all shards
+    fn update_shard_timestamp(
+        shard_timestamps: &mut [Timestamp],
+        shard_index: usize,
+        doc: &Document,
+    ) -> Timestamp {
+        if let Some(Bson::Timestamp(ts)) = doc.get("ts") {
+            shard_timestamps[shard_index] = *ts;
+        }
+        *shard_timestamps
+            .iter()
+            .min_by_key(|ts| (ts.time, ts.increment))
+            .unwrap()
+    }
+
+    /// chunk migrations between shards generate inserts in the recipient shard and deletes in
+    /// the donor shard, so do the range deleter for orphaned documents, they are NOT user changes
+    fn is_from_migrate(doc: &Document) -> bool {
+        doc.get_bool("fromMigrate").unwrap_or(false)
+    }
+
+    async fn tail_oplog(
+        shard_index: usize,
+        client: &Client,
+        start_timestamp: Timestamp,
+        tx: &mpsc::Sender<(usize, anyhow::Result<Document>)>,
+    ) -> anyhow::Result<()> {
         let filter = doc! {
             "ts": { "$gte": start_timestamp }
         };
@@ -104,117 +243,164 @@ impl MongoCdcExtractor {
             .cursor_type(mongodb::options::CursorType::TailableAwait)
             .build();
 
-        let oplog = self
-            .mongo_client
-            .database("local")
-            .collection::<Document>("oplog.rs");
+        let oplog = client.database("local").collection::<Document>("oplog.rs");
         let mut cursor = oplog.find(filter, options).await?;
-
         while cursor.advance().await? {
             let doc: Document = cursor.deserialize_current()?;
-            // https://github.com/mongodb/mongo/blob/master/src/mongo/db/repl/oplog.cpp
-            // op:
-            //     "i" insert
-            //     "u" update
-            //     "d" delete
-            //     "c" db cmd
-            //     "n" no op
-            //     "xi" insert global index key
-            //     "xd" delete global index key
-
-            let op = Self::get_op(&doc);
-            let mut row_type = RowType::Insert;
-            let mut before = HashMap::new();
-            let mut after = HashMap::new();
-            let o = doc.get("o");
-            let o2 = doc.get("o2");
-            let ts = doc.get("ts");
-            let ns = doc.get("ns");
-
-            match op.as_str() {
-                "i" => {
-                    after.insert(
-                        MongoConstants::DOC.to_string(),
-                        ColValue::MongoDoc(o.unwrap().as_document().unwrap().clone()),
-                    );
-                }
-                "u" => {
-                    row_type = RowType::Update;
-                    // for update op log, doc.o contains only diff instead of full doc
-                    let after_doc = o.unwrap().as_document().unwrap();
-                    // refer: https://www.mongodb.com/community/forums/t/oplog-update-entry-without-set-and-unset/171771
-                    // https://www.mongodb.com/docs/manual/reference/operator/update/#update-operators-1
-                    // in MongoDB 4.4 and earlier, after_doc contains $set with all new document fields,
-                    // after that, after_doc contains diff with only changed fields.
-                    let diff_doc = if let Some(doc) = after_doc.get("diff") {
-                        let doc = doc.as_document().unwrap();
-                        if let Some(i_doc) = doc.get("i") {
-                            doc! {MongoConstants::SET: i_doc.as_document().unwrap()}
-                        } else if let Some(u_doc) = doc.get("u") {
-                            doc! {MongoConstants::SET: u_doc.as_document().unwrap()}
-                        } else if let Some(d_doc) = doc.get("d") {
-                            doc! {MongoConstants::UNSET: d_doc.as_document().unwrap()}
-                        } else {
-                            doc! {}
-                        }
-                    } else if let Some(set_doc) = after_doc.get(MongoConstants::SET) {
-                        doc! {MongoConstants::SET: set_doc.as_document().unwrap()}
-                    } else if let Some(unset_doc) = after_doc.get(MongoConstants::UNSET) {
-                        doc! {MongoConstants::UNSET: unset_doc.as_document().unwrap()}
+            if tx.send((shard_index, Ok(doc))).await.is_err() {
+                break;
+            }
+        }
+        Ok(())
+    }
+
+    /// Connects to the replica set of each shard if the url is a mongos, oplogs can
+    /// only be tailed from shards. Returns empty if the url is NOT a mongos
+    async fn get_shard_clients(&self) -> anyhow::Result<Vec<Client>> {
+        let admin = self.mongo_client.database("admin");
+        let is_master = admin.run_command(doc! {"isMaster": 1}, None).await?;
+        if is_master.get_str("msg").unwrap_or_default() != "isdbgrid" {
+            return Ok(Vec::new());
+        }
+
+        let mut clients = Vec::new();
+        let list_shards = admin.run_command(doc! {"listShards": 1}, None).await?;
+        for shard in list_shards.get_array("shards")? {
+            let Some(host) = shard.as_document().and_then(|i| i.get_str("host").ok()) else {
+                continue;
+            };
+            // rs0/host1:27017,host2:27017
+            let (repl_set_name, hosts) = match host.split_once('/') {
+                Some((repl_set_name, hosts)) => (Some(repl_set_name.to_string()), hosts),
+                None => (None, host),
+            };
+
+            // shards are connected with the same credentials and options as mongos
+            let mut client_options = ClientOptions::parse_async(&self.url).await?;
+            client_options.hosts = hosts
+                .split(',')
+                .map(ServerAddress::parse)
+                .collect::<Result<_, _>>()?;
+            client_options.repl_set_name = repl_set_name;
+            client_options.direct_connection = None;
+            client_options.app_name = Some(self.app_name.clone());
+            log_info!("tailing oplog of shard: {}", host);
+            clients.push(Client::with_options(client_options)?);
+        }
+        Ok(clients)
+    }
+
+    async fn parse_oplog(&mut self, doc: Document, position: Position) -> anyhow::Result<()> {
+        if Self::is_from_migrate(&doc) {
+            return Ok(());
+        }
+
+        // https://github.com/mongodb/mongo/blob/master/src/mongo/db/repl/oplog.cpp
+        // op:
+        //     "i" insert
+        //     "u" update
+        //     "d" delete
+        //     "c" db cmd
+        //     "n" no op
+        //     "xi" insert global index key
+        //     "xd" delete global index key
+
+        let op = Self::get_op(&doc);
+        let mut row_type = RowType::Insert;
+        let mut before = HashMap::new();
+        let mut after = HashMap::new();
+        let o = doc.get("o");
+        let o2 = doc.get("o2");
+        let ns = doc.get("ns");
+
+        match op.as_str() {
+            "i" => {
+                after.insert(
+                    MongoConstants::DOC.to_string(),
+                    ColValue::MongoDoc(o.unwrap().as_document().unwrap().clone()),
+                );
+            }
+            "u" => {
+                row_type = RowType::Update;
+                // for update op log, doc.o contains only diff instead of full doc
+                let after_doc = o.unwrap().as_document().unwrap();
+                // refer: https://www.mongodb.com/community/forums/t/oplog-update-entry-without-set-and-unset/171771
+                // https://www.mongodb.com/docs/manual/reference/operator/update/#update-operators-1
+                // in MongoDB 4.4 and earlier, after_doc contains $set with all new document fields,
+                // after that, after_doc contains diff with only changed fields.
+                let diff_doc = if let Some(doc) = after_doc.get("diff") {
+                    let doc = doc.as_document().unwrap();
+                    if let Some(i_doc) = doc.get("i") {
+                        doc! {MongoConstants::SET: i_doc.as_document().unwrap()}
+                    } else if let Some(u_doc) = doc.get("u") {
+                        doc! {MongoConstants::SET: u_doc.as_document().unwrap()}
+                    } else if let Some(d_doc) = doc.get("d") {
+                        doc! {MongoConstants::UNSET: d_doc.as_document().unwrap()}
                     } else {
                         doc! {}
-                    };
-
-                    if diff_doc.is_empty() {
-                        log_error!(
-                            "update op_log is neither $set nor $unset, ignore, o2: {:?}, o: {:?}",
-                            o2,
-                            o
-                        );
-                        continue;
                     }
+                } else if let Some(set_doc) = after_doc.get(MongoConstants::SET) {
+                    doc! {MongoConstants::SET: set_doc.as_document().unwrap()}
+                } else if let Some(unset_doc) = after_doc.get(MongoConstants::UNSET) {
+                    doc! {MongoConstants::UNSET: unset_doc.as_document().unwrap()}
+                } else {
+                    doc! {}
+                };
 
-                    after.insert(
-                        MongoConstants::DIFF_DOC.to_string(),
-                        ColValue::MongoDoc(diff_doc.clone()),
-                    );
-                    before.insert(
-                        MongoConstants::DOC.to_string(),
-                        ColValue::MongoDoc(o2.unwrap().as_document().unwrap().clone()),
-                    );
-                }
-                "d" => {
-                    row_type = RowType::Delete;
-                    before.insert(
-                        MongoConstants::DOC.to_string(),
-                        ColValue::MongoDoc(o.unwrap().as_document().unwrap().clone()),
+                if diff_doc.is_empty() {
+                    log_error!(
+                        "update op_log is neither $set nor $unset, ignore, o2: {:?}, o: {:?}",
+                        o2,
+                        o
                     );
+                    return Ok(());
                 }
-                // TODO, DDL
-                "c" | "xi" | "xd" => {
-                    // after version 7.0, the oplog generated by deleteMany is "c" instead of "d"
-                    let data = Self::extract_oplog_delete_many(&doc);
-                    for (row_data, position) in data {
-                        self.push_row_to_buf(row_data, position).await.unwrap();
+
+                after.insert(
+                    MongoConstants::DIFF_DOC.to_string(),
+                    ColValue::MongoDoc(diff_doc.clone()),
+                );
+                before.insert(
+                    MongoConstants::DOC.to_string(),
+                    ColValue::MongoDoc(o2.unwrap().as_document().unwrap().clone()),
+                );
+            }
+            "d" => {
+                row_type = RowType::Delete;
+                before.insert(
+                    MongoConstants::DOC.to_string(),
+                    ColValue::MongoDoc(o.unwrap().as_document().unwrap().clone()),
+                );
+            }
+            "c" | "xi" | "xd" => {
+                if let (Some(Bson::String(ns)), Some(Bson::Document(o))) = (ns, o) {
+                    if let Some(ddl_data) = MongoDdl::from_oplog(ns, o) {
+                        return self.push_ddl_to_buf(ddl_data, position).await;
                     }
-                    continue;
-                }
-                "n" => {
-                    // TODO, heartbeat
-                    // Document({"op": String("n"), "ns": String(""), "o": Document({"msg": String("periodic noop")}), "ts": Timestamp { time: 1693470874, increment: 1 }, "t": Int64(67), "v": Int64(2), "wall": DateTime(2023-08-31 8:34:34.19 +00:00:00)})
-                    continue;
                 }
-                _ => {
-                    continue;
+
+                // after version 7.0, the oplog generated by deleteMany is "c" instead of "d"
+                for row_data in Self::extract_oplog_delete_many(&doc) {
+                    self.push_row_to_buf(row_data, position.clone()).await?;
                 }
+                return Ok(());
+            }
+            "n" => {
+                // Document({"op": String("n"), "ns": String(""), "o": Document({"msg": String("periodic noop")}), "ts": Timestamp { time: 1693470874, increment: 1 }, "t": Int64(67), "v": Int64(2), "wall": DateTime(2023-08-31 8:34:34.19 +00:00:00)})
+                // periodic noops are written by idle primaries, they move the position forward
+                return self
+                    .base_extractor
+                    .push_dt_data(DtData::Heartbeat {}, position)
+                    .await;
+            }
+            _ => {
+                return Ok(());
             }
-
-            // get db & tb
-            let (row_data, position) =
-                Self::build_oplog_row_data(&ns, &ts, row_type, before, after);
-            self.push_row_to_buf(row_data, position).await?;
         }
-        Ok(())
+
+        // get db & tb
+        let row_data = Self::build_oplog_row_data(&ns, row_type, before, after);
+        self.push_row_to_buf(row_data, position).await
     }
 
     fn get_op(doc: &Document) -> String {
@@ -225,7 +411,7 @@ impl MongoCdcExtractor {
         op.into()
     }
 
-    fn extract_oplog_delete_many(doc: &Document) -> Vec<(RowData, Position)> {
+    fn extract_oplog_delete_many(doc: &Document) -> Vec<RowData> {
         // Some(Document({
         //     "applyOps": Array([Document({
         //         "op": String("d"),
@@ -252,7 +438,6 @@ impl MongoCdcExtractor {
 
         let mut data = vec![];
         let o = doc.get("o");
-        let ts = doc.get("ts");
 
         if o.is_none() || o.unwrap().as_document().is_none() {
             return data;
@@ -277,7 +462,7 @@ impl MongoCdcExtractor {
             let op = Self::get_op(item);
             let ns = item.get("ns");
 
-            if op.as_str() != "d" {
+            if op.as_str() != "d" || Self::is_from_migrate(item) {
                 continue;
             }
 
@@ -290,7 +475,6 @@ impl MongoCdcExtractor {
 
             data.push(Self::build_oplog_row_data(
                 &ns,
-                &ts,
                 RowType::Delete,
                 before,
                 HashMap::new(),
@@ -301,12 +485,10 @@ impl MongoCdcExtractor {
 
     fn build_oplog_row_data(
         ns: &Option<&Bson>,
-        ts: &Option<&Bson>,
         row_type: RowType,
         before: HashMap<String, ColValue>,
         after: HashMap<String, ColValue>,
-    ) -> (RowData, Position) {
-        let ts = ts.unwrap().as_timestamp().unwrap();
+    ) -> RowData {
         let ns = ns.unwrap().as_str().unwrap();
 
         // get db & tb
@@ -320,24 +502,99 @@ impl MongoCdcExtractor {
         };
         let after = if after.is_empty() { None } else { Some(after) };
 
-        // get ts for position
-        let position = Position::MongoCdc {
+        RowData::new(db, tb, row_type, before, after)
+    }
+
+    fn build_oplog_position(ts: &Timestamp) -> Position {
+        Position::MongoCdc {
             resume_token: String::new(),
             operation_time: ts.time,
             timestamp: Position::format_timestamp_millis(ts.time as i64 * 1000),
-        };
-        let row_data = RowData::new(db, tb, row_type, before, after);
-        (row_data, position)
+        }
     }
 
     async fn extract_change_stream(&mut self) -> anyhow::Result<()> {
-        let (resume_token, start_timestamp) = if self.resume_token.is_empty() {
-            (None, Some(self.parse_start_timestamp()))
+        let show_expanded_events = self.check_expanded_events().await?;
+        let mut change_stream = if self.resume_token.is_empty() {
+            let start_timestamp = self.parse_start_timestamp();
+            self.open_change_stream(None, Some(start_timestamp), show_expanded_events)
+                .await?
         } else {
             let token: ResumeToken = serde_json::from_str(&self.resume_token)?;
-            (Some(token), None)
+            match self
+                .open_change_stream(Some(token), None, show_expanded_events)
+                .await
+            {
+                Ok(change_stream) => change_stream,
+                // resume tokens may fall off the oplog of some shard, or be missing after topology
+                // changes, e.g. replica set -> sharded cluster, start at the operation time instead
+                Err(err) if self.start_timestamp > 0 && Self::is_resume_token_lost(&err) => {
+                    log_warn!(
+                        "failed to open change stream by resume_token: {}, start at operation time: {} instead, error: {}",
+                        self.resume_token,
+                        self.start_timestamp,
+                        err
+                    );
+                    let start_timestamp = self.parse_start_timestamp();
+                    self.open_change_stream(None, Some(start_timestamp), show_expanded_events)
+                        .await?
+                }
+                Err(err) => return Err(err),
+            }
         };
 
+        loop {
+            if let Some(raw_doc) = change_stream.next_if_any().await? {
+                self.parse_change_stream_event(raw_doc).await?;
+            }
+        }
+    }
+
+    fn is_resume_token_lost(err: &anyhow::Error) -> bool {
+        match err.downcast_ref::<mongodb::error::Error>() {
+            Some(err) => matches!(
+                err.kind.as_ref(),
+                ErrorKind::Command(command_error)
+                    if command_error.code == CHANGE_STREAM_FATAL_ERROR
+                        || command_error.code == CHANGE_STREAM_HISTORY_LOST
+            ),
+            None => false,
+        }
+    }
+
+    /// Events of create, createIndexes, dropIndexes and modify are generated with
+    /// showExpandedEvents since 6.0, which fails change streams of earlier versions
+    async fn check_expanded_events(&self) -> anyhow::Result<bool> {
+        if self.filter.filter_all_ddl() {
+            return Ok(false);
+        }
+
+        let build_info = self
+            .mongo_client
+            .database("admin")
+            .run_command(doc! {"buildInfo": 1}, None)
+            .await?;
+        let version = build_info.get_str("version").unwrap_or_default();
+        match Self::parse_major_version(version) {
+            Some(major_version) if major_version >= EXPANDED_EVENTS_MIN_MAJOR_VERSION => Ok(true),
+            _ => bail! {Error::ConfigError(format!(
+                "ddl of change stream requires mongo 6.0+, server version: {}, unset [filter] do_ddls or set [extractor] source=op_log",
+                version
+            ))},
+        }
+    }
+
+    fn parse_major_version(version: &str) -> Option<u32> {
+        // 6.0.3, 7.0.0-rc1
+        version.split('.').next()?.parse().ok()
+    }
+
+    async fn open_change_stream(
+        &self,
+        resume_token: Option<ResumeToken>,
+        start_timestamp: Option<Timestamp>,
+        show_expanded_events: bool,
+    ) -> anyhow::Result<ChangeStream<Document>> {
         // refer: https://www.mongodb.com/docs/manual/changeStreams/
         // Starting in MongoDB 6.0, you can use change stream events to output the version of
         // a document before and after changes (the document pre- and post-images)
@@ -346,81 +603,90 @@ impl MongoCdcExtractor {
             .start_after(resume_token)
             .full_document(Some(FullDocumentType::UpdateLookup))
             .full_document_before_change(Some(FullDocumentBeforeChangeType::WhenAvailable))
+            // refer to: check_expanded_events
+            .show_expanded_events(show_expanded_events.then_some(true))
             .build();
 
-        let mut change_stream = self.mongo_client.watch(None, stream_options).await?;
-        loop {
-            let result = change_stream.next_if_any().await?;
-            if let Some(doc) = result {
-                let resume_token = doc.id;
-                let position = if let Some(operation_time) = doc.cluster_time {
-                    Position::MongoCdc {
-                        resume_token: json!(resume_token).to_string(),
-                        operation_time: operation_time.time,
-                        timestamp: Position::format_timestamp_millis(
-                            operation_time.time as i64 * 1000,
-                        ),
-                    }
-                } else {
-                    Position::MongoCdc {
-                        resume_token: json!(resume_token).to_string(),
-                        operation_time: 0,
-                        timestamp: String::new(),
-                    }
-                };
+        // a cluster-wide change stream of all databases, through mongos for sharded clusters,
+        // events of chunk migrations are NOT included.
+        // raw events are kept since operationDescription of ddl events is not in ChangeStreamEvent
+        let change_stream = self
+            .mongo_client
+            .watch(None, stream_options)
+            .await?
+            .with_type::<Document>();
+        Ok(change_stream)
+    }
 
-                let (mut db, mut tb) = (String::new(), String::new());
-                if let Some(ns) = doc.ns {
-                    db = ns.db.clone();
-                    if let Some(coll) = ns.coll {
-                        tb = coll.clone();
-                    }
-                }
+    async fn parse_change_stream_event(&mut self, raw_doc: Document) -> anyhow::Result<()> {
+        let doc: ChangeStreamEvent<Document> = mongodb::bson::from_document(raw_doc.clone())?;
+        let resume_token = doc.id;
+        let position = if let Some(operation_time) = doc.cluster_time {
+            Position::MongoCdc {
+                resume_token: json!(resume_token).to_string(),
+                operation_time: operation_time.time,
+                timestamp: Position::format_timestamp_millis(operation_time.time as i64 * 1000),
+            }
+        } else {
+            Position::MongoCdc {
+                resume_token: json!(resume_token).to_string(),
+                operation_time: 0,
+                timestamp: String::new(),
+            }
+        };
 
-                let mut row_type = RowType::Insert;
-                let mut before = HashMap::new();
-                let mut after = HashMap::new();
+        let (mut db, mut tb) = (String::new(), String::new());
+        if let Some(ns) = doc.ns {
+            db = ns.db.clone();
+            if let Some(coll) = ns.coll {
+                tb = coll.clone();
+            }
+        }
 
-                match doc.operation_type {
-                    OperationType::Insert => {
-                        after.insert(
-                            MongoConstants::DOC.to_string(),
-                            ColValue::MongoDoc(doc.full_document.unwrap()),
-                        );
-                    }
+        let mut row_type = RowType::Insert;
+        let mut before = HashMap::new();
+        let mut after = HashMap::new();
 
-                    OperationType::Delete => {
-                        row_type = RowType::Delete;
-                        before.insert(
-                            MongoConstants::DOC.to_string(),
-                            ColValue::MongoDoc(doc.document_key.unwrap()),
-                        );
-                    }
+        match doc.operation_type {
+            OperationType::Insert => {
+                after.insert(
+                    MongoConstants::DOC.to_string(),
+                    ColValue::MongoDoc(doc.full_document.unwrap()),
+                );
+            }
 
-                    OperationType::Update | OperationType::Replace => {
-                        row_type = RowType::Update;
-                        if let Some(document) = doc.full_document {
-                            before.insert(
-                                MongoConstants::DOC.to_string(),
-                                ColValue::MongoDoc(doc.document_key.unwrap()),
-                            );
-                            after.insert(
-                                MongoConstants::DOC.to_string(),
-                                ColValue::MongoDoc(document),
-                            );
-                        }
-                    }
+            OperationType::Delete => {
+                row_type = RowType::Delete;
+                before.insert(
+                    MongoConstants::DOC.to_string(),
+                    ColValue::MongoDoc(doc.document_key.unwrap()),
+                );
+            }
 
-                    // TODO, heartbeat and DDL
-                    _ => {
-                        continue;
-                    }
+            OperationType::Update | OperationType::Replace => {
+                row_type = RowType::Update;
+                if let Some(document) = doc.full_document {
+                    before.insert(
+                        MongoConstants::DOC.to_string(),
+                        ColValue::MongoDoc(doc.document_key.unwrap()),
+                    );
+                    after.insert(
+                        MongoConstants::DOC.to_string(),
+                        ColValue::MongoDoc(document),
+                    );
                 }
+            }
 
-                let row_data = RowData::new(db, tb, row_type, Some(before), Some(after));
-                self.push_row_to_buf(row_data, position).await?;
+            _ => {
+                if let Some(ddl_data) = MongoDdl::from_change_event(&raw_doc) {
+                    return self.push_ddl_to_buf(ddl_data, position).await;
+                }
+                return Ok(());
             }
         }
+
+        let row_data = RowData::new(db, tb, row_type, Some(before), Some(after));
+        self.push_row_to_buf(row_data, position).await
     }
 
     async fn push_row_to_buf(
@@ -443,6 +709,24 @@ impl MongoCdcExtractor {
         }
     }
 
+    async fn push_ddl_to_buf(
+        &mut self,
+        ddl_data: DdlData,
+        position: Position,
+    ) -> anyhow::Result<()> {
+        let (db, tb) = ddl_data.get_schema_tb();
+        if SYSTEM_DBS.contains(&db.as_str()) {
+            return Ok(());
+        }
+        if self.filter.filter_ddl(&db, &tb, &ddl_data.ddl_type) {
+            self.base_extractor
+                .push_dt_data(DtData::Heartbeat {}, position)
+                .await
+        } else {
+            self.base_extractor.push_ddl(ddl_data, position).await
+        }
+    }
+
     fn parse_start_timestamp(&mut self) -> Timestamp {
         let time = if self.start_timestamp > 0 {
             self.start_timestamp
@@ -464,11 +748,12 @@ impl MongoCdcExtractor {
 
         self.filter.add_ignore_tb(&db_tb[0], &db_tb[1]);
 
-        let (app_name, heartbeat_interval_secs, syncer, mongo_client) = (
+        let (app_name, heartbeat_interval_secs, syncer, mongo_client, monitor) = (
             self.app_name.clone(),
             self.heartbeat_interval_secs,
             self.syncer.clone(),
             self.mongo_client.clone(),
+            self.base_extractor.monitor.monitor.clone(),
         );
 
         tokio::spawn(async move {
@@ -478,6 +763,7 @@ impl MongoCdcExtractor {
                     Self::heartbeat(&app_name, &db_tb[0], &db_tb[1], &syncer, &mongo_client)
                         .await
                         .unwrap();
+                    BaseExtractor::update_heartbeat_delay(&monitor, &syncer).await;
                     start_time = Instant::now();
                 }
                 TimeUtil::sleep_millis(1000 * heartbeat_interval_secs).await;
@@ -547,3 +833,156 @@ impl MongoCdcExtractor {
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use mongodb::bson::from_document;
+
+    use super::*;
+
+    fn new_oplog(time: u32, increment: u32) -> Document {
+        doc! {"op": "n", "ts": Timestamp { time, increment }}
+    }
+
+    #[test]
+    fn test_update_shard_timestamp() {
+        let start_timestamp = Timestamp {
+            time: 100,
+            increment: 0,
+        };
+        let mut shard_timestamps = vec![start_timestamp; 3];
+
+        // other shards have not sent any oplog
+        let ts =
+            MongoCdcExtractor::update_shard_timestamp(&mut shard_timestamps, 0, &new_oplog(200, 1));
+        assert_eq!(ts, start_timestamp);
+
+        MongoCdcExtractor::update_shard_timestamp(&mut shard_timestamps, 1, &new_oplog(150, 2));
+        let ts =
+            MongoCdcExtractor::update_shard_timestamp(&mut shard_timestamps, 2, &new_oplog(150, 1));
+        assert_eq!(
+            ts,
+            Timestamp {
+                time: 150,
+                increment: 1
+            }
+        );
+
+        // the min ts moves forward when the slowest shard moves
+        let ts =
+            MongoCdcExtractor::update_shard_timestamp(&mut shard_timestamps, 2, &new_oplog(300, 1));
+        assert_eq!(
+            ts,
+            Timestamp {
+                time: 150,
+                increment: 2
+            }
+        );
+
+        // docs without ts keep the position
+        let ts = MongoCdcExtractor::update_shard_timestamp(&mut shard_timestamps, 1, &doc! {});
+        assert_eq!(ts.time, 150);
+    }
+
+    #[test]
+    fn test_dup_shard_ddl() {
+        let new_ddl_oplog = |o: Document, time: u32| {
+            doc! {"op": "c", "ns": "db_1.$cmd", "o": o, "ts": Timestamp { time, increment: 1 }}
+        };
+        let position_ts = Timestamp {
+            time: 100,
+            increment: 0,
+        };
+        let mut shard_ddls = HashMap::new();
+        let mut is_dup = |shard_index: usize, doc: &Document| {
+            MongoCdcExtractor::is_dup_shard_ddl(&mut shard_ddls, 2, shard_index, doc, &position_ts)
+        };
+
+        // the same drop from 2 shards
+        let drop = new_ddl_oplog(doc! {"drop": "tb_1"}, 100);
+        assert!(!is_dup(0, &drop));
+        assert!(is_dup(1, &new_ddl_oplog(doc! {"drop": "tb_1"}, 101)));
+        // dropped again after recreated
+        assert!(!is_dup(1, &drop));
+        assert!(is_dup(0, &drop));
+
+        // executed again by the same shard
+        let create = new_ddl_oplog(doc! {"create": "tb_2"}, 100);
+        assert!(!is_dup(0, &create));
+        assert!(!is_dup(0, &create));
+
+        // not ddl
+        assert!(!is_dup(1, &new_oplog(100, 1)));
+        assert!(!is_dup(0, &new_oplog(100, 1)));
+
+        // ddl not executed by all shards expires
+        let position_ts = Timestamp {
+            time: 101 + SHARD_DDL_WINDOW_SECS,
+            increment: 0,
+        };
+        assert!(!MongoCdcExtractor::is_dup_shard_ddl(
+            &mut shard_ddls,
+            2,
+            1,
+            &create,
+            &position_ts
+        ));
+    }
+
+    #[test]
+    fn test_filter_from_migrate() {
+        assert!(MongoCdcExtractor::is_from_migrate(
+            &doc! {"op": "i", "fromMigrate": true}
+        ));
+        assert!(!MongoCdcExtractor::is_from_migrate(
+            &doc! {"op": "i", "fromMigrate": false}
+        ));
+        assert!(!MongoCdcExtractor::is_from_migrate(&doc! {"op": "i"}));
+
+        let doc = doc! {
+            "op": "c",
+            "ns": "admin.$cmd",
+            "o": {"applyOps": [
+                {"op": "d", "ns": "db_1.tb_1", "o": {"_id": 1}},
+                {"op": "d", "ns": "db_1.tb_1", "o": {"_id": 2}, "fromMigrate": true},
+                {"op": "i", "ns": "db_1.tb_1", "o": {"_id": 3}},
+            ]}
+        };
+        let data = MongoCdcExtractor::extract_oplog_delete_many(&doc);
+        assert_eq!(data.len(), 1);
+        assert_eq!(data[0].tb, "tb_1");
+        assert_eq!(
+            data[0].before.as_ref().unwrap()[MongoConstants::DOC],
+            ColValue::MongoDoc(doc! {"_id": 1})
+        );
+    }
+
+    #[test]
+    fn test_is_resume_token_lost() {
+        let new_err = |code: i32| -> anyhow::Error {
+            let command_error =
+                from_document(doc! {"code": code, "codeName": "", "errmsg": ""}).unwrap();
+            mongodb::error::Error::from(ErrorKind::Command(command_error)).into()
+        };
+
+        assert!(MongoCdcExtractor::is_resume_token_lost(&new_err(
+            CHANGE_STREAM_HISTORY_LOST
+        )));
+        assert!(MongoCdcExtractor::is_resume_token_lost(&new_err(
+            CHANGE_STREAM_FATAL_ERROR
+        )));
+        // e.g. Unauthorized
+        assert!(!MongoCdcExtractor::is_resume_token_lost(&new_err(13)));
+        assert!(!MongoCdcExtractor::is_resume_token_lost(&anyhow::anyhow!(
+            "connection refused"
+        )));
+    }
+
+    #[test]
+    fn test_parse_major_version() {
+        assert_eq!(MongoCdcExtractor::parse_major_version("6.0.3"), Some(6));
+        assert_eq!(MongoCdcExtractor::parse_major_version("7.0.0-rc1"), Some(7));
+        assert_eq!(MongoCdcExtractor::parse_major_version("5.0.24"), Some(5));
+        assert_eq!(MongoCdcExtractor::parse_major_version(""), None);
+    }
+}
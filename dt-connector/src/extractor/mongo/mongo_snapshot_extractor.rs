@@ -1,29 +1,41 @@
 use async_trait::async_trait;
 use dt_common::meta::{
     col_value::ColValue,
+    dt_data::{DtData, DtItem},
+    dt_queue::DtQueue,
     mongo::{mongo_constant::MongoConstants, mongo_key::MongoKey},
     position::Position,
     row_data::RowData,
     row_type::RowType,
 };
-use dt_common::{config::config_enums::DbType, log_info};
+use dt_common::{config::config_enums::DbType, log_debug, log_info, utils::mongo_util::MongoUtil};
 use mongodb::{
-    bson::{doc, oid::ObjectId, Bson, Document},
-    options::FindOptions,
-    Client,
+    bson::{doc, Bson, Document},
+    options::{AggregateOptions, FindOptions},
+    Client, Collection, Cursor,
 };
-use std::collections::HashMap;
+use std::{collections::HashMap, sync::Arc};
+use tokio::task::JoinHandle;
 
 use crate::{
     extractor::{base_extractor::BaseExtractor, resumer::snapshot_resumer::SnapshotResumer},
+    rdb_router::RdbRouter,
     Extractor,
 };
 
+// each sub extractor handles several ranges, so the checkpoint moves forward more frequently
+const RANGES_PER_EXTRACTOR: usize = 4;
+// sampled _ids for each range boundary
+const SAMPLES_PER_RANGE: usize = 10;
+// collections smaller than this are not split
+const MIN_RANGE_SIZE: u64 = 10000;
+
 pub struct MongoSnapshotExtractor {
     pub base_extractor: BaseExtractor,
     pub resumer: SnapshotResumer,
     pub db: String,
     pub tb: String,
+    pub parallel_size: usize,
     pub mongo_client: Client,
 }
 
@@ -31,9 +43,10 @@ pub struct MongoSnapshotExtractor {
 impl Extractor for MongoSnapshotExtractor {
     async fn extract(&mut self) -> anyhow::Result<()> {
         log_info!(
-            "MongoSnapshotExtractor starts, schema: {}, tb: {}",
+            "MongoSnapshotExtractor starts, schema: {}, tb: {}, parallel_size: {}",
             self.db,
-            self.tb
+            self.tb,
+            self.parallel_size
         );
         self.extract_internal().await?;
         self.base_extractor.wait_task_finish().await
@@ -49,73 +62,235 @@ impl MongoSnapshotExtractor {
     pub async fn extract_internal(&mut self) -> anyhow::Result<()> {
         log_info!("start extracting data from {}.{}", self.db, self.tb);
 
-        let filter = if let Some(resume_value) =
+        let parallel_extract = self.parallel_size > 1;
+        let start_id = if let Some(resume_value) =
             self.resumer
-                .get_resume_value(&self.db, &self.tb, MongoConstants::ID, false)
+                .get_resume_value(&self.db, &self.tb, MongoConstants::ID, parallel_extract)
         {
-            let start_id = ObjectId::parse_str(resume_value)?;
-            log_info!("start_id: {}", start_id.to_string());
-            Some(doc! {MongoConstants::ID: {"$gt": start_id}})
+            let start_id = MongoUtil::decode_id(&resume_value)?;
+            log_info!("start_id: {}", start_id);
+            Some(start_id)
         } else {
             None
         };
 
-        // order by asc
-        let find_options = FindOptions::builder()
-            .sort(doc! {MongoConstants::ID: 1})
-            .build();
-
         let collection = self
             .mongo_client
             .database(&self.db)
             .collection::<Document>(&self.tb);
-        let mut cursor = collection.find(filter, find_options).await?;
-        while cursor.advance().await? {
-            let doc = cursor.deserialize_current()?;
-            let object_id = Self::get_object_id(&doc);
-
-            let mut after = HashMap::new();
-            let id: String = if let Some(key) = MongoKey::from_doc(&doc) {
-                key.to_string()
-            } else {
-                String::new()
-            };
-            after.insert(MongoConstants::ID.to_string(), ColValue::String(id));
-            after.insert(MongoConstants::DOC.to_string(), ColValue::MongoDoc(doc));
-            let row_data = RowData::new(
-                self.db.clone(),
-                self.tb.clone(),
-                RowType::Insert,
-                None,
-                Some(after),
-            );
-            let position = Position::RdbSnapshot {
-                db_type: DbType::Mongo.to_string(),
-                schema: self.db.clone(),
-                tb: self.tb.clone(),
-                order_col: MongoConstants::ID.into(),
-                value: object_id,
-            };
 
-            self.base_extractor.push_row(row_data, position).await?;
-        }
+        let split_ids = if parallel_extract {
+            self.get_split_ids(&collection, &start_id).await?
+        } else {
+            Vec::new()
+        };
+
+        let extracted_count = if split_ids.is_empty() {
+            self.serial_extract(&collection, start_id).await?
+        } else {
+            log_info!(
+                "parallel extracting, parallel_size: {}, range count: {}",
+                self.parallel_size,
+                split_ids.len() + 1
+            );
+            self.parallel_extract(&collection, start_id, split_ids)
+                .await?
+        };
 
         log_info!(
             "end extracting data from {}.{}, all count: {}",
             self.db,
             self.tb,
-            self.base_extractor.monitor.counters.pushed_record_count
+            extracted_count
         );
         Ok(())
     }
 
-    fn get_object_id(doc: &Document) -> String {
-        if let Some(id) = doc.get(MongoConstants::ID) {
-            match id {
-                Bson::ObjectId(v) => return v.to_string(),
-                _ => return String::new(),
+    async fn serial_extract(
+        &mut self,
+        collection: &Collection<Document>,
+        start_id: Option<Bson>,
+    ) -> anyhow::Result<u64> {
+        let mut extracted_count = 0;
+        let mut cursor = Self::find_range(collection, start_id, None).await?;
+        while cursor.advance().await? {
+            let doc = cursor.deserialize_current()?;
+            let (row_data, position) = Self::build_row_data(&self.db, &self.tb, doc);
+            self.base_extractor.push_row(row_data, position).await?;
+            extracted_count += 1;
+        }
+        Ok(extracted_count)
+    }
+
+    async fn parallel_extract(
+        &mut self,
+        collection: &Collection<Document>,
+        start_id: Option<Bson>,
+        split_ids: Vec<Bson>,
+    ) -> anyhow::Result<u64> {
+        // ranges: (start_id, split_ids[0]], (split_ids[0], split_ids[1]], ... (split_ids[n-1], +∞)
+        let mut ranges = Vec::new();
+        let mut range_start = start_id;
+        for split_id in split_ids {
+            ranges.push((range_start, Some(split_id.clone())));
+            range_start = Some(split_id);
+        }
+        ranges.push((range_start, None));
+
+        let router = Arc::new(self.base_extractor.router.clone());
+        let mut all_extracted_count = 0;
+        for round in ranges.chunks(self.parallel_size) {
+            // send a checkpoint position before each round,
+            // all documents with _id <= the start of the round have been extracted
+            if let Some(round_start) = &round[0].0 {
+                self.send_checkpoint_position(round_start).await?;
+            }
+
+            let mut futures = Vec::new();
+            for (range_start, range_end) in round.iter().cloned() {
+                let collection = collection.clone();
+                let buffer = self.base_extractor.buffer.clone();
+                let router = router.clone();
+                let future: JoinHandle<anyhow::Result<u64>> = tokio::spawn(async move {
+                    Self::extract_range(&collection, &buffer, &router, range_start, range_end).await
+                });
+                futures.push(future);
+            }
+
+            for future in futures {
+                all_extracted_count += future.await??;
             }
         }
-        String::new()
+        Ok(all_extracted_count)
+    }
+
+    async fn extract_range(
+        collection: &Collection<Document>,
+        buffer: &Arc<DtQueue>,
+        router: &Arc<RdbRouter>,
+        start_id: Option<Bson>,
+        end_id: Option<Bson>,
+    ) -> anyhow::Result<u64> {
+        let db = collection.namespace().db;
+        let tb = collection.name().to_string();
+        let mut extracted_count = 0;
+        let mut cursor = Self::find_range(collection, start_id, end_id).await?;
+        while cursor.advance().await? {
+            let doc = cursor.deserialize_current()?;
+            let (row_data, position) = Self::build_row_data(&db, &tb, doc);
+            Self::push_row(buffer, router, row_data, position).await?;
+            extracted_count += 1;
+        }
+        Ok(extracted_count)
+    }
+
+    async fn find_range(
+        collection: &Collection<Document>,
+        start_id: Option<Bson>,
+        end_id: Option<Bson>,
+    ) -> anyhow::Result<Cursor<Document>> {
+        let filter = MongoUtil::build_id_range_filter(start_id.as_ref(), end_id.as_ref());
+        // order by asc
+        let find_options = FindOptions::builder()
+            .sort(doc! {MongoConstants::ID: 1})
+            .build();
+        Ok(collection.find(filter, find_options).await?)
+    }
+
+    fn build_row_data(db: &str, tb: &str, doc: Document) -> (RowData, Position) {
+        let position = Self::build_position(db, tb, doc.get(MongoConstants::ID));
+
+        let mut after = HashMap::new();
+        let id: String = if let Some(key) = MongoKey::from_doc(&doc) {
+            key.to_string()
+        } else {
+            String::new()
+        };
+        after.insert(MongoConstants::ID.to_string(), ColValue::String(id));
+        after.insert(MongoConstants::DOC.to_string(), ColValue::MongoDoc(doc));
+        let row_data = RowData::new(db.into(), tb.into(), RowType::Insert, None, Some(after));
+        (row_data, position)
+    }
+
+    /// Samples _ids by $sample to split the collection into ranges with similar sizes
+    async fn get_split_ids(
+        &self,
+        collection: &Collection<Document>,
+        start_id: &Option<Bson>,
+    ) -> anyhow::Result<Vec<Bson>> {
+        let range_count = self.parallel_size * RANGES_PER_EXTRACTOR;
+        let doc_count = collection.estimated_document_count(None).await?;
+        if doc_count < range_count as u64 * MIN_RANGE_SIZE {
+            log_info!(
+                "{}.{} is not split since estimated document count: {} is small",
+                self.db,
+                self.tb,
+                doc_count
+            );
+            return Ok(Vec::new());
+        }
+
+        let mut pipeline = Vec::new();
+        // $sample after $match does a collection scan, it only happens when resuming
+        if let Some(filter) = MongoUtil::build_id_range_filter(start_id.as_ref(), None) {
+            pipeline.push(doc! {"$match": filter});
+        }
+        pipeline.push(doc! {"$sample": {"size": (range_count * SAMPLES_PER_RANGE) as i64}});
+        pipeline.push(doc! {"$project": {MongoConstants::ID: 1}});
+        pipeline.push(doc! {"$sort": {MongoConstants::ID: 1}});
+        let options = AggregateOptions::builder().allow_disk_use(true).build();
+
+        let mut sampled_ids = Vec::new();
+        let mut cursor = collection.aggregate(pipeline, options).await?;
+        while cursor.advance().await? {
+            if let Some(id) = cursor.deserialize_current()?.remove(MongoConstants::ID) {
+                sampled_ids.push(id);
+            }
+        }
+
+        let mut split_ids: Vec<Bson> = sampled_ids
+            .into_iter()
+            .skip(SAMPLES_PER_RANGE - 1)
+            .step_by(SAMPLES_PER_RANGE)
+            .collect();
+        split_ids.dedup();
+        Ok(split_ids)
+    }
+
+    async fn push_row(
+        buffer: &Arc<DtQueue>,
+        router: &Arc<RdbRouter>,
+        row_data: RowData,
+        position: Position,
+    ) -> anyhow::Result<()> {
+        let row_data = router.route_row(row_data);
+        let dt_data = DtData::Dml { row_data };
+        let item = DtItem {
+            dt_data,
+            position,
+            data_origin_node: String::new(),
+        };
+        log_debug!("extracted item: {:?}", item);
+        buffer.push(item).await
+    }
+
+    fn build_position(db: &str, tb: &str, id: Option<&Bson>) -> Position {
+        if let Some(id) = id {
+            Position::RdbSnapshot {
+                db_type: DbType::Mongo.to_string(),
+                schema: db.into(),
+                tb: tb.into(),
+                order_col: MongoConstants::ID.into(),
+                value: MongoUtil::encode_id(id),
+            }
+        } else {
+            Position::None
+        }
+    }
+
+    async fn send_checkpoint_position(&mut self, id: &Bson) -> anyhow::Result<()> {
+        let position = Self::build_position(&self.db, &self.tb, Some(id));
+        let commit = DtData::Commit { xid: String::new() };
+        self.base_extractor.push_dt_data(commit, position).await
     }
 }
@@ -434,11 +434,12 @@ impl MysqlCdcExtractor {
 
         self.filter.add_ignore_tb(&db_tb[0], &db_tb[1]);
 
-        let (server_id, heartbeat_interval_secs, syncer, conn_pool) = (
+        let (server_id, heartbeat_interval_secs, syncer, conn_pool, monitor) = (
             self.server_id,
             self.heartbeat_interval_secs,
             self.syncer.clone(),
             self.conn_pool.clone(),
+            self.base_extractor.monitor.monitor.clone(),
         );
 
         tokio::spawn(async move {
@@ -448,6 +449,7 @@ impl MysqlCdcExtractor {
                     Self::heartbeat(server_id, &db_tb[0], &db_tb[1], &syncer, &conn_pool)
                         .await
                         .unwrap();
+                    BaseExtractor::update_heartbeat_delay(&monitor, &syncer).await;
                     start_time = Instant::now();
                 }
                 TimeUtil::sleep_millis(1000 * heartbeat_interval_secs).await;
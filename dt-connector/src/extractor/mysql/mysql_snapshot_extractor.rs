@@ -33,6 +33,7 @@ use dt_common::{
         position::Position,
         row_data::RowData,
     },
+    monitor::trace_span::TraceSpan,
     rdb_filter::RdbFilter,
 };
 
@@ -182,6 +183,8 @@ impl MysqlSnapshotExtractor {
         );
 
         loop {
+            let batch_span = TraceSpan::start("extractor.extract_batch");
+            batch_span.set_tb(&self.db, &self.tb);
             let start_value_for_bind = start_value.clone();
             let query = if let ColValue::None = start_value {
                 sqlx::query(&sql_1)
@@ -216,6 +219,7 @@ impl MysqlSnapshotExtractor {
 
                 self.base_extractor.push_row(row_data, position).await?;
             }
+            batch_span.set_count("count", slice_count as u64);
 
             // all data extracted
             if slice_count < self.batch_size {
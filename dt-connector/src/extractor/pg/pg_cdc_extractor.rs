@@ -534,11 +534,12 @@ impl PgCdcExtractor {
 
         self.filter.add_ignore_tb(&schema_tb[0], &schema_tb[1]);
 
-        let (slot_name, heartbeat_interval_secs, syncer, conn_pool) = (
+        let (slot_name, heartbeat_interval_secs, syncer, conn_pool, monitor) = (
             self.slot_name.clone(),
             self.heartbeat_interval_secs,
             self.syncer.clone(),
             self.conn_pool.clone(),
+            self.base_extractor.monitor.monitor.clone(),
         );
         tokio::spawn(async move {
             let mut start_time = Instant::now();
@@ -553,6 +554,7 @@ impl PgCdcExtractor {
                     )
                     .await
                     .unwrap();
+                    BaseExtractor::update_heartbeat_delay(&monitor, &syncer).await;
                     start_time = Instant::now();
                 }
                 TimeUtil::sleep_millis(1000 * heartbeat_interval_secs).await;
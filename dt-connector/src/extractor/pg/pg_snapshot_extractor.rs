@@ -4,7 +4,7 @@ use futures::TryStreamExt;
 
 use sqlx::{Pool, Postgres};
 
-use dt_common::{config::config_enums::DbType, log_info};
+use dt_common::{config::config_enums::DbType, log_info, monitor::trace_span::TraceSpan};
 
 use dt_common::meta::{
     adaptor::{pg_col_value_convertor::PgColValueConvertor, sqlx_ext::SqlxPgExt},
@@ -126,6 +126,8 @@ impl PgSnapshotExtractor {
         let sql_2 = self.build_extract_sql(tb_meta, true)?;
         let ignore_cols = self.filter.get_ignore_cols(&self.schema, &self.tb);
         loop {
+            let batch_span = TraceSpan::start("extractor.extract_batch");
+            batch_span.set_tb(&self.schema, &self.tb);
             let start_value_for_bind = start_value.clone();
             let query = if let ColValue::None = start_value {
                 sqlx::query(&sql_1)
@@ -159,6 +161,7 @@ impl PgSnapshotExtractor {
 
                 self.base_extractor.push_row(row_data, position).await?;
             }
+            batch_span.set_count("count", slice_count as u64);
 
             // all data extracted
             if slice_count < self.batch_size {
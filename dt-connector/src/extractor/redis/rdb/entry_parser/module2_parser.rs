@@ -1,15 +1,87 @@
 use anyhow::bail;
+use byteorder::{ByteOrder, LittleEndian};
 use dt_common::error::Error;
-use dt_common::log_info;
-use dt_common::meta::redis::redis_object::{ModuleObject, RedisString};
+use dt_common::meta::redis::redis_object::{ModuleObject, RedisCmd, RedisString};
+use dt_common::{log_info, log_warn};
 
 use crate::extractor::redis::rdb::reader::rdb_reader::RdbReader;
+use crate::extractor::redis::StreamReader;
 
 pub struct ModuleParser {}
 
 const MODULE_TYPE_NAME_CHAR_SET: &str =
     "ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789-_";
 
+const MODULE_OPCODE_EOF: u64 = 0;
+const MODULE_OPCODE_SINT: u64 = 1;
+const MODULE_OPCODE_UINT: u64 = 2;
+const MODULE_OPCODE_FLOAT: u64 = 3;
+const MODULE_OPCODE_DOUBLE: u64 = 4;
+const MODULE_OPCODE_STRING: u64 = 5;
+
+const MODULE_REJSON: &str = "ReJSON-RL";
+const MODULE_BLOOM: &str = "MBbloom--";
+const MODULE_CUCKOO: &str = "MBbloomCF";
+
+// RedisJSON 2.x saves values as json strings since encver 2
+const JSON_MIN_STRING_ENC: u64 = 2;
+// encoding versions of RedisBloom
+const BF_MIN_OPTIONS_ENC: u64 = 2;
+const BF_MIN_GROWTH_ENC: u64 = 4;
+const CF_MIN_EXPANSION_ENC: u64 = 4;
+const CF_DEFAULT_BUCKET_SIZE: u64 = 2;
+const CF_DEFAULT_MAX_ITERATIONS: u64 = 20;
+const CF_DEFAULT_EXPANSION: u64 = 1;
+
+// max data size of a BF.LOADCHUNK / CF.LOADCHUNK command
+const MAX_CHUNK_SIZE: usize = 16 * 1024 * 1024;
+
+/// values saved by RedisModule_Save* in rdb, each of them starts with an opcode
+#[derive(Debug, Clone)]
+enum ModuleValue {
+    Int(u64),
+    Float(f32),
+    Double(f64),
+    String(RedisString),
+}
+
+struct ModuleValues {
+    values: Vec<ModuleValue>,
+    index: usize,
+}
+
+impl ModuleValues {
+    fn next(&mut self) -> anyhow::Result<&ModuleValue> {
+        if self.index >= self.values.len() {
+            bail! {Error::RedisRdbError("module values exhausted".into())}
+        }
+        self.index += 1;
+        Ok(&self.values[self.index - 1])
+    }
+
+    fn next_uint(&mut self) -> anyhow::Result<u64> {
+        match self.next()? {
+            ModuleValue::Int(v) => Ok(*v),
+            v => bail! {Error::RedisRdbError(format!("expect int, got: {:?}", v))},
+        }
+    }
+
+    fn next_double(&mut self) -> anyhow::Result<f64> {
+        match self.next()? {
+            ModuleValue::Double(v) => Ok(*v),
+            ModuleValue::Float(v) => Ok(*v as f64),
+            v => bail! {Error::RedisRdbError(format!("expect double, got: {:?}", v))},
+        }
+    }
+
+    fn next_string(&mut self) -> anyhow::Result<RedisString> {
+        match self.next()? {
+            ModuleValue::String(v) => Ok(v.clone()),
+            v => bail! {Error::RedisRdbError(format!("expect string, got: {:?}", v))},
+        }
+    }
+}
+
 impl ModuleParser {
     pub async fn load_from_buffer(
         reader: &mut RdbReader<'_>,
@@ -25,11 +97,32 @@ impl ModuleParser {
 
         let module_id = reader.read_length().await?;
         let module_name = Self::module_type_name_by_id(module_id);
+        // the lower 10 bits of module id is the encoding version
+        let encver = module_id & 1023;
+        let values = Self::load_module_values(reader).await?;
 
-        log_info!("load module2 type: [{}] with raw", module_name);
-        Self::skip_module_data(reader).await?;
+        let mut obj = ModuleObject::new();
+        obj.key = key;
+        obj.module_name = module_name;
+        let cmds = match obj.module_name.as_str() {
+            MODULE_REJSON => Self::decode_json(&obj.key, encver, values),
+            MODULE_BLOOM => Self::decode_bloom(&obj.key, encver, values),
+            MODULE_CUCKOO => Self::decode_cuckoo(&obj.key, encver, values),
+            _ => Ok(Vec::new()),
+        };
 
-        Ok(ModuleObject::new())
+        // undecoded values can still be synced by RESTORE
+        match cmds {
+            Ok(cmds) if !cmds.is_empty() => obj.cmds = cmds,
+            Ok(_) => log_info!("load module2 type: [{}] with raw", obj.module_name),
+            Err(err) => log_warn!(
+                "failed to decode module2 type: [{}], encver: {}, load with raw, error: {}",
+                obj.module_name,
+                encver,
+                err
+            ),
+        }
+        Ok(obj)
     }
 
     pub fn module_type_name_by_id(module_id: u64) -> String {
@@ -44,30 +137,287 @@ impl ModuleParser {
         String::from_utf8(name_list).unwrap()
     }
 
-    async fn skip_module_data(reader: &mut RdbReader<'_>) -> anyhow::Result<()> {
+    async fn load_module_values(reader: &mut RdbReader<'_>) -> anyhow::Result<ModuleValues> {
+        let mut values = Vec::new();
         let mut opcode = reader.read_length().await?;
-        while opcode != 0 {
-            match opcode {
-                1 | 2 => {
-                    reader.read_length().await?;
-                }
-                3 => {
-                    reader.read_float().await?;
-                }
-                4 => {
-                    reader.read_double().await?;
+        while opcode != MODULE_OPCODE_EOF {
+            let value = match opcode {
+                MODULE_OPCODE_SINT | MODULE_OPCODE_UINT => {
+                    ModuleValue::Int(reader.read_length().await?)
                 }
-                5 => {
-                    reader.read_string().await?;
+                MODULE_OPCODE_FLOAT => {
+                    // saved by rdbSaveBinaryFloatValue
+                    let buf = reader.read_bytes(4).await?;
+                    ModuleValue::Float(LittleEndian::read_f32(&buf))
                 }
+                MODULE_OPCODE_DOUBLE => ModuleValue::Double(reader.read_double().await?),
+                MODULE_OPCODE_STRING => ModuleValue::String(reader.read_string().await?),
                 _ => {
                     bail! {Error::RedisRdbError(format!(
                         "unknown module opcode: {}", opcode
                     ))}
                 }
-            }
+            };
+            values.push(value);
             opcode = reader.read_length().await?;
         }
-        Ok(())
+        Ok(ModuleValues { values, index: 0 })
+    }
+
+    fn decode_json(
+        key: &RedisString,
+        encver: u64,
+        mut values: ModuleValues,
+    ) -> anyhow::Result<Vec<RedisCmd>> {
+        if encver < JSON_MIN_STRING_ENC {
+            bail! {Error::RedisRdbError(format!(
+                "ReJSON encver: {} not supported",
+                encver
+            ))}
+        }
+
+        let json = values.next_string()?;
+        let mut cmd = RedisCmd::new();
+        cmd.add_str_arg("JSON.SET");
+        cmd.add_redis_arg(key);
+        cmd.add_str_arg("$");
+        cmd.add_redis_arg(&json);
+        Ok(vec![cmd])
+    }
+
+    /// rebuild the scalable bloom filter by BF.LOADCHUNK, the first chunk is the header
+    /// in the same layout as BF.SCANDUMP, others are bit arrays of sub filters
+    fn decode_bloom(
+        key: &RedisString,
+        encver: u64,
+        mut values: ModuleValues,
+    ) -> anyhow::Result<Vec<RedisCmd>> {
+        let size = values.next_uint()?;
+        let nfilters = values.next_uint()?;
+        let options = if encver >= BF_MIN_OPTIONS_ENC {
+            values.next_uint()?
+        } else {
+            0
+        };
+        let growth = if encver >= BF_MIN_GROWTH_ENC {
+            values.next_uint()?
+        } else {
+            2
+        };
+
+        // dumpedChainHeader
+        let mut header = Vec::new();
+        header.extend_from_slice(&size.to_le_bytes());
+        header.extend_from_slice(&(nfilters as u32).to_le_bytes());
+        header.extend_from_slice(&(options as u32).to_le_bytes());
+        header.extend_from_slice(&(growth as u32).to_le_bytes());
+
+        let mut filters = Vec::new();
+        for _ in 0..nfilters {
+            let entries = values.next_uint()?;
+            let error = values.next_double()?;
+            let hashes = values.next_uint()?;
+            let bpe = values.next_double()?;
+            let (bits, n2) = if encver == 0 {
+                ((entries as f64 * bpe) as u64, 0)
+            } else {
+                (values.next_uint()?, values.next_uint()?)
+            };
+            let bf = values.next_string()?;
+            let link_size = values.next_uint()?;
+
+            // dumpedChainLink
+            header.extend_from_slice(&(bf.bytes.len() as u64).to_le_bytes());
+            header.extend_from_slice(&bits.to_le_bytes());
+            header.extend_from_slice(&link_size.to_le_bytes());
+            header.extend_from_slice(&error.to_le_bytes());
+            header.extend_from_slice(&bpe.to_le_bytes());
+            header.extend_from_slice(&(hashes as u32).to_le_bytes());
+            header.extend_from_slice(&entries.to_le_bytes());
+            header.push(n2 as u8);
+            filters.push(bf);
+        }
+
+        Ok(Self::build_load_chunk_cmds(
+            "BF.LOADCHUNK",
+            key,
+            header,
+            &filters,
+        ))
+    }
+
+    /// rebuild the cuckoo filter by CF.LOADCHUNK, the first chunk is the header
+    /// in the same layout as CF.SCANDUMP, others are buckets of sub filters
+    fn decode_cuckoo(
+        key: &RedisString,
+        encver: u64,
+        mut values: ModuleValues,
+    ) -> anyhow::Result<Vec<RedisCmd>> {
+        let num_filters = values.next_uint()?;
+        let num_buckets = values.next_uint()?;
+        let num_items = values.next_uint()?;
+        let (num_deletes, bucket_size, max_iterations, expansion) =
+            if encver >= CF_MIN_EXPANSION_ENC {
+                (
+                    values.next_uint()?,
+                    values.next_uint()?,
+                    values.next_uint()?,
+                    values.next_uint()?,
+                )
+            } else {
+                (
+                    0,
+                    CF_DEFAULT_BUCKET_SIZE,
+                    CF_DEFAULT_MAX_ITERATIONS,
+                    CF_DEFAULT_EXPANSION,
+                )
+            };
+
+        let mut filters = Vec::new();
+        for _ in 0..num_filters {
+            if encver >= CF_MIN_EXPANSION_ENC {
+                // numBuckets of sub filter, derived from header by expansion when loading
+                values.next_uint()?;
+            }
+            filters.push(values.next_string()?);
+        }
+
+        // CFHeader
+        let mut header = Vec::new();
+        header.extend_from_slice(&num_items.to_le_bytes());
+        header.extend_from_slice(&num_buckets.to_le_bytes());
+        header.extend_from_slice(&num_deletes.to_le_bytes());
+        header.extend_from_slice(&num_filters.to_le_bytes());
+        header.extend_from_slice(&(bucket_size as u16).to_le_bytes());
+        header.extend_from_slice(&(max_iterations as u16).to_le_bytes());
+        header.extend_from_slice(&(expansion as u16).to_le_bytes());
+
+        Ok(Self::build_load_chunk_cmds(
+            "CF.LOADCHUNK",
+            key,
+            header,
+            &filters,
+        ))
+    }
+
+    /// iterator of a data chunk is: offset of the chunk end in all sub filters + 1,
+    /// and a chunk should not cross sub filters
+    fn build_load_chunk_cmds(
+        cmd_name: &str,
+        key: &RedisString,
+        header: Vec<u8>,
+        filters: &[RedisString],
+    ) -> Vec<RedisCmd> {
+        let mut cmds = Vec::new();
+        let mut cmd = RedisCmd::new();
+        cmd.add_str_arg(cmd_name);
+        cmd.add_redis_arg(key);
+        cmd.add_str_arg("1");
+        cmd.add_arg(header);
+        cmds.push(cmd);
+
+        let mut offset = 0;
+        for filter in filters.iter() {
+            for chunk in filter.as_bytes().chunks(MAX_CHUNK_SIZE) {
+                offset += chunk.len();
+                let mut cmd = RedisCmd::new();
+                cmd.add_str_arg(cmd_name);
+                cmd.add_redis_arg(key);
+                cmd.add_str_arg(&(offset + 1).to_string());
+                cmd.add_arg(chunk.to_vec());
+                cmds.push(cmd);
+            }
+        }
+        cmds
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn new_values(values: Vec<ModuleValue>) -> ModuleValues {
+        ModuleValues { values, index: 0 }
+    }
+
+    #[test]
+    fn test_module_type_name_by_id() {
+        // module id of ReJSON-RL with encver 3
+        let mut id: u64 = 0;
+        for c in MODULE_REJSON.chars() {
+            id = (id << 6) | MODULE_TYPE_NAME_CHAR_SET.find(c).unwrap() as u64;
+        }
+        let module_id = (id << 10) | 3;
+        assert_eq!(
+            ModuleParser::module_type_name_by_id(module_id),
+            MODULE_REJSON
+        );
+        assert_eq!(module_id & 1023, 3);
+    }
+
+    #[test]
+    fn test_decode_json() {
+        let key = RedisString::from("k".to_string());
+        let json = RedisString::from(r#"{"a":[1,2]}"#.to_string());
+        let values = new_values(vec![ModuleValue::String(json)]);
+        let cmds = ModuleParser::decode_json(&key, 3, values).unwrap();
+        assert_eq!(cmds[0].to_string(), r#"JSON.SET k $ {"a":[1,2]}"#);
+
+        let values = new_values(vec![]);
+        assert!(ModuleParser::decode_json(&key, 0, values).is_err());
+    }
+
+    #[test]
+    fn test_decode_bloom() {
+        let key = RedisString::from("k".to_string());
+        let values = new_values(vec![
+            // size, nfilters, options, growth
+            ModuleValue::Int(2),
+            ModuleValue::Int(1),
+            ModuleValue::Int(0),
+            ModuleValue::Int(2),
+            // entries, error, hashes, bpe, bits, n2, bf, size
+            ModuleValue::Int(100),
+            ModuleValue::Double(0.01),
+            ModuleValue::Int(7),
+            ModuleValue::Double(9.58),
+            ModuleValue::Int(958),
+            ModuleValue::Int(0),
+            ModuleValue::String(RedisString::from(vec![1u8; 120])),
+            ModuleValue::Int(2),
+        ]);
+        let cmds = ModuleParser::decode_bloom(&key, 4, values).unwrap();
+        assert_eq!(cmds.len(), 2);
+        assert_eq!(cmds[0].get_str_arg(2), "1");
+        // chain header: 8 + 4 * 3, link: 8 * 5 + 4 + 8 + 1
+        assert_eq!(cmds[0].args[3].len(), 20 + 53);
+        assert_eq!(cmds[1].get_str_arg(2), "121");
+        assert_eq!(cmds[1].args[3], vec![1u8; 120]);
+    }
+
+    #[test]
+    fn test_decode_cuckoo() {
+        let key = RedisString::from("k".to_string());
+        let values = new_values(vec![
+            // numFilters, numBuckets, numItems, numDeletes, bucketSize, maxIterations, expansion
+            ModuleValue::Int(2),
+            ModuleValue::Int(4),
+            ModuleValue::Int(3),
+            ModuleValue::Int(0),
+            ModuleValue::Int(2),
+            ModuleValue::Int(20),
+            ModuleValue::Int(1),
+            // numBuckets, data of sub filters
+            ModuleValue::Int(4),
+            ModuleValue::String(RedisString::from(vec![1u8; 8])),
+            ModuleValue::Int(4),
+            ModuleValue::String(RedisString::from(vec![2u8; 8])),
+        ]);
+        let cmds = ModuleParser::decode_cuckoo(&key, 4, values).unwrap();
+        assert_eq!(cmds.len(), 3);
+        assert_eq!(cmds[0].args[3].len(), 8 * 4 + 2 * 3);
+        assert_eq!(cmds[1].get_str_arg(2), "9");
+        assert_eq!(cmds[2].get_str_arg(2), "17");
+        assert_eq!(cmds[2].args[3], vec![2u8; 8]);
     }
 }
@@ -152,8 +152,9 @@ impl RdbParser<'_> {
             K_FLAG_EXPIRE_MS => {
                 let mut expire_ms = self.reader.read_u64().await? as i64;
                 expire_ms -= chrono::Utc::now().timestamp_millis();
-                if expire_ms < 0 {
-                    expire_ms = 1
+                if expire_ms <= 0 {
+                    // expired but not removed yet
+                    expire_ms = -1
                 }
                 self.expire_ms = expire_ms;
             }
@@ -161,8 +162,9 @@ impl RdbParser<'_> {
             K_FLAG_EXPIRE => {
                 let mut expire_ms = self.reader.read_u32().await? as i64 * 1000;
                 expire_ms -= chrono::Utc::now().timestamp_millis();
-                if expire_ms < 0 {
-                    expire_ms = 1
+                if expire_ms <= 0 {
+                    // expired but not removed yet
+                    expire_ms = -1
                 }
                 self.expire_ms = expire_ms;
             }
@@ -201,6 +203,11 @@ impl RdbParser<'_> {
                     entry.value = value.unwrap();
                     entry.value_type_byte = type_byte;
                     entry.expire_ms = self.expire_ms;
+                    if self.expire_ms < 0 {
+                        // expire it in target asap
+                        entry.is_expired = true;
+                        entry.expire_ms = 1;
+                    }
                     // reset expire_ms
                     self.expire_ms = 0;
                     return Ok(Some(entry));
@@ -0,0 +1,164 @@
+use std::io::Cursor;
+
+use anyhow::bail;
+use async_trait::async_trait;
+use redis::{Connection, FromRedisValue, Value};
+
+use super::{
+    rdb::{entry_parser::entry_parser::EntryParser, reader::rdb_reader::RdbReader},
+    StreamReader,
+};
+use crate::{
+    check_log::check_log::CheckLog,
+    extractor::{base_check_extractor::BaseCheckExtractor, base_extractor::BaseExtractor},
+    BatchCheckExtractor, Extractor,
+};
+use dt_common::{
+    error::Error,
+    log_info,
+    meta::{
+        dt_data::DtData,
+        position::Position,
+        redis::{
+            redis_constant::RedisConstants,
+            redis_entry::RedisEntry,
+            redis_object::{RedisCmd, RedisString},
+        },
+    },
+};
+
+/// re-extracts keys in check logs from source by DUMP, keys removed from source are extracted as DEL
+pub struct RedisCheckExtractor {
+    pub base_extractor: BaseExtractor,
+    pub conn: Connection,
+    pub check_log_dir: String,
+    pub batch_size: usize,
+}
+
+#[async_trait]
+impl Extractor for RedisCheckExtractor {
+    async fn extract(&mut self) -> anyhow::Result<()> {
+        log_info!("RedisCheckExtractor starts");
+        let base_check_extractor = BaseCheckExtractor {
+            check_log_dir: self.check_log_dir.clone(),
+            batch_size: self.batch_size,
+        };
+        base_check_extractor.extract(self).await?;
+        self.base_extractor.wait_task_finish().await
+    }
+}
+
+#[async_trait]
+impl BatchCheckExtractor for RedisCheckExtractor {
+    async fn batch_extract(&mut self, check_logs: &[CheckLog]) -> anyhow::Result<()> {
+        // schema of redis check log is db id
+        let db_id: i64 = check_logs[0].schema.parse()?;
+        redis::cmd("SELECT")
+            .arg(db_id)
+            .query::<()>(&mut self.conn)?;
+
+        let mut keys = Vec::new();
+        let mut pipe = redis::pipe();
+        for check_log in check_logs.iter() {
+            if let Some(Some(key)) = check_log.id_col_values.get(RedisConstants::KEY) {
+                pipe.cmd("DUMP").arg(key);
+                pipe.cmd("PTTL").arg(key);
+                keys.push(key.clone());
+            }
+        }
+        if keys.is_empty() {
+            return Ok(());
+        }
+        let values: Vec<Value> = pipe.query(&mut self.conn)?;
+
+        for (i, key) in keys.into_iter().enumerate() {
+            let payload = Option::<Vec<u8>>::from_redis_value(&values[i * 2])?;
+            let ttl = i64::from_redis_value(&values[i * 2 + 1])?;
+
+            let mut entry = if let Some(payload) = payload {
+                Self::decode_dump(key.into(), &payload, ttl).await?
+            } else {
+                log_info!("key: {} not exists in source, db: {}", key, db_id);
+                let mut entry = RedisEntry::new();
+                entry.cmd = RedisCmd::from_str_args(&["DEL", key.as_str()]);
+                entry
+            };
+            entry.db_id = db_id;
+
+            self.base_extractor
+                .push_dt_data(DtData::Redis { entry }, Position::None)
+                .await?;
+        }
+        Ok(())
+    }
+}
+
+impl RedisCheckExtractor {
+    /// payload: type byte + value + rdb version(2 bytes) + crc64(8 bytes)
+    async fn decode_dump(key: RedisString, payload: &[u8], ttl: i64) -> anyhow::Result<RedisEntry> {
+        if payload.len() <= 10 {
+            bail! {Error::RedisRdbError(format!(
+                "invalid dump payload of key: {}",
+                key
+            ))}
+        }
+
+        let type_byte = payload[0];
+        let raw_bytes = payload[1..payload.len() - 10].to_vec();
+
+        let mut cursor = Cursor::new(raw_bytes.as_slice());
+        let mut stream_reader: Box<&mut (dyn StreamReader + Send)> = Box::new(&mut cursor);
+        let mut reader = RdbReader {
+            conn: &mut stream_reader,
+            rdb_length: raw_bytes.len(),
+            position: 0,
+            copy_raw: false,
+            raw_bytes: Vec::new(),
+        };
+        let value = EntryParser::parse_object(&mut reader, type_byte, key.clone()).await?;
+
+        let mut entry = RedisEntry::new();
+        entry.is_base = true;
+        entry.key = key;
+        entry.value = value;
+        entry.value_type_byte = type_byte;
+        entry.raw_bytes = raw_bytes;
+        // PTTL: -1 if no expire
+        entry.expire_ms = ttl.max(0);
+        Ok(entry)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use dt_common::meta::redis::redis_object::RedisObject;
+
+    use super::*;
+
+    #[tokio::test]
+    async fn test_decode_dump() {
+        // DUMP of string "abc": type byte, length, bytes, rdb version, crc64
+        let mut payload = vec![0u8, 3];
+        payload.extend_from_slice(b"abc");
+        payload.extend_from_slice(&[11, 0]);
+        payload.extend_from_slice(&[0; 8]);
+
+        let key = RedisString::from("k".to_string());
+        let entry = RedisCheckExtractor::decode_dump(key, &payload, -1)
+            .await
+            .unwrap();
+        assert!(entry.is_raw());
+        assert_eq!(entry.expire_ms, 0);
+        assert_eq!(entry.raw_bytes, vec![3, b'a', b'b', b'c']);
+        match entry.value {
+            RedisObject::String(obj) => assert_eq!(obj.value.as_bytes(), b"abc"),
+            _ => panic!("unexpected value type"),
+        }
+
+        assert!(
+            RedisCheckExtractor::decode_dump(RedisString::new(), &[0; 10], -1)
+                .await
+                .is_err()
+        );
+    }
+}
@@ -48,13 +48,15 @@ impl RedisClient {
             cmd.add_str_arg(password);
 
             me.send(&cmd).await?;
-            if let Ok(Value::Okay) = me.read().await {
-                return Ok(me);
+            // keep the auth error, such as WRONGPASS, so callers know it's not worth retrying
+            match me.read().await {
+                Ok(Value::Okay) => return Ok(me),
+                Ok(value) => bail! {Error::RedisResultError(format!(
+                    "can't connect redis: {}, auth response: {:?}",
+                    url, value
+                ))},
+                Err(err) => return Err(err.context(format!("can't connect redis: {}", url))),
             }
-            bail! {Error::RedisResultError(format!(
-                "can't connect redis: {}",
-                url
-            ))}
         }
 
         Ok(me)
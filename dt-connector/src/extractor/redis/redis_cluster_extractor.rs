@@ -0,0 +1,487 @@
+use std::{
+    collections::{BTreeMap, HashMap},
+    sync::{atomic::Ordering, Arc},
+};
+
+use anyhow::anyhow;
+use async_trait::async_trait;
+use tokio::{sync::Mutex, task::JoinHandle};
+use url::Url;
+
+use super::{redis_client::RedisClient, redis_psync_extractor::RedisPsyncExtractor};
+use crate::{
+    extractor::{
+        base_extractor::BaseExtractor, extractor_monitor::ExtractorMonitor,
+        resumer::cdc_resumer::CdcResumer,
+    },
+    Extractor,
+};
+use dt_common::{
+    config::config_enums::ExtractType,
+    error::Error,
+    log_error, log_info, log_warn,
+    meta::{
+        position::{Position, RedisNodePosition},
+        redis::{cluster_node::ClusterNode, command::key_parser::KeyParser},
+        syncer::Syncer,
+    },
+    rdb_filter::RdbFilter,
+    time_filter::TimeFilter,
+    utils::{redis_util::RedisUtil, time_util::TimeUtil},
+};
+
+const TOPOLOGY_CHECK_INTERVAL_SECS: u64 = 10;
+// a node stream is restarted at most MAX_RETRY_TIMES times in a row,
+// the count is reset once the stream survives a topology check
+const MAX_RETRY_TIMES: u32 = 5;
+
+/// runs a psync stream for each master node of the cluster, discovers new masters
+/// after failovers / topology changes and restarts streams for them
+pub struct RedisClusterExtractor {
+    pub base_extractor: BaseExtractor,
+    pub url: String,
+    pub repl_port: u64,
+    pub keepalive_interval_secs: u64,
+    pub heartbeat_interval_secs: u64,
+    pub heartbeat_key: String,
+    pub syncer: Arc<Mutex<Syncer>>,
+    pub filter: RdbFilter,
+    pub resumer: CdcResumer,
+    pub extract_type: ExtractType,
+}
+
+#[async_trait]
+impl Extractor for RedisClusterExtractor {
+    async fn extract(&mut self) -> anyhow::Result<()> {
+        log_info!(
+            "RedisClusterExtractor starts, extract_type: {}, keepalive_interval_secs: {},
+             heartbeat_interval_secs: {}, heartbeat_key: {}",
+            self.extract_type,
+            self.keepalive_interval_secs,
+            self.heartbeat_interval_secs,
+            self.heartbeat_key
+        );
+
+        let node_positions = match &self.resumer.current_position {
+            Position::Redis { node_positions, .. } => node_positions.clone(),
+            _ => BTreeMap::new(),
+        };
+        log_info!("resumed node positions: {:?}", node_positions);
+        let node_positions = Arc::new(Mutex::new(node_positions));
+
+        if matches!(self.extract_type, ExtractType::Snapshot) {
+            self.extract_snapshot(node_positions).await?;
+        } else {
+            self.extract_cdc(node_positions).await?;
+        }
+        self.base_extractor.wait_task_finish().await
+    }
+}
+
+impl RedisClusterExtractor {
+    async fn extract_snapshot(
+        &mut self,
+        node_positions: Arc<Mutex<BTreeMap<String, RedisNodePosition>>>,
+    ) -> anyhow::Result<()> {
+        let nodes = self.get_master_nodes(&[]).await?;
+        let mut streams = Vec::new();
+        for node in nodes.iter() {
+            streams.push(self.start_stream(node, node_positions.clone()).await?);
+        }
+
+        for stream in streams {
+            stream.await??;
+        }
+        Ok(())
+    }
+
+    async fn extract_cdc(
+        &mut self,
+        node_positions: Arc<Mutex<BTreeMap<String, RedisNodePosition>>>,
+    ) -> anyhow::Result<()> {
+        let mut streams: HashMap<String, JoinHandle<anyhow::Result<()>>> = HashMap::new();
+        let res = self.run_cdc_streams(&mut streams, node_positions).await;
+        // streams are spawned tasks which never stop by themselves
+        for (address, stream) in streams {
+            log_info!("stop stream of node: {}", address);
+            stream.abort();
+        }
+        res
+    }
+
+    async fn run_cdc_streams(
+        &mut self,
+        streams: &mut HashMap<String, JoinHandle<anyhow::Result<()>>>,
+        node_positions: Arc<Mutex<BTreeMap<String, RedisNodePosition>>>,
+    ) -> anyhow::Result<()> {
+        // key: stream of node, or getting master nodes
+        let mut retry_times: HashMap<String, u32> = HashMap::new();
+        let get_nodes_action = "get master nodes";
+        let stream_action = |address: &str| format!("stream of node: {}", address);
+
+        while !self.base_extractor.shut_down.load(Ordering::Acquire) {
+            // streams stop if master nodes are down or removed from cluster
+            let mut finished = Vec::new();
+            for (address, stream) in streams.iter() {
+                if stream.is_finished() {
+                    finished.push(address.to_owned());
+                } else {
+                    retry_times.remove(&stream_action(address));
+                }
+            }
+            for address in finished {
+                let err = match streams.remove(&address).unwrap().await {
+                    Ok(Ok(())) => {
+                        log_info!("stream of node: {} finished", address);
+                        continue;
+                    }
+                    Ok(Err(err)) => err,
+                    Err(err) => anyhow!(err),
+                };
+                Self::check_retry(&mut retry_times, &stream_action(&address), err)?;
+            }
+
+            let addresses: Vec<String> = streams.keys().cloned().collect();
+            let nodes = match self.get_master_nodes(&addresses).await {
+                Ok(nodes) => {
+                    retry_times.remove(get_nodes_action);
+                    nodes
+                }
+                Err(err) => {
+                    Self::check_retry(&mut retry_times, get_nodes_action, err)?;
+                    Vec::new()
+                }
+            };
+
+            for node in nodes.iter() {
+                if streams.contains_key(&node.address) {
+                    continue;
+                }
+                match self.start_stream(node, node_positions.clone()).await {
+                    Ok(stream) => {
+                        streams.insert(node.address.clone(), stream);
+                    }
+                    Err(err) => {
+                        Self::check_retry(&mut retry_times, &stream_action(&node.address), err)?
+                    }
+                }
+            }
+
+            TimeUtil::sleep_millis(TOPOLOGY_CHECK_INTERVAL_SECS * 1000).await;
+        }
+        Ok(())
+    }
+
+    /// returns the error if it is fatal or has been retried too many times
+    fn check_retry(
+        retry_times: &mut HashMap<String, u32>,
+        action: &str,
+        err: anyhow::Error,
+    ) -> anyhow::Result<()> {
+        if Self::is_fatal_error(&err) {
+            return Err(err.context(format!("{} failed", action)));
+        }
+
+        let times = retry_times.entry(action.to_owned()).or_default();
+        *times += 1;
+        if *times > MAX_RETRY_TIMES {
+            return Err(err.context(format!(
+                "{} failed after {} retries",
+                action, MAX_RETRY_TIMES
+            )));
+        }
+
+        log_error!(
+            "{} failed, will retry, retry times: {}, error: {:?}",
+            action,
+            times,
+            err
+        );
+        Ok(())
+    }
+
+    fn is_fatal_error(err: &anyhow::Error) -> bool {
+        // wrong urls or passwords never recover by retrying
+        let is_fatal = |e: &(dyn std::error::Error + 'static)| {
+            if let Some(e) = e.downcast_ref::<redis::RedisError>() {
+                return e.kind() == redis::ErrorKind::AuthenticationFailed;
+            }
+            e.downcast_ref::<url::ParseError>().is_some()
+        };
+        if err.chain().any(is_fatal) {
+            return true;
+        }
+
+        let message = format!("{:#}", err);
+        ["NOAUTH", "WRONGPASS", "invalid password"]
+            .iter()
+            .any(|i| message.contains(i))
+    }
+
+    /// positions are found by repl_id since the node address changes after failover
+    fn take_over_position(
+        node_positions: &mut BTreeMap<String, RedisNodePosition>,
+        node_address: &str,
+        repl_ids: &[String],
+    ) -> RedisNodePosition {
+        let resumed = node_positions
+            .iter()
+            .find(|(_, i)| repl_ids.contains(&i.repl_id))
+            .map(|(address, i)| (address.to_owned(), i.to_owned()));
+        let Some((address, position)) = resumed else {
+            return RedisNodePosition::default();
+        };
+
+        if address != node_address {
+            log_info!(
+                "node: {} takes over position of node: {}, position: {:?}",
+                node_address,
+                address,
+                position
+            );
+            node_positions.remove(&address);
+            node_positions.insert(node_address.to_owned(), position.clone());
+        }
+        position
+    }
+
+    /// a hash tag of the first slot owned by the node, keys prefixed by it are routed to the node
+    fn get_heartbeat_hash_tag(node: &ClusterNode) -> String {
+        node.slots
+            .iter()
+            .min()
+            .and_then(|slot| node.slot_hash_tag_map.get(slot))
+            .cloned()
+            .unwrap_or_default()
+    }
+
+    async fn start_stream(
+        &self,
+        node: &ClusterNode,
+        node_positions: Arc<Mutex<BTreeMap<String, RedisNodePosition>>>,
+    ) -> anyhow::Result<JoinHandle<anyhow::Result<()>>> {
+        let url = self.get_node_url(&node.address)?;
+        let mut conn = RedisUtil::create_redis_conn(&url).await?;
+        let repl_ids = RedisUtil::get_repl_ids(&mut conn)?;
+
+        let position =
+            Self::take_over_position(&mut *node_positions.lock().await, &node.address, &repl_ids);
+        log_info!(
+            "start stream of node: {}, position: {:?}",
+            node.address,
+            position
+        );
+
+        let base_extractor = BaseExtractor {
+            buffer: self.base_extractor.buffer.clone(),
+            router: self.base_extractor.router.clone(),
+            shut_down: self.base_extractor.shut_down.clone(),
+            monitor: ExtractorMonitor::new(self.base_extractor.monitor.monitor.clone()).await,
+            data_marker: self.base_extractor.data_marker.clone(),
+            time_filter: TimeFilter::default(),
+            task_control: self.base_extractor.task_control.clone(),
+        };
+
+        let partial_sync = !position.repl_id.is_empty();
+        let mut extractor = RedisPsyncExtractor {
+            base_extractor,
+            conn: RedisClient::new(&url).await?,
+            repl_id: position.repl_id,
+            repl_offset: position.repl_offset,
+            repl_port: self.repl_port,
+            now_db_id: position.now_db_id,
+            keepalive_interval_secs: self.keepalive_interval_secs,
+            heartbeat_interval_secs: self.heartbeat_interval_secs,
+            heartbeat_key: self.heartbeat_key.clone(),
+            syncer: self.syncer.clone(),
+            filter: self.filter.clone(),
+            key_parser: KeyParser::new(),
+            resumer: self.resumer.clone(),
+            extract_type: self.extract_type.clone(),
+            node_address: node.address.clone(),
+            node_positions: Some(node_positions.clone()),
+            heartbeat_hash_tag: Self::get_heartbeat_hash_tag(node),
+        };
+
+        let address = node.address.clone();
+        let stream = tokio::spawn(async move {
+            let full_sync = match extractor.start_psync().await {
+                Ok(full_sync) => full_sync,
+                Err(err) => {
+                    if partial_sync {
+                        // the backlog of master may not contain the position,
+                        // remove it so the next attempt will do a full sync
+                        log_warn!(
+                            "partial sync of node: {} failed, will do full sync later",
+                            address
+                        );
+                        node_positions.lock().await.remove(&address);
+                    }
+                    return Err(err);
+                }
+            };
+
+            let res = extractor.receive(full_sync).await;
+            extractor.base_extractor.monitor.try_flush(true).await;
+            extractor.close().await?;
+            res
+        });
+        Ok(stream)
+    }
+
+    async fn get_master_nodes(&self, addresses: &[String]) -> anyhow::Result<Vec<ClusterNode>> {
+        // the configured node may be down, try nodes being extracted
+        let mut urls = vec![self.url.clone()];
+        for address in addresses {
+            urls.push(self.get_node_url(address)?);
+        }
+
+        let mut last_err = None;
+        for url in urls.iter() {
+            let nodes = match RedisUtil::create_redis_conn(url).await {
+                Ok(mut conn) => RedisUtil::get_cluster_master_nodes(&mut conn),
+                Err(err) => Err(err),
+            };
+            match nodes {
+                // masters without slots have no data
+                Ok(nodes) => {
+                    return Ok(nodes.into_iter().filter(|i| !i.slots.is_empty()).collect())
+                }
+                Err(err) => {
+                    log_warn!(
+                        "failed to get cluster nodes from: {}, error: {:?}",
+                        url,
+                        err
+                    );
+                    last_err = Some(err);
+                }
+            }
+        }
+
+        // the error is kept to check whether it is fatal, such as wrong password
+        let err = last_err.unwrap_or_else(|| anyhow!("no node to get master nodes"));
+        Err(err.context(Error::ExtractorError(
+            "can not get master nodes from any node of cluster".into(),
+        )))
+    }
+
+    fn get_node_url(&self, address: &str) -> anyhow::Result<String> {
+        let url_info = Url::parse(&self.url)?;
+        let username = url_info.username();
+        let url = match url_info.password() {
+            Some(password) => format!("redis://{}:{}@{}", username, password, address),
+            None => format!("redis://{}", address),
+        };
+        Ok(url)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn node_position(repl_id: &str, repl_offset: u64) -> RedisNodePosition {
+        RedisNodePosition {
+            repl_id: repl_id.into(),
+            repl_offset,
+            now_db_id: 0,
+        }
+    }
+
+    #[test]
+    fn test_take_over_position() {
+        let mut node_positions = BTreeMap::new();
+        node_positions.insert("127.0.0.1:6379".to_string(), node_position("id_1", 100));
+        node_positions.insert("127.0.0.1:6380".to_string(), node_position("id_2", 200));
+
+        // the same node
+        let position = RedisClusterExtractor::take_over_position(
+            &mut node_positions,
+            "127.0.0.1:6379",
+            &["id_1".into()],
+        );
+        assert_eq!(position, node_position("id_1", 100));
+        assert_eq!(node_positions.len(), 2);
+
+        // after failover, master_replid2 of the new master is the repl_id of the old master
+        let position = RedisClusterExtractor::take_over_position(
+            &mut node_positions,
+            "127.0.0.1:6381",
+            &["id_3".into(), "id_2".into()],
+        );
+        assert_eq!(position, node_position("id_2", 200));
+        assert!(!node_positions.contains_key("127.0.0.1:6380"));
+        assert_eq!(node_positions["127.0.0.1:6381"], position);
+
+        // a new node
+        let position = RedisClusterExtractor::take_over_position(
+            &mut node_positions,
+            "127.0.0.1:6382",
+            &["id_4".into()],
+        );
+        assert_eq!(position, RedisNodePosition::default());
+        assert_eq!(node_positions.len(), 2);
+    }
+
+    #[test]
+    fn test_get_heartbeat_hash_tag() {
+        let slots = vec![100, 5, 16383];
+        let mut slot_hash_tag_map = HashMap::new();
+        for i in 0.. {
+            let tag = i.to_string();
+            let slot = KeyParser::calc_slot(tag.as_bytes());
+            if slots.contains(&slot) {
+                slot_hash_tag_map.entry(slot).or_insert(tag);
+            }
+            if slot_hash_tag_map.len() == slots.len() {
+                break;
+            }
+        }
+        let mut node = ClusterNode {
+            is_master: true,
+            id: String::new(),
+            master_id: String::new(),
+            host: "127.0.0.1".into(),
+            port: "6379".into(),
+            address: "127.0.0.1:6379".into(),
+            slots,
+            slot_hash_tag_map,
+        };
+
+        let tag = RedisClusterExtractor::get_heartbeat_hash_tag(&node);
+        let key = format!("{{{}}}ape_dts_heartbeat", tag);
+        assert_eq!(KeyParser::calc_slot(key.as_bytes()), 5);
+
+        node.slots.clear();
+        assert!(RedisClusterExtractor::get_heartbeat_hash_tag(&node).is_empty());
+    }
+
+    #[test]
+    fn test_check_retry() {
+        let mut retry_times = HashMap::new();
+        for _ in 0..MAX_RETRY_TIMES {
+            RedisClusterExtractor::check_retry(
+                &mut retry_times,
+                "stream of node: 127.0.0.1:6379",
+                anyhow!("connection refused"),
+            )
+            .unwrap();
+        }
+        assert!(RedisClusterExtractor::check_retry(
+            &mut retry_times,
+            "stream of node: 127.0.0.1:6379",
+            anyhow!("connection refused"),
+        )
+        .is_err());
+
+        // fatal errors are not retried
+        let err = anyhow!("WRONGPASS invalid username-password pair").context("can't connect");
+        assert!(RedisClusterExtractor::check_retry(
+            &mut retry_times,
+            "stream of node: 127.0.0.1:6380",
+            err,
+        )
+        .is_err());
+        assert!(!retry_times.contains_key("stream of node: 127.0.0.1:6380"));
+    }
+}
@@ -1,10 +1,12 @@
+use std::collections::BTreeMap;
 use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::Arc;
 use std::time::{SystemTime, UNIX_EPOCH};
 
 use super::redis_client::RedisClient;
-use anyhow::bail;
+use anyhow::{bail, Context};
 use async_trait::async_trait;
+use serde_json::json;
 use tokio::{sync::Mutex, time::Instant};
 
 use crate::extractor::base_extractor::BaseExtractor;
@@ -17,7 +19,8 @@ use crate::Extractor;
 use dt_common::config::config_enums::{DbType, ExtractType};
 use dt_common::config::config_token_parser::ConfigTokenParser;
 use dt_common::meta::dt_data::DtData;
-use dt_common::meta::position::Position;
+use dt_common::meta::position::{Position, RedisNodePosition};
+use dt_common::meta::redis::command::key_parser::KeyParser;
 use dt_common::meta::redis::redis_entry::RedisEntry;
 use dt_common::meta::redis::redis_object::RedisCmd;
 use dt_common::meta::syncer::Syncer;
@@ -39,8 +42,15 @@ pub struct RedisPsyncExtractor {
     pub heartbeat_key: String,
     pub syncer: Arc<Mutex<Syncer>>,
     pub filter: RdbFilter,
+    pub key_parser: KeyParser,
     pub resumer: CdcResumer,
     pub extract_type: ExtractType,
+    // set when extracting from a master node of a cluster
+    pub node_address: String,
+    pub node_positions: Option<Arc<Mutex<BTreeMap<String, RedisNodePosition>>>>,
+    // set in cluster, a hash tag of a slot owned by the node, the heartbeat key is prefixed
+    // by it so heartbeats are executed by the node itself in db 0
+    pub heartbeat_hash_tag: String,
 }
 
 #[async_trait]
@@ -58,6 +68,17 @@ impl Extractor for RedisPsyncExtractor {
         );
 
         let full_sync = self.start_psync().await?;
+        self.receive(full_sync).await?;
+        self.base_extractor.wait_task_finish().await
+    }
+
+    async fn close(&mut self) -> anyhow::Result<()> {
+        self.conn.close().await
+    }
+}
+
+impl RedisPsyncExtractor {
+    pub async fn receive(&mut self, full_sync: bool) -> anyhow::Result<()> {
         if full_sync {
             // server won't send rdb if it's NOT full sync
             self.receive_rdb().await?;
@@ -69,16 +90,9 @@ impl Extractor for RedisPsyncExtractor {
         ) {
             self.receive_aof().await?;
         }
-
-        self.base_extractor.wait_task_finish().await
-    }
-
-    async fn close(&mut self) -> anyhow::Result<()> {
-        self.conn.close().await
+        Ok(())
     }
-}
 
-impl RedisPsyncExtractor {
     pub async fn start_psync(&mut self) -> anyhow::Result<bool> {
         // replconf listening-port [port]
         let repl_port = self.repl_port.to_string();
@@ -189,6 +203,7 @@ impl RedisPsyncExtractor {
                     Self::push_to_buf(
                         &mut self.base_extractor,
                         &mut self.filter,
+                        &self.key_parser,
                         entry,
                         Position::None,
                     )
@@ -212,22 +227,38 @@ impl RedisPsyncExtractor {
             repl_offset: self.repl_offset + 1,
             now_db_id: parser.now_db_id,
             timestamp: String::new(),
+            node_positions: BTreeMap::new(),
         };
-        log_position!("start_aof_position | {}", position.to_string());
+        log_position!(
+            payload:serde = json!({ "start_aof_position": position });
+            "start_aof_position | {}",
+            position.to_string()
+        );
         Ok(())
     }
 
     async fn receive_aof(&mut self) -> anyhow::Result<()> {
-        let heartbeat_db_key = ConfigTokenParser::parse(
+        let mut heartbeat_db_key = ConfigTokenParser::parse(
             &self.heartbeat_key,
             &['.'],
             &SqlUtil::get_escape_pairs(&DbType::Redis),
         );
-        let heartbeat_db_id = if heartbeat_db_key.len() == 2 {
+        let mut heartbeat_db_id = if heartbeat_db_key.len() == 2 {
             heartbeat_db_key[0].parse()?
         } else {
             i64::MIN
         };
+        if heartbeat_db_key.len() == 2 && !self.heartbeat_hash_tag.is_empty() {
+            // cluster only supports db 0
+            if heartbeat_db_id != 0 {
+                log_warn!(
+                    "heartbeat db: {} is ignored in cluster, use db 0 instead",
+                    heartbeat_db_id
+                );
+            }
+            heartbeat_db_id = 0;
+            heartbeat_db_key[1] = format!("{{{}}}{}", self.heartbeat_hash_tag, heartbeat_db_key[1]);
+        }
 
         // start hearbeat
         if heartbeat_db_key.len() == 2 {
@@ -285,6 +316,7 @@ impl RedisPsyncExtractor {
                     repl_offset: self.repl_offset,
                     now_db_id: self.now_db_id,
                     timestamp: heartbeat_timestamp.clone(),
+                    node_positions: self.get_node_positions().await,
                 };
 
                 // transaction begin
@@ -315,6 +347,7 @@ impl RedisPsyncExtractor {
                     self.base_extractor
                         .push_dt_data(DtData::Heartbeat {}, position)
                         .await?;
+                    self.update_node_position().await;
                     continue;
                 }
 
@@ -328,8 +361,15 @@ impl RedisPsyncExtractor {
                 entry.cmd = cmd;
                 entry.db_id = self.now_db_id;
 
-                Self::push_to_buf(&mut self.base_extractor, &mut self.filter, entry, position)
-                    .await?;
+                Self::push_to_buf(
+                    &mut self.base_extractor,
+                    &mut self.filter,
+                    &self.key_parser,
+                    entry,
+                    position,
+                )
+                .await?;
+                self.update_node_position().await;
             }
         }
     }
@@ -358,12 +398,56 @@ impl RedisPsyncExtractor {
         Ok(cmd)
     }
 
+    fn get_node_position(&self) -> RedisNodePosition {
+        RedisNodePosition {
+            repl_id: self.repl_id.clone(),
+            repl_offset: self.repl_offset,
+            now_db_id: self.now_db_id,
+        }
+    }
+
+    /// In cluster, shared positions of other nodes are updated only after their items are pushed,
+    /// so a logged position never contains offsets of items not pushed yet
+    async fn get_node_positions(&self) -> BTreeMap<String, RedisNodePosition> {
+        match &self.node_positions {
+            Some(node_positions) => {
+                let mut node_positions = node_positions.lock().await.clone();
+                node_positions.insert(self.node_address.clone(), self.get_node_position());
+                node_positions
+            }
+            None => BTreeMap::new(),
+        }
+    }
+
+    async fn update_node_position(&self) {
+        if let Some(node_positions) = &self.node_positions {
+            node_positions
+                .lock()
+                .await
+                .insert(self.node_address.clone(), self.get_node_position());
+        }
+    }
+
     async fn keep_alive_ack(&mut self) -> anyhow::Result<()> {
         // send replconf ack to keep the connection alive
         let mut position_repl_offset = self.repl_offset;
-        if let Position::Redis { repl_offset, .. } = self.syncer.lock().await.committed_position {
-            if repl_offset >= self.repl_offset {
-                position_repl_offset = repl_offset
+        if let Position::Redis {
+            repl_offset,
+            node_positions,
+            ..
+        } = &self.syncer.lock().await.committed_position
+        {
+            // in cluster, the committed position may be generated by another node
+            let committed_repl_offset = if self.node_positions.is_some() {
+                node_positions
+                    .get(&self.node_address)
+                    .map(|i| i.repl_offset)
+                    .unwrap_or_default()
+            } else {
+                *repl_offset
+            };
+            if committed_repl_offset >= self.repl_offset {
+                position_repl_offset = committed_repl_offset
             }
         }
 
@@ -399,21 +483,20 @@ impl RedisPsyncExtractor {
         let heartbeat_interval_secs = self.heartbeat_interval_secs;
         let key = key.to_string();
 
-        tokio::spawn(async move {
-            // set db
+        // errors of the first heartbeat are returned, such as: wrong db, key not owned by the node
+        if db_id != 0 {
             let cmd = RedisCmd::from_str_args(&["SELECT", &db_id.to_string()]);
-            if let Err(err) = conn.send(&cmd).await {
-                log_error!(
-                    "heartbeat failed, cmd: {}, error: {:?}",
-                    cmd.to_string(),
-                    err
-                );
-            }
+            Self::send_heartbeat_cmd(&mut conn, &cmd).await?;
+        }
+        Self::heartbeat(&key, &mut conn).await?;
 
+        tokio::spawn(async move {
             let mut start_time = Instant::now();
             while !shut_down.load(Ordering::Acquire) {
                 if start_time.elapsed().as_secs() >= heartbeat_interval_secs {
-                    Self::heartbeat(&key, &mut conn).await.unwrap();
+                    if let Err(err) = Self::heartbeat(&key, &mut conn).await {
+                        log_error!("heartbeat failed, error: {:?}", err);
+                    }
                     start_time = Instant::now();
                 }
                 TimeUtil::sleep_millis(1000 * heartbeat_interval_secs).await;
@@ -432,28 +515,79 @@ impl RedisPsyncExtractor {
 
         let cmd = RedisCmd::from_str_args(&["SET", key, &heartbeat_value]);
         log_info!("heartbeat cmd: {}", cmd.to_string());
-        if let Err(err) = conn.send(&cmd).await {
-            log_error!("heartbeat failed, error: {:?}", err);
+        Self::send_heartbeat_cmd(conn, &cmd).await
+    }
+
+    async fn send_heartbeat_cmd(conn: &mut RedisClient, cmd: &RedisCmd) -> anyhow::Result<()> {
+        conn.send(cmd).await?;
+        let value = conn
+            .read()
+            .await
+            .with_context(|| format!("heartbeat cmd: {} failed", cmd))?;
+        match value {
+            Value::Okay => Ok(()),
+            value => bail! {Error::RedisResultError(format!(
+                "heartbeat cmd: {} failed, response: {:?}",
+                cmd, value
+            ))},
         }
-        Ok(())
     }
 
     pub async fn push_to_buf(
         base_extractor: &mut BaseExtractor,
         filter: &mut RdbFilter,
+        key_parser: &KeyParser,
         mut entry: RedisEntry,
         position: Position,
     ) -> anyhow::Result<()> {
-        // currently only support db filter
-        if filter.filter_schema(&entry.db_id.to_string()) {
+        if filter.filter_schema(&entry.db_id.to_string())
+            || Self::filter_keys(filter, key_parser, &mut entry)
+        {
             base_extractor
                 .push_dt_data(DtData::Heartbeat {}, position)
                 .await
+        } else if !base_extractor.router.redis_map.is_empty() {
+            // redis -> rdb, keys not mapped are ignored
+            let rows = base_extractor.router.redis_entry_to_rows(&entry);
+            if rows.is_empty() {
+                return base_extractor
+                    .push_dt_data(DtData::Heartbeat {}, position)
+                    .await;
+            }
+            for row_data in rows {
+                base_extractor.push_row(row_data, position.clone()).await?;
+            }
+            Ok(())
         } else {
+            let mut entry = base_extractor.router.route_redis_entry(entry, key_parser);
             entry.data_size = entry.get_data_malloc_size();
             base_extractor
                 .push_dt_data(DtData::Redis { entry }, position)
                 .await
         }
     }
+
+    /// a command is filtered only if all of its keys are filtered,
+    /// commands without keys (eg: flushdb) are kept
+    fn filter_keys(filter: &RdbFilter, key_parser: &KeyParser, entry: &mut RedisEntry) -> bool {
+        if !filter.has_key_filter() {
+            return false;
+        }
+
+        if entry.is_base {
+            return filter.filter_key(&entry.key.to_string());
+        }
+
+        if entry.cmd.keys.is_empty() {
+            if let Err(err) = entry.cmd.parse_keys(key_parser) {
+                log_warn!(
+                    "failed to parse keys of cmd: [{}], error: {}",
+                    entry.cmd,
+                    err
+                );
+                return false;
+            }
+        }
+        !entry.cmd.keys.is_empty() && entry.cmd.keys.iter().all(|i| filter.filter_key(i))
+    }
 }
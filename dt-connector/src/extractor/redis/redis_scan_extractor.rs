@@ -61,6 +61,9 @@ impl Extractor for RedisScanExtractor {
 
                 cursor = result[0].parse()?;
                 for key in result.iter().skip(1) {
+                    if self.filter.filter_key(key) {
+                        continue;
+                    }
                     match self.statistic_type {
                         RedisStatisticType::HotKey => self.analyze_hot_key(db_id, key).await?,
                         RedisStatisticType::BigKey => self.analyze_big_key(db_id, key).await?,
@@ -123,17 +126,46 @@ impl RedisScanExtractor {
         let result = self.query(&cmd).await?;
         let key_type = result[0].clone();
 
+        // -1 if no expire, -2 if removed
+        let cmd = ["PTTL", key];
+        let result = self.query(&cmd).await?;
+        let ttl: i64 = result[0].parse()?;
+
+        let element_count = match Self::get_count_cmd(&key_type) {
+            Some(cmd_name) => {
+                let cmd = [cmd_name, key];
+                let result = self.query(&cmd).await?;
+                result[0].parse().unwrap_or_default()
+            }
+            // same as entries parsed from rdb
+            None if key_type == "string" => 1,
+            None => 0,
+        };
+
         let mut entry = RedisEntry::new();
         entry.db_id = db_id;
         entry.key = RedisString::from(key.to_owned());
         entry.data_size = data_size;
         entry.value = RedisObject::new(&key_type);
+        entry.expire_ms = ttl.max(0);
+        entry.element_count = element_count;
 
         self.base_extractor
             .push_dt_data(DtData::Redis { entry }, Position::None)
             .await
     }
 
+    fn get_count_cmd(key_type: &str) -> Option<&'static str> {
+        match key_type {
+            "list" => Some("LLEN"),
+            "hash" => Some("HLEN"),
+            "set" => Some("SCARD"),
+            "zset" => Some("ZCARD"),
+            "stream" => Some("XLEN"),
+            _ => None,
+        }
+    }
+
     async fn query(&mut self, cmd: &[&str]) -> anyhow::Result<Vec<String>> {
         let result = RedisUtil::send_cmd(&mut self.conn, cmd)?;
         RedisUtil::parse_result_as_string(result)
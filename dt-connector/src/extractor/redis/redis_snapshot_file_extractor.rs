@@ -9,11 +9,13 @@ use crate::extractor::redis::redis_psync_extractor::RedisPsyncExtractor;
 use crate::Extractor;
 use dt_common::log_info;
 use dt_common::meta::position::Position;
+use dt_common::meta::redis::command::key_parser::KeyParser;
 use dt_common::rdb_filter::RdbFilter;
 
 pub struct RedisSnapshotFileExtractor {
     pub file_path: String,
     pub filter: RdbFilter,
+    pub key_parser: KeyParser,
     pub base_extractor: BaseExtractor,
 }
 
@@ -59,6 +61,7 @@ impl Extractor for RedisSnapshotFileExtractor {
                 RedisPsyncExtractor::push_to_buf(
                     &mut self.base_extractor,
                     &mut self.filter,
+                    &self.key_parser,
                     entry,
                     Position::None,
                 )
@@ -13,7 +13,7 @@ use dt_common::{
 };
 use serde_json::json;
 
-use super::{CURRENT_POSITION_LOG_FLAG, TAIL_POSITION_COUNT};
+use super::{is_current_position_log, TAIL_POSITION_COUNT};
 
 #[derive(Clone, Default)]
 pub struct CdcResumer {
@@ -80,7 +80,7 @@ impl CdcResumer {
             return;
         }
 
-        if line.contains(CURRENT_POSITION_LOG_FLAG) {
+        if is_current_position_log(line) {
             self.current_position = position;
         } else {
             self.checkpoint_position = position;
@@ -2,4 +2,10 @@ pub mod cdc_resumer;
 pub mod snapshot_resumer;
 
 const CURRENT_POSITION_LOG_FLAG: &str = "| current_position |";
+// position.log in json log format, payload: {"current_position": {...}}
+const CURRENT_POSITION_JSON_LOG_FLAG: &str = r#""payload":{"current_position":"#;
 const TAIL_POSITION_COUNT: usize = 200;
+
+fn is_current_position_log(line: &str) -> bool {
+    line.contains(CURRENT_POSITION_LOG_FLAG) || line.contains(CURRENT_POSITION_JSON_LOG_FLAG)
+}
@@ -13,7 +13,7 @@ use dt_common::{
 };
 use dt_common::{log_info, meta::position::Position};
 
-use super::{CURRENT_POSITION_LOG_FLAG, TAIL_POSITION_COUNT};
+use super::{is_current_position_log, TAIL_POSITION_COUNT};
 
 #[derive(Clone, Default)]
 pub struct SnapshotResumer {
@@ -140,7 +140,7 @@ impl SnapshotResumer {
 
     fn load_resume_line(&mut self, line: &str) {
         // by default, all positions in resumer.config are checkpoint positions
-        let tb_positions = if line.contains(CURRENT_POSITION_LOG_FLAG) {
+        let tb_positions = if is_current_position_log(line) {
             &mut self.current_tb_positions
         } else {
             &mut self.checkpoint_tb_positions
@@ -165,6 +165,15 @@ impl SnapshotResumer {
                 tb_positions.insert((schema, tb, String::new()), s3_meta_file);
             }
 
+            Position::File {
+                schema,
+                tb,
+                file_path,
+                offset,
+            } => {
+                tb_positions.insert((schema, tb, file_path), offset.to_string());
+            }
+
             Position::RdbSnapshotFinished { schema, tb, .. } => {
                 self.finished_tbs.insert((schema, tb));
             }
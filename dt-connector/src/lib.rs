@@ -48,6 +48,12 @@ pub trait Sinker {
         Ok(())
     }
 
+    // called before positions are recorded by checkpoints, sinkers keeping data across batches
+    // should write them out, or return false to keep them and hold back positions unless forced
+    async fn flush(&mut self, _force: bool) -> anyhow::Result<bool> {
+        Ok(true)
+    }
+
     fn get_id(&self) -> String {
         String::new()
     }
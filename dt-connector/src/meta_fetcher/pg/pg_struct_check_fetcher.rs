@@ -264,6 +264,7 @@ impl PgStructCheckFetcher {
             element_oid: 0,
             category: String::new(),
             enum_values: None,
+            typmod: -1,
         };
 
         if !alias.is_empty() {
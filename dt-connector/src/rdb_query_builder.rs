@@ -108,7 +108,9 @@ impl RdbQueryBuilder<'_> {
     ) -> anyhow::Result<RdbQueryInfo<'a>> {
         match row_data.row_type {
             RowType::Insert => {
-                if replace {
+                if replace && self.is_partial_row(row_data) {
+                    self.get_partial_upsert_query(row_data, placeholder)
+                } else if replace {
                     self.get_replace_query(row_data, placeholder)
                 } else {
                     self.get_insert_query(row_data, placeholder)
@@ -126,11 +128,13 @@ impl RdbQueryBuilder<'_> {
         batch_size: usize,
     ) -> anyhow::Result<(RdbQueryInfo<'a>, usize)> {
         let mut data_size = 0;
+        // rows in a batch are of the same kind, either with all key columns or partial
+        let where_cols = self.get_delete_cols(data[start_index].before.as_ref().unwrap());
         let mut all_placeholders = Vec::new();
         let mut placeholder_index = 1;
         for _ in 0..batch_size {
             let mut placeholders = Vec::new();
-            for col in self.rdb_tb_meta.id_cols.iter() {
+            for col in where_cols.iter() {
                 placeholders.push(self.get_placeholder(placeholder_index, col)?);
                 placeholder_index += 1;
             }
@@ -141,7 +145,7 @@ impl RdbQueryBuilder<'_> {
             "DELETE FROM {}.{} WHERE ({}) IN ({})",
             self.escape(&self.rdb_tb_meta.schema),
             self.escape(&self.rdb_tb_meta.tb),
-            self.escape_cols(&self.rdb_tb_meta.id_cols).join(","),
+            self.escape_cols(&where_cols).join(","),
             all_placeholders.join(",")
         );
 
@@ -150,7 +154,7 @@ impl RdbQueryBuilder<'_> {
         for row_data in data.iter().skip(start_index).take(batch_size) {
             data_size += row_data.data_size;
             let before = row_data.before.as_ref().unwrap();
-            for col in self.rdb_tb_meta.id_cols.iter() {
+            for col in where_cols.iter() {
                 cols.push(col.clone());
                 let col_value = before.get(col);
                 if col_value.is_none() || *col_value.unwrap() == ColValue::None {
@@ -244,6 +248,79 @@ impl RdbQueryBuilder<'_> {
         Ok(query_info)
     }
 
+    fn is_partial_row(&self, row_data: &RowData) -> bool {
+        let Some(after) = row_data.after.as_ref() else {
+            return false;
+        };
+        self.rdb_tb_meta
+            .cols
+            .iter()
+            .any(|col| !after.contains_key(col))
+    }
+
+    /// rows carrying only some columns, such as those converted from redis HSET,
+    /// only the given columns are inserted, or updated if the row exists
+    fn get_partial_upsert_query<'a>(
+        &self,
+        row_data: &'a RowData,
+        placeholder: bool,
+    ) -> anyhow::Result<RdbQueryInfo<'a>> {
+        let after = row_data.after.as_ref().unwrap();
+        let mut cols = Vec::new();
+        let mut binds = Vec::new();
+        let mut col_values = Vec::new();
+        for col in self.rdb_tb_meta.cols.iter() {
+            if !after.contains_key(col) {
+                continue;
+            }
+            let sql_value =
+                self.get_sql_value(cols.len() + 1, col, &after.get(col), placeholder)?;
+            col_values.push(sql_value);
+            cols.push(col.clone());
+            binds.push(after.get(col));
+        }
+
+        let mut set_pairs = Vec::new();
+        for col in cols.iter() {
+            if self.rdb_tb_meta.id_cols.contains(col) {
+                continue;
+            }
+            let col = self.escape(col);
+            if self.db_type == DbType::Pg {
+                set_pairs.push(format!("{}=EXCLUDED.{}", col, col));
+            } else {
+                set_pairs.push(format!("{}=VALUES({})", col, col));
+            }
+        }
+
+        let mut sql = format!(
+            "INSERT INTO {}.{}({}) VALUES({})",
+            self.escape(&self.rdb_tb_meta.schema),
+            self.escape(&self.rdb_tb_meta.tb),
+            self.escape_cols(&cols).join(","),
+            col_values.join(",")
+        );
+        if self.db_type == DbType::Pg {
+            let conflict_cols = self.escape_cols(&self.rdb_tb_meta.id_cols).join(",");
+            if set_pairs.is_empty() {
+                sql = format!("{} ON CONFLICT ({}) DO NOTHING", sql, conflict_cols);
+            } else {
+                sql = format!(
+                    "{} ON CONFLICT ({}) DO UPDATE SET {}",
+                    sql,
+                    conflict_cols,
+                    set_pairs.join(",")
+                );
+            }
+        } else if set_pairs.is_empty() {
+            sql = format!("INSERT IGNORE{}", sql.trim_start_matches("INSERT"));
+        } else {
+            sql = format!("{} ON DUPLICATE KEY UPDATE {}", sql, set_pairs.join(","));
+        }
+
+        Ok(RdbQueryInfo { sql, cols, binds })
+    }
+
     fn get_insert_query<'a>(
         &self,
         row_data: &'a RowData,
@@ -281,14 +358,16 @@ impl RdbQueryBuilder<'_> {
         placeholder: bool,
     ) -> anyhow::Result<RdbQueryInfo<'a>> {
         let before = row_data.before.as_ref().unwrap();
-        let (where_sql, not_null_cols) = self.get_where_info(1, before, placeholder)?;
+        let where_cols = self.get_delete_cols(before);
+        let (where_sql, not_null_cols) =
+            self.get_where_info_by_cols(1, &where_cols, before, placeholder)?;
         let mut sql = format!(
             "DELETE FROM {}.{} WHERE {}",
             self.escape(&self.rdb_tb_meta.schema),
             self.escape(&self.rdb_tb_meta.tb),
             where_sql
         );
-        if self.rdb_tb_meta.key_map.is_empty() {
+        if self.rdb_tb_meta.key_map.is_empty() && where_cols == self.rdb_tb_meta.id_cols {
             sql += " LIMIT 1";
         }
 
@@ -301,6 +380,25 @@ impl RdbQueryBuilder<'_> {
         Ok(RdbQueryInfo { sql, cols, binds })
     }
 
+    /// rows carrying only some of the key columns, such as those removing child rows of
+    /// mongo documents by parent_col, delete all rows matching the given columns
+    fn get_delete_cols(&self, before: &HashMap<String, ColValue>) -> Vec<String> {
+        if self
+            .rdb_tb_meta
+            .id_cols
+            .iter()
+            .all(|col| before.contains_key(col))
+        {
+            return self.rdb_tb_meta.id_cols.clone();
+        }
+        self.rdb_tb_meta
+            .cols
+            .iter()
+            .filter(|col| before.contains_key(*col))
+            .cloned()
+            .collect()
+    }
+
     fn get_update_query<'a>(
         &self,
         row_data: &'a RowData,
@@ -432,15 +530,25 @@ impl RdbQueryBuilder<'_> {
     }
 
     fn get_where_info(
+        &self,
+        index: usize,
+        col_value_map: &HashMap<String, ColValue>,
+        placeholder: bool,
+    ) -> anyhow::Result<(String, Vec<String>)> {
+        self.get_where_info_by_cols(index, &self.rdb_tb_meta.id_cols, col_value_map, placeholder)
+    }
+
+    fn get_where_info_by_cols(
         &self,
         mut index: usize,
+        where_cols: &[String],
         col_value_map: &HashMap<String, ColValue>,
         placeholder: bool,
     ) -> anyhow::Result<(String, Vec<String>)> {
         let mut where_sql = String::new();
         let mut not_null_cols = Vec::new();
 
-        for col in self.rdb_tb_meta.id_cols.iter() {
+        for col in where_cols.iter() {
             if !where_sql.is_empty() {
                 where_sql += " AND";
             }
@@ -1,11 +1,21 @@
+use anyhow::bail;
 use dt_common::{
     config::{
         config_enums::DbType,
         config_token_parser::ConfigTokenParser,
-        router_config::{RouterConfig, ContentRoute},
+        router_config::{ContentRoute, RouterConfig},
     },
+    error::Error,
+    log_warn,
     meta::{
         ddl_meta::{ddl_data::DdlData, ddl_statement::DdlStatement},
+        mongo::mongo_rdb_mapping::MongoRdbMapping,
+        redis::{
+            command::key_parser::KeyParser,
+            redis_entry::RedisEntry,
+            redis_object::{RedisCmd, RedisObject, RedisString},
+            redis_rdb_mapping::RedisRdbMapping,
+        },
         struct_meta::{statement::struct_statement::StructStatement, struct_data::StructData},
     },
     utils::sql_util::SqlUtil,
@@ -19,6 +29,9 @@ type SchemaMap = HashMap<String, String>;
 type TbMap = HashMap<(String, String), (String, String)>;
 type TbColMap = HashMap<(String, String), HashMap<String, String>>;
 type ContentRoutes = HashMap<(String, String), ContentRoute>;
+type KeyMap = Vec<(String, String)>;
+type RedisMap = Vec<RedisRdbMapping>;
+type MongoMap = Vec<MongoRdbMapping>;
 
 const JSON_PREFIX: &str = "json:";
 
@@ -34,6 +47,12 @@ pub struct RdbRouter {
     pub topic_map: HashMap<(String, String), String>,
     // Content-based routing rules
     pub content_routes: ContentRoutes,
+    // Vec<(src_prefix, dst_prefix)> of redis keys
+    pub key_map: KeyMap,
+    // rdb table <-> redis key mappings
+    pub redis_map: RedisMap,
+    // mongo collection -> rdb tables mappings
+    pub mongo_map: MongoMap,
 }
 
 impl RdbRouter {
@@ -45,18 +64,37 @@ impl RdbRouter {
                 col_map,
                 topic_map,
                 content_routes,
+                key_map,
+                redis_map,
+                mongo_map,
             } => {
                 let schema_map = Self::parse_schema_map(schema_map, db_type)?;
                 let tb_map = Self::parse_tb_map(tb_map, db_type)?;
                 let col_map = Self::parse_col_map(col_map)?;
                 let topic_map = Self::parse_topic_map(topic_map, db_type)?;
                 let content_routes = Self::parse_content_routes(content_routes)?;
+                let key_map = Self::parse_key_map(key_map)?;
+                let redis_map = Self::parse_redis_map(redis_map)?;
+                let mongo_map = Self::parse_mongo_map(mongo_map)?;
+                if matches!(db_type, DbType::Redis) {
+                    for (src_db, dst_db) in schema_map.iter() {
+                        if src_db.parse::<i64>().is_err() || dst_db.parse::<i64>().is_err() {
+                            bail! {Error::ConfigError(format!(
+                                "db_map of redis should be db ids, got: {}:{}",
+                                src_db, dst_db
+                            ))}
+                        }
+                    }
+                }
                 Ok(Self {
                     schema_map,
                     tb_map,
                     col_map,
                     topic_map,
                     content_routes,
+                    key_map,
+                    redis_map,
+                    mongo_map,
                 })
             }
         }
@@ -125,6 +163,15 @@ impl RdbRouter {
             topic_map: self.topic_map.clone(),
             // content_routes should not be reversed
             content_routes: self.content_routes.clone(),
+            key_map: self
+                .key_map
+                .iter()
+                .map(|(src, dst)| (dst.to_owned(), src.to_owned()))
+                .collect(),
+            // redis_map works in both directions
+            redis_map: self.redis_map.clone(),
+            // mongo_map only applies to mongo sources
+            mongo_map: self.mongo_map.clone(),
         }
     }
 
@@ -170,7 +217,8 @@ impl RdbRouter {
         match &mut ddl_data.statement {
             DdlStatement::MysqlAlterTableRename(_)
             | DdlStatement::PgAlterTableRename(_)
-            | DdlStatement::RenameTable(_) => {
+            | DdlStatement::RenameTable(_)
+            | DdlStatement::MongoRenameCollection(_) => {
                 let (src_schema, src_tb) = ddl_data.get_schema_tb();
                 let (src_new_schema, src_new_tb) = ddl_data.get_rename_to_schema_tb();
                 let (dst_schema, dst_tb) = self.get_tb_map(&src_schema, &src_tb);
@@ -226,6 +274,161 @@ impl RdbRouter {
         struct_data
     }
 
+    /// routes db and keys of both rdb entries and aof commands
+    pub fn route_redis_entry(&self, mut entry: RedisEntry, key_parser: &KeyParser) -> RedisEntry {
+        if let Some(dst_db) = self.schema_map.get(&entry.db_id.to_string()) {
+            // validated in from_config
+            entry.db_id = dst_db.parse().unwrap();
+        }
+
+        if self.key_map.is_empty() {
+            return entry;
+        }
+
+        if entry.is_base {
+            if let Some(dst_key) = self.get_key_map(entry.key.as_bytes()) {
+                entry.key = RedisString::from(dst_key);
+                entry.value.set_key(entry.key.clone());
+            }
+            match &mut entry.value {
+                RedisObject::Stream(obj) => {
+                    for cmd in obj.cmds.iter_mut() {
+                        self.route_redis_cmd(cmd, key_parser);
+                    }
+                }
+                RedisObject::Module(obj) => {
+                    // module rewrite commands always take the key as the first arg
+                    for cmd in obj.cmds.iter_mut() {
+                        if let Some(arg) = cmd.args.get_mut(1) {
+                            *arg = entry.key.as_bytes().to_vec();
+                        } else {
+                            log_warn!("no key in module cmd: [{}], not routed", cmd);
+                        }
+                    }
+                }
+                _ => {}
+            }
+        } else {
+            self.route_redis_cmd(&mut entry.cmd, key_parser);
+        }
+        entry
+    }
+
+    fn route_redis_cmd(&self, cmd: &mut RedisCmd, key_parser: &KeyParser) {
+        if cmd.keys.is_empty() {
+            if let Err(err) = cmd.parse_keys(key_parser) {
+                log_warn!("failed to parse keys of cmd: [{}], error: {}", cmd, err);
+                return;
+            }
+        }
+
+        // key_indexes are 1-based
+        for (i, key_index) in cmd.key_indexes.clone().into_iter().enumerate() {
+            if let Some(dst_key) = self.get_key_map(&cmd.args[key_index - 1]) {
+                cmd.keys[i] = String::from_utf8_lossy(&dst_key).to_string();
+                cmd.args[key_index - 1] = dst_key;
+            }
+        }
+    }
+
+    fn get_key_map(&self, key: &[u8]) -> Option<Vec<u8>> {
+        // the first matched prefix wins
+        for (src_prefix, dst_prefix) in self.key_map.iter() {
+            if let Some(suffix) = key.strip_prefix(src_prefix.as_bytes()) {
+                let mut dst_key = dst_prefix.as_bytes().to_vec();
+                dst_key.extend_from_slice(suffix);
+                return Some(dst_key);
+            }
+        }
+        None
+    }
+
+    pub fn get_redis_mapping(&self, schema: &str, tb: &str) -> Option<&RedisRdbMapping> {
+        self.redis_map.iter().find(|i| i.db == schema && i.tb == tb)
+    }
+
+    /// converts redis entries of mapped keys into rows, keys not mapped are ignored
+    pub fn redis_entry_to_rows(&self, entry: &RedisEntry) -> Vec<RowData> {
+        let keys = if entry.is_base {
+            vec![entry.key.to_string()]
+        } else {
+            match entry.cmd.get_name().to_ascii_uppercase().as_str() {
+                // the only multi-key commands converted
+                "DEL" | "UNLINK" => entry.cmd.args_to_string()[1..].to_vec(),
+                _ => vec![entry.cmd.get_str_arg(1)],
+            }
+        };
+
+        let mut rows = Vec::new();
+        for key in keys.iter() {
+            // the first matched mapping wins
+            let matched = self
+                .redis_map
+                .iter()
+                .find_map(|i| i.parse_key(key).map(|key_cols| (i, key_cols)));
+            if let Some((mapping, key_cols)) = matched {
+                if let Some(row_data) = mapping.to_row_data(key_cols, entry) {
+                    rows.push(row_data);
+                }
+            }
+        }
+        rows
+    }
+
+    fn parse_redis_map(config_str: &str) -> anyhow::Result<RedisMap> {
+        if config_str.trim().is_empty() {
+            return Ok(RedisMap::new());
+        }
+        // redis_map=json:[{"db":"test_db","tb":"tb_1","key":"user:{tenant_id}:{id}","value_format":"hash","ttl_col":"ttl","cols":["name"]}]
+        let mut results: RedisMap =
+            serde_json::from_str(config_str.trim_start_matches(JSON_PREFIX))?;
+        for i in results.iter_mut() {
+            i.parse()?;
+        }
+        Ok(results)
+    }
+
+    /// mapping of a collection, collections not configured are flattened by default
+    pub fn get_mongo_mapping(&self, schema: &str, tb: &str) -> MongoRdbMapping {
+        self.mongo_map
+            .iter()
+            .find(|i| i.db == schema && i.tb == tb)
+            .cloned()
+            .unwrap_or_else(|| MongoRdbMapping::new(schema, tb))
+    }
+
+    fn parse_mongo_map(config_str: &str) -> anyhow::Result<MongoMap> {
+        if config_str.trim().is_empty() {
+            return Ok(MongoMap::new());
+        }
+        // mongo_map=json:[{"db":"test_db","tb":"users","id_col":"id","cols":[{"path":"profile.name","col":"name"}],"child_tbs":[{"path":"orders","tb":"user_orders"}]}]
+        let mut results: MongoMap =
+            serde_json::from_str(config_str.trim_start_matches(JSON_PREFIX))?;
+        for i in results.iter_mut() {
+            i.parse()?;
+        }
+        Ok(results)
+    }
+
+    fn parse_key_map(config_str: &str) -> anyhow::Result<KeyMap> {
+        let mut results = KeyMap::new();
+        if config_str.trim().is_empty() {
+            return Ok(results);
+        }
+        // key_map=json:[{"src_prefix":"order:","dst_prefix":"tenant_a:order:"}]
+        #[derive(Serialize, Deserialize)]
+        struct KeyMapType {
+            src_prefix: String,
+            dst_prefix: String,
+        }
+        let config: Vec<KeyMapType> =
+            serde_json::from_str(config_str.trim_start_matches(JSON_PREFIX))?;
+        for i in config {
+            results.push((i.src_prefix, i.dst_prefix));
+        }
+        Ok(results)
+    }
+
     fn parse_schema_map(config_str: &str, db_type: &DbType) -> anyhow::Result<SchemaMap> {
         // db_map=src_db_1:dst_db_1,src_db_2:dst_db_2
         let mut schema_map = HashMap::new();
@@ -329,7 +532,10 @@ impl RdbRouter {
         tb: &str,
         col_values: &HashMap<String, ColValue>,
     ) -> Option<(String, String, Option<String>)> {
-        if let Some(route_config) = self.content_routes.get(&(schema.to_string(), tb.to_string())) {
+        if let Some(route_config) = self
+            .content_routes
+            .get(&(schema.to_string(), tb.to_string()))
+        {
             // Convert ColValue HashMap to String HashMap for evaluation
             let str_map: HashMap<String, String> = col_values
                 .iter()
@@ -342,7 +548,6 @@ impl RdbRouter {
         }
         None
     }
-
 }
 
 #[cfg(test)]
@@ -351,6 +556,12 @@ mod tests {
 
     use dt_common::config::{config_enums::DbType, router_config::RouterConfig};
 
+    use dt_common::meta::redis::{
+        command::key_parser::KeyParser,
+        redis_entry::RedisEntry,
+        redis_object::{ModuleObject, RedisCmd, RedisObject, RedisString, StringObject},
+    };
+
     use super::{RdbRouter, TbColMap, TbMap};
 
     #[test]
@@ -470,6 +681,10 @@ mod tests {
             tb_map: tb_map_str.into(),
             col_map: col_map_str.into(),
             topic_map: topic_map.into(),
+            content_routes: String::new(),
+            key_map: String::new(),
+            redis_map: String::new(),
+            mongo_map: String::new(),
         };
         let router = RdbRouter::from_config(&config, &DbType::Mysql).unwrap();
 
@@ -496,4 +711,152 @@ mod tests {
         assert_eq!(router.get_topic("db:1", "tb:2"), "test2");
         assert_eq!(router.get_topic("db:2", "tb:1"), "test");
     }
+
+    #[test]
+    fn test_route_redis_entry() {
+        let config = RouterConfig::Rdb {
+            schema_map: "3:0".into(),
+            tb_map: String::new(),
+            col_map: String::new(),
+            topic_map: String::new(),
+            content_routes: String::new(),
+            key_map: r#"json:[{"src_prefix":"order:","dst_prefix":"a:order:"},{"src_prefix":"","dst_prefix":"b:"}]"#.into(),
+            redis_map: String::new(),
+            mongo_map: String::new(),
+        };
+        let router = RdbRouter::from_config(&config, &DbType::Redis).unwrap();
+        let key_parser = KeyParser::new();
+
+        // rdb entry
+        let mut entry = RedisEntry::new();
+        entry.is_base = true;
+        entry.db_id = 3;
+        entry.key = RedisString::from("order:1".to_string());
+        let mut obj = StringObject::new();
+        obj.key = entry.key.clone();
+        entry.value = RedisObject::String(obj);
+        let entry = router.route_redis_entry(entry, &key_parser);
+        assert_eq!(entry.db_id, 0);
+        assert_eq!(entry.key.to_string(), "a:order:1");
+        match entry.value {
+            RedisObject::String(obj) => assert_eq!(obj.key.to_string(), "a:order:1"),
+            _ => panic!("unexpected value type"),
+        }
+
+        // aof cmd with multiple keys
+        let mut entry = RedisEntry::new();
+        entry.db_id = 1;
+        entry.cmd = RedisCmd::from_str_args(&["MSET", "order:1", "v1", "user:1", "v2"]);
+        let entry = router.route_redis_entry(entry, &key_parser);
+        assert_eq!(entry.db_id, 1);
+        assert_eq!(entry.cmd.to_string(), "MSET a:order:1 v1 b:user:1 v2");
+        assert_eq!(entry.cmd.keys, vec!["a:order:1", "b:user:1"]);
+
+        // module entry, cmds without key are kept as they are
+        let mut entry = RedisEntry::new();
+        entry.is_base = true;
+        entry.key = RedisString::from("order:1".to_string());
+        let mut obj = ModuleObject::new();
+        obj.key = entry.key.clone();
+        obj.cmds = vec![
+            RedisCmd::from_str_args(&["JSON.SET", "order:1", "$", "{}"]),
+            RedisCmd::from_str_args(&["JSON.SET"]),
+        ];
+        entry.value = RedisObject::Module(obj);
+        let entry = router.route_redis_entry(entry, &key_parser);
+        match entry.value {
+            RedisObject::Module(obj) => {
+                assert_eq!(obj.key.to_string(), "a:order:1");
+                assert_eq!(obj.cmds[0].to_string(), "JSON.SET a:order:1 $ {}");
+                assert_eq!(obj.cmds[1].to_string(), "JSON.SET");
+            }
+            _ => panic!("unexpected value type"),
+        }
+
+        // db_map of redis should be db ids
+        let config = RouterConfig::Rdb {
+            schema_map: "a:0".into(),
+            tb_map: String::new(),
+            col_map: String::new(),
+            topic_map: String::new(),
+            content_routes: String::new(),
+            key_map: String::new(),
+            redis_map: String::new(),
+            mongo_map: String::new(),
+        };
+        assert!(RdbRouter::from_config(&config, &DbType::Redis).is_err());
+    }
+
+    #[test]
+    fn test_redis_entry_to_rows() {
+        let config = RouterConfig::Rdb {
+            schema_map: String::new(),
+            tb_map: String::new(),
+            col_map: String::new(),
+            topic_map: String::new(),
+            content_routes: String::new(),
+            key_map: String::new(),
+            redis_map: r#"json:[{"db":"db_1","tb":"tb_1","key":"user:{id}"},{"db":"db_1","tb":"tb_2","key":"order:{tenant}:{id}","value_format":"json"}]"#.into(),
+            mongo_map: String::new(),
+        };
+        let router = RdbRouter::from_config(&config, &DbType::Redis).unwrap();
+        assert_eq!(
+            router.get_redis_mapping("db_1", "tb_2").unwrap().key,
+            "order:{tenant}:{id}"
+        );
+        assert!(router.get_redis_mapping("db_1", "tb_3").is_none());
+
+        let mut entry = RedisEntry::new();
+        entry.cmd = RedisCmd::from_str_args(&["DEL", "user:1", "other:1", "order:t1:2"]);
+        let rows = router.redis_entry_to_rows(&entry);
+        assert_eq!(rows.len(), 2);
+        assert_eq!(rows[0].tb, "tb_1");
+        assert_eq!(
+            rows[0].before.as_ref().unwrap()["id"],
+            ColValue::String("1".into())
+        );
+        assert_eq!(rows[1].tb, "tb_2");
+        assert_eq!(
+            rows[1].before.as_ref().unwrap()["tenant"],
+            ColValue::String("t1".into())
+        );
+
+        entry.cmd = RedisCmd::from_str_args(&["EXPIRE", "user:1", "10"]);
+        assert!(router.redis_entry_to_rows(&entry).is_empty());
+
+        let config = RouterConfig::Rdb {
+            schema_map: String::new(),
+            tb_map: String::new(),
+            col_map: String::new(),
+            topic_map: String::new(),
+            content_routes: String::new(),
+            key_map: String::new(),
+            redis_map: r#"json:[{"db":"db_1","tb":"tb_1","key":"user:{id}{name}"}]"#.into(),
+            mongo_map: String::new(),
+        };
+        assert!(RdbRouter::from_config(&config, &DbType::Redis).is_err());
+    }
+
+    #[test]
+    fn test_parse_mongo_map() {
+        let config = RouterConfig::Rdb {
+            schema_map: String::new(),
+            tb_map: String::new(),
+            col_map: String::new(),
+            topic_map: String::new(),
+            content_routes: String::new(),
+            key_map: String::new(),
+            redis_map: String::new(),
+            mongo_map: r#"json:[{"db":"db_1","tb":"tb_1","id_col":"id","cols":[{"path":"a.b"}]}]"#
+                .into(),
+        };
+        let router = RdbRouter::from_config(&config, &DbType::Mongo).unwrap();
+        let mapping = router.get_mongo_mapping("db_1", "tb_1");
+        assert_eq!(mapping.id_col, "id");
+        assert_eq!(mapping.cols[0].col, "a_b");
+
+        let mapping = router.get_mongo_mapping("db_1", "tb_2");
+        assert_eq!(mapping.id_col, "_id");
+        assert!(mapping.cols.is_empty());
+    }
 }
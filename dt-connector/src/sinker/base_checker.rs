@@ -4,7 +4,11 @@ use dt_common::meta::{
     rdb_meta_manager::RdbMetaManager, rdb_tb_meta::RdbTbMeta, row_data::RowData,
     struct_meta::statement::struct_statement::StructStatement,
 };
-use dt_common::{log_diff, log_extra, log_miss, rdb_filter::RdbFilter};
+use dt_common::{
+    log_diff, log_extra, log_miss,
+    monitor::{counter_type::CounterType, monitor::Monitor},
+    rdb_filter::RdbFilter,
+};
 
 use crate::{
     check_log::{
@@ -82,12 +86,15 @@ impl BaseChecker {
         diff_col_values
     }
 
-    pub fn log_dml(miss: Vec<CheckLog>, diff: Vec<CheckLog>) {
+    pub fn log_dml(monitor: &Monitor, miss: Vec<CheckLog>, diff: Vec<CheckLog>) {
+        monitor
+            .add_counter(CounterType::CheckerMissTotal, miss.len() as u64)
+            .add_counter(CounterType::CheckerDiffTotal, diff.len() as u64);
         for log in miss {
-            log_miss!("{}", log.to_string());
+            log_miss!(schema = log.schema.as_str(), tb = log.tb.as_str(); "{}", log.to_string());
         }
         for log in diff {
-            log_diff!("{}", log.to_string());
+            log_diff!(schema = log.schema.as_str(), tb = log.tb.as_str(); "{}", log.to_string());
         }
     }
 
@@ -1,10 +1,13 @@
 use std::sync::Arc;
 
 use dt_common::{
+    meta::{mongo::mongo_rdb_mapping::MongoRdbMapping, row_data::RowData},
     monitor::{counter_type::CounterType, monitor::Monitor},
     utils::limit_queue::LimitedQueue,
 };
 
+use crate::rdb_router::RdbRouter;
+
 pub struct BaseSinker {}
 
 impl BaseSinker {
@@ -40,6 +43,49 @@ impl BaseSinker {
         monitor.add_multi_counter(CounterType::RtPerQuery, rts);
         Ok(())
     }
+
+    /// Flattens rows from mongo sources into rows of rdb tables by [router] mongo_map,
+    /// rows from other sources are returned as they are.
+    /// delete_children: whether to keep rows deleting child rows by parent_col, which are
+    /// only supported by sinkers writing with sql
+    pub fn flatten_mongo_rows(
+        router: &RdbRouter,
+        data: Vec<RowData>,
+        delete_children: bool,
+    ) -> anyhow::Result<Vec<RowData>> {
+        let mut results = Vec::with_capacity(data.len());
+        for row_data in data {
+            if !MongoRdbMapping::is_mongo_row(&row_data) {
+                results.push(row_data);
+                continue;
+            }
+            let mapping = router.get_mongo_mapping(&row_data.schema, &row_data.tb);
+            let mut rows = mapping.to_rows(&row_data)?;
+            if !delete_children {
+                rows.retain(|i| !mapping.is_child_delete_row(i));
+            }
+            results.extend(rows);
+        }
+        Ok(results)
+    }
+
+    /// Groups rows by (schema, tb, row_type) in the order they first appear,
+    /// since batch sinking requires all rows in a batch to be of the same table and type
+    pub fn group_by_tb(data: Vec<RowData>) -> Vec<Vec<RowData>> {
+        let mut groups: Vec<Vec<RowData>> = Vec::new();
+        for row_data in data {
+            let group = groups.iter_mut().find(|i| {
+                i[0].schema == row_data.schema
+                    && i[0].tb == row_data.tb
+                    && i[0].row_type == row_data.row_type
+            });
+            match group {
+                Some(group) => group.push(row_data),
+                None => groups.push(vec![row_data]),
+            }
+        }
+        groups
+    }
 }
 
 #[macro_export(local_inner_macros)]
@@ -7,11 +7,14 @@ use reqwest::{Client, Method, Response, StatusCode};
 use serde_json::json;
 use tokio::time::Instant;
 
-use crate::{call_batch_fn, sinker::base_sinker::BaseSinker, Sinker};
+use crate::{call_batch_fn, rdb_router::RdbRouter, sinker::base_sinker::BaseSinker, Sinker};
 use dt_common::{
     config::config_enums::DbType,
     error::Error,
-    meta::{col_value::ColValue, row_data::RowData, row_type::RowType},
+    meta::{
+        col_value::ColValue, mongo::mongo_rdb_mapping::MongoRdbMapping, row_data::RowData,
+        row_type::RowType,
+    },
     monitor::monitor::Monitor,
     utils::{limit_queue::LimitedQueue, sql_util::SqlUtil},
 };
@@ -23,6 +26,7 @@ const TIMESTAMP_COL_NAME: &str = "_ape_dts_timestamp";
 pub struct ClickhouseSinker {
     pub http_client: Client,
     pub batch_size: usize,
+    pub router: RdbRouter,
     pub host: String,
     pub port: String,
     pub username: String,
@@ -33,17 +37,38 @@ pub struct ClickhouseSinker {
 
 #[async_trait]
 impl Sinker for ClickhouseSinker {
-    async fn sink_dml(&mut self, mut data: Vec<RowData>, _batch: bool) -> anyhow::Result<()> {
+    async fn sink_dml(&mut self, mut data: Vec<RowData>, batch: bool) -> anyhow::Result<()> {
         if data.is_empty() {
             return Ok(());
         }
 
+        if MongoRdbMapping::is_mongo_row(&data[0]) {
+            return self.sink_mongo_dml(data, batch).await;
+        }
+
         call_batch_fn!(self, data, Self::batch_sink);
         Ok(())
     }
 }
 
 impl ClickhouseSinker {
+    async fn sink_mongo_dml(&mut self, data: Vec<RowData>, batch: bool) -> anyhow::Result<()> {
+        // stream load writes whole rows, applying a partial update would reset the other columns
+        if let Some(row_data) = data.iter().find(|i| MongoRdbMapping::is_diff_row(i)) {
+            bail! {Error::SinkerError(format!(
+                "$set / $unset diffs from mongo oplog are not supported by clickhouse sinker, use source=change_stream instead, row_data: {}",
+                row_data
+            ))}
+        }
+
+        // stream load deletes rows by primary keys, child rows can not be deleted by parent_col
+        let data = BaseSinker::flatten_mongo_rows(&self.router, data, false)?;
+        for tb_data in BaseSinker::group_by_tb(data) {
+            self.sink_dml(tb_data, batch).await?;
+        }
+        Ok(())
+    }
+
     async fn batch_sink(
         &mut self,
         data: &mut [RowData],
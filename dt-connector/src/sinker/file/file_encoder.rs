@@ -0,0 +1,648 @@
+use std::{collections::HashMap, sync::Arc};
+
+use anyhow::{bail, Context};
+use mongodb::bson::Bson;
+use orc_format::{
+    schema::{Field, Schema},
+    writer::{Config, Writer},
+};
+use parquet::{
+    basic::{Compression, LogicalType, Repetition, Type as PhysicalType},
+    data_type::{BoolType, ByteArray, ByteArrayType, DataType, DoubleType, Int64Type},
+    file::{
+        properties::WriterProperties,
+        writer::{SerializedColumnWriter, SerializedFileWriter},
+    },
+    schema::types::Type,
+};
+
+use dt_common::{
+    config::config_enums::FileFormat,
+    error::Error,
+    meta::{
+        col_value::ColValue,
+        mysql::mysql_col_type::MysqlColType,
+        pg::{pg_col_type::PgColType, pg_value_type::PgValueType},
+    },
+};
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum FileColType {
+    Boolean,
+    Long,
+    Double,
+    String,
+    Binary,
+}
+
+/// A data file being written, kept open across batches until rotated
+pub enum FileWriter {
+    // each batch is written as a row group
+    Parquet(SerializedFileWriter<Vec<u8>>),
+    // orc files are encoded at once when finished
+    Orc(Vec<HashMap<String, ColValue>>),
+    Csv(Vec<u8>),
+    Jsonl(Vec<u8>),
+}
+
+#[derive(Clone)]
+pub struct FileEncoder {
+    pub file_format: FileFormat,
+}
+
+impl FileColType {
+    pub fn from_mysql_col_type(col_type: &MysqlColType) -> Self {
+        match col_type {
+            MysqlColType::TinyInt { .. }
+            | MysqlColType::SmallInt { .. }
+            | MysqlColType::MediumInt { .. }
+            | MysqlColType::Int { .. }
+            | MysqlColType::BigInt { unsigned: false }
+            | MysqlColType::Year
+            | MysqlColType::Bit => Self::Long,
+
+            MysqlColType::Float | MysqlColType::Double => Self::Double,
+
+            MysqlColType::Binary { .. }
+            | MysqlColType::VarBinary { .. }
+            | MysqlColType::TinyBlob
+            | MysqlColType::MediumBlob
+            | MysqlColType::LongBlob
+            | MysqlColType::Blob => Self::Binary,
+
+            // u64 may overflow i64, decimals are kept as string to avoid losing precision
+            _ => Self::String,
+        }
+    }
+
+    pub fn from_pg_col_type(col_type: &PgColType) -> Self {
+        match col_type.value_type {
+            PgValueType::Boolean => Self::Boolean,
+            PgValueType::Int16 | PgValueType::Int32 | PgValueType::Int64 => Self::Long,
+            PgValueType::Float32 | PgValueType::Float64 => Self::Double,
+            PgValueType::Bytes => Self::Binary,
+            _ => Self::String,
+        }
+    }
+}
+
+impl FileEncoder {
+    pub fn new(file_format: FileFormat) -> Self {
+        Self { file_format }
+    }
+
+    pub fn get_file_extension(&self) -> &'static str {
+        match self.file_format {
+            FileFormat::Parquet => "parquet",
+            FileFormat::Orc => "orc",
+            FileFormat::Csv => "csv",
+            FileFormat::Jsonl => "jsonl",
+        }
+    }
+
+    pub fn create_writer(
+        &self,
+        cols: &[String],
+        col_types: &[FileColType],
+    ) -> anyhow::Result<FileWriter> {
+        let writer = match self.file_format {
+            FileFormat::Parquet => {
+                FileWriter::Parquet(Self::create_parquet_writer(cols, col_types)?)
+            }
+            FileFormat::Orc => FileWriter::Orc(Vec::new()),
+            FileFormat::Csv => FileWriter::Csv(Self::encode_csv_header(cols)),
+            FileFormat::Jsonl => FileWriter::Jsonl(Vec::new()),
+        };
+        Ok(writer)
+    }
+
+    pub fn write(
+        &self,
+        writer: &mut FileWriter,
+        cols: &[String],
+        col_types: &[FileColType],
+        records: Vec<HashMap<String, ColValue>>,
+    ) -> anyhow::Result<()> {
+        match writer {
+            FileWriter::Parquet(writer) => Self::write_parquet(writer, cols, col_types, &records),
+            FileWriter::Orc(buffer) => {
+                buffer.extend(records);
+                Ok(())
+            }
+            FileWriter::Csv(buffer) => {
+                Self::encode_csv(buffer, cols, &records);
+                Ok(())
+            }
+            FileWriter::Jsonl(buffer) => Self::encode_jsonl(buffer, cols, &records),
+        }
+    }
+
+    pub fn finish(
+        &self,
+        writer: FileWriter,
+        cols: &[String],
+        col_types: &[FileColType],
+    ) -> anyhow::Result<Vec<u8>> {
+        match writer {
+            // writes the footer and returns the buffer
+            FileWriter::Parquet(writer) => Ok(writer.into_inner()?),
+            FileWriter::Orc(records) => Self::encode_orc(cols, col_types, &records),
+            FileWriter::Csv(buffer) | FileWriter::Jsonl(buffer) => Ok(buffer),
+        }
+    }
+
+    pub fn encode(
+        &self,
+        cols: &[String],
+        col_types: &[FileColType],
+        records: Vec<HashMap<String, ColValue>>,
+    ) -> anyhow::Result<Vec<u8>> {
+        let mut writer = self.create_writer(cols, col_types)?;
+        self.write(&mut writer, cols, col_types, records)?;
+        self.finish(writer, cols, col_types)
+    }
+
+    // values which can not be converted to the column type are not written as NULL silently
+    fn to_long(value: &ColValue) -> anyhow::Result<Option<i64>> {
+        let v = match value {
+            ColValue::Tiny(v) => *v as i64,
+            ColValue::UnsignedTiny(v) => *v as i64,
+            ColValue::Short(v) => *v as i64,
+            ColValue::UnsignedShort(v) => *v as i64,
+            ColValue::Long(v) => *v as i64,
+            ColValue::UnsignedLong(v) => *v as i64,
+            ColValue::LongLong(v) => *v,
+            ColValue::Year(v) => *v as i64,
+            ColValue::Bit(v) => *v as i64,
+            ColValue::Set(v) => *v as i64,
+            ColValue::Enum(v) => *v as i64,
+            ColValue::Bool(v) => *v as i64,
+            ColValue::None => return Ok(None),
+            // such as: enum / set values of mysql in string
+            _ => {
+                let Some(str) = value.to_option_string() else {
+                    return Ok(None);
+                };
+                match str.parse() {
+                    Ok(v) => v,
+                    Err(_) => bail! {Error::SinkerError(format!(
+                        "failed to convert value: {} to long",
+                        str
+                    ))},
+                }
+            }
+        };
+        Ok(Some(v))
+    }
+
+    fn to_double(value: &ColValue) -> anyhow::Result<Option<f64>> {
+        match value {
+            ColValue::Float(v) => Ok(Some(*v as f64)),
+            ColValue::Double(v) => Ok(Some(*v)),
+            ColValue::Decimal(v) | ColValue::String(v) => match v.parse() {
+                Ok(v) => Ok(Some(v)),
+                Err(_) => bail! {Error::SinkerError(format!(
+                    "failed to convert value: {} to double",
+                    v
+                ))},
+            },
+            _ => Ok(Self::to_long(value)?.map(|v| v as f64)),
+        }
+    }
+
+    fn to_bool(value: &ColValue) -> anyhow::Result<Option<bool>> {
+        match value {
+            ColValue::Bool(v) => Ok(Some(*v)),
+            ColValue::None => Ok(None),
+            _ => Ok(Self::to_long(value)?.map(|v| v != 0)),
+        }
+    }
+
+    fn to_bytes(value: &ColValue) -> Option<Vec<u8>> {
+        match value {
+            ColValue::Blob(v) | ColValue::RawString(v) | ColValue::Json(v) => Some(v.clone()),
+            _ => Self::to_str_value(value).map(|v| v.into_bytes()),
+        }
+    }
+
+    fn to_str_value(value: &ColValue) -> Option<String> {
+        match value {
+            ColValue::MongoDoc(v) => {
+                Some(Bson::Document(v.clone()).into_relaxed_extjson().to_string())
+            }
+            _ => value.to_option_string(),
+        }
+    }
+
+    fn to_json_value(value: &ColValue) -> anyhow::Result<serde_json::Value> {
+        let json_value = match value {
+            ColValue::MongoDoc(v) => Bson::Document(v.clone()).into_relaxed_extjson(),
+            ColValue::Blob(_) | ColValue::RawString(_) | ColValue::Json(_) => {
+                Self::to_str_value(value).into()
+            }
+            _ => serde_json::to_value(value)?,
+        };
+        Ok(json_value)
+    }
+}
+
+// jsonl and csv
+impl FileEncoder {
+    fn encode_jsonl(
+        buffer: &mut Vec<u8>,
+        cols: &[String],
+        records: &[HashMap<String, ColValue>],
+    ) -> anyhow::Result<()> {
+        for record in records.iter() {
+            let mut map = serde_json::Map::new();
+            for col in cols.iter() {
+                let json_value = match record.get(col) {
+                    Some(v) => Self::to_json_value(v)?,
+                    None => serde_json::Value::Null,
+                };
+                map.insert(col.clone(), json_value);
+            }
+            serde_json::to_writer(&mut *buffer, &map)?;
+            buffer.push(b'\n');
+        }
+        Ok(())
+    }
+
+    fn encode_csv_header(cols: &[String]) -> Vec<u8> {
+        let header: Vec<String> = cols.iter().map(|i| Self::escape_csv(i)).collect();
+        let mut buffer = header.join(",").into_bytes();
+        buffer.push(b'\n');
+        buffer
+    }
+
+    fn encode_csv(buffer: &mut Vec<u8>, cols: &[String], records: &[HashMap<String, ColValue>]) {
+        for record in records.iter() {
+            let fields: Vec<String> = cols
+                .iter()
+                .map(|col| {
+                    // NULL is written as empty field
+                    record
+                        .get(col)
+                        .and_then(Self::to_str_value)
+                        .map(|v| Self::escape_csv(&v))
+                        .unwrap_or_default()
+                })
+                .collect();
+            buffer.extend_from_slice(fields.join(",").as_bytes());
+            buffer.push(b'\n');
+        }
+    }
+
+    pub fn escape_csv(value: &str) -> String {
+        // refer to: RFC 4180
+        if value.contains([',', '"', '\n', '\r']) {
+            format!("\"{}\"", value.replace('"', "\"\""))
+        } else {
+            value.to_string()
+        }
+    }
+}
+
+// parquet
+impl FileEncoder {
+    fn create_parquet_writer(
+        cols: &[String],
+        col_types: &[FileColType],
+    ) -> anyhow::Result<SerializedFileWriter<Vec<u8>>> {
+        let mut fields = Vec::with_capacity(cols.len());
+        for (col, col_type) in cols.iter().zip(col_types.iter()) {
+            let (physical_type, logical_type) = match col_type {
+                FileColType::Boolean => (PhysicalType::BOOLEAN, None),
+                FileColType::Long => (PhysicalType::INT64, None),
+                FileColType::Double => (PhysicalType::DOUBLE, None),
+                FileColType::String => (PhysicalType::BYTE_ARRAY, Some(LogicalType::String)),
+                FileColType::Binary => (PhysicalType::BYTE_ARRAY, None),
+            };
+            let field = Type::primitive_type_builder(col, physical_type)
+                .with_repetition(Repetition::OPTIONAL)
+                .with_logical_type(logical_type)
+                .build()?;
+            fields.push(Arc::new(field));
+        }
+        let schema = Type::group_type_builder("schema")
+            .with_fields(fields)
+            .build()?;
+
+        let props = WriterProperties::builder()
+            .set_compression(Compression::SNAPPY)
+            .build();
+        Ok(SerializedFileWriter::new(
+            Vec::new(),
+            Arc::new(schema),
+            Arc::new(props),
+        )?)
+    }
+
+    fn write_parquet(
+        writer: &mut SerializedFileWriter<Vec<u8>>,
+        cols: &[String],
+        col_types: &[FileColType],
+        records: &[HashMap<String, ColValue>],
+    ) -> anyhow::Result<()> {
+        let mut row_group_writer = writer.next_row_group()?;
+        for (col, col_type) in cols.iter().zip(col_types.iter()) {
+            let mut col_writer = row_group_writer
+                .next_column()?
+                .with_context(|| format!("no parquet column writer for: {}", col))?;
+            let values = records.iter().map(|i| i.get(col));
+            match col_type {
+                FileColType::Boolean => Self::write_parquet_col::<BoolType>(
+                    &mut col_writer,
+                    values
+                        .map(|v| v.map_or(Ok(None), Self::to_bool))
+                        .collect::<anyhow::Result<_>>()
+                        .with_context(|| format!("failed to write column: {}", col))?,
+                )?,
+
+                FileColType::Long => Self::write_parquet_col::<Int64Type>(
+                    &mut col_writer,
+                    values
+                        .map(|v| v.map_or(Ok(None), Self::to_long))
+                        .collect::<anyhow::Result<_>>()
+                        .with_context(|| format!("failed to write column: {}", col))?,
+                )?,
+
+                FileColType::Double => Self::write_parquet_col::<DoubleType>(
+                    &mut col_writer,
+                    values
+                        .map(|v| v.map_or(Ok(None), Self::to_double))
+                        .collect::<anyhow::Result<_>>()
+                        .with_context(|| format!("failed to write column: {}", col))?,
+                )?,
+
+                FileColType::String | FileColType::Binary => {
+                    Self::write_parquet_col::<ByteArrayType>(
+                        &mut col_writer,
+                        values
+                            .map(|v| v.and_then(Self::to_bytes).map(ByteArray::from))
+                            .collect(),
+                    )?
+                }
+            }
+            col_writer.close()?;
+        }
+        row_group_writer.close()?;
+        Ok(())
+    }
+
+    fn write_parquet_col<T: DataType>(
+        col_writer: &mut SerializedColumnWriter<'_>,
+        values: Vec<Option<T::T>>,
+    ) -> anyhow::Result<()> {
+        // definition level: 1 for non-null, 0 for null
+        let def_levels: Vec<i16> = values.iter().map(|v| v.is_some() as i16).collect();
+        let values: Vec<T::T> = values.into_iter().flatten().collect();
+        col_writer
+            .typed::<T>()
+            .write_batch(&values, Some(&def_levels), None)?;
+        Ok(())
+    }
+}
+
+// orc
+impl FileEncoder {
+    fn encode_orc(
+        cols: &[String],
+        col_types: &[FileColType],
+        records: &[HashMap<String, ColValue>],
+    ) -> anyhow::Result<Vec<u8>> {
+        let fields: Vec<Field> = cols
+            .iter()
+            .zip(col_types.iter())
+            .map(|(col, col_type)| {
+                let schema = match col_type {
+                    FileColType::Boolean | FileColType::Long => Schema::Long,
+                    FileColType::Double => Schema::Double,
+                    FileColType::String => Schema::String,
+                    FileColType::Binary => Schema::Binary,
+                };
+                Field(col.to_owned(), schema)
+            })
+            .collect();
+        let schema = Schema::Struct(fields);
+
+        let mut buffer = Vec::new();
+        let mut writer = Writer::new(&mut buffer, &schema, Config::new())?;
+        let root = writer.data().unwrap_struct();
+
+        for (i, (col, col_type)) in cols.iter().zip(col_types.iter()).enumerate() {
+            let values = records.iter().map(|record| record.get(col));
+            match col_type {
+                FileColType::Boolean | FileColType::Long => {
+                    let field_data = root.child(i).unwrap_long();
+                    for value in values {
+                        let value = value
+                            .map_or(Ok(None), Self::to_long)
+                            .with_context(|| format!("failed to write column: {}", col))?;
+                        match value {
+                            Some(v) => field_data.write(v),
+                            None => field_data.write_null(),
+                        }
+                    }
+                }
+
+                FileColType::Double => {
+                    let field_data = root.child(i).unwrap_double();
+                    for value in values {
+                        let value = value
+                            .map_or(Ok(None), Self::to_double)
+                            .with_context(|| format!("failed to write column: {}", col))?;
+                        match value {
+                            Some(v) => field_data.write(v),
+                            None => field_data.write_null(),
+                        }
+                    }
+                }
+
+                FileColType::String => {
+                    let field_data = root.child(i).unwrap_string();
+                    for value in values {
+                        match value.and_then(Self::to_str_value) {
+                            Some(v) => field_data.write(&v),
+                            None => field_data.write_null(),
+                        }
+                    }
+                }
+
+                FileColType::Binary => {
+                    let field_data = root.child(i).unwrap_binary();
+                    for value in values {
+                        match value.and_then(Self::to_bytes) {
+                            Some(v) => field_data.write(&v),
+                            None => field_data.write_null(),
+                        }
+                    }
+                }
+            }
+        }
+
+        for _ in 0..records.len() {
+            root.write();
+        }
+
+        writer.write_batch(records.len() as u64)?;
+        let orc_data = writer.finish()?.to_owned();
+        Ok(orc_data)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn build_records() -> (
+        Vec<String>,
+        Vec<FileColType>,
+        Vec<HashMap<String, ColValue>>,
+    ) {
+        let cols = vec!["id".to_string(), "name".to_string(), "score".to_string()];
+        let col_types = vec![FileColType::Long, FileColType::String, FileColType::Double];
+        let records = vec![
+            HashMap::from([
+                ("id".to_string(), ColValue::Long(1)),
+                ("name".to_string(), ColValue::String("a,\"b\"".into())),
+                ("score".to_string(), ColValue::LongLong(10)),
+            ]),
+            HashMap::from([
+                ("id".to_string(), ColValue::Long(2)),
+                ("name".to_string(), ColValue::None),
+                ("score".to_string(), ColValue::Double(9.5)),
+            ]),
+        ];
+        (cols, col_types, records)
+    }
+
+    #[test]
+    fn test_col_types_from_meta() {
+        assert_eq!(
+            FileColType::from_mysql_col_type(&MysqlColType::BigInt { unsigned: false }),
+            FileColType::Long
+        );
+        // u64 may overflow i64
+        assert_eq!(
+            FileColType::from_mysql_col_type(&MysqlColType::BigInt { unsigned: true }),
+            FileColType::String
+        );
+        assert_eq!(
+            FileColType::from_mysql_col_type(&MysqlColType::Decimal {
+                precision: 10,
+                scale: 2
+            }),
+            FileColType::String
+        );
+        assert_eq!(
+            FileColType::from_mysql_col_type(&MysqlColType::VarBinary { length: 10 }),
+            FileColType::Binary
+        );
+
+        let pg_col_type = |value_type| PgColType {
+            value_type,
+            name: String::new(),
+            alias: String::new(),
+            oid: 0,
+            parent_oid: 0,
+            element_oid: 0,
+            category: String::new(),
+            enum_values: None,
+            typmod: -1,
+        };
+        assert_eq!(
+            FileColType::from_pg_col_type(&pg_col_type(PgValueType::Boolean)),
+            FileColType::Boolean
+        );
+        assert_eq!(
+            FileColType::from_pg_col_type(&pg_col_type(PgValueType::Float32)),
+            FileColType::Double
+        );
+        assert_eq!(
+            FileColType::from_pg_col_type(&pg_col_type(PgValueType::Numeric)),
+            FileColType::String
+        );
+    }
+
+    #[test]
+    fn test_encode_csv_and_jsonl() {
+        let (cols, col_types, records) = build_records();
+
+        let encoder = FileEncoder::new(FileFormat::Csv);
+        let data = encoder.encode(&cols, &col_types, records.clone()).unwrap();
+        assert_eq!(
+            String::from_utf8(data).unwrap(),
+            "id,name,score\n1,\"a,\"\"b\"\"\",10\n2,,9.5\n"
+        );
+
+        let encoder = FileEncoder::new(FileFormat::Jsonl);
+        let data = encoder.encode(&cols, &col_types, records).unwrap();
+        let lines: Vec<serde_json::Value> = String::from_utf8(data)
+            .unwrap()
+            .lines()
+            .map(|i| serde_json::from_str(i).unwrap())
+            .collect();
+        assert_eq!(lines.len(), 2);
+        assert_eq!(lines[0]["name"], "a,\"b\"");
+        assert_eq!(lines[1]["name"], serde_json::Value::Null);
+        assert_eq!(lines[1]["score"], 9.5);
+    }
+
+    #[test]
+    fn test_encode_csv_in_batches() {
+        let (cols, col_types, records) = build_records();
+        let encoder = FileEncoder::new(FileFormat::Csv);
+        let mut writer = encoder.create_writer(&cols, &col_types).unwrap();
+        for record in records {
+            encoder
+                .write(&mut writer, &cols, &col_types, vec![record])
+                .unwrap();
+        }
+        let data = encoder.finish(writer, &cols, &col_types).unwrap();
+        // header is written only once
+        assert_eq!(
+            String::from_utf8(data).unwrap(),
+            "id,name,score\n1,\"a,\"\"b\"\"\",10\n2,,9.5\n"
+        );
+    }
+
+    #[test]
+    fn test_encode_parquet() {
+        use parquet::file::{reader::FileReader, serialized_reader::SerializedFileReader};
+
+        let (cols, mut col_types, mut records) = build_records();
+        // a column of all NULLs keeps the type from meta
+        col_types[1] = FileColType::Long;
+        records[0].insert("name".to_string(), ColValue::None);
+        let encoder = FileEncoder::new(FileFormat::Parquet);
+        let mut writer = encoder.create_writer(&cols, &col_types).unwrap();
+        encoder
+            .write(&mut writer, &cols, &col_types, records.clone())
+            .unwrap();
+        encoder
+            .write(&mut writer, &cols, &col_types, vec![records[1].clone()])
+            .unwrap();
+        let data = encoder.finish(writer, &cols, &col_types).unwrap();
+
+        let reader = SerializedFileReader::new(bytes::Bytes::from(data)).unwrap();
+        let file_meta = reader.metadata().file_metadata();
+        assert_eq!(file_meta.num_rows(), 3);
+        assert_eq!(reader.metadata().num_row_groups(), 2);
+        assert_eq!(file_meta.schema_descr().num_columns(), 3);
+        assert_eq!(
+            file_meta.schema_descr().column(1).physical_type(),
+            PhysicalType::INT64
+        );
+    }
+
+    #[test]
+    fn test_encode_invalid_value() {
+        let (cols, mut col_types, records) = build_records();
+        // "a,\"b\"" can not be written as long
+        col_types[1] = FileColType::Long;
+        let encoder = FileEncoder::new(FileFormat::Parquet);
+        let err = encoder.encode(&cols, &col_types, records).unwrap_err();
+        assert!(format!("{:#}", err).contains("failed to write column: name"));
+    }
+}
@@ -0,0 +1,395 @@
+use std::{
+    cmp,
+    collections::{BTreeSet, HashMap},
+    path::Path,
+    sync::Arc,
+};
+
+use anyhow::Context;
+use async_trait::async_trait;
+use chrono::Utc;
+use rusoto_core::ByteStream;
+use rusoto_s3::{PutObjectRequest, S3Client, S3};
+use tokio::time::Instant;
+use uuid::Uuid;
+
+use super::file_encoder::{FileColType, FileEncoder, FileWriter};
+use crate::{rdb_router::RdbRouter, sinker::base_sinker::BaseSinker, Sinker};
+use dt_common::{
+    config::{config_enums::FilePartitionBy, s3_config::S3Config},
+    log_info,
+    meta::{
+        col_value::ColValue,
+        ddl_meta::ddl_data::DdlData,
+        dt_data::{DtData, DtItem},
+        file::file_manifest::FileManifest,
+        position::Position,
+        rdb_meta_manager::RdbMetaManager,
+        row_data::RowData,
+        row_type::RowType,
+    },
+    monitor::monitor::Monitor,
+    utils::limit_queue::LimitedQueue,
+};
+
+const OP_COL: &str = "_dt_op";
+const MANIFEST_DIR: &str = "_manifest";
+
+pub struct FileSinker {
+    pub root_dir: String,
+    pub encoder: FileEncoder,
+    pub partition_by: FilePartitionBy,
+    pub file_max_rows: usize,
+    pub file_max_bytes: usize,
+    // finish files opened in previous windows of this length at checkpoints,
+    // 0 means finishing at every checkpoint
+    pub file_max_secs: u64,
+    // write to s3 if set, otherwise write to local root_dir
+    pub s3_client: Option<S3Client>,
+    pub s3_config: S3Config,
+    pub router: RdbRouter,
+    pub reverse_router: RdbRouter,
+    pub meta_manager: Option<RdbMetaManager>,
+    pub monitor: Arc<Monitor>,
+    // files being written, key: (schema, tb)
+    pub open_files: HashMap<(String, String), OpenFile>,
+}
+
+/// A file kept open across batches and checkpoints, finished when rotated by file_max_rows /
+/// file_max_bytes / file_max_secs, or when the sinker closes
+pub struct OpenFile {
+    cols: Vec<String>,
+    col_types: Vec<FileColType>,
+    writer: FileWriter,
+    row_count: usize,
+    data_size: usize,
+    // timestamp in seconds
+    open_time: i64,
+    first_position: Position,
+    last_position: Position,
+}
+
+#[async_trait]
+impl Sinker for FileSinker {
+    async fn sink_raw(&mut self, data: Vec<DtItem>, _batch: bool) -> anyhow::Result<()> {
+        if data.is_empty() {
+            return Ok(());
+        }
+
+        let batch_size = data.len();
+        let mut all_data_size = 0;
+        let mut rts = LimitedQueue::new(cmp::min(100, batch_size));
+        for (schema, tb, rows) in Self::split_by_tb(data) {
+            let start_time = Instant::now();
+            all_data_size += self.write_rows(schema, tb, rows).await?;
+            rts.push((start_time.elapsed().as_millis() as u64, 1));
+        }
+
+        BaseSinker::update_monitor_rt(&self.monitor, &rts).await?;
+        BaseSinker::update_batch_monitor(&self.monitor, batch_size as u64, all_data_size as u64)
+            .await
+    }
+
+    async fn refresh_meta(&mut self, data: Vec<DdlData>) -> anyhow::Result<()> {
+        if let Some(meta_manager) = self.meta_manager.as_mut() {
+            for ddl_data in data.iter() {
+                meta_manager.invalidate_cache_by_ddl_data(ddl_data);
+            }
+        }
+        Ok(())
+    }
+
+    async fn flush(&mut self, force: bool) -> anyhow::Result<bool> {
+        // rows of open files are not written yet, positions are held back until all files
+        // are finished together, so file_max_secs also bounds the delay of checkpoints.
+        // windows are aligned to the wall clock, so parallel sinkers finish files at the same
+        // checkpoint instead of holding back positions in turns
+        let window = |timestamp: i64| timestamp / cmp::max(self.file_max_secs, 1) as i64;
+        let now = window(Utc::now().timestamp());
+        let expired =
+            self.file_max_secs == 0 || self.open_files.values().any(|i| window(i.open_time) < now);
+        if !force && !expired && !self.open_files.is_empty() {
+            return Ok(false);
+        }
+        self.finish_all_files().await?;
+        Ok(true)
+    }
+
+    async fn close(&mut self) -> anyhow::Result<()> {
+        self.finish_all_files().await?;
+        if let Some(meta_manager) = &self.meta_manager {
+            meta_manager.close().await?;
+        }
+        Ok(())
+    }
+}
+
+impl FileSinker {
+    fn split_by_tb(data: Vec<DtItem>) -> Vec<(String, String, Vec<(RowData, Position)>)> {
+        let mut tb_rows: Vec<(String, String, Vec<(RowData, Position)>)> = Vec::new();
+        for item in data {
+            // there may be DtData::Commit items, ignore them
+            let mut row_data = match item.dt_data {
+                DtData::Dml { row_data } => row_data,
+                _ => continue,
+            };
+            row_data.convert_raw_string();
+
+            match tb_rows
+                .iter_mut()
+                .find(|(schema, tb, _)| *schema == row_data.schema && *tb == row_data.tb)
+            {
+                Some((_, _, rows)) => rows.push((row_data, item.position)),
+                None => tb_rows.push((
+                    row_data.schema.clone(),
+                    row_data.tb.clone(),
+                    vec![(row_data, item.position)],
+                )),
+            }
+        }
+        tb_rows
+    }
+
+    async fn write_rows(
+        &mut self,
+        schema: String,
+        tb: String,
+        rows: Vec<(RowData, Position)>,
+    ) -> anyhow::Result<usize> {
+        let key = (schema, tb);
+        let (mut cols, mut col_types) = self.get_cols(&key.0, &key.1, &rows).await?;
+        if let Some(open_file) = self.open_files.get(&key) {
+            if cols.iter().all(|col| open_file.cols.contains(col)) {
+                // missing columns of rows are written as NULL
+                cols = open_file.cols.clone();
+                col_types = open_file.col_types.clone();
+            } else {
+                // columns changed by ddl, or new fields in documents
+                self.finish_file(&key).await?;
+            }
+        }
+
+        let mut all_data_size = 0;
+        let mut records = Vec::new();
+        for (row_data, position) in rows {
+            if !self.open_files.contains_key(&key) {
+                let open_file = OpenFile {
+                    writer: self.encoder.create_writer(&cols, &col_types)?,
+                    cols: cols.clone(),
+                    col_types: col_types.clone(),
+                    row_count: 0,
+                    data_size: 0,
+                    open_time: Utc::now().timestamp(),
+                    first_position: position.clone(),
+                    last_position: Position::None,
+                };
+                self.open_files.insert(key.clone(), open_file);
+            }
+
+            let open_file = self.open_files.get_mut(&key).unwrap();
+            all_data_size += row_data.data_size;
+            open_file.row_count += 1;
+            open_file.data_size += row_data.data_size;
+            open_file.last_position = position;
+            records.push(Self::row_data_to_record(row_data));
+
+            let rotate = (self.file_max_rows > 0 && open_file.row_count >= self.file_max_rows)
+                || (self.file_max_bytes > 0 && open_file.data_size >= self.file_max_bytes);
+            if rotate {
+                let records = std::mem::take(&mut records);
+                self.encoder
+                    .write(&mut open_file.writer, &cols, &col_types, records)?;
+                self.finish_file(&key).await?;
+            }
+        }
+
+        if let Some(open_file) = self.open_files.get_mut(&key) {
+            if !records.is_empty() {
+                self.encoder
+                    .write(&mut open_file.writer, &cols, &col_types, records)?;
+            }
+        }
+        Ok(all_data_size)
+    }
+
+    async fn finish_all_files(&mut self) -> anyhow::Result<()> {
+        let keys: Vec<(String, String)> = self.open_files.keys().cloned().collect();
+        for key in keys.iter() {
+            self.finish_file(key).await?;
+        }
+        Ok(())
+    }
+
+    async fn finish_file(&mut self, key: &(String, String)) -> anyhow::Result<()> {
+        let Some(open_file) = self.open_files.remove(key) else {
+            return Ok(());
+        };
+        let file_data =
+            self.encoder
+                .finish(open_file.writer, &open_file.cols, &open_file.col_types)?;
+
+        let (data_file_name, manifest_file_name) = self.get_file_names(&key.0, &key.1);
+        let manifest = FileManifest {
+            schema: key.0.clone(),
+            tb: key.1.clone(),
+            file_format: self.encoder.file_format.to_string(),
+            data_file_name,
+            data_size: file_data.len(),
+            row_count: open_file.row_count,
+            first_position: open_file.first_position,
+            last_position: open_file.last_position,
+        };
+
+        // the manifest is written after the data file, a data file without manifest
+        // may be left by an interrupted task and should be ignored by readers
+        self.put_file(&manifest.data_file_name, file_data).await?;
+        self.put_file(&manifest_file_name, manifest.to_string().into_bytes())
+            .await
+    }
+
+    fn row_data_to_record(row_data: RowData) -> HashMap<String, ColValue> {
+        let (op, col_values) = match row_data.row_type {
+            RowType::Insert => ("insert", row_data.after),
+            RowType::Update => ("update", row_data.after),
+            RowType::Delete => ("delete", row_data.before),
+        };
+        let mut record = col_values.unwrap_or_default();
+        record.insert(OP_COL.into(), ColValue::String(op.into()));
+        record
+    }
+
+    async fn get_cols(
+        &mut self,
+        schema: &str,
+        tb: &str,
+        rows: &[(RowData, Position)],
+    ) -> anyhow::Result<(Vec<String>, Vec<FileColType>)> {
+        let mut cols = Vec::new();
+        let mut col_types = Vec::new();
+        // rows have been routed by extractor, get tb_meta by source schema and tb
+        if let Some(meta_manager) = self.meta_manager.as_mut() {
+            let (src_schema, src_tb) = self.reverse_router.get_tb_map(schema, tb);
+            let tb_col_types = Self::get_tb_col_types(meta_manager, src_schema, src_tb).await?;
+            let col_map = self.router.get_col_map(src_schema, src_tb);
+            for (col, col_type) in tb_col_types {
+                match col_map.and_then(|i| i.get(&col)) {
+                    Some(dst_col) => cols.push(dst_col.to_owned()),
+                    None => cols.push(col),
+                }
+                col_types.push(col_type);
+            }
+        }
+
+        // columns not in tb_meta (mongo, or columns added by ddl) are appended in order of names
+        let mut extra_cols = BTreeSet::new();
+        for (row_data, _) in rows.iter() {
+            let col_values = match row_data.row_type {
+                RowType::Delete => row_data.before.as_ref(),
+                _ => row_data.after.as_ref(),
+            };
+            if let Some(col_values) = col_values {
+                extra_cols.extend(col_values.keys().filter(|i| !cols.contains(i)).cloned());
+            }
+        }
+        cols.extend(extra_cols);
+        cols.push(OP_COL.into());
+        // types of columns not in tb_meta are unknown, keep them as string
+        col_types.resize(cols.len(), FileColType::String);
+        Ok((cols, col_types))
+    }
+
+    /// column types are decided by tb_meta, so files of the same table share the same schema
+    async fn get_tb_col_types(
+        meta_manager: &mut RdbMetaManager,
+        schema: &str,
+        tb: &str,
+    ) -> anyhow::Result<Vec<(String, FileColType)>> {
+        if let Some(mysql_meta_manager) = meta_manager.mysql_meta_manager.as_mut() {
+            let tb_meta = mysql_meta_manager.get_tb_meta(schema, tb).await?;
+            return Ok(tb_meta
+                .basic
+                .cols
+                .iter()
+                .map(|col| {
+                    let col_type = tb_meta
+                        .col_type_map
+                        .get(col)
+                        .map(FileColType::from_mysql_col_type)
+                        .unwrap_or(FileColType::String);
+                    (col.to_owned(), col_type)
+                })
+                .collect());
+        }
+
+        if let Some(pg_meta_manager) = meta_manager.pg_meta_manager.as_mut() {
+            let tb_meta = pg_meta_manager.get_tb_meta(schema, tb).await?;
+            return Ok(tb_meta
+                .basic
+                .cols
+                .iter()
+                .map(|col| {
+                    let col_type = tb_meta
+                        .col_type_map
+                        .get(col)
+                        .map(FileColType::from_pg_col_type)
+                        .unwrap_or(FileColType::String);
+                    (col.to_owned(), col_type)
+                })
+                .collect());
+        }
+        Ok(Vec::new())
+    }
+
+    fn get_file_names(&self, schema: &str, tb: &str) -> (String, String) {
+        let mut tb_dir = format!("{}/{}", schema, tb);
+        if !self.root_dir.is_empty() {
+            tb_dir = format!("{}/{}", self.root_dir.trim_end_matches('/'), tb_dir);
+        }
+
+        let now = Utc::now();
+        let partition_dir = match self.partition_by {
+            FilePartitionBy::None => String::new(),
+            FilePartitionBy::Day => format!("dt={}/", now.format("%Y-%m-%d")),
+            FilePartitionBy::Hour => {
+                format!("dt={}/hr={}/", now.format("%Y-%m-%d"), now.format("%H"))
+            }
+        };
+
+        let file_name = format!(
+            "part-{}-{}.{}",
+            now.timestamp_millis(),
+            Uuid::new_v4(),
+            self.encoder.get_file_extension()
+        );
+        (
+            format!("{}/{}{}", tb_dir, partition_dir, file_name),
+            format!("{}/{}/{}.json", tb_dir, MANIFEST_DIR, file_name),
+        )
+    }
+
+    async fn put_file(&self, key: &str, data: Vec<u8>) -> anyhow::Result<()> {
+        if let Some(s3_client) = &self.s3_client {
+            let request = PutObjectRequest {
+                bucket: self.s3_config.bucket.clone(),
+                key: key.to_string(),
+                body: Some(ByteStream::from(data)),
+                ..Default::default()
+            };
+            s3_client
+                .put_object(request)
+                .await
+                .with_context(|| format!("failed to push: {}", key))?;
+        } else {
+            if let Some(dir) = Path::new(key).parent() {
+                tokio::fs::create_dir_all(dir)
+                    .await
+                    .with_context(|| format!("failed to create dir: {}", dir.display()))?;
+            }
+            tokio::fs::write(key, data)
+                .await
+                .with_context(|| format!("failed to write: {}", key))?;
+        }
+        log_info!("file written: {}", key);
+        Ok(())
+    }
+}
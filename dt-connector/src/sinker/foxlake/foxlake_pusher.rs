@@ -1,6 +1,6 @@
 use std::{cmp, str::FromStr, sync::Arc};
 
-use anyhow::{Context, Ok};
+use anyhow::{bail, Context, Ok};
 use async_trait::async_trait;
 use chrono::{DateTime, Datelike, Timelike, Utc};
 use orc_format::{
@@ -21,16 +21,16 @@ use super::{
 use crate::{rdb_router::RdbRouter, sinker::base_sinker::BaseSinker, Sinker};
 use dt_common::{
     config::{config_enums::ExtractType, s3_config::S3Config},
+    error::Error,
     log_info,
     meta::{
         col_value::ColValue,
         ddl_meta::ddl_data::DdlData,
         dt_data::{DtData, DtItem},
         foxlake::s3_file_meta::S3FileMeta,
-        mysql::{
-            mysql_col_type::MysqlColType, mysql_meta_manager::MysqlMetaManager,
-            mysql_tb_meta::MysqlTbMeta,
-        },
+        mysql::{mysql_col_type::MysqlColType, mysql_tb_meta::MysqlTbMeta},
+        pg::{pg_col_type::PgColType, pg_tb_meta::PgTbMeta, pg_value_type::PgValueType},
+        rdb_meta_manager::RdbMetaManager,
         row_data::RowData,
         row_type::RowType,
         time::dt_utc_time::DtNaiveTime,
@@ -42,7 +42,8 @@ use dt_common::{
 pub struct FoxlakePusher {
     pub url: String,
     pub batch_size: usize,
-    pub meta_manager: MysqlMetaManager,
+    // mysql source: meta of foxlake tables, pg source: meta of source tables
+    pub meta_manager: RdbMetaManager,
     pub monitor: Arc<Monitor>,
     pub s3_client: S3Client,
     pub s3_config: S3Config,
@@ -57,6 +58,11 @@ pub struct FoxlakePusher {
 const CDC_ACTION: &str = "cdc_action";
 const CDC_LOG_SEQUENCE: &str = "cdc_log_sequence";
 
+enum FoxlakeTbMeta {
+    Mysql(MysqlTbMeta),
+    Pg(PgTbMeta),
+}
+
 #[async_trait]
 impl Sinker for FoxlakePusher {
     async fn sink_raw(&mut self, data: Vec<DtItem>, _batch: bool) -> anyhow::Result<()> {
@@ -152,24 +158,20 @@ impl FoxlakePusher {
                 continue;
             }
 
-            // push current batch
-            let tb_meta = self
-                .meta_manager
-                .get_tb_meta_by_row_data(&batch_data[0])
-                .await?
-                .to_owned();
+            // push current batch, rows have been routed to foxlake tables
+            let schema = batch_data[0].schema.clone();
+            let tb = batch_data[0].tb.clone();
+            let tb_meta = self.get_tb_meta(&schema, &tb).await?;
 
             let (orc_data, insert_only) = self.generate_orc_data(batch_data, &tb_meta).await?;
 
-            let (src_schema, src_tb) = self
-                .reverse_router
-                .get_tb_map(&tb_meta.basic.schema, &tb_meta.basic.tb);
+            let (src_schema, src_tb) = self.reverse_router.get_tb_map(&schema, &tb);
             let (data_file_name, meta_file_name, sequence_info) =
                 self.get_s3_file_info(src_schema, src_tb).await;
 
             let s3_file_meta = S3FileMeta {
-                schema: tb_meta.basic.schema.clone(),
-                tb: tb_meta.basic.tb.clone(),
+                schema,
+                tb,
                 insert_only,
                 data_file_name,
                 meta_file_name,
@@ -217,6 +219,38 @@ impl FoxlakePusher {
         Ok((s3_file_metas, all_data_size))
     }
 
+    async fn get_tb_meta(&mut self, schema: &str, tb: &str) -> anyhow::Result<FoxlakeTbMeta> {
+        if let Some(meta_manager) = self.meta_manager.mysql_meta_manager.as_mut() {
+            let tb_meta = meta_manager.get_tb_meta(schema, tb).await?;
+            return Ok(FoxlakeTbMeta::Mysql(tb_meta.to_owned()));
+        }
+
+        if let Some(meta_manager) = self.meta_manager.pg_meta_manager.as_mut() {
+            // get meta of source table by source schema and tb
+            let (src_schema, src_tb) = self.reverse_router.get_tb_map(schema, tb);
+            let mut tb_meta = meta_manager
+                .get_tb_meta(src_schema, src_tb)
+                .await?
+                .to_owned();
+            // map columns to foxlake columns
+            if let Some(col_map) = self.reverse_router.get_col_map(schema, tb) {
+                for col in tb_meta.basic.cols.iter_mut() {
+                    if let Some((dst_col, _)) = col_map.iter().find(|(_, v)| *v == col) {
+                        if let Some(col_type) = tb_meta.col_type_map.remove(col.as_str()) {
+                            tb_meta.col_type_map.insert(dst_col.to_owned(), col_type);
+                        }
+                        *col = dst_col.to_owned();
+                    }
+                }
+            }
+            return Ok(FoxlakeTbMeta::Pg(tb_meta));
+        }
+
+        bail! {Error::Unexpected(
+            "no available meta_manager in foxlake pusher".into()
+        )}
+    }
+
     async fn push(
         s3_client: &S3Client,
         bucket: &str,
@@ -248,22 +282,27 @@ impl FoxlakePusher {
     async fn generate_orc_data(
         &self,
         data: Vec<RowData>,
-        tb_meta: &MysqlTbMeta,
+        tb_meta: &FoxlakeTbMeta,
     ) -> anyhow::Result<(Vec<u8>, bool)> {
         let mut insert_only = true;
         let (tb_schema, fields) = self.get_tb_orc_schema(tb_meta)?;
-        let col_count = tb_meta.basic.cols.len();
+        // ignore cdc_action and cdc_log_sequence
+        let col_count = fields.len() - 2;
 
         let mut buffer = Vec::new();
         let mut writer = Writer::new(&mut buffer, &tb_schema, Config::new()).unwrap();
         let root = writer.data().unwrap_struct();
 
-        // ignore cdc_action and cdc_log_sequence
         for (i, field) in fields.into_iter().take(col_count).enumerate() {
             let col = &field.0;
             let col_schema = field.1;
             match col_schema {
                 Schema::Long => {
+                    // pg date values are strings
+                    let is_pg_date = matches!(
+                        Self::get_pg_col_type(tb_meta, col)?,
+                        Some(col_type) if col_type.value_type == PgValueType::Date
+                    );
                     let field_data = root.child(i).unwrap_long();
                     for row_data in data.iter() {
                         match Self::get_col_value(row_data, col) {
@@ -277,6 +316,7 @@ impl FoxlakePusher {
                             Some(ColValue::UnsignedLongLong(v)) => field_data.write(*v as i64),
                             Some(ColValue::Year(v)) => field_data.write(*v as i64),
                             Some(ColValue::Bit(v)) => field_data.write(*v as i64),
+                            Some(ColValue::Bool(v)) => field_data.write(*v as i64),
                             Some(ColValue::Set(v)) => field_data.write(*v as i64),
                             Some(ColValue::Enum(v)) => field_data.write(*v as i64),
 
@@ -285,9 +325,10 @@ impl FoxlakePusher {
                                 field_data.write(timestamp)
                             }
 
-                            Some(ColValue::Date(v)) => {
-                                let timestamp = Self::date_to_long(v)?;
-                                field_data.write(timestamp)
+                            Some(ColValue::Date(v)) => field_data.write(Self::date_to_long(v)?),
+
+                            Some(ColValue::String(v)) if is_pg_date => {
+                                field_data.write(Self::date_to_long(v)?)
                             }
 
                             Some(ColValue::DateTime(v)) | Some(ColValue::Timestamp(v)) => {
@@ -299,13 +340,14 @@ impl FoxlakePusher {
                     }
                 }
 
-                Schema::Decimal(..) => {
+                Schema::Decimal(_, scale) => {
                     let field_data = root.child(i).unwrap_decimal();
                     for row_data in data.iter() {
                         match Self::get_col_value(row_data, col) {
                             Some(ColValue::Decimal(v)) => {
-                                let decimal = Decimal::from_str(v)
+                                let mut decimal = Decimal::from_str(v)
                                     .with_context(|| format!("invalide decimal: {}", v))?;
+                                decimal.rescale(scale);
                                 field_data.write_i128(decimal.mantissa())
                             }
                             _ => field_data.write_null(),
@@ -340,7 +382,9 @@ impl FoxlakePusher {
                             Some(ColValue::String(v))
                             | Some(ColValue::Set2(v))
                             | Some(ColValue::Enum2(v))
-                            | Some(ColValue::Json2(v)) => field_data.write(v),
+                            | Some(ColValue::Json2(v))
+                            | Some(ColValue::Decimal(v))
+                            | Some(ColValue::Time(v)) => field_data.write(v),
                             _ => field_data.write_null(),
                         };
                     }
@@ -359,18 +403,20 @@ impl FoxlakePusher {
                                 field_data.write(&bit)
                             }
 
-                            Some(ColValue::Decimal(v)) => match tb_meta.get_col_type(col)? {
-                                MysqlColType::Decimal { precision, scale } => {
-                                    let latin1_data = DecimalUtil::string_to_mysql_binlog(
-                                        v,
-                                        *precision as usize,
-                                        *scale as usize,
-                                    )?;
-                                    let utf8_data = UnicodeUtil::latin1_to_utf8(&latin1_data);
-                                    field_data.write(&utf8_data)
+                            Some(ColValue::Decimal(v)) => {
+                                match Self::get_mysql_col_type(tb_meta, col)? {
+                                    Some(MysqlColType::Decimal { precision, scale }) => {
+                                        let latin1_data = DecimalUtil::string_to_mysql_binlog(
+                                            v,
+                                            *precision as usize,
+                                            *scale as usize,
+                                        )?;
+                                        let utf8_data = UnicodeUtil::latin1_to_utf8(&latin1_data);
+                                        field_data.write(&utf8_data)
+                                    }
+                                    _ => field_data.write_null(),
                                 }
-                                _ => field_data.write_null(),
-                            },
+                            }
 
                             _ => field_data.write_null(),
                         };
@@ -409,12 +455,44 @@ impl FoxlakePusher {
         Ok((orc_data, insert_only))
     }
 
-    fn get_tb_orc_schema(&self, tb_meta: &MysqlTbMeta) -> anyhow::Result<(Schema, Vec<Field>)> {
+    fn get_mysql_col_type<'a>(
+        tb_meta: &'a FoxlakeTbMeta,
+        col: &str,
+    ) -> anyhow::Result<Option<&'a MysqlColType>> {
+        match tb_meta {
+            FoxlakeTbMeta::Mysql(tb_meta) => Ok(Some(tb_meta.get_col_type(col)?)),
+            FoxlakeTbMeta::Pg(_) => Ok(None),
+        }
+    }
+
+    fn get_pg_col_type<'a>(
+        tb_meta: &'a FoxlakeTbMeta,
+        col: &str,
+    ) -> anyhow::Result<Option<&'a PgColType>> {
+        match tb_meta {
+            FoxlakeTbMeta::Mysql(_) => Ok(None),
+            FoxlakeTbMeta::Pg(tb_meta) => Ok(Some(tb_meta.get_col_type(col)?)),
+        }
+    }
+
+    fn get_tb_orc_schema(&self, tb_meta: &FoxlakeTbMeta) -> anyhow::Result<(Schema, Vec<Field>)> {
         let mut fields = Vec::new();
-        for col in tb_meta.basic.cols.iter() {
-            let col_type = tb_meta.get_col_type(col)?;
-            let schema = self.get_col_orc_schema(col_type);
-            fields.push(Field(col.to_owned(), schema))
+        match tb_meta {
+            FoxlakeTbMeta::Mysql(tb_meta) => {
+                for col in tb_meta.basic.cols.iter() {
+                    let col_type = tb_meta.get_col_type(col)?;
+                    let schema = self.get_col_orc_schema(col_type);
+                    fields.push(Field(col.to_owned(), schema))
+                }
+            }
+
+            FoxlakeTbMeta::Pg(tb_meta) => {
+                for col in tb_meta.basic.cols.iter() {
+                    let col_type = tb_meta.get_col_type(col)?;
+                    let schema = Self::get_pg_col_orc_schema(col_type);
+                    fields.push(Field(col.to_owned(), schema))
+                }
+            }
         }
         fields.push(Field(CDC_ACTION.to_owned(), Schema::Int));
         fields.push(Field(CDC_LOG_SEQUENCE.to_owned(), Schema::Long));
@@ -471,9 +549,42 @@ impl FoxlakePusher {
         }
     }
 
+    fn get_pg_col_orc_schema(col_type: &PgColType) -> Schema {
+        if col_type.is_array() {
+            return Schema::String;
+        }
+
+        match col_type.value_type {
+            PgValueType::Int16 | PgValueType::Int32 | PgValueType::Int64 | PgValueType::Boolean => {
+                Schema::Long
+            }
+
+            PgValueType::Float32 => Schema::Float,
+            PgValueType::Float64 => Schema::Double,
+
+            PgValueType::Date | PgValueType::Timestamp | PgValueType::TimestampTZ => Schema::Long,
+
+            PgValueType::Bytes => Schema::Binary,
+
+            // numeric without precision can hold up to 131072 digits, 2^127 > 10^38
+            PgValueType::Numeric => match col_type.get_numeric_precision_scale() {
+                Some((precision, scale)) if precision <= 38 => Schema::Decimal(precision, scale),
+                _ => Schema::String,
+            },
+
+            // time is also written as string since pg time has no date part
+            _ => Schema::String,
+        }
+    }
+
     #[inline(always)]
     fn timestamp_to_long(timestamp: &str) -> anyhow::Result<i64> {
-        let datetime: DateTime<Utc> = TimeUtil::datetime_from_utc_str(timestamp)?;
+        // pg timestamptz values have timezone offsets, e.g. 2024-01-01 00:00:00+08
+        let datetime: DateTime<Utc> =
+            match DateTime::parse_from_str(timestamp, "%Y-%m-%d %H:%M:%S%.f%#z") {
+                std::result::Result::Ok(datetime) => datetime.with_timezone(&Utc),
+                Err(_) => TimeUtil::datetime_from_utc_str(timestamp)?,
+            };
         let ymd = ((datetime.year() as i64 * 13 + datetime.month() as i64) << 5)
             | (datetime.day() as i64);
         let hms = ((datetime.hour() as i64) << 12)
@@ -571,3 +682,54 @@ impl FoxlakePusher {
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_timestamp_to_long() {
+        // pg timestamptz values are converted to utc
+        assert_eq!(
+            FoxlakePusher::timestamp_to_long("2024-01-01 08:00:00.123+08").unwrap(),
+            FoxlakePusher::timestamp_to_long("2024-01-01 00:00:00.123").unwrap()
+        );
+        assert_eq!(
+            FoxlakePusher::timestamp_to_long("2023-12-31 23:00:00-01:00").unwrap(),
+            FoxlakePusher::timestamp_to_long("2024-01-01 00:00:00").unwrap()
+        );
+    }
+
+    #[test]
+    fn test_get_pg_col_orc_schema() {
+        let col_type = |value_type, typmod| PgColType {
+            value_type,
+            name: String::new(),
+            alias: String::new(),
+            oid: 0,
+            parent_oid: 0,
+            element_oid: 0,
+            category: String::new(),
+            enum_values: None,
+            typmod,
+        };
+        // numeric(10, 2)
+        assert!(matches!(
+            FoxlakePusher::get_pg_col_orc_schema(&col_type(PgValueType::Numeric, (10 << 16) + 6)),
+            Schema::Decimal(10, 2)
+        ));
+        // numeric without precision, numeric(50, 2)
+        assert!(matches!(
+            FoxlakePusher::get_pg_col_orc_schema(&col_type(PgValueType::Numeric, -1)),
+            Schema::String
+        ));
+        assert!(matches!(
+            FoxlakePusher::get_pg_col_orc_schema(&col_type(PgValueType::Numeric, (50 << 16) + 6)),
+            Schema::String
+        ));
+        assert!(matches!(
+            FoxlakePusher::get_pg_col_orc_schema(&col_type(PgValueType::Date, -1)),
+            Schema::Long
+        ));
+    }
+}
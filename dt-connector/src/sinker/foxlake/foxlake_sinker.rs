@@ -93,6 +93,7 @@ impl Sinker for FoxlakeSinker {
 
     async fn close(&mut self) -> anyhow::Result<()> {
         self.meta_manager.close().await?;
+        self.pusher.meta_manager.close().await?;
         return close_conn_pool!(self);
     }
 }
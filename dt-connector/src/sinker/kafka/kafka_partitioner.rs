@@ -0,0 +1,278 @@
+use std::collections::HashMap;
+
+use anyhow::bail;
+use dt_common::{
+    config::{
+        config_enums::{DbType, KafkaPartitionStrategy},
+        config_token_parser::ConfigTokenParser,
+    },
+    error::Error,
+    meta::{row_data::RowData, row_type::RowType},
+    utils::sql_util::SqlUtil,
+};
+
+const ASTRISK: &str = "*";
+
+#[derive(Clone, Default)]
+pub struct KafkaPartitioner {
+    pub strategy: KafkaPartitionStrategy,
+    // HashMap<(schema, tb), col>, for KafkaPartitionStrategy::Column
+    pub partition_cols: HashMap<(String, String), String>,
+    // HashMap<topic, partition count>
+    pub topic_partitions: HashMap<String, i32>,
+    pub round_robin_index: usize,
+}
+
+impl KafkaPartitioner {
+    pub fn new(
+        strategy: KafkaPartitionStrategy,
+        partition_cols: &str,
+        db_type: &DbType,
+        topic_partitions: HashMap<String, i32>,
+    ) -> anyhow::Result<Self> {
+        let partition_cols = Self::parse_partition_cols(partition_cols, db_type)?;
+        if strategy == KafkaPartitionStrategy::Column && partition_cols.is_empty() {
+            bail! {Error::ConfigError(
+                "partition_cols is required if partition_strategy is column".into()
+            )}
+        }
+
+        Ok(Self {
+            strategy,
+            partition_cols,
+            topic_partitions,
+            round_robin_index: 0,
+        })
+    }
+
+    /// returns -1 if the partition should be decided by kafka producer
+    pub fn get_partition(&mut self, topic: &str, key: &str, row_data: &RowData) -> i32 {
+        let partition_count = match self.topic_partitions.get(topic) {
+            Some(count) if *count > 0 => *count,
+            _ => return -1,
+        };
+
+        match self.strategy {
+            KafkaPartitionStrategy::Default => -1,
+
+            KafkaPartitionStrategy::PkHash => {
+                if key.is_empty() {
+                    // no primary key, keep the order of rows in the same table
+                    Self::hash_to_partition(&Self::get_tb_key(row_data), partition_count)
+                } else {
+                    Self::hash_to_partition(key, partition_count)
+                }
+            }
+
+            KafkaPartitionStrategy::Table => {
+                Self::hash_to_partition(&Self::get_tb_key(row_data), partition_count)
+            }
+
+            KafkaPartitionStrategy::Column => {
+                let col_value = self
+                    .get_partition_col(&row_data.schema, &row_data.tb)
+                    .and_then(|col| Self::get_col_value(row_data, col));
+                match col_value {
+                    Some(value) => Self::hash_to_partition(&value, partition_count),
+                    None => Self::hash_to_partition(&Self::get_tb_key(row_data), partition_count),
+                }
+            }
+
+            KafkaPartitionStrategy::RoundRobin => {
+                self.round_robin_index = self.round_robin_index.wrapping_add(1);
+                (self.round_robin_index % partition_count as usize) as i32
+            }
+        }
+    }
+
+    pub fn get_ddl_partition(&self, topic: &str, schema: &str, tb: &str) -> i32 {
+        match (self.strategy.clone(), self.topic_partitions.get(topic)) {
+            // keep ddl in the same partition with dmls of the table
+            (KafkaPartitionStrategy::Table, Some(count)) if *count > 0 && !tb.is_empty() => {
+                Self::hash_to_partition(&format!("{}.{}", schema, tb), *count)
+            }
+            _ => -1,
+        }
+    }
+
+    fn get_partition_col(&self, schema: &str, tb: &str) -> Option<&String> {
+        self.partition_cols
+            .get(&(schema.into(), tb.into()))
+            .or_else(|| self.partition_cols.get(&(schema.into(), ASTRISK.into())))
+            .or_else(|| self.partition_cols.get(&(ASTRISK.into(), ASTRISK.into())))
+    }
+
+    fn get_col_value(row_data: &RowData, col: &str) -> Option<String> {
+        let col_values = match row_data.row_type {
+            RowType::Insert => row_data.after.as_ref(),
+            RowType::Update | RowType::Delete => row_data.before.as_ref(),
+        }?;
+        col_values.get(col)?.to_option_string()
+    }
+
+    fn get_tb_key(row_data: &RowData) -> String {
+        format!("{}.{}", row_data.schema, row_data.tb)
+    }
+
+    fn hash_to_partition(key: &str, partition_count: i32) -> i32 {
+        // same as the default partitioner of kafka java client
+        ((Self::murmur2(key.as_bytes()) & 0x7fffffff) % partition_count as u32) as i32
+    }
+
+    // refer to: org.apache.kafka.common.utils.Utils.murmur2
+    fn murmur2(data: &[u8]) -> u32 {
+        let seed: u32 = 0x9747b28c;
+        let m: u32 = 0x5bd1e995;
+        let r = 24;
+
+        let mut h: u32 = seed ^ (data.len() as u32);
+        let chunks = data.chunks_exact(4);
+        let tail = chunks.remainder();
+        for chunk in chunks {
+            let mut k = u32::from_le_bytes([chunk[0], chunk[1], chunk[2], chunk[3]]);
+            k = k.wrapping_mul(m);
+            k ^= k >> r;
+            k = k.wrapping_mul(m);
+            h = h.wrapping_mul(m);
+            h ^= k;
+        }
+
+        match tail.len() {
+            3 => {
+                h ^= (tail[2] as u32) << 16;
+                h ^= (tail[1] as u32) << 8;
+                h ^= tail[0] as u32;
+                h = h.wrapping_mul(m);
+            }
+            2 => {
+                h ^= (tail[1] as u32) << 8;
+                h ^= tail[0] as u32;
+                h = h.wrapping_mul(m);
+            }
+            1 => {
+                h ^= tail[0] as u32;
+                h = h.wrapping_mul(m);
+            }
+            _ => {}
+        }
+
+        h ^= h >> 13;
+        h = h.wrapping_mul(m);
+        h ^= h >> 15;
+        h
+    }
+
+    fn parse_partition_cols(
+        config_str: &str,
+        db_type: &DbType,
+    ) -> anyhow::Result<HashMap<(String, String), String>> {
+        // partition_cols=*.*:id,test_db_1.*:f_1,test_db_1.tb_1:f_2
+        let mut partition_cols = HashMap::new();
+        let delimiters = vec![',', '.', ':'];
+        let escape_pairs = SqlUtil::get_escape_pairs(db_type);
+        let tokens: Vec<String> =
+            ConfigTokenParser::parse_config(config_str, db_type, &delimiters)?
+                .into_iter()
+                .map(|mut token| {
+                    for escape_pair in escape_pairs.iter() {
+                        token = SqlUtil::unescape(&token, escape_pair);
+                    }
+                    token
+                })
+                .collect();
+
+        if tokens.len() % 3 != 0 {
+            bail! {Error::ConfigError(format!(
+                "invalid partition_cols: {}",
+                config_str
+            ))}
+        }
+
+        for i in tokens.chunks(3) {
+            partition_cols.insert((i[0].clone(), i[1].clone()), i[2].clone());
+        }
+        Ok(partition_cols)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use dt_common::meta::col_value::ColValue;
+
+    use super::*;
+
+    #[test]
+    fn test_murmur2() {
+        // refer to: org.apache.kafka.common.utils.UtilsTest
+        let cases = [
+            ("21", -973932308),
+            ("foobar", -790332482),
+            ("a-little-bit-long-string", -985981536),
+            ("a-little-bit-longer-string", -1486304829),
+            (
+                "lkjh234lh9fiuh90y23oiuhsafujhadof229phr9h19h89h8",
+                -58897971,
+            ),
+            ("abc", 479470107),
+        ];
+        for (key, expected) in cases {
+            assert_eq!(KafkaPartitioner::murmur2(key.as_bytes()) as i32, expected);
+        }
+    }
+
+    #[test]
+    fn test_get_partition() {
+        let topic_partitions = HashMap::from([("test".to_string(), 4)]);
+        let mut after = HashMap::new();
+        after.insert("id".to_string(), ColValue::Long(1));
+        after.insert("region".to_string(), ColValue::String("us".into()));
+        let row_data = RowData::new(
+            "db_1".into(),
+            "tb_1".into(),
+            RowType::Insert,
+            None,
+            Some(after),
+        );
+
+        let mut partitioner = KafkaPartitioner::new(
+            KafkaPartitionStrategy::Column,
+            "*.*:id,db_1.tb_1:region",
+            &DbType::Mysql,
+            topic_partitions.clone(),
+        )
+        .unwrap();
+        let partition = partitioner.get_partition("test", "1", &row_data);
+        assert_eq!(partition, KafkaPartitioner::hash_to_partition("us", 4));
+        // unknown topic
+        assert_eq!(partitioner.get_partition("test_2", "1", &row_data), -1);
+
+        let mut partitioner = KafkaPartitioner::new(
+            KafkaPartitionStrategy::PkHash,
+            "",
+            &DbType::Mysql,
+            topic_partitions.clone(),
+        )
+        .unwrap();
+        let partition = partitioner.get_partition("test", "1", &row_data);
+        assert_eq!(partition, KafkaPartitioner::hash_to_partition("1", 4));
+        let partition = partitioner.get_partition("test", "", &row_data);
+        assert_eq!(
+            partition,
+            KafkaPartitioner::hash_to_partition("db_1.tb_1", 4)
+        );
+
+        let mut partitioner = KafkaPartitioner::new(
+            KafkaPartitionStrategy::RoundRobin,
+            "",
+            &DbType::Mysql,
+            topic_partitions,
+        )
+        .unwrap();
+        let partitions: Vec<i32> = (0..4)
+            .map(|_| partitioner.get_partition("test", "1", &row_data))
+            .collect();
+        assert_eq!(partitions, vec![1, 2, 3, 0]);
+    }
+}
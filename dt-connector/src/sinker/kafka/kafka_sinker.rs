@@ -4,6 +4,7 @@ use async_trait::async_trait;
 use kafka::producer::{Producer, Record};
 use tokio::time::Instant;
 
+use super::kafka_partitioner::KafkaPartitioner;
 use crate::{call_batch_fn, rdb_router::RdbRouter, sinker::base_sinker::BaseSinker, Sinker};
 use dt_common::{
     meta::{avro::avro_converter::AvroConverter, ddl_meta::ddl_data::DdlData, row_data::RowData},
@@ -16,6 +17,7 @@ pub struct KafkaSinker {
     pub router: RdbRouter,
     pub producer: Producer,
     pub avro_converter: AvroConverter,
+    pub partitioner: KafkaPartitioner,
     pub monitor: Arc<Monitor>,
 }
 
@@ -34,12 +36,14 @@ impl Sinker for KafkaSinker {
         let mut messages = Vec::new();
         for ddl_data in data {
             let topic = self.router.get_topic(&ddl_data.default_schema, "");
+            let (schema, tb) = ddl_data.get_schema_tb();
+            let partition = self.partitioner.get_ddl_partition(topic, &schema, &tb);
             let payload = self.avro_converter.ddl_data_to_avro_value(ddl_data).await?;
             messages.push(Record {
                 key: String::new(),
                 value: payload,
                 topic,
-                partition: -1,
+                partition,
             });
         }
         self.producer.send_all(&messages)?;
@@ -68,6 +72,7 @@ impl KafkaSinker {
             row_data.convert_raw_string();
             let topic = self.router.get_topic(&row_data.schema, &row_data.tb);
             let key = self.avro_converter.row_data_to_avro_key(row_data).await?;
+            let partition = self.partitioner.get_partition(topic, &key, row_data);
             let payload = self
                 .avro_converter
                 .row_data_to_avro_value(row_data.clone())
@@ -76,7 +81,7 @@ impl KafkaSinker {
                 key,
                 value: payload,
                 topic,
-                partition: -1,
+                partition,
             });
         }
 
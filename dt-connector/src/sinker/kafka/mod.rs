@@ -1,2 +1,3 @@
+pub mod kafka_partitioner;
 pub mod kafka_sinker;
 pub mod rdkafka_sinker;
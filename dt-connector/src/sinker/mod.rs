@@ -3,6 +3,7 @@ pub mod base_sinker;
 pub mod base_struct_sinker;
 pub mod clickhouse;
 pub mod dummy_sinker;
+pub mod file;
 pub mod foxlake;
 pub mod kafka;
 pub mod mongo;
@@ -126,7 +126,7 @@ impl MongoChecker {
                 miss.push(miss_log);
             };
         }
-        BaseChecker::log_dml(miss, diff);
+        BaseChecker::log_dml(&self.monitor, miss, diff);
 
         BaseSinker::update_batch_monitor(&self.monitor, batch_size as u64, 0).await
     }
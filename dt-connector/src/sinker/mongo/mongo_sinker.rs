@@ -3,6 +3,7 @@ use std::{cmp, sync::Arc};
 use async_trait::async_trait;
 use mongodb::{
     bson::{doc, Document},
+    error::ErrorKind,
     options::UpdateOptions,
     Client, Collection,
 };
@@ -10,15 +11,23 @@ use tokio::time::Instant;
 
 use crate::{call_batch_fn, rdb_router::RdbRouter, sinker::base_sinker::BaseSinker, Sinker};
 use dt_common::{
-    log_error,
+    log_error, log_info, log_warn,
     meta::{
-        col_value::ColValue, mongo::mongo_constant::MongoConstants, row_data::RowData,
+        col_value::ColValue,
+        ddl_meta::ddl_data::DdlData,
+        mongo::{mongo_constant::MongoConstants, mongo_ddl::MongoDdl},
+        row_data::RowData,
         row_type::RowType,
     },
     monitor::monitor::Monitor,
     utils::limit_queue::LimitedQueue,
 };
 
+// errors caused by replaying ddls which have been executed, e.g. when resuming from an earlier position
+const NAMESPACE_NOT_FOUND: i32 = 26;
+const INDEX_NOT_FOUND: i32 = 27;
+const NAMESPACE_EXISTS: i32 = 48;
+
 #[derive(Clone)]
 pub struct MongoSinker {
     pub router: RdbRouter,
@@ -50,6 +59,39 @@ impl Sinker for MongoSinker {
         Ok(())
     }
 
+    async fn sink_ddl(&mut self, data: Vec<DdlData>, _batch: bool) -> anyhow::Result<()> {
+        let mut rts = LimitedQueue::new(cmp::min(100, data.len()));
+        let mut data_size = 0;
+
+        for ddl_data in data.iter() {
+            data_size += ddl_data.get_data_size();
+            let (db, command) = MongoDdl::to_command(ddl_data)?;
+            log_info!("sink ddl, db: {}, command: {}", db, command);
+
+            let start_time = Instant::now();
+            if let Err(error) = self
+                .mongo_client
+                .database(&db)
+                .run_command(command, None)
+                .await
+            {
+                match *error.kind {
+                    ErrorKind::Command(ref command_error)
+                        if [NAMESPACE_NOT_FOUND, INDEX_NOT_FOUND, NAMESPACE_EXISTS]
+                            .contains(&command_error.code) =>
+                    {
+                        log_warn!("ddl ignored, db: {}, error: {}", db, error);
+                    }
+                    _ => return Err(error.into()),
+                }
+            }
+            rts.push((start_time.elapsed().as_millis() as u64, 1));
+        }
+
+        BaseSinker::update_serial_monitor(&self.monitor, data.len() as u64, data_size).await?;
+        BaseSinker::update_monitor_rt(&self.monitor, &rts).await
+    }
+
     async fn close(&mut self) -> anyhow::Result<()> {
         self.mongo_client.clone().shutdown().await;
         Ok(())
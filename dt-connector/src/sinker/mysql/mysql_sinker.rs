@@ -17,6 +17,7 @@ use dt_common::{
     meta::{
         dcl_meta::dcl_data::DclData,
         ddl_meta::{ddl_data::DdlData, ddl_type::DdlType},
+        mongo::mongo_rdb_mapping::MongoRdbMapping,
         mysql::mysql_meta_manager::MysqlMetaManager,
         row_data::RowData,
         row_type::RowType,
@@ -44,6 +45,10 @@ impl Sinker for MysqlSinker {
             return Ok(());
         }
 
+        if MongoRdbMapping::is_mongo_row(&data[0]) {
+            return self.sink_mongo_dml(data, batch).await;
+        }
+
         if !batch {
             self.serial_sink(&data).await?;
         } else {
@@ -129,6 +134,21 @@ impl Sinker for MysqlSinker {
 }
 
 impl MysqlSinker {
+    async fn sink_mongo_dml(&mut self, data: Vec<RowData>, batch: bool) -> anyhow::Result<()> {
+        let data = BaseSinker::flatten_mongo_rows(&self.router, data, true)?;
+        if data.is_empty() {
+            return Ok(());
+        }
+
+        if !batch {
+            return self.serial_sink(&data).await;
+        }
+        for tb_data in BaseSinker::group_by_tb(data) {
+            self.sink_dml(tb_data, batch).await?;
+        }
+        Ok(())
+    }
+
     async fn serial_sink(&mut self, data: &[RowData]) -> anyhow::Result<()> {
         let mut tx = self.conn_pool.begin().await?;
         if let Some(sql) = self.get_data_marker_sql().await {
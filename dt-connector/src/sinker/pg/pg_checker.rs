@@ -109,7 +109,7 @@ impl PgChecker {
                 miss.push(miss_log);
             }
         }
-        BaseChecker::log_dml(miss, diff);
+        BaseChecker::log_dml(&self.monitor, miss, diff);
 
         BaseSinker::update_serial_monitor(&self.monitor, data.len() as u64, 0).await?;
         BaseSinker::update_monitor_rt(&self.monitor, &rts).await
@@ -151,7 +151,7 @@ impl PgChecker {
             &self.reverse_router,
         )
         .await?;
-        BaseChecker::log_dml(miss, diff);
+        BaseChecker::log_dml(&self.monitor, miss, diff);
 
         BaseSinker::update_batch_monitor(&self.monitor, batch_size as u64, 0).await?;
         BaseSinker::update_monitor_rt(&self.monitor, &rts).await
@@ -16,6 +16,7 @@ use dt_common::{
     log_error, log_info,
     meta::{
         ddl_meta::{ddl_data::DdlData, ddl_type::DdlType},
+        mongo::mongo_rdb_mapping::MongoRdbMapping,
         pg::pg_meta_manager::PgMetaManager,
         row_data::RowData,
         row_type::RowType,
@@ -43,6 +44,10 @@ impl Sinker for PgSinker {
             return Ok(());
         }
 
+        if MongoRdbMapping::is_mongo_row(&data[0]) {
+            return self.sink_mongo_dml(data, batch).await;
+        }
+
         if !batch {
             self.serial_sink(&data).await?;
         } else {
@@ -116,6 +121,21 @@ impl Sinker for PgSinker {
 }
 
 impl PgSinker {
+    async fn sink_mongo_dml(&mut self, data: Vec<RowData>, batch: bool) -> anyhow::Result<()> {
+        let data = BaseSinker::flatten_mongo_rows(&self.router, data, true)?;
+        if data.is_empty() {
+            return Ok(());
+        }
+
+        if !batch {
+            return self.serial_sink(&data).await;
+        }
+        for tb_data in BaseSinker::group_by_tb(data) {
+            self.sink_dml(tb_data, batch).await?;
+        }
+        Ok(())
+    }
+
     async fn serial_sink(&mut self, data: &[RowData]) -> anyhow::Result<()> {
         let mut data_size = 0;
 
@@ -305,10 +305,14 @@ impl EntryRewriter {
         Ok(cmds)
     }
 
-    pub fn rewrite_module(_obj: &mut ModuleObject) -> anyhow::Result<Vec<RedisCmd>> {
-        bail! {Error::RedisRdbError(
-            "module rewrite not implemented".into(),
-        )}
+    pub fn rewrite_module(obj: &mut ModuleObject) -> anyhow::Result<Vec<RedisCmd>> {
+        if obj.cmds.is_empty() {
+            bail! {Error::RedisRdbError(format!(
+                "module type: [{}] rewrite not supported",
+                obj.module_name
+            ))}
+        }
+        Ok(obj.cmds.drain(..).collect())
     }
 
     pub fn rewrite_set(obj: &mut SetObject) -> anyhow::Result<Vec<RedisCmd>> {
@@ -378,7 +382,7 @@ impl EntryRewriter {
         buf
     }
 
-    fn calc_crc64(p: &[u8]) -> u64 {
+    pub fn calc_crc64(p: &[u8]) -> u64 {
         let mut crc: u64 = 0;
         for b in p {
             let inx = (crc as u8) ^ *b;
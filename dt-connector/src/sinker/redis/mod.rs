@@ -1,3 +1,5 @@
 pub mod entry_rewriter;
+pub mod redis_checker;
 pub mod redis_sinker;
+pub mod redis_statistic_report;
 pub mod redis_statistic_sinker;
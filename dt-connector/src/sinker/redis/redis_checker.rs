@@ -0,0 +1,433 @@
+use std::{
+    collections::{BTreeMap, HashMap, HashSet},
+    sync::Arc,
+};
+
+use async_trait::async_trait;
+use redis::{Connection, FromRedisValue, Value};
+use tokio::time::Instant;
+
+use super::entry_rewriter::EntryRewriter;
+use crate::{
+    call_batch_fn,
+    check_log::{
+        check_log::{CheckLog, DiffColValue},
+        log_type::LogType,
+    },
+    sinker::{base_checker::BaseChecker, base_sinker::BaseSinker},
+    Sinker,
+};
+use dt_common::{
+    meta::{
+        dt_data::{DtData, DtItem},
+        redis::{
+            cluster_node::ClusterNode, redis_constant::RedisConstants, redis_entry::RedisEntry,
+            redis_object::RedisObject,
+        },
+    },
+    monitor::monitor::Monitor,
+    utils::limit_queue::LimitedQueue,
+};
+
+// ttl of source key is captured when parsing rdb, dst ttl keeps decreasing before checked
+const TTL_TOLERANCE_MS: i64 = 10000;
+// longer string values are logged by length
+const MAX_LOGGED_VALUE_LEN: usize = 64;
+const CORE_TYPES: [&str; 6] = ["string", "list", "hash", "set", "zset", "stream"];
+
+pub struct RedisChecker {
+    pub cluster_node: Option<ClusterNode>,
+    pub conn: Connection,
+    pub batch_size: usize,
+    // compare DUMP payloads, only meaningful if source and target share the same encodings
+    pub compare_digest: bool,
+    pub monitor: Arc<Monitor>,
+}
+
+#[async_trait]
+impl Sinker for RedisChecker {
+    async fn sink_raw(&mut self, mut data: Vec<DtItem>, _batch: bool) -> anyhow::Result<()> {
+        if data.is_empty() {
+            return Ok(());
+        }
+
+        call_batch_fn!(self, data, Self::batch_check);
+        Ok(())
+    }
+
+    fn get_id(&self) -> String {
+        if let Some(node) = &self.cluster_node {
+            node.address.clone()
+        } else {
+            String::new()
+        }
+    }
+}
+
+impl RedisChecker {
+    async fn batch_check(
+        &mut self,
+        data: &mut [DtItem],
+        start_index: usize,
+        batch_size: usize,
+    ) -> anyhow::Result<()> {
+        let mut data_size = 0;
+
+        // only keys decoded from rdb can be checked, commands from aof are ignored
+        let mut db_entries: BTreeMap<i64, Vec<&RedisEntry>> = BTreeMap::new();
+        for dt_item in data.iter().skip(start_index).take(batch_size) {
+            data_size += dt_item.dt_data.get_data_size();
+            if let DtData::Redis { entry } = &dt_item.dt_data {
+                if entry.is_base && !entry.key.bytes.is_empty() {
+                    db_entries.entry(entry.db_id).or_default().push(entry);
+                }
+            }
+        }
+
+        let mut miss = Vec::new();
+        let mut diff = Vec::new();
+        let start_time = Instant::now();
+        let mut rts = LimitedQueue::new(1);
+        for (db_id, entries) in db_entries {
+            let (db_miss, db_diff) = self.check_entries(db_id, &entries)?;
+            miss.extend(db_miss);
+            diff.extend(db_diff);
+        }
+        rts.push((start_time.elapsed().as_millis() as u64, 1));
+        BaseSinker::update_monitor_rt(&self.monitor, &rts).await?;
+
+        BaseChecker::log_dml(&self.monitor, miss, diff);
+        BaseSinker::update_batch_monitor(&self.monitor, batch_size as u64, data_size).await
+    }
+
+    fn check_entries(
+        &mut self,
+        db_id: i64,
+        entries: &[&RedisEntry],
+    ) -> anyhow::Result<(Vec<CheckLog>, Vec<CheckLog>)> {
+        redis::cmd("SELECT")
+            .arg(db_id)
+            .query::<()>(&mut self.conn)?;
+
+        // 1, fetch types, ttls and dump payloads of dst keys
+        let step = if self.compare_digest { 3 } else { 2 };
+        let mut pipe = redis::pipe();
+        for entry in entries.iter() {
+            pipe.cmd("TYPE").arg(entry.key.as_bytes());
+            pipe.cmd("PTTL").arg(entry.key.as_bytes());
+            if self.compare_digest {
+                pipe.cmd("DUMP").arg(entry.key.as_bytes());
+            }
+        }
+        let metas: Vec<Value> = pipe.query(&mut self.conn)?;
+
+        let mut dst_types = Vec::new();
+        for i in 0..entries.len() {
+            dst_types.push(String::from_redis_value(&metas[i * step])?);
+        }
+
+        // 2, fetch values of dst keys with the same types as src
+        let mut pipe = redis::pipe();
+        let mut value_indexes = HashMap::new();
+        for (i, entry) in entries.iter().enumerate() {
+            if !Self::is_same_type(&entry.value, &dst_types[i]) {
+                continue;
+            }
+            if let Some(cmd) = Self::build_fetch_cmd(entry) {
+                value_indexes.insert(i, value_indexes.len());
+                pipe.add_command(cmd);
+            }
+        }
+        let dst_values: Vec<Value> = if value_indexes.is_empty() {
+            Vec::new()
+        } else {
+            pipe.query(&mut self.conn)?
+        };
+
+        // 3, compare
+        let mut miss = Vec::new();
+        let mut diff = Vec::new();
+        for (i, entry) in entries.iter().enumerate() {
+            let dst_type = &dst_types[i];
+            if dst_type == "none" {
+                miss.push(Self::build_check_log(LogType::Miss, entry, HashMap::new()));
+                continue;
+            }
+
+            let mut diff_col_values = HashMap::new();
+            if !Self::is_same_type(&entry.value, dst_type) {
+                let diff_col_value = DiffColValue {
+                    src: Some(entry.get_type()),
+                    dst: Some(dst_type.to_owned()),
+                };
+                diff_col_values.insert(RedisConstants::TYPE.to_string(), diff_col_value);
+            } else if let Some(j) = value_indexes.get(&i) {
+                if let Some(diff_col_value) = Self::compare_value(&entry.value, &dst_values[*j])? {
+                    diff_col_values.insert(RedisConstants::VALUE.to_string(), diff_col_value);
+                }
+            }
+
+            let dst_ttl = i64::from_redis_value(&metas[i * step + 1])?;
+            if let Some(diff_col_value) = Self::compare_ttl(entry.expire_ms, dst_ttl) {
+                diff_col_values.insert(RedisConstants::TTL.to_string(), diff_col_value);
+            }
+
+            if self.compare_digest {
+                let dst_payload = Option::<Vec<u8>>::from_redis_value(&metas[i * step + 2])?;
+                if let Some(diff_col_value) = Self::compare_digest(entry, dst_payload) {
+                    diff_col_values.insert(RedisConstants::DIGEST.to_string(), diff_col_value);
+                }
+            }
+
+            if !diff_col_values.is_empty() {
+                diff.push(Self::build_check_log(LogType::Diff, entry, diff_col_values));
+            }
+        }
+        Ok((miss, diff))
+    }
+
+    fn is_same_type(src: &RedisObject, dst_type: &str) -> bool {
+        match src {
+            // TYPE returns the name of module type, such as: ReJSON-RL
+            RedisObject::Module(_) => dst_type != "none" && !CORE_TYPES.contains(&dst_type),
+            _ => src.get_type() == dst_type,
+        }
+    }
+
+    fn build_fetch_cmd(entry: &RedisEntry) -> Option<redis::Cmd> {
+        let key = entry.key.as_bytes();
+        let cmd = match entry.value {
+            RedisObject::String(_) => redis::cmd("GET").arg(key).to_owned(),
+            RedisObject::List(_) => redis::cmd("LRANGE").arg(key).arg(0).arg(-1).to_owned(),
+            RedisObject::Hash(_) => redis::cmd("HGETALL").arg(key).to_owned(),
+            RedisObject::Set(_) => redis::cmd("SMEMBERS").arg(key).to_owned(),
+            RedisObject::Zset(_) => redis::cmd("ZRANGE")
+                .arg(key)
+                .arg(0)
+                .arg(-1)
+                .arg("WITHSCORES")
+                .to_owned(),
+            RedisObject::Stream(_) => redis::cmd("XRANGE").arg(key).arg("-").arg("+").to_owned(),
+            // module values can only be compared by digest
+            _ => return None,
+        };
+        Some(cmd)
+    }
+
+    fn compare_value(src: &RedisObject, dst: &Value) -> anyhow::Result<Option<DiffColValue>> {
+        let diff_col_value = match src {
+            RedisObject::String(obj) => {
+                let dst = Vec::<u8>::from_redis_value(dst)?;
+                if obj.value.bytes == dst {
+                    return Ok(None);
+                }
+                DiffColValue {
+                    src: Some(Self::summarize_bytes(&obj.value.bytes)),
+                    dst: Some(Self::summarize_bytes(&dst)),
+                }
+            }
+
+            RedisObject::List(obj) => {
+                let src: Vec<&[u8]> = obj.elements.iter().map(|i| i.as_bytes()).collect();
+                let dst = Vec::<Vec<u8>>::from_redis_value(dst)?;
+                if src == dst {
+                    return Ok(None);
+                }
+                Self::summarize_len(src.len(), dst.len())
+            }
+
+            RedisObject::Set(obj) => {
+                let src: HashSet<Vec<u8>> = obj.elements.iter().map(|i| i.bytes.clone()).collect();
+                let dst = HashSet::<Vec<u8>>::from_redis_value(dst)?;
+                if src == dst {
+                    return Ok(None);
+                }
+                Self::summarize_len(src.len(), dst.len())
+            }
+
+            RedisObject::Hash(obj) => {
+                let src: HashMap<Vec<u8>, Vec<u8>> = obj
+                    .value
+                    .iter()
+                    .map(|(k, (v, _))| (k.bytes.clone(), v.bytes.clone()))
+                    .collect();
+                let dst = HashMap::<Vec<u8>, Vec<u8>>::from_redis_value(dst)?;
+                if src == dst {
+                    return Ok(None);
+                }
+                Self::summarize_len(src.len(), dst.len())
+            }
+
+            RedisObject::Zset(obj) => {
+                let mut src = HashMap::new();
+                for ele in obj.elements.iter() {
+                    let score: f64 = ele.score.to_string().parse().unwrap_or(f64::NAN);
+                    src.insert(ele.member.bytes.clone(), score);
+                }
+                let dst = HashMap::<Vec<u8>, f64>::from_redis_value(dst)?;
+                if src == dst {
+                    return Ok(None);
+                }
+                Self::summarize_len(src.len(), dst.len())
+            }
+
+            RedisObject::Stream(obj) => {
+                // stream entries are decoded as: xadd key id field value [field value ...]
+                let src: Vec<(String, Vec<Vec<u8>>)> = obj
+                    .cmds
+                    .iter()
+                    .filter(|cmd| cmd.get_name().eq_ignore_ascii_case("xadd"))
+                    .map(|cmd| (cmd.get_str_arg(2), cmd.args[3..].to_vec()))
+                    .collect();
+                let mut dst = Vec::new();
+                for item in Vec::<Value>::from_redis_value(dst)?.iter() {
+                    dst.push(<(String, Vec<Vec<u8>>)>::from_redis_value(item)?);
+                }
+                if src == dst {
+                    return Ok(None);
+                }
+                Self::summarize_len(src.len(), dst.len())
+            }
+
+            _ => return Ok(None),
+        };
+        Ok(Some(diff_col_value))
+    }
+
+    fn compare_ttl(src_ttl: i64, dst_ttl: i64) -> Option<DiffColValue> {
+        // src: 0 if no expire, dst: -1 if no expire, -2 if key not exists
+        let same = match (src_ttl > 0, dst_ttl >= 0) {
+            (false, false) => dst_ttl == -1,
+            (true, true) => (src_ttl - dst_ttl).abs() <= TTL_TOLERANCE_MS,
+            _ => false,
+        };
+
+        if same {
+            return None;
+        }
+        Some(DiffColValue {
+            src: Some(src_ttl.to_string()),
+            dst: Some(dst_ttl.to_string()),
+        })
+    }
+
+    fn compare_digest(entry: &RedisEntry, dst_payload: Option<Vec<u8>>) -> Option<DiffColValue> {
+        if entry.raw_bytes.is_empty() {
+            return None;
+        }
+
+        // payload: type byte + value + rdb version(2 bytes) + crc64(8 bytes)
+        let mut src = vec![entry.value_type_byte];
+        src.extend_from_slice(&entry.raw_bytes);
+        let dst = dst_payload
+            .filter(|i| i.len() > 10)
+            .map(|i| i[..i.len() - 10].to_vec());
+
+        if Some(&src) == dst.as_ref() {
+            return None;
+        }
+        Some(DiffColValue {
+            src: Some(format!("{:016x}", EntryRewriter::calc_crc64(&src))),
+            dst: dst.map(|i| format!("{:016x}", EntryRewriter::calc_crc64(&i))),
+        })
+    }
+
+    fn summarize_bytes(bytes: &[u8]) -> String {
+        if bytes.len() > MAX_LOGGED_VALUE_LEN {
+            format!("len: {}", bytes.len())
+        } else {
+            String::from_utf8_lossy(bytes).to_string()
+        }
+    }
+
+    fn summarize_len(src_len: usize, dst_len: usize) -> DiffColValue {
+        DiffColValue {
+            src: Some(format!("len: {}", src_len)),
+            dst: Some(format!("len: {}", dst_len)),
+        }
+    }
+
+    fn build_check_log(
+        log_type: LogType,
+        entry: &RedisEntry,
+        diff_col_values: HashMap<String, DiffColValue>,
+    ) -> CheckLog {
+        let mut id_col_values = HashMap::new();
+        id_col_values.insert(RedisConstants::KEY.to_string(), Some(entry.key.to_string()));
+        CheckLog {
+            log_type,
+            schema: entry.db_id.to_string(),
+            tb: String::new(),
+            id_col_values,
+            diff_col_values,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use dt_common::meta::redis::redis_object::{
+        ModuleObject, RedisString, StringObject, ZSetEntry, ZsetObject,
+    };
+
+    use super::*;
+
+    fn bulk(s: &str) -> Value {
+        Value::BulkString(s.as_bytes().to_vec())
+    }
+
+    #[test]
+    fn test_compare_ttl() {
+        assert!(RedisChecker::compare_ttl(0, -1).is_none());
+        assert!(RedisChecker::compare_ttl(60000, 59000).is_none());
+        assert!(RedisChecker::compare_ttl(60000, -1).is_some());
+        assert!(RedisChecker::compare_ttl(0, 1000).is_some());
+        assert!(RedisChecker::compare_ttl(60000, 30000).is_some());
+    }
+
+    #[test]
+    fn test_compare_value() {
+        let mut obj = StringObject::new();
+        obj.value = RedisString::from("abc".to_string());
+        let src = RedisObject::String(obj);
+        assert!(RedisChecker::compare_value(&src, &bulk("abc"))
+            .unwrap()
+            .is_none());
+        let diff = RedisChecker::compare_value(&src, &bulk("abd"))
+            .unwrap()
+            .unwrap();
+        assert_eq!(diff.src, Some("abc".into()));
+        assert_eq!(diff.dst, Some("abd".into()));
+
+        let mut obj = ZsetObject::new();
+        obj.elements.push(ZSetEntry {
+            member: RedisString::from("m1".to_string()),
+            score: RedisString::from("1.5".to_string()),
+        });
+        let src = RedisObject::Zset(obj);
+        let dst = Value::Array(vec![bulk("m1"), bulk("1.5")]);
+        assert!(RedisChecker::compare_value(&src, &dst).unwrap().is_none());
+        let dst = Value::Array(vec![bulk("m1"), bulk("2")]);
+        assert!(RedisChecker::compare_value(&src, &dst).unwrap().is_some());
+    }
+
+    #[test]
+    fn test_is_same_type() {
+        let src = RedisObject::String(StringObject::new());
+        assert!(RedisChecker::is_same_type(&src, "string"));
+        assert!(!RedisChecker::is_same_type(&src, "hash"));
+        let src = RedisObject::Module(ModuleObject::new());
+        assert!(RedisChecker::is_same_type(&src, "ReJSON-RL"));
+        assert!(!RedisChecker::is_same_type(&src, "none"));
+    }
+
+    #[test]
+    fn test_build_fetch_cmd() {
+        let mut entry = RedisEntry::new();
+        entry.key = RedisString::from("k".to_string());
+        entry.value = RedisObject::Module(ModuleObject::new());
+        assert!(RedisChecker::build_fetch_cmd(&entry).is_none());
+        entry.value = RedisObject::String(StringObject::new());
+        assert!(RedisChecker::build_fetch_cmd(&entry).is_some());
+    }
+}
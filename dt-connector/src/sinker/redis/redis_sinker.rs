@@ -11,6 +11,7 @@ use tokio::{sync::RwLock, time::Instant};
 use super::entry_rewriter::EntryRewriter;
 use crate::call_batch_fn;
 use crate::data_marker::DataMarker;
+use crate::rdb_router::RdbRouter;
 use crate::sinker::base_sinker::BaseSinker;
 use crate::Sinker;
 use dt_common::error::Error;
@@ -39,6 +40,8 @@ pub struct RedisSinker {
     pub monitor: Arc<Monitor>,
     pub data_marker: Option<Arc<RwLock<DataMarker>>>,
     pub key_parser: KeyParser,
+    // maps rows to redis keys by [router] redis_map
+    pub router: RdbRouter,
 }
 
 #[async_trait]
@@ -143,8 +146,11 @@ impl RedisSinker {
                         RedisObject::Hash(ref mut obj) => EntryRewriter::rewrite_hash(obj),
                         RedisObject::Zset(ref mut obj) => EntryRewriter::rewrite_zset(obj),
                         RedisObject::Stream(ref mut obj) => Ok(obj.cmds.drain(..).collect()),
+                        RedisObject::Module(ref mut obj) if !obj.cmds.is_empty() => {
+                            EntryRewriter::rewrite_module(obj)
+                        }
                         RedisObject::Module(_) => {
-                            // TODO: support rewrite some 8.0+ major module2 types, such as: JSON, Bloom, CountMinSketch, TDigest, TopK, TimeSeries
+                            // module types not decoded by parser are synced by RESTORE
                             let cmd = EntryRewriter::rewrite_as_restore(entry, self.version)?;
                             Ok(vec![cmd])
                         }
@@ -174,9 +180,7 @@ impl RedisSinker {
         let mut cmds = Vec::new();
         for row_data in data.iter_mut().skip(start_index).take(batch_size) {
             data_size += row_data.data_size;
-            if let Some(cmd) = self.dml_to_redis_cmd(row_data).await? {
-                cmds.push(cmd);
-            }
+            cmds.extend(self.dml_to_redis_cmds(row_data).await?);
         }
         self.batch_sink(&cmds).await?;
 
@@ -188,17 +192,23 @@ impl RedisSinker {
 
         for row_data in data.iter_mut() {
             data_size += row_data.data_size;
-            if let Some(cmd) = self.dml_to_redis_cmd(row_data).await? {
-                self.batch_sink(&[cmd]).await?
-            }
+            let cmds = self.dml_to_redis_cmds(row_data).await?;
+            self.batch_sink(&cmds).await?
         }
 
         BaseSinker::update_serial_monitor(&self.monitor, data.len() as u64, data_size as u64).await
     }
 
-    async fn dml_to_redis_cmd(&mut self, row_data: &RowData) -> anyhow::Result<Option<RedisCmd>> {
+    async fn dml_to_redis_cmds(&mut self, row_data: &RowData) -> anyhow::Result<Vec<RedisCmd>> {
+        if let Some(mapping) = self
+            .router
+            .get_redis_mapping(&row_data.schema, &row_data.tb)
+        {
+            return Ok(mapping.to_redis_cmds(row_data));
+        }
+
         if self.meta_manager.is_none() {
-            return Ok(None);
+            return Ok(Vec::new());
         }
 
         let tb_meta = self
@@ -208,29 +218,23 @@ impl RedisSinker {
             .get_tb_meta(&row_data.schema, &row_data.tb)
             .await?;
 
-        // no single primary / unique key exists, do not sink to redis
-        if tb_meta.order_col.is_none() {
-            return Ok(None);
+        // no primary / unique key exists, do not sink to redis
+        if tb_meta.key_map.is_empty() {
+            return Ok(Vec::new());
         }
 
-        let key = if let Some(col) = &tb_meta.order_col {
-            match row_data.row_type {
-                RowType::Insert | RowType::Update => row_data.after.as_ref().unwrap().get(col),
-                RowType::Delete => row_data.before.as_ref().unwrap().get(col),
-            }
-        } else {
-            None
+        // schema.tb.id_col_1_value.id_col_2_value
+        let col_values = match row_data.row_type {
+            RowType::Insert | RowType::Update => row_data.after.as_ref().unwrap(),
+            RowType::Delete => row_data.before.as_ref().unwrap(),
         };
-
-        let key = if let Some(v) = key {
-            if let Some(v) = v.to_option_string() {
-                format!("{}.{}.{}", row_data.schema, row_data.tb, v)
-            } else {
-                return Ok(None);
+        let mut key = format!("{}.{}", row_data.schema, row_data.tb);
+        for col in tb_meta.id_cols.iter() {
+            match col_values.get(col).and_then(|v| v.to_option_string()) {
+                Some(v) => key = format!("{}.{}", key, v),
+                None => return Ok(Vec::new()),
             }
-        } else {
-            return Ok(None);
-        };
+        }
 
         let mut cmd = RedisCmd::new();
         match row_data.row_type {
@@ -251,7 +255,7 @@ impl RedisSinker {
                 cmd.add_str_arg(&key);
             }
         }
-        Ok(Some(cmd))
+        Ok(vec![cmd])
     }
 }
 
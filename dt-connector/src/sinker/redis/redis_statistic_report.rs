@@ -0,0 +1,401 @@
+use std::{
+    cmp::Reverse,
+    collections::{BTreeMap, BinaryHeap, HashMap},
+    fs::{self, File},
+    io::Write,
+};
+
+use serde::Serialize;
+
+use dt_common::{
+    log_info,
+    meta::redis::{redis_entry::RedisEntry, redis_statistic_type::RedisStatisticType},
+};
+
+use crate::sinker::file::file_encoder::FileEncoder;
+
+const REPORT_JSON_FILE: &str = "statistic_report.json";
+const TOP_KEYS_CSV_FILE: &str = "statistic_top_keys.csv";
+const PREFIXES_CSV_FILE: &str = "statistic_prefixes.csv";
+
+// keys without delimiter may generate too many prefixes
+const MAX_PREFIXES: usize = 10000;
+const NO_PREFIX: &str = "(none)";
+const OTHER_PREFIXES: &str = "(others)";
+
+const TTL_NONE: &str = "none";
+const TTL_EXPIRED: &str = "expired";
+const TTL_RANGES: [(&str, i64); 5] = [
+    ("<1m", 60 * 1000),
+    ("<1h", 3600 * 1000),
+    ("<1d", 24 * 3600 * 1000),
+    ("<7d", 7 * 24 * 3600 * 1000),
+    (">=7d", i64::MAX),
+];
+
+#[derive(Serialize, Clone, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub struct KeyStatistic {
+    pub db_id: i64,
+    pub key_type: String,
+    pub key: String,
+    pub data_size: usize,
+    pub element_count: usize,
+    // 0 if no expire
+    pub ttl_ms: i64,
+    pub freq: i64,
+}
+
+#[derive(Serialize, Default, Debug)]
+pub struct TypeStatistic {
+    pub key_count: u64,
+    pub data_size: u64,
+    pub element_count: u64,
+}
+
+#[derive(Serialize, Default, Debug)]
+pub struct PrefixStatistic {
+    pub prefix: String,
+    pub key_count: u64,
+    pub data_size: u64,
+}
+
+#[derive(Serialize, Debug)]
+pub struct TtlStatistic {
+    pub ttl: String,
+    pub key_count: u64,
+}
+
+/// keeps the n items with the largest weights
+struct TopN {
+    n: usize,
+    heap: BinaryHeap<Reverse<(u64, KeyStatistic)>>,
+}
+
+impl TopN {
+    fn new(n: usize) -> Self {
+        Self {
+            n,
+            heap: BinaryHeap::new(),
+        }
+    }
+
+    fn push(&mut self, weight: u64, item: KeyStatistic) {
+        if self.heap.len() < self.n {
+            self.heap.push(Reverse((weight, item)));
+        } else if let Some(Reverse((min_weight, _))) = self.heap.peek() {
+            if weight > *min_weight {
+                self.heap.pop();
+                self.heap.push(Reverse((weight, item)));
+            }
+        }
+    }
+
+    fn to_sorted_vec(&self) -> Vec<KeyStatistic> {
+        let mut items: Vec<&(u64, KeyStatistic)> = self.heap.iter().map(|i| &i.0).collect();
+        items.sort_by(|a, b| b.0.cmp(&a.0));
+        items.into_iter().map(|i| i.1.clone()).collect()
+    }
+}
+
+#[derive(Serialize)]
+struct ReportOutput {
+    statistic_type: String,
+    key_count: u64,
+    data_size: u64,
+    expired_key_count: u64,
+    types: BTreeMap<String, TypeStatistic>,
+    ttls: Vec<TtlStatistic>,
+    prefixes: Vec<PrefixStatistic>,
+    top_big_keys: BTreeMap<String, Vec<KeyStatistic>>,
+    top_hot_keys: Vec<KeyStatistic>,
+    expired_keys: Vec<KeyStatistic>,
+}
+
+/// aggregates keys from all statistic sinkers, and writes summary files when the task finishes
+pub struct RedisStatisticReport {
+    statistic_type: RedisStatisticType,
+    top_n: usize,
+    prefix_delimiter: String,
+    key_count: u64,
+    data_size: u64,
+    expired_key_count: u64,
+    types: BTreeMap<String, TypeStatistic>,
+    ttls: HashMap<String, u64>,
+    prefixes: HashMap<String, PrefixStatistic>,
+    top_big_keys: BTreeMap<String, TopN>,
+    top_hot_keys: TopN,
+    expired_keys: TopN,
+    is_written: bool,
+}
+
+impl RedisStatisticReport {
+    pub fn new(statistic_type: RedisStatisticType, top_n: usize, prefix_delimiter: &str) -> Self {
+        Self {
+            statistic_type,
+            top_n,
+            prefix_delimiter: prefix_delimiter.to_string(),
+            key_count: 0,
+            data_size: 0,
+            expired_key_count: 0,
+            types: BTreeMap::new(),
+            ttls: HashMap::new(),
+            prefixes: HashMap::new(),
+            top_big_keys: BTreeMap::new(),
+            top_hot_keys: TopN::new(top_n),
+            expired_keys: TopN::new(top_n),
+            is_written: false,
+        }
+    }
+
+    pub fn add(&mut self, entry: &RedisEntry) {
+        // values of rdb entries are decoded, while entries from scan carry the counts
+        let element_count = if entry.is_base {
+            entry.value.get_element_count()
+        } else {
+            entry.element_count
+        };
+        let key = KeyStatistic {
+            db_id: entry.db_id,
+            key_type: entry.get_type(),
+            key: entry.key.to_string(),
+            data_size: entry.get_data_malloc_size(),
+            element_count,
+            ttl_ms: entry.expire_ms,
+            freq: entry.freq,
+        };
+        self.key_count += 1;
+
+        if let RedisStatisticType::HotKey = self.statistic_type {
+            self.top_hot_keys.push(key.freq.max(0) as u64, key);
+            return;
+        }
+
+        self.data_size += key.data_size as u64;
+        let type_statistic = self.types.entry(key.key_type.clone()).or_default();
+        type_statistic.key_count += 1;
+        type_statistic.data_size += key.data_size as u64;
+        type_statistic.element_count += key.element_count as u64;
+
+        let ttl = Self::get_ttl_range(entry.is_expired, key.ttl_ms);
+        *self.ttls.entry(ttl.to_string()).or_default() += 1;
+
+        let prefix = self.get_prefix(&key.key);
+        let prefix_statistic =
+            self.prefixes
+                .entry(prefix.clone())
+                .or_insert_with(|| PrefixStatistic {
+                    prefix,
+                    ..Default::default()
+                });
+        prefix_statistic.key_count += 1;
+        prefix_statistic.data_size += key.data_size as u64;
+
+        if entry.is_expired {
+            self.expired_key_count += 1;
+            self.expired_keys.push(key.data_size as u64, key.clone());
+        }
+
+        let top_n = self.top_n;
+        self.top_big_keys
+            .entry(key.key_type.clone())
+            .or_insert_with(|| TopN::new(top_n))
+            .push(key.data_size as u64, key);
+    }
+
+    /// writes once even if called by multiple sinkers
+    pub fn write(&mut self, dir: &str) -> anyhow::Result<()> {
+        if self.is_written {
+            return Ok(());
+        }
+        self.is_written = true;
+
+        fs::create_dir_all(dir)?;
+        let output = self.build_output();
+
+        let json_file = format!("{}/{}", dir, REPORT_JSON_FILE);
+        File::create(&json_file)?.write_all(serde_json::to_string_pretty(&output)?.as_bytes())?;
+
+        // big keys of all types or hot keys
+        let mut top_keys: Vec<&KeyStatistic> = output.top_big_keys.values().flatten().collect();
+        top_keys.extend(output.top_hot_keys.iter());
+        let mut lines = vec!["db_id,key_type,key,data_size,element_count,ttl_ms,freq".to_string()];
+        for i in top_keys {
+            lines.push(format!(
+                "{},{},{},{},{},{},{}",
+                i.db_id,
+                FileEncoder::escape_csv(&i.key_type),
+                FileEncoder::escape_csv(&i.key),
+                i.data_size,
+                i.element_count,
+                i.ttl_ms,
+                i.freq
+            ));
+        }
+        Self::write_lines(&format!("{}/{}", dir, TOP_KEYS_CSV_FILE), &lines)?;
+
+        let mut lines = vec!["prefix,key_count,data_size".to_string()];
+        for i in output.prefixes.iter() {
+            lines.push(format!(
+                "{},{},{}",
+                FileEncoder::escape_csv(&i.prefix),
+                i.key_count,
+                i.data_size
+            ));
+        }
+        Self::write_lines(&format!("{}/{}", dir, PREFIXES_CSV_FILE), &lines)?;
+
+        log_info!("redis statistic report written to: {}", dir);
+        Ok(())
+    }
+
+    fn build_output(&mut self) -> ReportOutput {
+        let mut ttls = Vec::new();
+        let ranges = [TTL_NONE, TTL_EXPIRED]
+            .into_iter()
+            .chain(TTL_RANGES.iter().map(|i| i.0));
+        for ttl in ranges {
+            if let Some(key_count) = self.ttls.get(ttl) {
+                ttls.push(TtlStatistic {
+                    ttl: ttl.to_string(),
+                    key_count: *key_count,
+                });
+            }
+        }
+
+        let mut prefixes: Vec<PrefixStatistic> = self.prefixes.drain().map(|i| i.1).collect();
+        prefixes.sort_by(|a, b| {
+            b.data_size
+                .cmp(&a.data_size)
+                .then(b.key_count.cmp(&a.key_count))
+        });
+
+        ReportOutput {
+            statistic_type: match self.statistic_type {
+                RedisStatisticType::BigKey => "big_key",
+                RedisStatisticType::HotKey => "hot_key",
+            }
+            .to_string(),
+            key_count: self.key_count,
+            data_size: self.data_size,
+            expired_key_count: self.expired_key_count,
+            types: std::mem::take(&mut self.types),
+            ttls,
+            prefixes,
+            top_big_keys: self
+                .top_big_keys
+                .iter()
+                .map(|(key_type, top_n)| (key_type.clone(), top_n.to_sorted_vec()))
+                .collect(),
+            top_hot_keys: self.top_hot_keys.to_sorted_vec(),
+            expired_keys: self.expired_keys.to_sorted_vec(),
+        }
+    }
+
+    fn get_prefix(&self, key: &str) -> String {
+        let prefix = match key.find(&self.prefix_delimiter) {
+            Some(i) if !self.prefix_delimiter.is_empty() => &key[..i],
+            _ => NO_PREFIX,
+        };
+        if self.prefixes.len() >= MAX_PREFIXES && !self.prefixes.contains_key(prefix) {
+            return OTHER_PREFIXES.to_string();
+        }
+        prefix.to_string()
+    }
+
+    fn get_ttl_range(is_expired: bool, ttl_ms: i64) -> &'static str {
+        if is_expired {
+            return TTL_EXPIRED;
+        }
+        if ttl_ms <= 0 {
+            return TTL_NONE;
+        }
+        TTL_RANGES
+            .iter()
+            .find(|(_, upper)| ttl_ms < *upper)
+            .map(|i| i.0)
+            .unwrap_or(TTL_NONE)
+    }
+
+    fn write_lines(file: &str, lines: &[String]) -> anyhow::Result<()> {
+        let mut content = lines.join("\n");
+        content.push('\n');
+        File::create(file)?.write_all(content.as_bytes())?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use dt_common::meta::redis::redis_object::{ListObject, RedisObject, RedisString};
+
+    use super::*;
+
+    fn mock_entry(key: &str, elements: usize, expire_ms: i64, is_expired: bool) -> RedisEntry {
+        let mut obj = ListObject::new();
+        obj.elements = vec![RedisString::from("a".to_string()); elements];
+        let mut entry = RedisEntry::new();
+        entry.is_base = true;
+        entry.key = RedisString::from(key.to_string());
+        entry.value = RedisObject::List(obj);
+        entry.data_size = elements * 10;
+        entry.expire_ms = expire_ms;
+        entry.is_expired = is_expired;
+        entry
+    }
+
+    #[test]
+    fn test_big_key_report() {
+        let mut report = RedisStatisticReport::new(RedisStatisticType::BigKey, 2, ":");
+        report.add(&mock_entry("user:1", 1, 0, false));
+        report.add(&mock_entry("user:2", 5, 30 * 1000, false));
+        report.add(&mock_entry("order:1", 3, 1, true));
+        report.add(&mock_entry("nodelimiter", 2, 2 * 3600 * 1000, false));
+
+        let output = report.build_output();
+        assert_eq!(output.key_count, 4);
+        assert_eq!(output.data_size, 110);
+        assert_eq!(output.expired_key_count, 1);
+        assert_eq!(output.types["list"].element_count, 11);
+
+        let top_keys: Vec<&str> = output.top_big_keys["list"]
+            .iter()
+            .map(|i| i.key.as_str())
+            .collect();
+        assert_eq!(top_keys, vec!["user:2", "order:1"]);
+        assert_eq!(output.expired_keys[0].key, "order:1");
+
+        let prefixes: Vec<(&str, u64)> = output
+            .prefixes
+            .iter()
+            .map(|i| (i.prefix.as_str(), i.key_count))
+            .collect();
+        assert_eq!(prefixes, vec![("user", 2), ("order", 1), (NO_PREFIX, 1)]);
+
+        let ttls: Vec<(&str, u64)> = output
+            .ttls
+            .iter()
+            .map(|i| (i.ttl.as_str(), i.key_count))
+            .collect();
+        assert_eq!(
+            ttls,
+            vec![(TTL_NONE, 1), (TTL_EXPIRED, 1), ("<1m", 1), ("<1d", 1)]
+        );
+    }
+
+    #[test]
+    fn test_hot_key_report() {
+        let mut report = RedisStatisticReport::new(RedisStatisticType::HotKey, 1, ":");
+        for (key, freq) in [("a", 3), ("b", 10), ("c", 5)] {
+            let mut entry = RedisEntry::new();
+            entry.key = RedisString::from(key.to_string());
+            entry.freq = freq;
+            report.add(&entry);
+        }
+
+        let output = report.build_output();
+        assert_eq!(output.key_count, 3);
+        assert_eq!(output.top_hot_keys.len(), 1);
+        assert_eq!(output.top_hot_keys[0].key, "b");
+        assert!(output.top_big_keys.is_empty());
+    }
+}
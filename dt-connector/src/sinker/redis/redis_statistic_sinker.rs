@@ -1,4 +1,4 @@
-use std::sync::Arc;
+use std::sync::{Arc, Mutex};
 
 use async_trait::async_trait;
 use serde::Serialize;
@@ -10,6 +10,7 @@ use dt_common::meta::dt_data::DtItem;
 use dt_common::meta::redis::redis_statistic_type::RedisStatisticType;
 use dt_common::monitor::monitor::Monitor;
 
+use super::redis_statistic_report::RedisStatisticReport;
 use crate::Sinker;
 
 pub struct RedisStatisticSinker {
@@ -17,6 +18,9 @@ pub struct RedisStatisticSinker {
     pub monitor: Arc<Monitor>,
     pub data_size_threshold: usize,
     pub freq_threshold: i64,
+    // shared by all sinkers
+    pub report: Arc<Mutex<RedisStatisticReport>>,
+    pub statistic_log_dir: String,
 }
 
 #[derive(Serialize)]
@@ -39,6 +43,7 @@ impl Sinker for RedisStatisticSinker {
     async fn sink_raw(&mut self, mut data: Vec<DtItem>, _batch: bool) -> anyhow::Result<()> {
         for dt_item in data.iter_mut() {
             if let DtData::Redis { entry } = &mut dt_item.dt_data {
+                self.report.lock().unwrap().add(entry);
                 match self.statistic_type {
                     RedisStatisticType::BigKey => {
                         if entry.get_data_malloc_size() < self.data_size_threshold {
@@ -69,4 +74,8 @@ impl Sinker for RedisStatisticSinker {
         }
         Ok(())
     }
+
+    async fn close(&mut self) -> anyhow::Result<()> {
+        self.report.lock().unwrap().write(&self.statistic_log_dir)
+    }
 }
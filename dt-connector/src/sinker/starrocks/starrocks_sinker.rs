@@ -1,6 +1,6 @@
 use std::{cmp, collections::HashMap, str::FromStr, sync::Arc};
 
-use crate::{call_batch_fn, sinker::base_sinker::BaseSinker, Sinker};
+use crate::{call_batch_fn, rdb_router::RdbRouter, sinker::base_sinker::BaseSinker, Sinker};
 use anyhow::bail;
 use async_trait::async_trait;
 use chrono::Utc;
@@ -14,6 +14,7 @@ use dt_common::{
     log_error,
     meta::{
         col_value::ColValue,
+        mongo::mongo_rdb_mapping::MongoRdbMapping,
         mysql::{
             mysql_col_type::MysqlColType, mysql_meta_manager::MysqlMetaManager,
             mysql_tb_meta::MysqlTbMeta,
@@ -38,6 +39,7 @@ pub struct StarRocksSinker {
     pub username: String,
     pub password: String,
     pub meta_manager: MysqlMetaManager,
+    pub router: RdbRouter,
     pub monitor: Arc<Monitor>,
     pub sync_timestamp: i64,
     pub hard_delete: bool,
@@ -50,6 +52,10 @@ impl Sinker for StarRocksSinker {
             return Ok(());
         }
 
+        if MongoRdbMapping::is_mongo_row(&data[0]) {
+            return self.sink_mongo_dml(data, batch).await;
+        }
+
         if !batch {
             self.serial_sink(data).await?;
         } else {
@@ -64,6 +70,26 @@ impl Sinker for StarRocksSinker {
 }
 
 impl StarRocksSinker {
+    async fn sink_mongo_dml(&mut self, data: Vec<RowData>, batch: bool) -> anyhow::Result<()> {
+        // stream load writes whole rows, applying a partial update would reset the other columns
+        if let Some(row_data) = data.iter().find(|i| MongoRdbMapping::is_diff_row(i)) {
+            bail! {Error::SinkerError(format!(
+                "$set / $unset diffs from mongo oplog are not supported by starrocks sinker, use source=change_stream instead, row_data: {}",
+                row_data
+            ))}
+        }
+
+        // stream load deletes rows by primary keys, child rows can not be deleted by parent_col
+        let data = BaseSinker::flatten_mongo_rows(&self.router, data, false)?;
+        if !batch {
+            return self.serial_sink(data).await;
+        }
+        for tb_data in BaseSinker::group_by_tb(data) {
+            self.sink_dml(tb_data, batch).await?;
+        }
+        Ok(())
+    }
+
     async fn serial_sink(&mut self, mut data: Vec<RowData>) -> anyhow::Result<()> {
         let mut data_size = 0;
 
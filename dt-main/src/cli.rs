@@ -207,6 +207,20 @@ pub enum Commands {
         #[arg(short, long)]
         metrics: bool,
     },
+
+    /// Start a local endpoint receiving alerts
+    ///
+    /// Used to verify the [alert] configs without external services,
+    /// set webhook_url or slack_webhook_url to the printed url.
+    AlertStub {
+        /// Host to listen on
+        #[arg(long, default_value = "127.0.0.1")]
+        host: String,
+
+        /// Port to listen on
+        #[arg(short, long, default_value_t = 9092)]
+        port: u16,
+    },
 }
 
 impl Default for Cli {
@@ -427,6 +441,23 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_alert_stub_command() {
+        let cli = Cli::try_parse_from(&[
+            "ape-dts",
+            "alert-stub",
+            "--port", "19091"
+        ]).unwrap();
+
+        match cli.command {
+            Some(Commands::AlertStub { host, port }) => {
+                assert_eq!(host, "127.0.0.1");
+                assert_eq!(port, 19091);
+            }
+            _ => panic!("Expected AlertStub command"),
+        }
+    }
+
     #[test]
     fn test_help_text_generation() {
         let mut cmd = Cli::command();
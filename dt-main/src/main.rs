@@ -6,6 +6,7 @@ use anyhow::{Context, Result};
 use tracing::{error, info, warn, debug};
 use tracing_subscriber::EnvFilter;
 
+use dt_common::alert::alert_stub::AlertStub;
 use dt_precheck::{config::task_config::PrecheckTaskConfig, do_precheck};
 use dt_task::task_runner::TaskRunner;
 
@@ -28,6 +29,11 @@ async fn main() -> Result<()> {
     info!("Starting ape-dts v{}", env!("CARGO_PKG_VERSION"));
     debug!("Command line arguments: {:?}", std::env::args().collect::<Vec<_>>());
 
+    // The alert stub needs no configuration file
+    if let Some(Commands::AlertStub { host, port }) = &cli.command {
+        return run_alert_stub(host, *port).await;
+    }
+
     // Validate configuration file exists
     if !Path::new(&cli.config).exists() {
         error!("Configuration file '{}' not found", cli.config);
@@ -276,6 +282,9 @@ async fn execute_command(command: &Commands, cli: &Cli) -> Result<()> {
         Commands::Info { connections, metrics } => {
             display_info(&cli.config, *connections, *metrics).await
         },
+        Commands::AlertStub { host, port } => {
+            run_alert_stub(host, *port).await
+        },
     }
 }
 
@@ -353,6 +362,20 @@ async fn execute_check_operation(config_file: &str, cli: &Cli) -> Result<()> {
     }
 }
 
+async fn run_alert_stub(host: &str, port: u16) -> Result<()> {
+    let stub = AlertStub::start(host, port)
+        .with_context(|| format!("Failed to start alert stub on {}:{}", host, port))?;
+    info!("Alert stub started: {}", stub.get_url());
+    println!("🔔 Alert stub listening on {}", stub.get_url());
+    println!("💡 Set [alert] webhook_url or slack_webhook_url to this url.");
+    println!("💡 List received alerts by: curl {}", stub.get_url());
+    println!("💡 Press Ctrl+C to stop.");
+
+    // stopped by the Ctrl+C handler
+    std::future::pending::<()>().await;
+    Ok(())
+}
+
 async fn display_info(config_file: &str, show_connections: bool, show_metrics: bool) -> Result<()> {
     println!("📋 Configuration Information");
     println!("==========================");
@@ -1,14 +1,17 @@
 use std::collections::VecDeque;
 use std::sync::Arc;
+use std::time::Duration;
 
 use anyhow::bail;
 use ratelimit::Ratelimiter;
 
+use dt_common::control::task_control::TaskControl;
 use dt_common::meta::dcl_meta::dcl_data::DclData;
 use dt_common::meta::ddl_meta::ddl_data::DdlData;
 use dt_common::meta::{dt_data::DtItem, dt_queue::DtQueue, row_data::RowData};
 use dt_common::monitor::counter::Counter;
 use dt_common::monitor::counter_type::CounterType;
+use dt_common::monitor::trace_span::TraceSpan;
 use dt_common::{error::Error, monitor::monitor::Monitor};
 use dt_connector::Sinker;
 
@@ -17,10 +20,13 @@ pub struct BaseParallelizer {
     pub poped_data: VecDeque<DtItem>,
     pub monitor: Arc<Monitor>,
     pub rps_limiter: Option<Ratelimiter>,
+    // max_rps may be changed by the control api while running
+    pub task_control: Arc<TaskControl>,
 }
 
 impl BaseParallelizer {
     pub async fn drain(&mut self, buffer: &DtQueue) -> anyhow::Result<Vec<DtItem>> {
+        self.refresh_rps_limiter()?;
         let mut data = Vec::new();
         while let Some(item) = self.poped_data.pop_front() {
             data.push(item);
@@ -50,6 +56,7 @@ impl BaseParallelizer {
         buffer: &DtQueue,
         max_count: usize,
     ) -> anyhow::Result<Vec<DtItem>> {
+        self.refresh_rps_limiter()?;
         let mut data = Vec::new();
         let mut record_size_counter = Counter::new(0, 0);
         while let Ok(item) = self.pop(buffer, &mut record_size_counter).await {
@@ -62,6 +69,29 @@ impl BaseParallelizer {
         Ok(data)
     }
 
+    fn refresh_rps_limiter(&mut self) -> anyhow::Result<()> {
+        let max_rps = self.task_control.get_max_rps();
+        let curr_max_rps = self
+            .rps_limiter
+            .as_ref()
+            .map_or(0, |rps_limiter| rps_limiter.max_tokens());
+        if max_rps == curr_max_rps {
+            return Ok(());
+        }
+
+        self.rps_limiter = if max_rps > 0 {
+            Some(
+                Ratelimiter::builder(max_rps, Duration::from_secs(1))
+                    .max_tokens(max_rps)
+                    .initial_available(max_rps)
+                    .build()?,
+            )
+        } else {
+            None
+        };
+        Ok(())
+    }
+
     pub async fn pop(
         &self,
         buffer: &DtQueue,
@@ -112,7 +142,15 @@ impl BaseParallelizer {
         for i in 0..sub_datas.len() {
             let data = sub_datas.remove(0);
             let sinker = sinkers[i % parallel_size].clone();
-            join_set.spawn(async move { sinker.lock().await.sink_dml(data, batch).await });
+            let span = TraceSpan::start("sinker.sink_dml");
+            span.set_count("count", data.len() as u64);
+            if let Some(row_data) = data.first() {
+                span.set_tb(&row_data.schema, &row_data.tb);
+            }
+            join_set.spawn(async move {
+                span.in_scope(async { sinker.lock().await.sink_dml(data, batch).await })
+                    .await
+            });
         }
         while let Some(result) = join_set.join_next().await {
             result??;
@@ -131,7 +169,12 @@ impl BaseParallelizer {
         for i in 0..sub_datas.len() {
             let data = sub_datas.remove(0);
             let sinker = sinkers[i % parallel_size].clone();
-            join_set.spawn(async move { sinker.lock().await.sink_ddl(data, batch).await });
+            let span = TraceSpan::start("sinker.sink_ddl");
+            span.set_count("count", data.len() as u64);
+            join_set.spawn(async move {
+                span.in_scope(async { sinker.lock().await.sink_ddl(data, batch).await })
+                    .await
+            });
         }
         while let Some(result) = join_set.join_next().await {
             result??;
@@ -174,7 +217,12 @@ impl BaseParallelizer {
         for i in 0..sub_datas.len() {
             let data = sub_datas.remove(0);
             let sinker = sinkers[i % parallel_size].clone();
-            join_set.spawn(async move { sinker.lock().await.sink_raw(data, batch).await });
+            let span = TraceSpan::start("sinker.sink_raw");
+            span.set_count("count", data.len() as u64);
+            join_set.spawn(async move {
+                span.in_scope(async { sinker.lock().await.sink_raw(data, batch).await })
+                    .await
+            });
         }
         while let Some(result) = join_set.join_next().await {
             result??;
@@ -10,6 +10,7 @@ use dt_common::meta::dt_queue::DtQueue;
 use dt_common::meta::{
     dt_data::DtItem, rdb_meta_manager::RdbMetaManager, row_data::RowData, row_type::RowType,
 };
+use dt_common::monitor::trace_span::TraceSpan;
 use dt_connector::Sinker;
 
 pub struct MergeParallelizer {
@@ -57,7 +58,10 @@ impl Parallelizer for MergeParallelizer {
     ) -> anyhow::Result<DataSize> {
         let mut data_size = DataSize::default();
         // no need to check foreign key since foreign key checks were disabled in MySQL/Postgres connections
-        let mut tb_merged_datas = self.merger.merge(data).await?;
+        let mut tb_merged_datas = TraceSpan::start("parallelizer.merge")
+            .set_count("count", data.len() as u64)
+            .in_scope(self.merger.merge(data))
+            .await?;
         data_size.add(
             self.sink_dml_internal(&mut tb_merged_datas, sinkers, MergeType::Delete)
                 .await?,
@@ -145,8 +149,15 @@ impl MergeParallelizer {
                         let sub_size = cmp::min(batch_size, data.len() - i);
                         let sub_data = data[i..i + sub_size].to_vec();
                         let sinker = sinkers[futures.len() % self.parallel_size].clone();
+                        let span = TraceSpan::start("sinker.sink_dml");
+                        span.set_tb(&sub_data[0].schema, &sub_data[0].tb)
+                            .set_count("count", sub_size as u64);
                         let future = tokio::spawn(async move {
-                            sinker.lock().await.sink_dml(sub_data, true).await.unwrap();
+                            span.in_scope(async {
+                                sinker.lock().await.sink_dml(sub_data, true).await
+                            })
+                            .await
+                            .unwrap();
                         });
                         futures.push(future);
                         i += batch_size;
@@ -155,8 +166,13 @@ impl MergeParallelizer {
 
                 MergeType::Unmerged => {
                     let sinker = sinkers[futures.len() % self.parallel_size].clone();
+                    let span = TraceSpan::start("sinker.sink_dml");
+                    span.set_tb(&data[0].schema, &data[0].tb)
+                        .set_count("count", data.len() as u64);
                     let future = tokio::spawn(async move {
-                        Self::sink_unmerged_rows(sinker, data).await.unwrap();
+                        span.in_scope(Self::sink_unmerged_rows(sinker, data))
+                            .await
+                            .unwrap();
                     });
                     futures.push(future);
                 }
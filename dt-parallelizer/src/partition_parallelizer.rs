@@ -9,7 +9,7 @@ use dt_common::meta::{
     dt_queue::DtQueue,
     row_data::RowData,
 };
-use dt_common::monitor::counter::Counter;
+use dt_common::monitor::{counter::Counter, trace_span::TraceSpan};
 use dt_connector::Sinker;
 
 pub struct PartitionParallelizer {
@@ -72,7 +72,10 @@ impl Parallelizer for PartitionParallelizer {
             bytes: data.iter().map(|v| v.get_data_size()).sum(),
         };
 
-        let sub_datas = self.partitioner.partition(data, self.parallel_size).await?;
+        let sub_datas = TraceSpan::start("parallelizer.partition")
+            .set_count("count", data_size.count)
+            .in_scope(self.partitioner.partition(data, self.parallel_size))
+            .await?;
         self.base_parallelizer
             .sink_dml(sub_datas, sinkers, self.parallel_size, false)
             .await?;
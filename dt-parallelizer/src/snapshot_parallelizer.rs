@@ -5,6 +5,7 @@ use async_trait::async_trait;
 use super::base_parallelizer::BaseParallelizer;
 use crate::{DataSize, Parallelizer};
 use dt_common::meta::{dt_data::DtItem, dt_queue::DtQueue, row_data::RowData};
+use dt_common::monitor::trace_span::TraceSpan;
 use dt_connector::Sinker;
 
 pub struct SnapshotParallelizer {
@@ -32,7 +33,10 @@ impl Parallelizer for SnapshotParallelizer {
             bytes: data.iter().map(|v| v.data_size as u64).sum(),
         };
 
-        let sub_datas = Self::partition(data, self.parallel_size)?;
+        let sub_datas = TraceSpan::start("parallelizer.partition")
+            .set_count("count", data_size.count)
+            .in_scope(async { Self::partition(data, self.parallel_size) })
+            .await?;
         self.base_parallelizer
             .sink_dml(sub_datas, sinkers, self.parallel_size, true)
             .await?;
@@ -50,7 +54,10 @@ impl Parallelizer for SnapshotParallelizer {
             bytes: data.iter().map(|v| v.get_data_size()).sum(),
         };
 
-        let sub_datas = Self::partition(data, self.parallel_size)?;
+        let sub_datas = TraceSpan::start("parallelizer.partition")
+            .set_count("count", data_size.count)
+            .in_scope(async { Self::partition(data, self.parallel_size) })
+            .await?;
         self.base_parallelizer
             .sink_raw(sub_datas, sinkers, self.parallel_size, true)
             .await?;
@@ -1,25 +1,31 @@
-use std::sync::{
-    atomic::{AtomicBool, Ordering},
-    Arc,
+use std::{
+    collections::HashMap,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
 };
 
 use async_trait::async_trait;
+use chrono::Utc;
+use serde_json::json;
 use tokio::{sync::Mutex, sync::RwLock, time::Instant};
 
-use crate::{lua_processor::LuaProcessor, Pipeline};
+use crate::{processor_chain::ProcessorChain, Pipeline};
 use dt_common::{
     config::sinker_config::SinkerConfig,
+    control::task_control::TaskControl,
     log_info, log_position,
     meta::{
         dcl_meta::dcl_data::DclData,
-        ddl_meta::ddl_data::DdlData,
+        ddl_meta::{ddl_data::DdlData, ddl_type::DdlType},
         dt_data::{DtData, DtItem},
         dt_queue::DtQueue,
         position::Position,
         row_data::RowData,
         syncer::Syncer,
     },
-    monitor::{counter_type::CounterType, monitor::Monitor},
+    monitor::{counter_type::CounterType, monitor::Monitor, trace_span::TraceSpan},
     utils::time_util::TimeUtil,
 };
 use dt_connector::{data_marker::DataMarker, Sinker};
@@ -36,7 +42,9 @@ pub struct BasePipeline {
     pub syncer: Arc<Mutex<Syncer>>,
     pub monitor: Arc<Monitor>,
     pub data_marker: Option<Arc<RwLock<DataMarker>>>,
-    pub lua_processor: Option<LuaProcessor>,
+    // processors of [processor], executed on dml and ddl before sinking
+    pub processor_chain: ProcessorChain,
+    pub task_control: Arc<TaskControl>,
 }
 
 enum SinkMethod {
@@ -50,6 +58,7 @@ enum SinkMethod {
 #[async_trait]
 impl Pipeline for BasePipeline {
     async fn stop(&mut self) -> anyhow::Result<()> {
+        self.processor_chain.close()?;
         for sinker in self.sinkers.iter_mut() {
             sinker.lock().await.close().await?;
         }
@@ -69,6 +78,7 @@ impl Pipeline for BasePipeline {
         let mut last_received_position = Position::None;
         let mut last_commit_position = Position::None;
         let mut record_time = Instant::now();
+        let mut checkpoint_seq = self.task_control.get_checkpoint_seq();
 
         while !self.shut_down.load(Ordering::Acquire) || !self.buffer.is_empty() {
             // to avoid too many sub counters, only add counter when buffer is not empty
@@ -86,6 +96,13 @@ impl Pipeline for BasePipeline {
                 record_time = Instant::now();
             }
 
+            // skip tracing idle loops, which happen every millisecond
+            let batch_span = if self.buffer.is_empty() {
+                TraceSpan::empty()
+            } else {
+                TraceSpan::start("pipeline.batch")
+            };
+
             // some sinkers (foxlake) need to accumulate data to a big batch and sink
             let data = if last_sink_time.elapsed().as_secs() < self.batch_sink_interval_secs
                 && !self.buffer.is_full()
@@ -93,7 +110,12 @@ impl Pipeline for BasePipeline {
                 Vec::new()
             } else {
                 last_sink_time = Instant::now();
-                self.parallelizer.drain(self.buffer.as_ref()).await?
+                let drain_span = batch_span.start_child("pipeline.drain");
+                let data = drain_span
+                    .in_scope(self.parallelizer.drain(self.buffer.as_ref()))
+                    .await?;
+                drain_span.set_count("count", data.len() as u64);
+                data
             };
 
             if let Some(data_marker) = &mut self.data_marker {
@@ -103,29 +125,46 @@ impl Pipeline for BasePipeline {
             }
 
             // process all row_datas in buffer at a time
-            let (data_size, last_received, last_commit) = match self.get_sink_method(&data) {
-                SinkMethod::Ddl => self.sink_ddl(data).await?,
-                SinkMethod::Dcl => self.sink_dcl(data).await?,
-                SinkMethod::Dml => self.sink_dml(data).await?,
-                SinkMethod::Raw => self.sink_raw(data).await?,
-                SinkMethod::Struct => self.sink_struct(data).await?,
-            };
+            let sink_method = self.get_sink_method(&data);
+            let (data_size, last_received, last_commit) = batch_span
+                .in_scope(async {
+                    match sink_method {
+                        SinkMethod::Ddl => self.sink_ddl(data).await,
+                        SinkMethod::Dcl => self.sink_dcl(data).await,
+                        SinkMethod::Dml => self.sink_dml(data).await,
+                        SinkMethod::Raw => self.sink_raw(data).await,
+                        SinkMethod::Struct => self.sink_struct(data).await,
+                    }
+                })
+                .await?;
+            batch_span
+                .set_count("count", data_size.count)
+                .set_count("bytes", data_size.bytes);
 
             if let Some(position) = &last_received {
                 self.syncer.lock().await.received_position = position.to_owned();
                 last_received_position = position.to_owned();
+                self.update_delay(position);
             }
             if let Some(position) = &last_commit {
                 last_commit_position = position.to_owned();
             }
 
+            // record checkpoint immediately if requested by the control api
+            let last_checkpoint_time_option =
+                if checkpoint_seq != self.task_control.get_checkpoint_seq() {
+                    checkpoint_seq = self.task_control.get_checkpoint_seq();
+                    None
+                } else {
+                    Some(last_checkpoint_time)
+                };
             last_checkpoint_time = self
                 .record_checkpoint(
-                    Some(last_checkpoint_time),
+                    last_checkpoint_time_option,
                     &last_received_position,
                     &last_commit_position,
                 )
-                .await;
+                .await?;
 
             self.monitor
                 .add_counter(CounterType::SinkedRecordTotal, data_size.count)
@@ -136,7 +175,7 @@ impl Pipeline for BasePipeline {
         }
 
         self.record_checkpoint(None, &last_received_position, &last_commit_position)
-            .await;
+            .await?;
         Ok(())
     }
 }
@@ -146,8 +185,16 @@ impl BasePipeline {
         &mut self,
         all_data: Vec<DtItem>,
     ) -> anyhow::Result<(DataSize, Option<Position>, Option<Position>)> {
+        // positions are fetched before processing, since processors may filter rows,
+        // only dml / ddl are processed, redis entries and foxlake files are kept as they are
         let (data_count, last_received_position, last_commit_position) = Self::fetch_raw(&all_data);
-        if data_count > 0 {
+        let all_data = if self.processor_chain.is_empty() {
+            all_data
+        } else {
+            self.processor_chain.process_items(all_data)?
+        };
+
+        if data_count > 0 && !all_data.is_empty() {
             let data_size = self.parallelizer.sink_raw(all_data, &self.sinkers).await?;
             Ok((data_size, last_received_position, last_commit_position))
         } else {
@@ -177,14 +224,19 @@ impl BasePipeline {
         &mut self,
         all_data: Vec<DtItem>,
     ) -> anyhow::Result<(DataSize, Option<Position>, Option<Position>)> {
-        let (mut data, last_received_position, last_commit_position) = Self::fetch_dml(all_data);
-        if !data.is_empty() {
-            // execute lua processor
-            if let Some(lua_processor) = &self.lua_processor {
-                data = lua_processor.process(data)?;
-            }
+        // positions are fetched before processing, since processors may filter rows
+        let (_, last_received_position, last_commit_position) = Self::fetch_raw(&all_data);
+        let all_data = if self.processor_chain.is_empty() {
+            all_data
+        } else {
+            self.processor_chain.process_items(all_data)?
+        };
 
+        let tb_timestamps = Self::fetch_tb_timestamps(&all_data);
+        let (data, _, _) = Self::fetch_dml(all_data);
+        if !data.is_empty() {
             let data_size = self.parallelizer.sink_dml(data, &self.sinkers).await?;
+            self.update_tb_delays(tb_timestamps);
             Ok((data_size, last_received_position, last_commit_position))
         } else {
             Ok((
@@ -199,7 +251,16 @@ impl BasePipeline {
         &mut self,
         all_data: Vec<DtItem>,
     ) -> anyhow::Result<(DataSize, Option<Position>, Option<Position>)> {
-        let (data, last_received_position, last_commit_position) = Self::fetch_ddl(all_data);
+        // positions are fetched before processing, since processors may filter ddl
+        let (_, last_received_position, last_commit_position) = Self::fetch_raw(&all_data);
+        let all_data = if self.processor_chain.is_empty() {
+            all_data
+        } else {
+            self.processor_chain.process_items(all_data)?
+        };
+
+        let (data, _, _) = Self::fetch_ddl(all_data);
+
         if !data.is_empty() {
             let data_size = self
                 .parallelizer
@@ -211,6 +272,7 @@ impl BasePipeline {
             }
             self.monitor
                 .add_counter(CounterType::DDLRecordTotal, data_size.count);
+            self.remove_tb_delays(&data);
             Ok((data_size, last_received_position, last_commit_position))
         } else {
             Ok((
@@ -350,6 +412,7 @@ impl BasePipeline {
                     SinkerConfig::FoxlakePush { .. }
                     | SinkerConfig::FoxlakeMerge { .. }
                     | SinkerConfig::Foxlake { .. }
+                    | SinkerConfig::File { .. }
                     | SinkerConfig::Redis { .. } => return SinkMethod::Raw,
                     _ => return SinkMethod::Dml,
                 },
@@ -367,25 +430,102 @@ impl BasePipeline {
         last_checkpoint_time: Option<Instant>,
         last_received_position: &Position,
         last_commit_position: &Position,
-    ) -> Instant {
+    ) -> anyhow::Result<Instant> {
         if let Some(last) = last_checkpoint_time {
             if last.elapsed().as_secs() < self.checkpoint_interval_secs {
-                return last;
+                return Ok(last);
             }
         }
 
-        log_position!("current_position | {}", last_received_position.to_string());
-        log_position!("checkpoint_position | {}", last_commit_position.to_string());
+        // positions should never get ahead of data kept by sinkers,
+        // checkpoints requested by the control api or on exit are forced
+        let force = last_checkpoint_time.is_none();
+        let mut flushed = true;
+        for sinker in self.sinkers.iter() {
+            flushed &= sinker.lock().await.flush(force).await?;
+        }
+
+        log_position!(
+            payload:serde = json!({ "current_position": last_received_position });
+            "current_position | {}",
+            last_received_position.to_string()
+        );
+        if !flushed {
+            return Ok(Instant::now());
+        }
+        log_position!(
+            payload:serde = json!({ "checkpoint_position": last_commit_position });
+            "checkpoint_position | {}",
+            last_commit_position.to_string()
+        );
 
         if !matches!(last_commit_position, Position::None) {
             self.syncer.lock().await.committed_position = last_commit_position.to_owned();
         }
 
-        self.monitor.set_counter(
-            CounterType::Timestamp,
-            last_received_position.to_timestamp(),
-        );
+        Ok(Instant::now())
+    }
 
-        Instant::now()
+    fn update_delay(&self, position: &Position) {
+        let timestamp = position.to_timestamp();
+        if timestamp == 0 {
+            return;
+        }
+        let now = Utc::now().timestamp_millis() as u64;
+        self.monitor
+            .set_counter(CounterType::Timestamp, timestamp)
+            .set_counter(CounterType::Delay, now.saturating_sub(timestamp));
+    }
+
+    /// Source commit time of the last record of each table, in millis
+    fn fetch_tb_timestamps(data: &[DtItem]) -> HashMap<(String, String), u64> {
+        let mut tb_timestamps = HashMap::new();
+        // iterate reversely so the position of each table is only parsed once
+        for item in data.iter().rev() {
+            if let DtData::Dml { row_data } = &item.dt_data {
+                let key = (row_data.schema.as_str(), row_data.tb.as_str());
+                if !tb_timestamps.contains_key(&key) {
+                    tb_timestamps.insert(key, item.position.to_timestamp());
+                }
+            }
+        }
+        tb_timestamps
+            .into_iter()
+            .filter(|(_, timestamp)| *timestamp > 0)
+            .map(|((schema, tb), timestamp)| ((schema.to_string(), tb.to_string()), timestamp))
+            .collect()
+    }
+
+    /// Delays of dropped / renamed tables are no longer reported
+    fn remove_tb_delays(&self, data: &[DdlData]) {
+        for ddl_data in data.iter() {
+            match ddl_data.ddl_type {
+                DdlType::DropTable | DdlType::RenameTable => {
+                    let (schema, tb) = ddl_data.get_schema_tb();
+                    if !tb.is_empty() {
+                        self.monitor.remove_tb_counters(&schema, &tb);
+                    }
+                }
+                DdlType::DropDatabase | DdlType::DropSchema => {
+                    let (schema, _) = ddl_data.get_schema_tb();
+                    self.monitor.remove_tb_counters(&schema, "");
+                }
+                _ => {}
+            }
+        }
+    }
+
+    fn update_tb_delays(&self, tb_timestamps: HashMap<(String, String), u64>) {
+        let now = Utc::now().timestamp_millis() as u64;
+        for ((schema, tb), timestamp) in tb_timestamps {
+            self.monitor
+                .set_tb_counter(&schema, &tb, CounterType::Timestamp, timestamp)
+                .set_tb_counter(
+                    &schema,
+                    &tb,
+                    CounterType::Delay,
+                    now.saturating_sub(timestamp),
+                );
+        }
     }
 }
@@ -101,12 +101,12 @@ impl FilterProcessor {
 
 // Implement DataProcessor trait for processor chaining
 impl DataProcessor for FilterProcessor {
-    fn process_row(&self, row: RowData) -> Option<RowData> {
-        self.process(row)
+    fn process_row(&self, row: RowData) -> anyhow::Result<Option<RowData>> {
+        Ok(self.process(row))
     }
 
-    fn process_dt_data(&self, dt_data: DtData) -> Option<DtData> {
-        FilterProcessor::process_dt_data(self, dt_data)
+    fn process_dt_data(&self, dt_data: DtData) -> anyhow::Result<Option<DtData>> {
+        Ok(FilterProcessor::process_dt_data(self, dt_data))
     }
 
     fn name(&self) -> &str {
@@ -11,6 +11,7 @@ use anyhow::bail;
 use async_trait::async_trait;
 use futures::executor::block_on;
 use serde::{Deserialize, Serialize};
+use serde_json::json;
 use tokio::sync::Mutex;
 
 use crate::{base_pipeline::BasePipeline, Pipeline};
@@ -320,9 +321,17 @@ fn refresh_appending_ack_positions(
 
 fn record_checkpoint(position_info: PositionInfo) {
     if let Some(current_position) = position_info.0 {
-        log_position!("current_position | {}", current_position.to_string());
+        log_position!(
+            payload:serde = json!({ "current_position": current_position });
+            "current_position | {}",
+            current_position.to_string()
+        );
     }
     if let Some(checkpoint_position) = position_info.1 {
-        log_position!("checkpoint_position | {}", checkpoint_position.to_string());
+        log_position!(
+            payload:serde = json!({ "checkpoint_position": checkpoint_position });
+            "checkpoint_position | {}",
+            checkpoint_position.to_string()
+        );
     }
 }
@@ -4,6 +4,10 @@ pub mod http_server_pipeline;
 pub mod lua_processor;
 pub mod processor_chain;
 pub mod router_processor;
+pub mod transform_processor;
+
+#[cfg(feature = "wasm")]
+pub mod wasm_processor;
 
 use async_trait::async_trait;
 
@@ -1,86 +1,276 @@
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::str::FromStr;
+use std::sync::{Mutex, MutexGuard};
 
+use anyhow::bail;
+use dt_common::error::Error;
 use dt_common::meta::col_value::ColValue;
+use dt_common::meta::ddl_meta::ddl_data::DdlData;
+use dt_common::meta::ddl_meta::ddl_parser::DdlParser;
+use dt_common::meta::dt_data::{DtData, DtItem};
 use dt_common::meta::row_data::RowData;
 use dt_common::meta::row_type::RowType;
 use mlua::{IntoLua, Lua};
 
+use crate::processor_chain::DataProcessor;
+
+// the global variable data_type tells scripts why they are executed
+const DATA_TYPE_LOAD: &str = "load";
+const DATA_TYPE_INIT: &str = "init";
+const DATA_TYPE_DML: &str = "dml";
+const DATA_TYPE_DDL: &str = "ddl";
+const DATA_TYPE_TEARDOWN: &str = "teardown";
+
+// optional hooks, only called if defined by the script
+const ON_INIT: &str = "on_init";
+const ON_DDL: &str = "on_ddl";
+const ON_TEARDOWN: &str = "on_teardown";
+
 pub struct LuaProcessor {
     pub lua_code: String,
+    // one lua state for the whole task, scripts can keep states in globals
+    lua: Mutex<Lua>,
+    // hooks defined by the script
+    hooks: HashSet<&'static str>,
 }
 
 impl LuaProcessor {
-    pub fn process(&self, data: Vec<RowData>) -> anyhow::Result<Vec<RowData>> {
-        let mut new_data = Vec::new();
+    pub fn new(lua_code: String) -> anyhow::Result<Self> {
         let lua = Lua::new();
+        let mut hooks = HashSet::new();
+        // scripts without hooks are only executed for rows, as they were,
+        // others are executed once to define the hooks
+        if [ON_INIT, ON_DDL, ON_TEARDOWN]
+            .iter()
+            .any(|hook| lua_code.contains(&format!("function {}", hook)))
+        {
+            Self::set_globals(
+                &lua,
+                DATA_TYPE_LOAD,
+                "",
+                "",
+                "",
+                lua.create_table()?,
+                lua.create_table()?,
+            )?;
+            lua.load(&lua_code).exec()?;
+            for hook in [ON_INIT, ON_DDL, ON_TEARDOWN] {
+                if lua
+                    .globals()
+                    .get::<_, Option<mlua::Function>>(hook)?
+                    .is_some()
+                {
+                    hooks.insert(hook);
+                }
+            }
+        }
+
+        let me = Self {
+            lua_code,
+            lua: Mutex::new(lua),
+            hooks,
+        };
+        me.call_hook(ON_INIT, DATA_TYPE_INIT)?;
+        Ok(me)
+    }
 
+    pub fn process(&self, data: Vec<RowData>) -> anyhow::Result<Vec<RowData>> {
+        let lua = self.lock_lua()?;
+        let mut new_data = Vec::new();
         for row_data in data {
-            // to lua
-            let (lua_before, blob_before) = self.col_values_to_lua_table(row_data.before, &lua)?;
-            let (lua_after, blob_after) = self.col_values_to_lua_table(row_data.after, &lua)?;
-
-            lua.globals().set("before", lua_before)?;
-            lua.globals().set("after", lua_after)?;
-            lua.globals().set("schema", row_data.schema)?;
-            lua.globals().set("tb", row_data.tb)?;
-            lua.globals()
-                .set("row_type", row_data.row_type.to_string())?;
-
-            // execute lua
-            lua.load(&self.lua_code).exec()?;
-
-            // row filtered
-            let row_type: String = lua.globals().get("row_type")?;
-            if row_type.is_empty() {
-                continue;
-            }
+            new_data.extend(self.process_with(&lua, row_data)?);
+        }
+        Ok(new_data)
+    }
+
+    pub fn process_ddl(&self, ddl_data: DdlData) -> anyhow::Result<Option<DdlData>> {
+        let lua = self.lock_lua()?;
+        self.process_ddl_with(&lua, ddl_data)
+    }
+
+    fn lock_lua(&self) -> anyhow::Result<MutexGuard<'_, Lua>> {
+        self.lua.lock().map_err(|err| {
+            Error::Unexpected(format!("failed to lock lua state, error: {}", err)).into()
+        })
+    }
 
-            // from lua
+    /// returns empty if the row is filtered in lua,
+    /// or multiple rows if extra_rows are added in lua
+    fn process_with(&self, lua: &Lua, row_data: RowData) -> anyhow::Result<Vec<RowData>> {
+        // to lua
+        let mut blob_cols = HashSet::new();
+        let lua_before = self.col_values_to_lua_table(row_data.before, &mut blob_cols, lua)?;
+        let lua_after = self.col_values_to_lua_table(row_data.after, &mut blob_cols, lua)?;
+        let row_type = row_data.row_type.to_string();
+        Self::set_globals(
+            lua,
+            DATA_TYPE_DML,
+            &row_data.schema,
+            &row_data.tb,
+            &row_type,
+            lua_before,
+            lua_after,
+        )?;
+
+        // execute lua
+        lua.load(&self.lua_code).exec()?;
+
+        let mut rows = Vec::new();
+        // row filtered if row_type was set to ""
+        let new_row_type: String = lua.globals().get("row_type")?;
+        if !new_row_type.is_empty() {
             let lua_before: mlua::Table = lua.globals().get("before")?;
             let lua_after: mlua::Table = lua.globals().get("after")?;
-            let before = self.lua_table_to_col_values(lua_before, blob_before)?;
-            let after = self.lua_table_to_col_values(lua_after, blob_after)?;
-
+            let before = self.lua_table_to_col_values(lua_before, &blob_cols)?;
+            let after = self.lua_table_to_col_values(lua_after, &blob_cols)?;
             let schema = lua.globals().get("schema")?;
             let tb = lua.globals().get("tb")?;
-            let row_type = RowType::from_str(&row_type)?;
-            let new_row_data = RowData::new(schema, tb, row_type, before, after);
-            new_data.push(new_row_data);
+            let new_row_type = RowType::from_str(&new_row_type)?;
+            rows.push(RowData::new(schema, tb, new_row_type, before, after));
         }
 
-        Ok(new_data)
+        // fan out, extra_rows = { { schema = "", tb = "", row_type = "", before = {}, after = {} } }
+        // schema / tb / row_type default to those of the input row
+        let extra_rows: mlua::Table = lua.globals().get("extra_rows")?;
+        for extra_row in extra_rows.sequence_values::<mlua::Table>() {
+            let extra_row = extra_row?;
+            let schema: Option<String> = extra_row.get("schema")?;
+            let tb: Option<String> = extra_row.get("tb")?;
+            let extra_row_type: Option<String> = extra_row.get("row_type")?;
+            let before = match extra_row.get::<_, Option<mlua::Table>>("before")? {
+                Some(lua_before) => self.lua_table_to_col_values(lua_before, &blob_cols)?,
+                None => None,
+            };
+            let after = match extra_row.get::<_, Option<mlua::Table>>("after")? {
+                Some(lua_after) => self.lua_table_to_col_values(lua_after, &blob_cols)?,
+                None => None,
+            };
+            rows.push(RowData::new(
+                schema.unwrap_or_else(|| row_data.schema.clone()),
+                tb.unwrap_or_else(|| row_data.tb.clone()),
+                RowType::from_str(&extra_row_type.unwrap_or_else(|| row_type.clone()))?,
+                before,
+                after,
+            ));
+        }
+        Ok(rows)
+    }
+
+    /// returns None if the ddl is dropped in on_ddl by setting sql to ""
+    fn process_ddl_with(&self, lua: &Lua, ddl_data: DdlData) -> anyhow::Result<Option<DdlData>> {
+        if !self.hooks.contains(ON_DDL) {
+            return Ok(Some(ddl_data));
+        }
+        let (schema, tb) = ddl_data.get_schema_tb();
+        let sql = ddl_data.to_sql();
+        Self::set_globals(
+            lua,
+            DATA_TYPE_DDL,
+            &schema,
+            &tb,
+            "",
+            lua.create_table()?,
+            lua.create_table()?,
+        )?;
+        lua.globals()
+            .set("ddl_type", ddl_data.ddl_type.to_string())?;
+        lua.globals().set("sql", sql.as_str())?;
+
+        lua.globals()
+            .get::<_, mlua::Function>(ON_DDL)?
+            .call::<_, ()>(())?;
+
+        let new_sql: Option<String> = lua.globals().get("sql")?;
+        let new_sql = new_sql.unwrap_or_default();
+        if new_sql.trim().is_empty() {
+            return Ok(None);
+        }
+        if new_sql == sql {
+            return Ok(Some(ddl_data));
+        }
+
+        // sql rewritten in lua
+        let parser = DdlParser::new(ddl_data.db_type.clone());
+        match parser.parse(&new_sql)? {
+            Some(mut new_ddl_data) => {
+                new_ddl_data.default_schema = ddl_data.default_schema;
+                new_ddl_data.query = new_sql;
+                Ok(Some(new_ddl_data))
+            }
+            None => bail! {Error::Unexpected(format!(
+                "sql rewritten by lua is not a ddl: {}",
+                new_sql
+            ))},
+        }
+    }
+
+    fn call_hook(&self, hook: &str, data_type: &str) -> anyhow::Result<()> {
+        if !self.hooks.contains(hook) {
+            return Ok(());
+        }
+        let lua = self.lock_lua()?;
+        Self::set_globals(
+            &lua,
+            data_type,
+            "",
+            "",
+            "",
+            lua.create_table()?,
+            lua.create_table()?,
+        )?;
+        lua.globals()
+            .get::<_, mlua::Function>(hook)?
+            .call::<_, ()>(())?;
+        Ok(())
+    }
+
+    fn set_globals<'lua>(
+        lua: &'lua Lua,
+        data_type: &str,
+        schema: &str,
+        tb: &str,
+        row_type: &str,
+        lua_before: mlua::Table<'lua>,
+        lua_after: mlua::Table<'lua>,
+    ) -> anyhow::Result<()> {
+        let globals = lua.globals();
+        globals.set("data_type", data_type)?;
+        globals.set("schema", schema)?;
+        globals.set("tb", tb)?;
+        globals.set("row_type", row_type)?;
+        globals.set("before", lua_before)?;
+        globals.set("after", lua_after)?;
+        globals.set("extra_rows", lua.create_table()?)?;
+        globals.set("ddl_type", mlua::Value::Nil)?;
+        globals.set("sql", mlua::Value::Nil)?;
+        Ok(())
     }
 
     fn col_values_to_lua_table<'lua>(
         &'lua self,
         col_values: Option<HashMap<String, ColValue>>,
+        blob_cols: &mut HashSet<String>,
         lua: &'lua mlua::Lua,
-    ) -> anyhow::Result<(mlua::Table<'lua>, HashMap<String, ColValue>)> {
+    ) -> anyhow::Result<mlua::Table<'lua>> {
         let lua_table = lua.create_table()?;
-        let mut blob_col_values = HashMap::new();
-
         if let Some(map) = col_values {
             for (key, col_value) in map {
-                let lua_value = match col_value {
-                    // do not support editing Blob columns in lua, pass empty values into lua
-                    ColValue::Blob(_) => {
-                        blob_col_values.insert(key.clone(), col_value);
-                        self.col_value_to_lua_value(ColValue::Blob(Vec::new()), lua)?
-                    }
-                    _ => self.col_value_to_lua_value(col_value, lua)?,
-                };
+                // blob values are passed as lua strings which are binary safe,
+                // and converted back to blob values
+                if let ColValue::Blob(_) = col_value {
+                    blob_cols.insert(key.clone());
+                }
+                let lua_value = self.col_value_to_lua_value(col_value, lua)?;
                 lua_table.set(key, lua_value)?;
             }
         }
-
-        Ok((lua_table, blob_col_values))
+        Ok(lua_table)
     }
 
     fn lua_table_to_col_values(
         &self,
         lua_table: mlua::Table,
-        blob_col_values: HashMap<String, ColValue>,
+        blob_cols: &HashSet<String>,
     ) -> anyhow::Result<Option<HashMap<String, ColValue>>> {
         if lua_table.is_empty() {
             return Ok(None);
@@ -88,25 +278,15 @@ impl LuaProcessor {
 
         let mut map = HashMap::new();
         for pair in lua_table.pairs() {
-            let pair = pair?;
-            let lua_value: mlua::Value = pair.1;
-            let col_value = self.lua_value_to_col_value(lua_value)?;
-            map.insert(pair.0, col_value);
-        }
-
-        for (col, blob_col_value) in blob_col_values {
-            // if some col was removed(set to nil) in lua, the col should not exist in map
-            // Some(col_value) = map.get(&col) means: col was NOT removed in lua
-            if let Some(col_value) = map.get(&col) {
-                // since we passed mlua::Value::NULL into lua for blob columns,
-                // *col_value == ColValue::None means: column value was not removed and not changed in lua,
-                // in this case, set the original blob_col_value back
-                if *col_value == ColValue::None {
-                    map.insert(col, blob_col_value);
+            let (col, lua_value): (String, mlua::Value) = pair?;
+            let col_value = match lua_value {
+                mlua::Value::String(v) if blob_cols.contains(&col) => {
+                    ColValue::Blob(v.as_bytes().to_vec())
                 }
-            }
+                _ => self.lua_value_to_col_value(lua_value)?,
+            };
+            map.insert(col, col_value);
         }
-
         Ok(Some(map))
     }
 
@@ -145,11 +325,11 @@ impl LuaProcessor {
 
             ColValue::RawString(_) => col_value.to_string().into_lua(lua)?,
 
-            ColValue::Json3(_)
-            | ColValue::Blob(_)
-            | ColValue::Json(_)
-            | ColValue::MongoDoc(_)
-            | ColValue::None => mlua::Value::NULL,
+            ColValue::Blob(v) => mlua::Value::String(lua.create_string(&v)?),
+
+            ColValue::Json3(_) | ColValue::Json(_) | ColValue::MongoDoc(_) | ColValue::None => {
+                mlua::Value::NULL
+            }
         };
         Ok(lua_value)
     }
@@ -159,9 +339,287 @@ impl LuaProcessor {
             mlua::Value::Boolean(v) => ColValue::Bool(v),
             mlua::Value::Integer(v) => ColValue::LongLong(v),
             mlua::Value::Number(v) => ColValue::Double(v),
-            mlua::Value::String(v) => ColValue::String(v.to_str()?.to_string()),
+            mlua::Value::String(v) => match v.to_str() {
+                Ok(str) => ColValue::String(str.to_string()),
+                // binary data created in lua
+                Err(_) => ColValue::Blob(v.as_bytes().to_vec()),
+            },
             _ => ColValue::None,
         };
         Ok(col_value)
     }
 }
+
+impl DataProcessor for LuaProcessor {
+    fn process_row(&self, row: RowData) -> anyhow::Result<Option<RowData>> {
+        // rows fanned out are only returned by process_items
+        Ok(self.process(vec![row])?.into_iter().next())
+    }
+
+    fn process_dt_data(&self, dt_data: DtData) -> anyhow::Result<Option<DtData>> {
+        match dt_data {
+            DtData::Dml { row_data } => Ok(self
+                .process_row(row_data)?
+                .map(|row_data| DtData::Dml { row_data })),
+            DtData::Ddl { ddl_data } => Ok(self
+                .process_ddl(ddl_data)?
+                .map(|ddl_data| DtData::Ddl { ddl_data })),
+            other => Ok(Some(other)),
+        }
+    }
+
+    fn process_items(&self, items: Vec<DtItem>) -> anyhow::Result<Vec<DtItem>> {
+        let lua = self.lock_lua()?;
+        let mut new_items = Vec::new();
+        for item in items {
+            match item.dt_data {
+                DtData::Dml { row_data } => {
+                    // rows fanned out share the position of the input row
+                    for row_data in self.process_with(&lua, row_data)? {
+                        new_items.push(DtItem {
+                            dt_data: DtData::Dml { row_data },
+                            position: item.position.clone(),
+                            data_origin_node: item.data_origin_node.clone(),
+                        });
+                    }
+                }
+
+                DtData::Ddl { ddl_data } => {
+                    if let Some(ddl_data) = self.process_ddl_with(&lua, ddl_data)? {
+                        new_items.push(DtItem {
+                            dt_data: DtData::Ddl { ddl_data },
+                            ..item
+                        });
+                    }
+                }
+
+                other => new_items.push(DtItem {
+                    dt_data: other,
+                    ..item
+                }),
+            }
+        }
+        Ok(new_items)
+    }
+
+    fn close(&self) -> anyhow::Result<()> {
+        self.call_hook(ON_TEARDOWN, DATA_TYPE_TEARDOWN)
+    }
+
+    fn name(&self) -> &str {
+        "LuaProcessor"
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use dt_common::{config::config_enums::DbType, meta::position::Position};
+
+    use super::*;
+
+    fn new_item(dt_data: DtData) -> DtItem {
+        DtItem {
+            dt_data,
+            position: Position::None,
+            data_origin_node: String::new(),
+        }
+    }
+
+    fn new_row(tb: &str, id: i64, blob: Vec<u8>) -> RowData {
+        let mut after = HashMap::new();
+        after.insert("id".to_string(), ColValue::LongLong(id));
+        after.insert("f_blob".to_string(), ColValue::Blob(blob));
+        RowData::new(
+            "lua_test".into(),
+            tb.into(),
+            RowType::Insert,
+            None,
+            Some(after),
+        )
+    }
+
+    #[test]
+    fn test_blob_and_fan_out() {
+        let lua_code = r#"
+            if data_type == "dml" and tb == "tb_1" then
+                -- binary safe, append a byte to the blob
+                after.f_blob = after.f_blob .. "\xff"
+                extra_rows[1] = { tb = "tb_1_copy", after = { id = after.id, f_blob = after.f_blob } }
+                extra_rows[2] = { tb = "tb_1_log", row_type = "delete", before = { id = after.id } }
+            end
+        "#;
+        let processor = LuaProcessor::new(lua_code.into()).unwrap();
+
+        let items = processor
+            .process_items(vec![new_item(DtData::Dml {
+                row_data: new_row("tb_1", 1, vec![0, 159, 146, 150]),
+            })])
+            .unwrap();
+        assert_eq!(items.len(), 3);
+
+        let rows: Vec<RowData> = items
+            .into_iter()
+            .map(|i| match i.dt_data {
+                DtData::Dml { row_data } => row_data,
+                _ => panic!("dml expected"),
+            })
+            .collect();
+        let expected_blob = ColValue::Blob(vec![0, 159, 146, 150, 255]);
+        assert_eq!(rows[0].tb, "tb_1");
+        assert_eq!(rows[0].after.as_ref().unwrap()["f_blob"], expected_blob);
+        assert_eq!(rows[1].tb, "tb_1_copy");
+        assert_eq!(rows[1].row_type, RowType::Insert);
+        assert_eq!(rows[1].after.as_ref().unwrap()["f_blob"], expected_blob);
+        assert_eq!(rows[2].tb, "tb_1_log");
+        assert_eq!(rows[2].row_type, RowType::Delete);
+        assert_eq!(
+            rows[2].before.as_ref().unwrap()["id"],
+            ColValue::LongLong(1)
+        );
+        assert!(rows[2].after.is_none());
+
+        // blob not changed in lua
+        let rows = processor
+            .process(vec![new_row("tb_2", 2, vec![1, 2])])
+            .unwrap();
+        assert_eq!(
+            rows[0].after.as_ref().unwrap()["f_blob"],
+            ColValue::Blob(vec![1, 2])
+        );
+    }
+
+    #[test]
+    fn test_persistent_state() {
+        let lua_code = r#"
+            function on_init()
+                count = 0
+            end
+
+            function on_teardown()
+                assert(count == 3)
+            end
+
+            if data_type == "dml" then
+                count = count + 1
+                after.seq = count
+            end
+        "#;
+        let processor = LuaProcessor::new(lua_code.into()).unwrap();
+
+        let rows = processor
+            .process(vec![new_row("tb_1", 1, vec![]), new_row("tb_1", 2, vec![])])
+            .unwrap();
+        assert_eq!(
+            rows[1].after.as_ref().unwrap()["seq"],
+            ColValue::LongLong(2)
+        );
+
+        // states are kept across batches
+        let rows = processor.process(vec![new_row("tb_1", 3, vec![])]).unwrap();
+        assert_eq!(
+            rows[0].after.as_ref().unwrap()["seq"],
+            ColValue::LongLong(3)
+        );
+
+        processor.close().unwrap();
+    }
+
+    #[test]
+    fn test_without_hooks() {
+        // fails if executed without a row
+        let lua_code = r#"
+            after.id = after.id + 1
+        "#;
+        let processor = LuaProcessor::new(lua_code.into()).unwrap();
+
+        let parser = DdlParser::new(DbType::Mysql);
+        let sql = "CREATE TABLE lua_test.tb_1 (id int)";
+        let mut ddl_data = parser.parse(sql).unwrap().unwrap();
+        ddl_data.query = sql.into();
+        let items = processor
+            .process_items(vec![
+                new_item(DtData::Ddl { ddl_data }),
+                new_item(DtData::Dml {
+                    row_data: new_row("tb_1", 1, vec![]),
+                }),
+            ])
+            .unwrap();
+        assert_eq!(items.len(), 2);
+        assert!(matches!(items[0].dt_data, DtData::Ddl { .. }));
+        let DtData::Dml { row_data } = &items[1].dt_data else {
+            panic!("dml expected")
+        };
+        assert_eq!(
+            row_data.after.as_ref().unwrap()["id"],
+            ColValue::LongLong(2)
+        );
+
+        processor.close().unwrap();
+    }
+
+    #[test]
+    fn test_lua_error() {
+        let lua_code = r#"
+            if data_type == "dml" and tb == "tb_bad" then
+                error("bad row")
+            end
+        "#;
+        let processor = LuaProcessor::new(lua_code.into()).unwrap();
+
+        // errors are returned instead of filtering the row
+        assert!(processor.process_row(new_row("tb_bad", 1, vec![])).is_err());
+        assert!(processor
+            .process_items(vec![
+                new_item(DtData::Dml {
+                    row_data: new_row("tb_1", 1, vec![]),
+                }),
+                new_item(DtData::Dml {
+                    row_data: new_row("tb_bad", 2, vec![]),
+                }),
+            ])
+            .is_err());
+        assert!(processor
+            .process_row(new_row("tb_1", 3, vec![]))
+            .unwrap()
+            .is_some());
+    }
+
+    #[test]
+    fn test_ddl() {
+        let lua_code = r#"
+            function on_ddl()
+                if tb == "tb_drop" then
+                    sql = ""
+                elseif tb == "tb_1" and ddl_type == "create_table" then
+                    sql = string.gsub(sql, "tb_1", "tb_1_dst")
+                end
+            end
+        "#;
+        let processor = LuaProcessor::new(lua_code.into()).unwrap();
+        let parser = DdlParser::new(DbType::Mysql);
+        let new_ddl = |sql: &str| {
+            let mut ddl_data = parser.parse(sql).unwrap().unwrap();
+            ddl_data.default_schema = "lua_test".into();
+            ddl_data.query = sql.into();
+            new_item(DtData::Ddl { ddl_data })
+        };
+
+        let items = processor
+            .process_items(vec![
+                new_ddl("CREATE TABLE lua_test.tb_1 (id int)"),
+                new_ddl("CREATE TABLE lua_test.tb_drop (id int)"),
+                new_ddl("CREATE TABLE lua_test.tb_2 (id int)"),
+            ])
+            .unwrap();
+        assert_eq!(items.len(), 2);
+
+        let tbs: Vec<String> = items
+            .iter()
+            .map(|i| match &i.dt_data {
+                DtData::Ddl { ddl_data } => ddl_data.get_schema_tb().1,
+                _ => panic!("ddl expected"),
+            })
+            .collect();
+        assert_eq!(tbs, vec!["tb_1_dst", "tb_2"]);
+    }
+}
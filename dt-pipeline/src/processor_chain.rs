@@ -1,14 +1,39 @@
-use dt_common::meta::{dt_data::DtData, row_data::RowData};
+use dt_common::meta::{
+    dt_data::{DtData, DtItem},
+    row_data::RowData,
+};
 
 /// Trait for data processors that can be chained together
 pub trait DataProcessor: Send + Sync {
     /// Process a single row of data
     /// Returns None if the row should be filtered out
-    fn process_row(&self, row: RowData) -> Option<RowData>;
+    fn process_row(&self, row: RowData) -> anyhow::Result<Option<RowData>>;
 
     /// Process DtData (supports DML, DDL, etc.)
     /// Returns None if the data should be filtered out
-    fn process_dt_data(&self, dt_data: DtData) -> Option<DtData>;
+    fn process_dt_data(&self, dt_data: DtData) -> anyhow::Result<Option<DtData>>;
+
+    /// Process a batch of items, positions and origin nodes of items are kept
+    /// Processors with costly setup (e.g. lua), fallible logic or needing
+    /// source info (e.g. derived columns) should override it
+    fn process_items(&self, items: Vec<DtItem>) -> anyhow::Result<Vec<DtItem>> {
+        let mut new_items = Vec::new();
+        for item in items {
+            if let Some(dt_data) = self.process_dt_data(item.dt_data)? {
+                new_items.push(DtItem {
+                    dt_data,
+                    position: item.position,
+                    data_origin_node: item.data_origin_node,
+                });
+            }
+        }
+        Ok(new_items)
+    }
+
+    /// Release resources when the task stops
+    fn close(&self) -> anyhow::Result<()> {
+        Ok(())
+    }
 
     /// Get processor name for logging/debugging
     fn name(&self) -> &str;
@@ -34,35 +59,63 @@ impl ProcessorChain {
 
     /// Process a row through all processors in the chain
     /// Returns None if any processor filters out the row
-    pub fn process_row(&self, mut row: RowData) -> Option<RowData> {
+    pub fn process_row(&self, mut row: RowData) -> anyhow::Result<Option<RowData>> {
         for processor in &self.processors {
-            row = processor.process_row(row)?;
+            match processor.process_row(row)? {
+                Some(new_row) => row = new_row,
+                None => return Ok(None),
+            }
         }
-        Some(row)
+        Ok(Some(row))
     }
 
     /// Process DtData through all processors in the chain
     /// Returns None if any processor filters out the data
-    pub fn process_dt_data(&self, mut dt_data: DtData) -> Option<DtData> {
+    pub fn process_dt_data(&self, mut dt_data: DtData) -> anyhow::Result<Option<DtData>> {
         for processor in &self.processors {
-            dt_data = processor.process_dt_data(dt_data)?;
+            match processor.process_dt_data(dt_data)? {
+                Some(new_dt_data) => dt_data = new_dt_data,
+                None => return Ok(None),
+            }
         }
-        Some(dt_data)
+        Ok(Some(dt_data))
     }
 
     /// Process a batch of rows through the chain
-    pub fn process_batch(&self, rows: Vec<RowData>) -> Vec<RowData> {
-        rows.into_iter()
-            .filter_map(|row| self.process_row(row))
-            .collect()
+    pub fn process_batch(&self, rows: Vec<RowData>) -> anyhow::Result<Vec<RowData>> {
+        let mut new_rows = Vec::new();
+        for row in rows {
+            new_rows.extend(self.process_row(row)?);
+        }
+        Ok(new_rows)
+    }
+
+    /// Process a batch of items through the chain, errors of any processor are returned
+    pub fn process_items(&self, mut items: Vec<DtItem>) -> anyhow::Result<Vec<DtItem>> {
+        for processor in &self.processors {
+            if items.is_empty() {
+                break;
+            }
+            items = processor.process_items(items)?;
+        }
+        Ok(items)
+    }
+
+    /// Close all processors in the chain
+    pub fn close(&self) -> anyhow::Result<()> {
+        for processor in &self.processors {
+            processor.close()?;
+        }
+        Ok(())
     }
 
     /// Process a batch of DtData through the chain
-    pub fn process_dt_data_batch(&self, items: Vec<DtData>) -> Vec<DtData> {
-        items
-            .into_iter()
-            .filter_map(|item| self.process_dt_data(item))
-            .collect()
+    pub fn process_dt_data_batch(&self, items: Vec<DtData>) -> anyhow::Result<Vec<DtData>> {
+        let mut new_items = Vec::new();
+        for item in items {
+            new_items.extend(self.process_dt_data(item)?);
+        }
+        Ok(new_items)
     }
 
     /// Get the number of processors in the chain
@@ -90,30 +143,30 @@ impl Default for ProcessorChain {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use dt_common::meta::{col_value::ColValue, row_type::RowType};
+    use dt_common::meta::{col_value::ColValue, position::Position, row_type::RowType};
     use std::collections::HashMap;
 
     // Mock processor that filters rows where id < 10
     struct IdFilterProcessor;
 
     impl DataProcessor for IdFilterProcessor {
-        fn process_row(&self, row: RowData) -> Option<RowData> {
+        fn process_row(&self, row: RowData) -> anyhow::Result<Option<RowData>> {
             if let Some(after) = &row.after {
                 if let Some(ColValue::Long(id)) = after.get("id") {
                     if *id < 10 {
-                        return None;
+                        return Ok(None);
                     }
                 }
             }
-            Some(row)
+            Ok(Some(row))
         }
 
-        fn process_dt_data(&self, dt_data: DtData) -> Option<DtData> {
+        fn process_dt_data(&self, dt_data: DtData) -> anyhow::Result<Option<DtData>> {
             match dt_data {
-                DtData::Dml { row_data } => {
-                    self.process_row(row_data).map(|r| DtData::Dml { row_data: r })
-                }
-                other => Some(other),
+                DtData::Dml { row_data } => Ok(self
+                    .process_row(row_data)?
+                    .map(|r| DtData::Dml { row_data: r })),
+                other => Ok(Some(other)),
             }
         }
 
@@ -126,19 +179,22 @@ mod tests {
     struct FlagAddingProcessor;
 
     impl DataProcessor for FlagAddingProcessor {
-        fn process_row(&self, mut row: RowData) -> Option<RowData> {
+        fn process_row(&self, mut row: RowData) -> anyhow::Result<Option<RowData>> {
             if let Some(after) = &mut row.after {
-                after.insert("processed".to_string(), ColValue::String("true".to_string()));
+                after.insert(
+                    "processed".to_string(),
+                    ColValue::String("true".to_string()),
+                );
             }
-            Some(row)
+            Ok(Some(row))
         }
 
-        fn process_dt_data(&self, dt_data: DtData) -> Option<DtData> {
+        fn process_dt_data(&self, dt_data: DtData) -> anyhow::Result<Option<DtData>> {
             match dt_data {
-                DtData::Dml { row_data } => {
-                    self.process_row(row_data).map(|r| DtData::Dml { row_data: r })
-                }
-                other => Some(other),
+                DtData::Dml { row_data } => Ok(self
+                    .process_row(row_data)?
+                    .map(|r| DtData::Dml { row_data: r })),
+                other => Ok(Some(other)),
             }
         }
 
@@ -164,7 +220,7 @@ mod tests {
             Some(after.clone()),
         );
 
-        let result = chain.process_row(row);
+        let result = chain.process_row(row).unwrap();
         assert!(result.is_some());
     }
 
@@ -188,7 +244,7 @@ mod tests {
             Some(after),
         );
 
-        assert!(chain.process_row(row).is_none());
+        assert!(chain.process_row(row).unwrap().is_none());
 
         // Should keep id >= 10
         let mut after = HashMap::new();
@@ -202,7 +258,7 @@ mod tests {
             Some(after),
         );
 
-        assert!(chain.process_row(row).is_some());
+        assert!(chain.process_row(row).unwrap().is_some());
     }
 
     #[test]
@@ -230,7 +286,7 @@ mod tests {
             Some(after),
         );
 
-        assert!(chain.process_row(row).is_none());
+        assert!(chain.process_row(row).unwrap().is_none());
 
         // Should keep id >= 10 and add processed flag
         let mut after = HashMap::new();
@@ -244,7 +300,7 @@ mod tests {
             Some(after),
         );
 
-        let result = chain.process_row(row).unwrap();
+        let result = chain.process_row(row).unwrap().unwrap();
         let after = result.after.unwrap();
         assert_eq!(after.get("id"), Some(&ColValue::Long(15)));
         assert_eq!(
@@ -253,6 +309,70 @@ mod tests {
         );
     }
 
+    // Mock processor that fails on rows of tb "bad"
+    struct FailingProcessor;
+
+    impl DataProcessor for FailingProcessor {
+        fn process_row(&self, row: RowData) -> anyhow::Result<Option<RowData>> {
+            Ok(Some(row))
+        }
+
+        fn process_dt_data(&self, dt_data: DtData) -> anyhow::Result<Option<DtData>> {
+            Ok(Some(dt_data))
+        }
+
+        fn process_items(&self, items: Vec<DtItem>) -> anyhow::Result<Vec<DtItem>> {
+            for item in items.iter() {
+                if let DtData::Dml { row_data } = &item.dt_data {
+                    if row_data.tb == "bad" {
+                        anyhow::bail!("bad row");
+                    }
+                }
+            }
+            Ok(items)
+        }
+
+        fn name(&self) -> &str {
+            "FailingProcessor"
+        }
+    }
+
+    #[test]
+    fn test_process_items() {
+        let mut chain = ProcessorChain::new();
+        chain
+            .add_processor(Box::new(IdFilterProcessor))
+            .add_processor(Box::new(FailingProcessor));
+
+        let new_item = |tb: &str, id: i32| {
+            let mut after = HashMap::new();
+            after.insert("id".to_string(), ColValue::Long(id));
+            let row_data = RowData::new(
+                "test_db".to_string(),
+                tb.to_string(),
+                RowType::Insert,
+                None,
+                Some(after),
+            );
+            DtItem {
+                dt_data: DtData::Dml { row_data },
+                position: Position::None,
+                data_origin_node: String::new(),
+            }
+        };
+
+        let items = chain
+            .process_items(vec![new_item("users", 5), new_item("users", 15)])
+            .unwrap();
+        assert_eq!(items.len(), 1);
+
+        // the bad row is filtered by IdFilterProcessor before reaching FailingProcessor
+        let items = chain.process_items(vec![new_item("bad", 5)]).unwrap();
+        assert!(items.is_empty());
+
+        assert!(chain.process_items(vec![new_item("bad", 15)]).is_err());
+    }
+
     #[test]
     fn test_batch_processing() {
         let mut chain = ProcessorChain::new();
@@ -272,7 +392,7 @@ mod tests {
             })
             .collect();
 
-        let result = chain.process_batch(rows);
+        let result = chain.process_batch(rows).unwrap();
         // Should only keep rows with id >= 10
         assert_eq!(result.len(), 10);
         for row in result {
@@ -113,12 +113,12 @@ impl RouterProcessor {
 
 // Implement DataProcessor trait for processor chaining
 impl DataProcessor for RouterProcessor {
-    fn process_row(&self, row: RowData) -> Option<RowData> {
-        Some(self.process(row))
+    fn process_row(&self, row: RowData) -> anyhow::Result<Option<RowData>> {
+        Ok(Some(self.process(row)))
     }
 
-    fn process_dt_data(&self, dt_data: DtData) -> Option<DtData> {
-        Some(RouterProcessor::process_dt_data(self, dt_data))
+    fn process_dt_data(&self, dt_data: DtData) -> anyhow::Result<Option<DtData>> {
+        Ok(Some(RouterProcessor::process_dt_data(self, dt_data)))
     }
 
     fn name(&self) -> &str {
@@ -143,6 +143,9 @@ mod tests {
             col_map: "".to_string(),
             topic_map: "*.*:default_topic".to_string(),
             content_routes: "".to_string(),
+            key_map: "".to_string(),
+            redis_map: "".to_string(),
+            mongo_map: "".to_string(),
         };
 
         let router = RdbRouter::from_config(&config, &DbType::Mysql).unwrap();
@@ -172,6 +175,9 @@ mod tests {
             col_map: "".to_string(),
             topic_map: "*.*:default_topic".to_string(),
             content_routes: "".to_string(),
+            key_map: "".to_string(),
+            redis_map: "".to_string(),
+            mongo_map: "".to_string(),
         };
 
         let router = RdbRouter::from_config(&config, &DbType::Mysql).unwrap();
@@ -231,6 +237,9 @@ mod tests {
             col_map: "".to_string(),
             topic_map: "*.*:default_topic".to_string(),
             content_routes: format!(r#"json:[{}]"#, serde_json::to_string(&content_route).unwrap()),
+            key_map: "".to_string(),
+            redis_map: "".to_string(),
+            mongo_map: "".to_string(),
         };
 
         let router = RdbRouter::from_config(&config, &DbType::Mysql).unwrap();
@@ -281,6 +290,9 @@ mod tests {
             col_map: col_map_json.to_string(),
             topic_map: "*.*:default_topic".to_string(),
             content_routes: "".to_string(),
+            key_map: "".to_string(),
+            redis_map: "".to_string(),
+            mongo_map: "".to_string(),
         };
 
         let router = RdbRouter::from_config(&config, &DbType::Mysql).unwrap();
@@ -0,0 +1,538 @@
+use std::collections::HashMap;
+
+use anyhow::bail;
+use chrono::DateTime;
+use dt_common::{
+    config::transform_config::TransformConfig,
+    error::Error,
+    meta::{
+        col_value::ColValue,
+        dt_data::{DtData, DtItem},
+        position::Position,
+        row_data::RowData,
+        row_type::RowType,
+    },
+};
+use openssl::{hash::MessageDigest, pkey::PKey, sign::Signer};
+use serde::Deserialize;
+
+use crate::processor_chain::DataProcessor;
+
+const JSON_PREFIX: &str = "json:";
+const WILDCARD: &str = "*";
+const SOURCE_TS_FORMAT: &str = "%Y-%m-%d %H:%M:%S%.3f";
+
+#[derive(Debug, Clone, Deserialize)]
+struct TransformRule {
+    db: String,
+    tb: String,
+    col: String,
+    #[serde(flatten)]
+    action: TransformAction,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+enum TransformAction {
+    // 13812345678 -> 138****5678, different values may be masked into the same,
+    // so primary / unique key columns should be hashed instead
+    Mask {
+        #[serde(default)]
+        keep_prefix: usize,
+        #[serde(default)]
+        keep_suffix: usize,
+        #[serde(default = "default_mask_char")]
+        mask_char: char,
+    },
+    Nullify,
+    // hex of HMAC-SHA256(hash_salt, value), truncated to length if length > 0
+    Hash {
+        #[serde(default)]
+        length: usize,
+    },
+    // derived columns
+    Constant {
+        value: String,
+    },
+    SourceTs {
+        #[serde(default)]
+        format: SourceTsFormat,
+    },
+    RowType,
+    SourceNode,
+    Cast {
+        to: CastType,
+    },
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(rename_all = "snake_case")]
+enum SourceTsFormat {
+    // 2024-01-01 00:00:00.000, in UTC
+    #[default]
+    Datetime,
+    Ms,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "snake_case")]
+enum CastType {
+    String,
+    Int,
+    Float,
+    Bool,
+}
+
+fn default_mask_char() -> char {
+    '*'
+}
+
+impl TransformRule {
+    fn matches(&self, schema: &str, tb: &str) -> bool {
+        (self.db == WILDCARD || self.db == schema) && (self.tb == WILDCARD || self.tb == tb)
+    }
+}
+
+/// TransformProcessor masks, hashes, casts and derives columns by per table rules
+pub struct TransformProcessor {
+    rules: Vec<TransformRule>,
+    hash_key: Option<PKey<openssl::pkey::Private>>,
+}
+
+impl TransformProcessor {
+    pub fn new(config: &TransformConfig) -> anyhow::Result<Self> {
+        let rules = Self::parse_rules(&config.transforms)?;
+
+        let need_hash_key = rules
+            .iter()
+            .any(|i| matches!(i.action, TransformAction::Hash { .. }));
+        let hash_key = if need_hash_key {
+            if config.hash_salt.is_empty() {
+                bail! {Error::ConfigError(
+                    "hash_salt is required by hash transforms".into()
+                )}
+            }
+            Some(PKey::hmac(config.hash_salt.as_bytes())?)
+        } else {
+            None
+        };
+
+        Ok(Self { rules, hash_key })
+    }
+
+    /// Apply matched rules to the row in order,
+    /// position and data_origin_node are the sources of derived columns
+    pub fn transform(
+        &self,
+        mut row_data: RowData,
+        position: &Position,
+        data_origin_node: &str,
+    ) -> anyhow::Result<RowData> {
+        let mut transformed = false;
+        for rule in self.rules.iter() {
+            if !rule.matches(&row_data.schema, &row_data.tb) {
+                continue;
+            }
+            transformed = true;
+
+            match &rule.action {
+                TransformAction::Constant { .. }
+                | TransformAction::SourceTs { .. }
+                | TransformAction::RowType
+                | TransformAction::SourceNode => {
+                    // only added to after, before values of updates / deletes are used
+                    // to locate target rows, which do not have derived values of this event
+                    let col_value =
+                        Self::derive_col_value(&rule.action, &row_data, position, data_origin_node);
+                    if let Some(after) = row_data.after.as_mut() {
+                        after.insert(rule.col.clone(), col_value);
+                    }
+                }
+
+                _ => {
+                    for col_values in [row_data.before.as_mut(), row_data.after.as_mut()]
+                        .into_iter()
+                        .flatten()
+                    {
+                        if let Some(col_value) = col_values.get_mut(&rule.col) {
+                            *col_value = self.transform_col_value(&rule.action, col_value)?;
+                        }
+                    }
+                }
+            }
+        }
+
+        if !transformed {
+            return Ok(row_data);
+        }
+        // refresh data_size
+        Ok(RowData::new(
+            row_data.schema,
+            row_data.tb,
+            row_data.row_type,
+            row_data.before,
+            row_data.after,
+        ))
+    }
+
+    fn transform_col_value(
+        &self,
+        action: &TransformAction,
+        col_value: &ColValue,
+    ) -> anyhow::Result<ColValue> {
+        if *col_value == ColValue::None {
+            return Ok(ColValue::None);
+        }
+
+        let col_value = match action {
+            TransformAction::Nullify => ColValue::None,
+
+            TransformAction::Mask {
+                keep_prefix,
+                keep_suffix,
+                mask_char,
+            } => {
+                let str = col_value.to_option_string().unwrap_or_default();
+                ColValue::String(Self::mask(&str, *keep_prefix, *keep_suffix, *mask_char))
+            }
+
+            TransformAction::Hash { length } => {
+                let bytes = match col_value {
+                    ColValue::Blob(v) | ColValue::RawString(v) | ColValue::Json(v) => v.clone(),
+                    _ => col_value
+                        .to_option_string()
+                        .unwrap_or_default()
+                        .into_bytes(),
+                };
+                let mut hash = self.hash(&bytes)?;
+                if *length > 0 && *length < hash.len() {
+                    hash.truncate(*length);
+                }
+                ColValue::String(hash)
+            }
+
+            TransformAction::Cast { to } => Self::cast(col_value, to)?,
+
+            _ => col_value.clone(),
+        };
+        Ok(col_value)
+    }
+
+    fn derive_col_value(
+        action: &TransformAction,
+        row_data: &RowData,
+        position: &Position,
+        data_origin_node: &str,
+    ) -> ColValue {
+        match action {
+            TransformAction::Constant { value } => ColValue::String(value.clone()),
+
+            TransformAction::SourceTs { format } => {
+                // snapshot rows have no source timestamp
+                let timestamp = position.to_timestamp();
+                if timestamp == 0 {
+                    return ColValue::None;
+                }
+                match format {
+                    SourceTsFormat::Ms => ColValue::LongLong(timestamp as i64),
+                    SourceTsFormat::Datetime => {
+                        match DateTime::from_timestamp_millis(timestamp as i64) {
+                            Some(datetime) => {
+                                ColValue::String(datetime.format(SOURCE_TS_FORMAT).to_string())
+                            }
+                            None => ColValue::None,
+                        }
+                    }
+                }
+            }
+
+            TransformAction::RowType => ColValue::String(row_data.row_type.to_string()),
+
+            TransformAction::SourceNode => {
+                if data_origin_node.is_empty() {
+                    ColValue::None
+                } else {
+                    ColValue::String(data_origin_node.to_string())
+                }
+            }
+
+            _ => ColValue::None,
+        }
+    }
+
+    fn mask(str: &str, keep_prefix: usize, keep_suffix: usize, mask_char: char) -> String {
+        let chars: Vec<char> = str.chars().collect();
+        // mask all if nothing would be hidden
+        if chars.len() <= keep_prefix + keep_suffix {
+            return mask_char.to_string().repeat(chars.len());
+        }
+
+        let mut result = String::with_capacity(str.len());
+        for (i, c) in chars.iter().enumerate() {
+            if i < keep_prefix || i >= chars.len() - keep_suffix {
+                result.push(*c);
+            } else {
+                result.push(mask_char);
+            }
+        }
+        result
+    }
+
+    fn hash(&self, bytes: &[u8]) -> anyhow::Result<String> {
+        let Some(hash_key) = &self.hash_key else {
+            bail! {Error::ConfigError("hash_salt is not set".into())}
+        };
+        let mut signer = Signer::new(MessageDigest::sha256(), hash_key)?;
+        signer.update(bytes)?;
+        Ok(hex::encode(signer.sign_to_vec()?))
+    }
+
+    fn cast(col_value: &ColValue, to: &CastType) -> anyhow::Result<ColValue> {
+        let str = col_value.to_option_string().unwrap_or_default();
+        let cast_err = || {
+            anyhow::anyhow!(
+                "cast transform failed, value: [{}], to type: [{:?}]",
+                str,
+                to
+            )
+        };
+
+        let col_value = match to {
+            CastType::String => ColValue::String(str.clone()),
+            CastType::Int => ColValue::LongLong(str.trim().parse().map_err(|_| cast_err())?),
+            CastType::Float => ColValue::Double(str.trim().parse().map_err(|_| cast_err())?),
+            CastType::Bool => match str.trim().to_lowercase().as_str() {
+                "true" | "1" => ColValue::Bool(true),
+                "false" | "0" => ColValue::Bool(false),
+                _ => return Err(cast_err()),
+            },
+        };
+        Ok(col_value)
+    }
+
+    fn parse_rules(config_str: &str) -> anyhow::Result<Vec<TransformRule>> {
+        if config_str.trim().is_empty() {
+            return Ok(Vec::new());
+        }
+        // transforms=json:[{"db":"test_db","tb":"users","col":"phone","type":"mask","keep_prefix":3,"keep_suffix":4}]
+        let rules: Vec<TransformRule> =
+            serde_json::from_str(config_str.trim_start_matches(JSON_PREFIX)).map_err(|err| {
+                Error::ConfigError(format!(
+                    "invalid transforms: {}, error: {}",
+                    config_str, err
+                ))
+            })?;
+        for rule in rules.iter() {
+            if rule.db.is_empty() || rule.tb.is_empty() || rule.col.is_empty() {
+                bail! {Error::ConfigError(format!(
+                    "db, tb and col are required in transforms: {}",
+                    config_str
+                ))}
+            }
+        }
+        Ok(rules)
+    }
+}
+
+impl DataProcessor for TransformProcessor {
+    fn process_row(&self, row: RowData) -> anyhow::Result<Option<RowData>> {
+        // pipeline calls process_items, no source info here
+        Ok(Some(self.transform(row, &Position::None, "")?))
+    }
+
+    fn process_dt_data(&self, dt_data: DtData) -> anyhow::Result<Option<DtData>> {
+        match dt_data {
+            DtData::Dml { row_data } => Ok(self
+                .process_row(row_data)?
+                .map(|row_data| DtData::Dml { row_data })),
+            other => Ok(Some(other)),
+        }
+    }
+
+    fn process_items(&self, items: Vec<DtItem>) -> anyhow::Result<Vec<DtItem>> {
+        let mut new_items = Vec::with_capacity(items.len());
+        for item in items {
+            let dt_data = match item.dt_data {
+                DtData::Dml { row_data } => DtData::Dml {
+                    row_data: self.transform(row_data, &item.position, &item.data_origin_node)?,
+                },
+                other => other,
+            };
+            new_items.push(DtItem { dt_data, ..item });
+        }
+        Ok(new_items)
+    }
+
+    fn name(&self) -> &str {
+        "TransformProcessor"
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn new_processor(transforms: &str) -> TransformProcessor {
+        let config = TransformConfig {
+            transforms: transforms.into(),
+            hash_salt: "secret".into(),
+        };
+        TransformProcessor::new(&config).unwrap()
+    }
+
+    fn new_row(row_type: RowType) -> RowData {
+        let mut col_values = HashMap::new();
+        col_values.insert("id".to_string(), ColValue::Long(1));
+        col_values.insert("phone".to_string(), ColValue::String("13812345678".into()));
+        col_values.insert("email".to_string(), ColValue::String("a@b.com".into()));
+        col_values.insert("age".to_string(), ColValue::String("18".into()));
+        col_values.insert("memo".to_string(), ColValue::None);
+        let (before, after) = match row_type {
+            RowType::Insert => (None, Some(col_values)),
+            RowType::Update => (Some(col_values.clone()), Some(col_values)),
+            RowType::Delete => (Some(col_values), None),
+        };
+        RowData::new("db_1".into(), "tb_1".into(), row_type, before, after)
+    }
+
+    #[test]
+    fn test_mask_nullify_hash_cast() {
+        let processor = new_processor(
+            r#"json:[{"db":"db_1","tb":"tb_1","col":"phone","type":"mask","keep_prefix":3,"keep_suffix":4},
+            {"db":"*","tb":"tb_1","col":"memo","type":"mask"},
+            {"db":"db_1","tb":"*","col":"email","type":"hash","length":16},
+            {"db":"db_1","tb":"tb_1","col":"age","type":"cast","to":"int"},
+            {"db":"db_1","tb":"tb_2","col":"id","type":"nullify"}]"#,
+        );
+
+        let row = processor
+            .transform(new_row(RowType::Update), &Position::None, "")
+            .unwrap();
+        for col_values in [row.before.unwrap(), row.after.unwrap()] {
+            assert_eq!(col_values["phone"], ColValue::String("138****5678".into()));
+            assert_eq!(col_values["memo"], ColValue::None);
+            assert_eq!(col_values["age"], ColValue::LongLong(18));
+            assert_eq!(col_values["id"], ColValue::Long(1));
+            let ColValue::String(hash) = &col_values["email"] else {
+                panic!("email should be hashed")
+            };
+            assert_eq!(hash.len(), 16);
+            assert_ne!(hash, "a@b.com");
+        }
+
+        // deterministic
+        let hash = |processor: &TransformProcessor| {
+            let row = processor
+                .transform(new_row(RowType::Insert), &Position::None, "")
+                .unwrap();
+            row.after.unwrap()["email"].clone()
+        };
+        assert_eq!(hash(&processor), hash(&processor));
+
+        // nullify
+        let processor =
+            new_processor(r#"json:[{"db":"db_1","tb":"tb_1","col":"phone","type":"nullify"}]"#);
+        let row = processor
+            .transform(new_row(RowType::Insert), &Position::None, "")
+            .unwrap();
+        assert_eq!(row.after.unwrap()["phone"], ColValue::None);
+
+        // cast failed
+        let processor = new_processor(
+            r#"json:[{"db":"db_1","tb":"tb_1","col":"phone","type":"cast","to":"bool"}]"#,
+        );
+        assert!(processor
+            .transform(new_row(RowType::Insert), &Position::None, "")
+            .is_err());
+    }
+
+    #[test]
+    fn test_mask() {
+        assert_eq!(TransformProcessor::mask("abcdef", 1, 1, '*'), "a****f");
+        assert_eq!(TransformProcessor::mask("abc", 2, 2, '#'), "###");
+        assert_eq!(TransformProcessor::mask("张三丰", 1, 0, '*'), "张**");
+        assert_eq!(TransformProcessor::mask("", 0, 0, '*'), "");
+    }
+
+    #[test]
+    fn test_derived_cols() {
+        let processor = new_processor(
+            r#"json:[{"db":"db_1","tb":"tb_1","col":"_src","type":"constant","value":"mysql_1"},
+            {"db":"db_1","tb":"tb_1","col":"_ts","type":"source_ts"},
+            {"db":"db_1","tb":"tb_1","col":"_ts_ms","type":"source_ts","format":"ms"},
+            {"db":"db_1","tb":"tb_1","col":"_op","type":"row_type"},
+            {"db":"db_1","tb":"tb_1","col":"_node","type":"source_node"}]"#,
+        );
+
+        let position = Position::MysqlCdc {
+            server_id: String::new(),
+            binlog_filename: "mysql-bin.000001".into(),
+            next_event_position: 100,
+            gtid_set: String::new(),
+            timestamp: "2024-01-02 03:04:05.678".into(),
+        };
+        let new_item = |row_type: RowType| DtItem {
+            dt_data: DtData::Dml {
+                row_data: new_row(row_type),
+            },
+            position: position.clone(),
+            data_origin_node: "node_1".into(),
+        };
+
+        let mut items = processor
+            .process_items(vec![new_item(RowType::Update), new_item(RowType::Delete)])
+            .unwrap();
+        let DtData::Dml { row_data } = items.remove(0).dt_data else {
+            panic!("dml expected")
+        };
+        let after = row_data.after.unwrap();
+        assert_eq!(after["_src"], ColValue::String("mysql_1".into()));
+        assert_eq!(after["_op"], ColValue::String("update".into()));
+        assert_eq!(after["_node"], ColValue::String("node_1".into()));
+        assert_eq!(after["_ts_ms"], ColValue::LongLong(1704164645678));
+        assert_eq!(
+            after["_ts"],
+            ColValue::String("2024-01-02 03:04:05.678".into())
+        );
+        // before values are kept
+        assert_eq!(row_data.before.unwrap().len(), 5);
+
+        let DtData::Dml { row_data } = items.remove(0).dt_data else {
+            panic!("dml expected")
+        };
+        assert!(row_data.after.is_none());
+        let before = row_data.before.unwrap();
+        assert_eq!(before.len(), 5);
+        assert!(!before.contains_key("_src"));
+
+        // snapshot rows
+        let row = processor
+            .transform(new_row(RowType::Insert), &Position::None, "")
+            .unwrap();
+        let after = row.after.unwrap();
+        assert_eq!(after["_ts"], ColValue::None);
+        assert_eq!(after["_node"], ColValue::None);
+        assert_eq!(after["_op"], ColValue::String("insert".into()));
+    }
+
+    #[test]
+    fn test_invalid_config() {
+        let config = TransformConfig {
+            transforms: r#"json:[{"db":"db_1","tb":"tb_1","col":"email","type":"hash"}]"#.into(),
+            hash_salt: String::new(),
+        };
+        assert!(TransformProcessor::new(&config).is_err());
+
+        let config = TransformConfig {
+            transforms: r#"json:[{"db":"db_1","tb":"tb_1","col":"email","type":"unknown"}]"#.into(),
+            hash_salt: String::new(),
+        };
+        assert!(TransformProcessor::new(&config).is_err());
+
+        let config = TransformConfig {
+            transforms: r#"json:[{"db":"db_1","tb":"tb_1","type":"nullify"}]"#.into(),
+            hash_salt: String::new(),
+        };
+        assert!(TransformProcessor::new(&config).is_err());
+    }
+}
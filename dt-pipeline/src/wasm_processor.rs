@@ -0,0 +1,569 @@
+use std::{
+    collections::HashMap,
+    str::FromStr,
+    sync::{Mutex, MutexGuard},
+};
+
+use anyhow::{bail, Context};
+use dt_common::{
+    error::Error,
+    log_info, log_warn,
+    meta::{
+        col_value::ColValue,
+        ddl_meta::ddl_parser::DdlParser,
+        dt_data::{DtData, DtItem},
+        row_data::RowData,
+        row_type::RowType,
+    },
+};
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use wasmtime::{
+    Config, Engine, Instance, Memory, Module, Store, StoreLimits, StoreLimitsBuilder, TypedFunc,
+};
+
+use crate::processor_chain::DataProcessor;
+
+const BLOB_KEY: &str = "blob";
+
+/// Data exchanged with wasm modules, serialized as json:
+/// {"type":"dml","schema":"db_1","tb":"tb_1","row_type":"insert","before":null,"after":{"id":1}}
+/// {"type":"ddl","schema":"db_1","tb":"tb_1","ddl_type":"create_table","sql":"CREATE TABLE ..."}
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+enum WasmData {
+    Dml {
+        schema: String,
+        tb: String,
+        row_type: String,
+        #[serde(default)]
+        before: Option<Map<String, Value>>,
+        #[serde(default)]
+        after: Option<Map<String, Value>>,
+    },
+    Ddl {
+        schema: String,
+        tb: String,
+        ddl_type: String,
+        sql: String,
+    },
+}
+
+struct WasmInstance {
+    store: Store<StoreLimits>,
+    memory: Memory,
+    alloc: TypedFunc<i32, i32>,
+    dealloc: TypedFunc<(i32, i32), ()>,
+    process: TypedFunc<(i32, i32), i64>,
+}
+
+/// WasmProcessor runs a user wasm module on dml / ddl, refer to docs/en/etl/wasm.md for the ABI
+pub struct WasmProcessor {
+    fuel: u64,
+    max_memory_mb: u64,
+    module: Module,
+    // one instance for the whole task, modules can keep states in their memory,
+    // re-created if a call fails since the instance may be left in a broken state
+    instance: Mutex<WasmInstance>,
+}
+
+impl WasmProcessor {
+    pub fn new(wasm_file: &str, fuel: u64, max_memory_mb: u64) -> anyhow::Result<Self> {
+        let bytes = std::fs::read(wasm_file)
+            .with_context(|| format!("failed to read wasm file: {}", wasm_file))?;
+        let me = Self::from_bytes(&bytes, fuel, max_memory_mb)?;
+        log_info!(
+            "wasm module loaded: {}, fuel: {}, max_memory_mb: {}",
+            wasm_file,
+            fuel,
+            max_memory_mb
+        );
+        Ok(me)
+    }
+
+    pub fn from_bytes(bytes: &[u8], fuel: u64, max_memory_mb: u64) -> anyhow::Result<Self> {
+        let mut config = Config::new();
+        config.consume_fuel(fuel > 0);
+        let engine = Engine::new(&config)?;
+        let module = Module::new(&engine, bytes)?;
+        let instance = Self::new_instance(&module, fuel, max_memory_mb)?;
+
+        Ok(Self {
+            fuel,
+            max_memory_mb,
+            module,
+            instance: Mutex::new(instance),
+        })
+    }
+
+    fn new_instance(
+        module: &Module,
+        fuel: u64,
+        max_memory_mb: u64,
+    ) -> anyhow::Result<WasmInstance> {
+        let limits = StoreLimitsBuilder::new()
+            .memory_size((max_memory_mb * 1024 * 1024) as usize)
+            .instances(1)
+            .build();
+        let mut store = Store::new(module.engine(), limits);
+        store.limiter(|limits| limits);
+        if fuel > 0 {
+            store.set_fuel(fuel)?;
+        }
+
+        // no host functions are provided, modules run in a sandbox
+        let instance = Instance::new(&mut store, module, &[])?;
+        let memory = instance
+            .get_memory(&mut store, "memory")
+            .context("wasm module should export memory")?;
+        let alloc = instance.get_typed_func(&mut store, "alloc")?;
+        let dealloc = instance.get_typed_func(&mut store, "dealloc")?;
+        let process = instance.get_typed_func(&mut store, "process")?;
+
+        Ok(WasmInstance {
+            store,
+            memory,
+            alloc,
+            dealloc,
+            process,
+        })
+    }
+
+    fn lock_instance(&self) -> anyhow::Result<MutexGuard<'_, WasmInstance>> {
+        self.instance.lock().map_err(|err| {
+            Error::Unexpected(format!("failed to lock wasm instance, error: {}", err)).into()
+        })
+    }
+
+    /// Process a batch of data in one call, returns outputs for each input,
+    /// an empty output means filtered, multiple outputs mean fanned out
+    pub fn process(&self, data: Vec<DtData>) -> anyhow::Result<Vec<Vec<DtData>>> {
+        let mut inputs = Vec::with_capacity(data.len());
+        for dt_data in data.iter() {
+            inputs.push(Self::to_wasm_data(dt_data)?);
+        }
+
+        let outputs: Vec<Vec<WasmData>> = {
+            let mut instance = self.lock_instance()?;
+            let output = match self.call(&mut instance, &serde_json::to_vec(&inputs)?) {
+                Ok(output) => output,
+                Err(err) => {
+                    // after a trap, the memory of the instance may be half written
+                    log_warn!("wasm call failed, the instance will be re-created");
+                    *instance = Self::new_instance(&self.module, self.fuel, self.max_memory_mb)?;
+                    return Err(err);
+                }
+            };
+            serde_json::from_slice(&output).map_err(|err| {
+                Error::Unexpected(format!(
+                    "invalid output of wasm module: {}, error: {}",
+                    String::from_utf8_lossy(&output),
+                    err
+                ))
+            })?
+        };
+        if outputs.len() != data.len() {
+            bail! {Error::Unexpected(format!(
+                "wasm module should return {} outputs, but got {}",
+                data.len(),
+                outputs.len()
+            ))}
+        }
+
+        let mut results = Vec::with_capacity(data.len());
+        for (dt_data, output) in data.into_iter().zip(outputs) {
+            let mut result = Vec::with_capacity(output.len());
+            for wasm_data in output {
+                result.push(Self::from_wasm_data(wasm_data, &dt_data)?);
+            }
+            results.push(result);
+        }
+        Ok(results)
+    }
+
+    fn call(&self, instance: &mut WasmInstance, input: &[u8]) -> anyhow::Result<Vec<u8>> {
+        if self.fuel > 0 {
+            instance.store.set_fuel(self.fuel)?;
+        }
+
+        let input_len = input.len() as i32;
+        let input_ptr = instance.alloc.call(&mut instance.store, input_len)?;
+        instance
+            .memory
+            .write(&mut instance.store, input_ptr as usize, input)?;
+
+        // output is packed as (ptr << 32) | len
+        let packed = instance
+            .process
+            .call(&mut instance.store, (input_ptr, input_len))
+            .context("failed to call process of wasm module")?;
+        let output_ptr = (packed >> 32) as i32;
+        let output_len = (packed & 0xffff_ffff) as i32;
+
+        let mut output = vec![0u8; output_len as usize];
+        instance
+            .memory
+            .read(&instance.store, output_ptr as usize, &mut output)?;
+        instance
+            .dealloc
+            .call(&mut instance.store, (input_ptr, input_len))?;
+        instance
+            .dealloc
+            .call(&mut instance.store, (output_ptr, output_len))?;
+        Ok(output)
+    }
+
+    fn to_wasm_data(dt_data: &DtData) -> anyhow::Result<WasmData> {
+        let wasm_data = match dt_data {
+            DtData::Dml { row_data } => WasmData::Dml {
+                schema: row_data.schema.clone(),
+                tb: row_data.tb.clone(),
+                row_type: row_data.row_type.to_string(),
+                before: row_data
+                    .before
+                    .as_ref()
+                    .map(Self::col_values_to_json)
+                    .transpose()?,
+                after: row_data
+                    .after
+                    .as_ref()
+                    .map(Self::col_values_to_json)
+                    .transpose()?,
+            },
+            DtData::Ddl { ddl_data } => {
+                let (schema, tb) = ddl_data.get_schema_tb();
+                WasmData::Ddl {
+                    schema,
+                    tb,
+                    ddl_type: ddl_data.ddl_type.to_string(),
+                    sql: ddl_data.to_sql(),
+                }
+            }
+            _ => bail! {Error::Unexpected(format!(
+                "unsupported data for wasm processor: {:?}",
+                dt_data
+            ))},
+        };
+        Ok(wasm_data)
+    }
+
+    fn from_wasm_data(wasm_data: WasmData, input: &DtData) -> anyhow::Result<DtData> {
+        match wasm_data {
+            WasmData::Dml {
+                schema,
+                tb,
+                row_type,
+                before,
+                after,
+            } => {
+                let before = before.map(Self::json_to_col_values).transpose()?;
+                let after = after.map(Self::json_to_col_values).transpose()?;
+                let row_type = RowType::from_str(&row_type)?;
+                let row_data = RowData::new(schema, tb, row_type, before, after);
+                Ok(DtData::Dml { row_data })
+            }
+
+            WasmData::Ddl { sql, .. } => {
+                let DtData::Ddl { ddl_data } = input else {
+                    bail! {Error::Unexpected(
+                        "wasm module can only return ddl for ddl".into()
+                    )}
+                };
+                if sql == ddl_data.to_sql() {
+                    return Ok(input.clone());
+                }
+
+                // sql rewritten in wasm
+                let parser = DdlParser::new(ddl_data.db_type.clone());
+                let Some(mut new_ddl_data) = parser.parse(&sql)? else {
+                    bail! {Error::Unexpected(format!(
+                        "sql rewritten by wasm is not a ddl: {}",
+                        sql
+                    ))}
+                };
+                new_ddl_data.default_schema = ddl_data.default_schema.clone();
+                new_ddl_data.query = sql;
+                Ok(DtData::Ddl {
+                    ddl_data: new_ddl_data,
+                })
+            }
+        }
+    }
+
+    fn col_values_to_json(
+        col_values: &HashMap<String, ColValue>,
+    ) -> anyhow::Result<Map<String, Value>> {
+        let mut map = Map::new();
+        for (col, col_value) in col_values {
+            let value = match col_value {
+                ColValue::None => Value::Null,
+                // bson types would be lost in json
+                ColValue::MongoDoc(_) => bail! {Error::Unexpected(format!(
+                    "wasm processor does not support mongo documents, column: {}",
+                    col
+                ))},
+                ColValue::Blob(v) => Self::blob_to_json(v),
+                ColValue::RawString(v) | ColValue::Json(v) => match std::str::from_utf8(v) {
+                    Ok(str) => Value::String(str.to_string()),
+                    Err(_) => Self::blob_to_json(v),
+                },
+                ColValue::Json3(v) => Value::String(v.to_string()),
+                // numbers / bools / strings
+                _ => serde_json::to_value(col_value).unwrap_or(Value::Null),
+            };
+            map.insert(col.clone(), value);
+        }
+        Ok(map)
+    }
+
+    // {"blob":"00ff"}, hex of binary values
+    fn blob_to_json(v: &[u8]) -> Value {
+        let mut map = Map::new();
+        map.insert(BLOB_KEY.to_string(), Value::String(hex::encode(v)));
+        Value::Object(map)
+    }
+
+    fn json_to_col_values(map: Map<String, Value>) -> anyhow::Result<HashMap<String, ColValue>> {
+        let mut col_values = HashMap::new();
+        for (col, value) in map {
+            let col_value = match value {
+                Value::Null => ColValue::None,
+                Value::Bool(v) => ColValue::Bool(v),
+                Value::Number(v) => {
+                    if let Some(v) = v.as_i64() {
+                        ColValue::LongLong(v)
+                    } else if let Some(v) = v.as_u64() {
+                        ColValue::UnsignedLongLong(v)
+                    } else {
+                        ColValue::Double(v.as_f64().unwrap_or_default())
+                    }
+                }
+                Value::String(v) => ColValue::String(v),
+                Value::Object(v) => match v.get(BLOB_KEY) {
+                    Some(Value::String(hex_str)) => ColValue::Blob(hex::decode(hex_str)?),
+                    _ => bail! {Error::Unexpected(format!(
+                        "invalid value of column: {} from wasm module",
+                        col
+                    ))},
+                },
+                Value::Array(_) => bail! {Error::Unexpected(format!(
+                    "invalid value of column: {} from wasm module",
+                    col
+                ))},
+            };
+            col_values.insert(col, col_value);
+        }
+        Ok(col_values)
+    }
+}
+
+impl DataProcessor for WasmProcessor {
+    fn process_row(&self, row: RowData) -> anyhow::Result<Option<RowData>> {
+        match self.process_dt_data(DtData::Dml { row_data: row })? {
+            Some(DtData::Dml { row_data }) => Ok(Some(row_data)),
+            _ => Ok(None),
+        }
+    }
+
+    fn process_dt_data(&self, dt_data: DtData) -> anyhow::Result<Option<DtData>> {
+        if !matches!(dt_data, DtData::Dml { .. } | DtData::Ddl { .. }) {
+            return Ok(Some(dt_data));
+        }
+        Ok(self.process(vec![dt_data])?.into_iter().flatten().next())
+    }
+
+    fn process_items(&self, items: Vec<DtItem>) -> anyhow::Result<Vec<DtItem>> {
+        // only dml / ddl are sent to wasm module, others are kept in place
+        let mut data = Vec::new();
+        for item in items.iter() {
+            if matches!(item.dt_data, DtData::Dml { .. } | DtData::Ddl { .. }) {
+                data.push(item.dt_data.clone());
+            }
+        }
+        if data.is_empty() {
+            return Ok(items);
+        }
+
+        let mut outputs = self.process(data)?.into_iter();
+        let mut new_items = Vec::with_capacity(items.len());
+        for item in items {
+            if !matches!(item.dt_data, DtData::Dml { .. } | DtData::Ddl { .. }) {
+                new_items.push(item);
+                continue;
+            }
+            // outputs share the position of the input
+            for dt_data in outputs.next().unwrap_or_default() {
+                new_items.push(DtItem {
+                    dt_data,
+                    position: item.position.clone(),
+                    data_origin_node: item.data_origin_node.clone(),
+                });
+            }
+        }
+        Ok(new_items)
+    }
+
+    fn name(&self) -> &str {
+        "WasmProcessor"
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use dt_common::meta::position::Position;
+    use serde_json::json;
+
+    use super::*;
+
+    // a module returning a constant output, with a bump allocator
+    fn const_output_wat(output: &str) -> String {
+        format!(
+            r#"(module
+              (memory (export "memory") 1)
+              (global $heap (mut i32) (i32.const 4096))
+              (data (i32.const 0) "{}")
+              (func (export "alloc") (param $len i32) (result i32)
+                (local $ptr i32)
+                (local.set $ptr (global.get $heap))
+                (global.set $heap (i32.add (global.get $heap) (local.get $len)))
+                (local.get $ptr))
+              (func (export "dealloc") (param i32 i32))
+              (func (export "process") (param i32 i32) (result i64)
+                (i64.const {})))"#,
+            output.replace('"', "\\\""),
+            output.len()
+        )
+    }
+
+    fn new_item(tb: &str, id: i64) -> DtItem {
+        let mut after = HashMap::new();
+        after.insert("id".to_string(), ColValue::LongLong(id));
+        after.insert("f_blob".to_string(), ColValue::Blob(vec![0, 255]));
+        DtItem {
+            dt_data: DtData::Dml {
+                row_data: RowData::new(
+                    "db_1".into(),
+                    tb.into(),
+                    RowType::Insert,
+                    None,
+                    Some(after),
+                ),
+            },
+            position: Position::None,
+            data_origin_node: String::new(),
+        }
+    }
+
+    #[test]
+    fn test_process_items() {
+        // first row filtered, second row fanned out
+        let output = r#"[[],[{"type":"dml","schema":"db_1","tb":"tb_2","row_type":"insert","after":{"id":2,"f_blob":{"blob":"00ff"}}},{"type":"dml","schema":"db_1","tb":"tb_2_log","row_type":"insert","after":{"id":2,"f_str":"a","f_null":null}}]]"#;
+        let wat = const_output_wat(output);
+        let processor = WasmProcessor::from_bytes(wat.as_bytes(), 1_000_000, 1).unwrap();
+
+        let items = processor
+            .process_items(vec![
+                new_item("tb_1", 1),
+                DtItem {
+                    dt_data: DtData::Commit { xid: String::new() },
+                    position: Position::None,
+                    data_origin_node: String::new(),
+                },
+                new_item("tb_2", 2),
+            ])
+            .unwrap();
+        assert_eq!(items.len(), 3);
+        assert!(matches!(items[0].dt_data, DtData::Commit { .. }));
+
+        let DtData::Dml { row_data } = &items[1].dt_data else {
+            panic!("dml expected")
+        };
+        let after = row_data.after.as_ref().unwrap();
+        assert_eq!(row_data.tb, "tb_2");
+        assert_eq!(after["id"], ColValue::LongLong(2));
+        assert_eq!(after["f_blob"], ColValue::Blob(vec![0, 255]));
+
+        let DtData::Dml { row_data } = &items[2].dt_data else {
+            panic!("dml expected")
+        };
+        let after = row_data.after.as_ref().unwrap();
+        assert_eq!(row_data.tb, "tb_2_log");
+        assert_eq!(after["f_str"], ColValue::String("a".into()));
+        assert_eq!(after["f_null"], ColValue::None);
+
+        // output count mismatch
+        assert!(processor.process_items(vec![new_item("tb_1", 1)]).is_err());
+    }
+
+    #[test]
+    fn test_limits() {
+        // fuel exhausted by an endless loop
+        let wat = r#"(module
+              (memory (export "memory") 1)
+              (func (export "alloc") (param i32) (result i32) (i32.const 0))
+              (func (export "dealloc") (param i32 i32))
+              (func (export "process") (param i32 i32) (result i64)
+                (loop $l (br $l))
+                (i64.const 0)))"#;
+        let processor = WasmProcessor::from_bytes(wat.as_bytes(), 1_000_000, 1).unwrap();
+        assert!(processor.process_items(vec![new_item("tb_1", 1)]).is_err());
+
+        // 2 pages (128k) of memory exceeds the limit of 0 mb
+        let wat = r#"(module (memory (export "memory") 2))"#;
+        assert!(WasmProcessor::from_bytes(wat.as_bytes(), 0, 0).is_err());
+    }
+
+    #[test]
+    fn test_recreate_instance() {
+        // traps on inputs longer than 200 bytes, and on all calls after that
+        let wat = r#"(module
+              (memory (export "memory") 1)
+              (global $heap (mut i32) (i32.const 4096))
+              (global $broken (mut i32) (i32.const 0))
+              (data (i32.const 0) "[[]]")
+              (func (export "alloc") (param $len i32) (result i32)
+                (local $ptr i32)
+                (local.set $ptr (global.get $heap))
+                (global.set $heap (i32.add (global.get $heap) (local.get $len)))
+                (local.get $ptr))
+              (func (export "dealloc") (param i32 i32))
+              (func (export "process") (param $ptr i32) (param $len i32) (result i64)
+                (if (i32.gt_u (local.get $len) (i32.const 200))
+                  (then (global.set $broken (i32.const 1)) (unreachable)))
+                (if (global.get $broken) (then (unreachable)))
+                (i64.const 4)))"#;
+        let processor = WasmProcessor::from_bytes(wat.as_bytes(), 0, 1).unwrap();
+        assert!(processor
+            .process_items(vec![new_item(&"t".repeat(256), 1)])
+            .is_err());
+        // the broken instance was replaced
+        assert!(processor
+            .process_items(vec![new_item("tb_1", 1)])
+            .unwrap()
+            .is_empty());
+    }
+
+    #[test]
+    fn test_col_values_json() {
+        let mut col_values = HashMap::new();
+        col_values.insert("f_1".to_string(), ColValue::Long(1));
+        col_values.insert("f_2".to_string(), ColValue::String("a".into()));
+        col_values.insert("f_3".to_string(), ColValue::Blob(vec![1, 2]));
+        col_values.insert("f_4".to_string(), ColValue::RawString(vec![0xff]));
+        col_values.insert("f_5".to_string(), ColValue::Double(1.5));
+        col_values.insert("f_6".to_string(), ColValue::None);
+
+        let json = WasmProcessor::col_values_to_json(&col_values).unwrap();
+        assert_eq!(
+            Value::Object(json.clone()),
+            json!({"f_1":1,"f_2":"a","f_3":{"blob":"0102"},"f_4":{"blob":"ff"},"f_5":1.5,"f_6":null})
+        );
+
+        let col_values = WasmProcessor::json_to_col_values(json).unwrap();
+        assert_eq!(col_values["f_1"], ColValue::LongLong(1));
+        assert_eq!(col_values["f_3"], ColValue::Blob(vec![1, 2]));
+        assert_eq!(col_values["f_4"], ColValue::Blob(vec![0xff]));
+        assert_eq!(col_values["f_5"], ColValue::Double(1.5));
+        assert_eq!(col_values["f_6"], ColValue::None);
+    }
+}
@@ -15,7 +15,8 @@ use dt_common::{
         extractor_config::ExtractorConfig,
         task_config::TaskConfig,
     },
-    meta::{dt_queue::DtQueue, syncer::Syncer},
+    control::task_control::TaskControl,
+    meta::{dt_queue::DtQueue, redis::command::key_parser::KeyParser, syncer::Syncer},
     monitor::monitor::Monitor,
     rdb_filter::RdbFilter,
     time_filter::TimeFilter,
@@ -91,6 +92,7 @@ impl Prechecker for RedisPrechecker {
             monitor: ExtractorMonitor::new(monitor).await,
             data_marker: None,
             time_filter: TimeFilter::default(),
+            task_control: Arc::new(TaskControl::default()),
         };
 
         let mut psyncer = RedisPsyncExtractor {
@@ -107,6 +109,10 @@ impl Prechecker for RedisPrechecker {
             keepalive_interval_secs: 0,
             heartbeat_interval_secs: 0,
             heartbeat_key: String::new(),
+            key_parser: KeyParser::new(),
+            node_address: String::new(),
+            node_positions: None,
+            heartbeat_hash_tag: String::new(),
         };
 
         if let Err(error) = psyncer.start_psync().await {
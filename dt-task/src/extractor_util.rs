@@ -1,5 +1,6 @@
 use std::{
     cmp,
+    collections::BTreeMap,
     str::FromStr,
     sync::{atomic::AtomicBool, Arc},
 };
@@ -12,9 +13,13 @@ use dt_common::{
         extractor_config::ExtractorConfig,
         task_config::TaskConfig,
     },
+    control::task_control::TaskControl,
+    log_warn,
     meta::{
-        avro::avro_converter::AvroConverter, mongo::mongo_cdc_source::MongoCdcSource,
-        pg::pg_meta_manager::PgMetaManager, redis::redis_statistic_type::RedisStatisticType,
+        avro::avro_converter::AvroConverter,
+        mongo::mongo_cdc_source::MongoCdcSource,
+        pg::pg_meta_manager::PgMetaManager,
+        redis::{command::key_parser::KeyParser, redis_statistic_type::RedisStatisticType},
         syncer::Syncer,
     },
     meta::{
@@ -31,6 +36,7 @@ use dt_connector::{
     extractor::{
         base_extractor::BaseExtractor,
         extractor_monitor::ExtractorMonitor,
+        file::file_extractor::FileExtractor,
         foxlake::foxlake_s3_extractor::FoxlakeS3Extractor,
         kafka::kafka_extractor::KafkaExtractor,
         mongo::{
@@ -47,7 +53,9 @@ use dt_connector::{
             pg_snapshot_extractor::PgSnapshotExtractor, pg_struct_extractor::PgStructExtractor,
         },
         redis::{
-            redis_client::RedisClient, redis_psync_extractor::RedisPsyncExtractor,
+            redis_check_extractor::RedisCheckExtractor, redis_client::RedisClient,
+            redis_cluster_extractor::RedisClusterExtractor,
+            redis_psync_extractor::RedisPsyncExtractor,
             redis_reshard_extractor::RedisReshardExtractor,
             redis_scan_extractor::RedisScanExtractor,
             redis_snapshot_file_extractor::RedisSnapshotFileExtractor,
@@ -74,6 +82,7 @@ impl ExtractorUtil {
         router: RdbRouter,
         snapshot_resumer: SnapshotResumer,
         cdc_resumer: CdcResumer,
+        task_control: Arc<TaskControl>,
     ) -> anyhow::Result<Box<dyn Extractor + Send>> {
         let mut base_extractor = BaseExtractor {
             buffer,
@@ -82,6 +91,7 @@ impl ExtractorUtil {
             monitor: ExtractorMonitor::new(monitor).await,
             data_marker,
             time_filter: TimeFilter::default(),
+            task_control,
         };
 
         let enable_sqlx_log = TaskUtil::check_enable_sqlx_log(&config.runtime.log_level);
@@ -278,12 +288,14 @@ impl ExtractorUtil {
                 app_name,
                 db,
                 tb,
+                parallel_size,
             } => {
                 let mongo_client = TaskUtil::create_mongo_client(&url, &app_name).await?;
                 let extractor = MongoSnapshotExtractor {
                     resumer: snapshot_resumer,
                     db,
                     tb,
+                    parallel_size,
                     mongo_client,
                     base_extractor,
                 };
@@ -299,12 +311,23 @@ impl ExtractorUtil {
                 heartbeat_interval_secs,
                 heartbeat_tb,
             } => {
+                // mongo ddls are db commands, which can only be replayed by mongo sinkers
+                let mut filter = filter;
+                if config.sinker_basic.db_type != DbType::Mongo && !filter.filter_all_ddl() {
+                    log_warn!(
+                        "ddls from mongo are ignored since the sinker is {}",
+                        config.sinker_basic.db_type
+                    );
+                    filter.do_ddls.clear();
+                }
+
                 let mongo_client = TaskUtil::create_mongo_client(&url, &app_name).await?;
                 let extractor = MongoCdcExtractor {
                     filter,
                     resume_token,
                     start_timestamp,
                     source: MongoCdcSource::from_str(&source)?,
+                    url,
                     mongo_client,
                     app_name,
                     base_extractor,
@@ -363,12 +386,33 @@ impl ExtractorUtil {
                 Box::new(extractor)
             }
 
-            ExtractorConfig::RedisSnapshot { url, repl_port } => {
+            ExtractorConfig::RedisSnapshot {
+                url,
+                repl_port,
+                is_cluster,
+            } => {
+                if is_cluster {
+                    let extractor = RedisClusterExtractor {
+                        base_extractor,
+                        url,
+                        repl_port,
+                        keepalive_interval_secs: 0,
+                        heartbeat_interval_secs: 0,
+                        heartbeat_key: String::new(),
+                        syncer,
+                        filter,
+                        resumer: cdc_resumer,
+                        extract_type: ExtractType::Snapshot,
+                    };
+                    return Ok(Box::new(extractor));
+                }
+
                 let extractor = RedisPsyncExtractor {
                     conn: RedisClient::new(&url).await?,
                     syncer,
                     repl_port,
                     filter,
+                    key_parser: KeyParser::new(),
                     resumer: cdc_resumer,
                     base_extractor,
                     extract_type: ExtractType::Snapshot,
@@ -378,6 +422,9 @@ impl ExtractorUtil {
                     keepalive_interval_secs: 0,
                     heartbeat_interval_secs: 0,
                     heartbeat_key: String::new(),
+                    node_address: String::new(),
+                    node_positions: None,
+                    heartbeat_hash_tag: String::new(),
                 };
                 Box::new(extractor)
             }
@@ -386,6 +433,7 @@ impl ExtractorUtil {
                 let extractor = RedisSnapshotFileExtractor {
                     file_path,
                     filter,
+                    key_parser: KeyParser::new(),
                     base_extractor,
                 };
                 Box::new(extractor)
@@ -417,7 +465,24 @@ impl ExtractorUtil {
                 keepalive_interval_secs,
                 heartbeat_interval_secs,
                 heartbeat_key,
+                is_cluster,
             } => {
+                if is_cluster {
+                    let extractor = RedisClusterExtractor {
+                        base_extractor,
+                        url,
+                        repl_port,
+                        keepalive_interval_secs,
+                        heartbeat_interval_secs,
+                        heartbeat_key,
+                        syncer,
+                        filter,
+                        resumer: cdc_resumer,
+                        extract_type: ExtractType::Cdc,
+                    };
+                    return Ok(Box::new(extractor));
+                }
+
                 let extractor = RedisPsyncExtractor {
                     conn: RedisClient::new(&url).await?,
                     repl_id,
@@ -429,9 +494,13 @@ impl ExtractorUtil {
                     repl_port,
                     now_db_id,
                     filter,
+                    key_parser: KeyParser::new(),
                     resumer: cdc_resumer,
                     base_extractor,
                     extract_type: ExtractType::Cdc,
+                    node_address: String::new(),
+                    node_positions: None,
+                    heartbeat_hash_tag: String::new(),
                 };
                 Box::new(extractor)
             }
@@ -443,12 +512,30 @@ impl ExtractorUtil {
                 keepalive_interval_secs,
                 heartbeat_interval_secs,
                 heartbeat_key,
+                is_cluster,
             } => {
+                if is_cluster {
+                    let extractor = RedisClusterExtractor {
+                        base_extractor,
+                        url,
+                        repl_port,
+                        keepalive_interval_secs,
+                        heartbeat_interval_secs,
+                        heartbeat_key,
+                        syncer,
+                        filter,
+                        resumer: cdc_resumer,
+                        extract_type: ExtractType::SnapshotAndCdc,
+                    };
+                    return Ok(Box::new(extractor));
+                }
+
                 let extractor = RedisPsyncExtractor {
                     conn: RedisClient::new(&url).await?,
                     syncer,
                     repl_port,
                     filter,
+                    key_parser: KeyParser::new(),
                     resumer: cdc_resumer,
                     base_extractor,
                     extract_type: ExtractType::SnapshotAndCdc,
@@ -458,6 +545,24 @@ impl ExtractorUtil {
                     keepalive_interval_secs,
                     heartbeat_interval_secs,
                     heartbeat_key,
+                    node_address: String::new(),
+                    node_positions: None,
+                    heartbeat_hash_tag: String::new(),
+                };
+                Box::new(extractor)
+            }
+
+            ExtractorConfig::RedisCheck {
+                url,
+                check_log_dir,
+                batch_size,
+            } => {
+                let conn = RedisUtil::create_redis_conn(&url).await?;
+                let extractor = RedisCheckExtractor {
+                    conn,
+                    check_log_dir,
+                    batch_size,
+                    base_extractor,
                 };
                 Box::new(extractor)
             }
@@ -473,20 +578,23 @@ impl ExtractorUtil {
             ExtractorConfig::Kafka {
                 url,
                 group,
-                topic,
+                topics,
                 partition,
                 offset,
                 ack_interval_secs,
+                auto_offset_reset,
             } => {
                 let meta_manager = TaskUtil::create_rdb_meta_manager(config).await?;
                 let avro_converter = AvroConverter::new(meta_manager, false);
                 let extractor = KafkaExtractor {
                     url,
                     group,
-                    topic,
+                    topics,
                     partition,
                     offset,
                     ack_interval_secs,
+                    auto_offset_reset,
+                    partition_offsets: BTreeMap::new(),
                     avro_converter,
                     syncer,
                     resumer: cdc_resumer,
@@ -514,6 +622,37 @@ impl ExtractorUtil {
                 };
                 Box::new(extractor)
             }
+
+            ExtractorConfig::File {
+                file_path,
+                file_format,
+                schema,
+                tb,
+                csv_delimiter,
+                csv_has_header,
+                s3_config,
+            } => {
+                let s3_client = if s3_config.bucket.is_empty() {
+                    None
+                } else {
+                    Some(TaskUtil::create_s3_client(&s3_config))
+                };
+                let meta_manager = TaskUtil::create_rdb_meta_manager(config).await?;
+                let extractor = FileExtractor {
+                    base_extractor,
+                    resumer: snapshot_resumer,
+                    file_path,
+                    file_format,
+                    schema,
+                    tb,
+                    csv_delimiter,
+                    csv_has_header,
+                    s3_client,
+                    s3_config,
+                    meta_manager,
+                };
+                Box::new(extractor)
+            }
         };
         Ok(extractor)
     }
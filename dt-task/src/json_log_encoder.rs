@@ -0,0 +1,105 @@
+use anyhow::bail;
+use log::{kv::Key, Record};
+use log4rs::{
+    config::{Config, Deserialize, Deserializers, RawConfig},
+    encode::{Encode, Write},
+};
+use serde_yaml::{Mapping, Value};
+
+use dt_common::{
+    error::Error,
+    log_event::{LogEvent, PAYLOAD_KEY, SCHEMA_KEY, TB_KEY},
+};
+
+const JSON_LOG_ENCODER: &str = "json_event";
+
+/// Encodes every log record as a LogEvent in a single line
+#[derive(Debug)]
+pub struct JsonLogEncoder {
+    task_id: String,
+}
+
+impl Encode for JsonLogEncoder {
+    fn encode(&self, w: &mut dyn Write, record: &Record) -> anyhow::Result<()> {
+        let mut event = LogEvent::new(record.level().as_str(), &self.task_id, record.target());
+        let key_values = record.key_values();
+        if let Some(schema) = key_values.get(Key::from_str(SCHEMA_KEY)) {
+            event.schema = schema.to_string();
+        }
+        if let Some(tb) = key_values.get(Key::from_str(TB_KEY)) {
+            event.tb = tb.to_string();
+        }
+        event.payload = match key_values.get(Key::from_str(PAYLOAD_KEY)) {
+            Some(payload) => serde_json::to_value(&payload)?,
+            None => LogEvent::build_payload(&record.args().to_string()),
+        };
+
+        serde_json::to_writer(&mut *w, &event)?;
+        w.write_all(b"\n")?;
+        Ok(())
+    }
+}
+
+#[derive(serde::Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct JsonLogEncoderConfig {
+    #[serde(default)]
+    task_id: String,
+}
+
+pub struct JsonLogEncoderDeserializer;
+
+impl Deserialize for JsonLogEncoderDeserializer {
+    type Trait = dyn Encode;
+    type Config = JsonLogEncoderConfig;
+
+    fn deserialize(
+        &self,
+        config: JsonLogEncoderConfig,
+        _: &Deserializers,
+    ) -> anyhow::Result<Box<dyn Encode>> {
+        Ok(Box::new(JsonLogEncoder {
+            task_id: config.task_id,
+        }))
+    }
+}
+
+impl JsonLogEncoder {
+    /// Inits log4rs with the encoders of all appenders replaced by JsonLogEncoder,
+    /// so the log files and paths are the same as text log format
+    pub fn init_log4rs(config_str: &str, task_id: &str) -> anyhow::Result<()> {
+        let mut encoder = Mapping::new();
+        encoder.insert("kind".into(), JSON_LOG_ENCODER.into());
+        encoder.insert("task_id".into(), task_id.into());
+
+        let mut raw_config: Value = serde_yaml::from_str(config_str)?;
+        if let Some(appenders) = raw_config
+            .get_mut("appenders")
+            .and_then(|v| v.as_mapping_mut())
+        {
+            for (_, appender) in appenders.iter_mut() {
+                if let Some(appender) = appender.as_mapping_mut() {
+                    appender.insert("encoder".into(), Value::Mapping(encoder.clone()));
+                }
+            }
+        }
+
+        let raw_config: RawConfig = serde_yaml::from_value(raw_config)?;
+        let mut deserializers = Deserializers::default();
+        deserializers.insert(JSON_LOG_ENCODER, JsonLogEncoderDeserializer);
+        let (appenders, errors) = raw_config.appenders_lossy(&deserializers);
+        if !errors.is_empty() {
+            bail! {Error::ConfigError(format!(
+                "invalid log4rs appenders: {:?}",
+                errors
+            ))}
+        }
+
+        let config = Config::builder()
+            .appenders(appenders)
+            .loggers(raw_config.loggers())
+            .build(raw_config.root())?;
+        log4rs::init_config(config)?;
+        Ok(())
+    }
+}
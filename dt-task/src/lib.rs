@@ -1,7 +1,9 @@
 #![allow(clippy::too_many_arguments)]
 
 pub mod extractor_util;
+pub mod json_log_encoder;
 pub mod parallelizer_util;
+pub mod processor_util;
 pub mod sinker_util;
 pub mod task_runner;
 pub mod task_util;
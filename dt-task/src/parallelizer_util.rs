@@ -3,8 +3,6 @@ use std::{
     sync::Arc,
 };
 
-use ratelimit::Ratelimiter;
-
 use super::task_util::TaskUtil;
 use dt_common::{
     config::{
@@ -12,6 +10,7 @@ use dt_common::{
         sinker_config::SinkerConfig,
         task_config::TaskConfig,
     },
+    control::task_control::TaskControl,
     meta::redis::command::key_parser::KeyParser,
     monitor::monitor::Monitor,
     utils::redis_util::RedisUtil,
@@ -31,14 +30,15 @@ impl ParallelizerUtil {
     pub async fn create_parallelizer(
         config: &TaskConfig,
         monitor: Arc<Monitor>,
-        rps_limiter: Option<Ratelimiter>,
+        task_control: Arc<TaskControl>,
     ) -> anyhow::Result<Box<dyn Parallelizer + Send + Sync>> {
         let parallel_size = config.parallelizer.parallel_size;
         let parallel_type = &config.parallelizer.parallel_type;
         let base_parallelizer = BaseParallelizer {
             poped_data: VecDeque::new(),
             monitor,
-            rps_limiter,
+            rps_limiter: None,
+            task_control,
         };
 
         let parallelizer: Box<dyn Parallelizer + Send + Sync> = match parallel_type {
@@ -100,7 +100,9 @@ impl ParallelizerUtil {
 
             ParallelType::Redis => {
                 let mut slot_node_map = HashMap::new();
-                if let SinkerConfig::Redis { is_cluster, .. } = config.sinker {
+                if let SinkerConfig::Redis { is_cluster, .. }
+                | SinkerConfig::RedisCheck { is_cluster, .. } = config.sinker
+                {
                     let mut conn = RedisUtil::create_redis_conn(&config.sinker_basic.url).await?;
                     if is_cluster {
                         let nodes = RedisUtil::get_cluster_master_nodes(&mut conn)?;
@@ -0,0 +1,97 @@
+#[cfg(not(feature = "wasm"))]
+use dt_common::error::Error;
+use dt_common::{
+    config::{processor_config::ProcessorItemConfig, task_config::TaskConfig},
+    log_info,
+    rdb_filter::RdbFilter,
+};
+use dt_connector::rdb_router::RdbRouter;
+#[cfg(feature = "wasm")]
+use dt_pipeline::wasm_processor::WasmProcessor;
+use dt_pipeline::{
+    filter_processor::FilterProcessor, lua_processor::LuaProcessor,
+    processor_chain::ProcessorChain, router_processor::RouterProcessor,
+    transform_processor::TransformProcessor,
+};
+
+pub struct ProcessorUtil {}
+
+impl ProcessorUtil {
+    pub fn create_processor_chain(config: &TaskConfig) -> anyhow::Result<ProcessorChain> {
+        let mut chain = ProcessorChain::new();
+        let processor_config = match &config.processor {
+            Some(processor_config) => processor_config,
+            None => return Ok(chain),
+        };
+
+        // compatible with [processor] which only has lua_code_file
+        if processor_config.processors.is_empty() {
+            if !processor_config.lua_code.is_empty() {
+                chain.add_processor(Box::new(LuaProcessor::new(
+                    processor_config.lua_code.clone(),
+                )?));
+            }
+            return Ok(chain);
+        }
+
+        // data reaching pipeline has been routed, but names are parsed the same way as [filter]
+        let db_type = &config.extractor_basic.db_type;
+        for item in processor_config.processors.iter() {
+            match item {
+                ProcessorItemConfig::Filter { filter, .. } => {
+                    let filter = RdbFilter::from_config(filter, db_type)?;
+                    chain.add_processor(Box::new(FilterProcessor::new(filter)));
+                }
+
+                ProcessorItemConfig::Router { router, .. } => {
+                    let router = RdbRouter::from_config(router, db_type)?;
+                    chain.add_processor(Box::new(RouterProcessor::new(router)));
+                }
+
+                ProcessorItemConfig::Lua { lua_code, .. } => {
+                    chain.add_processor(Box::new(LuaProcessor::new(lua_code.clone())?));
+                }
+
+                ProcessorItemConfig::Transform { transform, .. } => {
+                    chain.add_processor(Box::new(TransformProcessor::new(transform)?));
+                }
+
+                #[cfg(feature = "wasm")]
+                ProcessorItemConfig::Wasm {
+                    wasm_file,
+                    fuel,
+                    max_memory_mb,
+                    ..
+                } => {
+                    chain.add_processor(Box::new(WasmProcessor::new(
+                        wasm_file,
+                        *fuel,
+                        *max_memory_mb,
+                    )?));
+                }
+
+                #[cfg(not(feature = "wasm"))]
+                ProcessorItemConfig::Wasm { name, .. } => {
+                    anyhow::bail! {Error::ConfigError(format!(
+                        "processor [{}] requires ape-dts built with feature: wasm",
+                        name
+                    ))}
+                }
+            }
+        }
+
+        let names: Vec<&str> = processor_config
+            .processors
+            .iter()
+            .map(|i| match i {
+                ProcessorItemConfig::Filter { name, .. }
+                | ProcessorItemConfig::Router { name, .. }
+                | ProcessorItemConfig::Lua { name, .. }
+                | ProcessorItemConfig::Transform { name, .. }
+                | ProcessorItemConfig::Wasm { name, .. } => name.as_str(),
+            })
+            .collect();
+        log_info!("processor chain: [{}]", names.join(", "));
+        Ok(chain)
+    }
+}
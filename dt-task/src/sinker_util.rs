@@ -1,4 +1,4 @@
-use std::{str::FromStr, sync::Arc};
+use std::{collections::HashMap, str::FromStr, sync::Arc};
 
 use anyhow::Context;
 use kafka::producer::{Producer, RequiredAcks};
@@ -17,6 +17,7 @@ use dt_common::{
         avro::avro_converter::AvroConverter,
         mysql::mysql_meta_manager::MysqlMetaManager,
         pg::pg_meta_manager::PgMetaManager,
+        rdb_meta_manager::RdbMetaManager,
         redis::{redis_statistic_type::RedisStatisticType, redis_write_method::RedisWriteMethod},
     },
     monitor::monitor::Monitor,
@@ -34,19 +35,24 @@ use dt_connector::{
             clickhouse_sinker::ClickhouseSinker, clickhouse_struct_sinker::ClickhouseStructSinker,
         },
         dummy_sinker::DummySinker,
+        file::{file_encoder::FileEncoder, file_sinker::FileSinker},
         foxlake::{
             foxlake_merger::FoxlakeMerger, foxlake_pusher::FoxlakePusher,
             foxlake_sinker::FoxlakeSinker, foxlake_struct_sinker::FoxlakeStructSinker,
             orc_sequencer::OrcSequencer,
         },
-        kafka::kafka_sinker::KafkaSinker,
+        kafka::{kafka_partitioner::KafkaPartitioner, kafka_sinker::KafkaSinker},
         mongo::{mongo_checker::MongoChecker, mongo_sinker::MongoSinker},
         mysql::{
             mysql_checker::MysqlChecker, mysql_sinker::MysqlSinker,
             mysql_struct_sinker::MysqlStructSinker,
         },
         pg::{pg_checker::PgChecker, pg_sinker::PgSinker, pg_struct_sinker::PgStructSinker},
-        redis::{redis_sinker::RedisSinker, redis_statistic_sinker::RedisStatisticSinker},
+        redis::{
+            redis_checker::RedisChecker, redis_sinker::RedisSinker,
+            redis_statistic_report::RedisStatisticReport,
+            redis_statistic_sinker::RedisStatisticSinker,
+        },
         sql_sinker::SqlSinker,
         starrocks::{
             starrocks_sinker::StarRocksSinker, starrocks_struct_sinker::StarrocksStructSinker,
@@ -262,6 +268,11 @@ impl SinkerUtil {
                 ack_timeout_secs,
                 required_acks,
                 with_field_defs,
+                partition_strategy,
+                partition_cols,
+                create_topics,
+                topic_partitions,
+                topic_replication_factor,
             } => {
                 let router = RdbRouter::from_config(
                     &task_config.router,
@@ -272,6 +283,31 @@ impl SinkerUtil {
                 let meta_manager = ExtractorUtil::get_extractor_meta_manager(task_config).await?;
                 let avro_converter = AvroConverter::new(meta_manager, with_field_defs);
 
+                let mut topics: Vec<String> = router.topic_map.values().cloned().collect();
+                topics.sort();
+                topics.dedup();
+                if create_topics {
+                    TaskUtil::create_kafka_topics(
+                        &url,
+                        &topics,
+                        topic_partitions,
+                        topic_replication_factor,
+                    )
+                    .await?;
+                }
+                // partition counts are only needed if partitions are not decided by kafka producer
+                let partition_counts = if partition_strategy == KafkaPartitionStrategy::Default {
+                    HashMap::new()
+                } else {
+                    TaskUtil::get_kafka_topic_partitions(&url, &topics).await?
+                };
+                let partitioner = KafkaPartitioner::new(
+                    partition_strategy,
+                    &partition_cols,
+                    &task_config.extractor_basic.db_type,
+                    partition_counts,
+                )?;
+
                 let brokers = vec![url.to_string()];
                 let acks = match required_acks.as_str() {
                     "all" => RequiredAcks::All,
@@ -294,6 +330,7 @@ impl SinkerUtil {
                         router: router.clone(),
                         producer,
                         avro_converter: avro_converter.clone(),
+                        partitioner: partitioner.clone(),
                         monitor: monitor.clone(),
                     };
                     sub_sinkers.push(Arc::new(async_mutex::Mutex::new(Box::new(sinker))));
@@ -351,6 +388,11 @@ impl SinkerUtil {
                 let mut conn = RedisUtil::create_redis_conn(&url).await?;
                 let version = RedisUtil::get_redis_version(&mut conn)?;
                 let method = RedisWriteMethod::from_str(&method)?;
+                // rows are mapped to redis keys by [router] redis_map
+                let router = RdbRouter::from_config(
+                    &task_config.router,
+                    &task_config.extractor_basic.db_type,
+                )?;
 
                 if is_cluster {
                     let url_info = Url::parse(&url)?;
@@ -376,6 +418,7 @@ impl SinkerUtil {
                             monitor: monitor.clone(),
                             data_marker: data_marker.clone(),
                             key_parser: KeyParser::new(),
+                            router: router.clone(),
                         };
                         sub_sinkers.push(Arc::new(async_mutex::Mutex::new(Box::new(sinker))));
                     }
@@ -393,6 +436,48 @@ impl SinkerUtil {
                             monitor: monitor.clone(),
                             data_marker: data_marker.clone(),
                             key_parser: KeyParser::new(),
+                            router: router.clone(),
+                        };
+                        sub_sinkers.push(Arc::new(async_mutex::Mutex::new(Box::new(sinker))));
+                    }
+                }
+            }
+
+            SinkerConfig::RedisCheck {
+                url,
+                batch_size,
+                is_cluster,
+                compare_digest,
+                ..
+            } => {
+                if is_cluster {
+                    let url_info = Url::parse(&url)?;
+                    let username = url_info.username();
+                    let password = url_info.password().unwrap_or("").to_string();
+
+                    let mut conn = RedisUtil::create_redis_conn(&url).await?;
+                    let nodes = RedisUtil::get_cluster_master_nodes(&mut conn)?;
+                    for node in nodes.iter() {
+                        let new_url = format!("redis://{}:{}@{}", username, password, node.address);
+                        let conn = RedisUtil::create_redis_conn(&new_url).await?;
+                        let sinker = RedisChecker {
+                            cluster_node: Some(node.clone()),
+                            conn,
+                            batch_size,
+                            compare_digest,
+                            monitor: monitor.clone(),
+                        };
+                        sub_sinkers.push(Arc::new(async_mutex::Mutex::new(Box::new(sinker))));
+                    }
+                } else {
+                    for _ in 0..parallel_size {
+                        let conn = RedisUtil::create_redis_conn(&url).await?;
+                        let sinker = RedisChecker {
+                            cluster_node: None,
+                            conn,
+                            batch_size,
+                            compare_digest,
+                            monitor: monitor.clone(),
                         };
                         sub_sinkers.push(Arc::new(async_mutex::Mutex::new(Box::new(sinker))));
                     }
@@ -403,15 +488,29 @@ impl SinkerUtil {
                 statistic_type,
                 data_size_threshold,
                 freq_threshold,
-                ..
+                statistic_log_dir,
+                top_n,
+                prefix_delimiter,
             } => {
                 let statistic_type = RedisStatisticType::from_str(&statistic_type)?;
+                let statistic_log_dir = if statistic_log_dir.is_empty() {
+                    format!("{}/statistic", task_config.runtime.log_dir)
+                } else {
+                    statistic_log_dir
+                };
+                let report = Arc::new(std::sync::Mutex::new(RedisStatisticReport::new(
+                    statistic_type.clone(),
+                    top_n,
+                    &prefix_delimiter,
+                )));
                 for _ in 0..parallel_size {
                     let sinker = RedisStatisticSinker {
                         statistic_type: statistic_type.clone(),
                         data_size_threshold,
                         freq_threshold,
                         monitor: monitor.clone(),
+                        report: report.clone(),
+                        statistic_log_dir: statistic_log_dir.clone(),
                     };
                     sub_sinkers.push(Arc::new(async_mutex::Mutex::new(Box::new(sinker))));
                 }
@@ -428,6 +527,7 @@ impl SinkerUtil {
                 batch_size,
                 stream_load_url,
             } => {
+                let router = create_router!(task_config, Mysql);
                 for _ in 0..parallel_size {
                     let url_info = Url::parse(&stream_load_url)?;
                     let host = url_info.host_str().unwrap().to_string();
@@ -461,6 +561,7 @@ impl SinkerUtil {
                         password,
                         batch_size,
                         meta_manager,
+                        router: router.clone(),
                         monitor: monitor.clone(),
                         sync_timestamp: Utc::now().timestamp_millis(),
                         hard_delete: false,
@@ -501,6 +602,7 @@ impl SinkerUtil {
             }
 
             SinkerConfig::ClickHouse { url, batch_size } => {
+                let router = create_router!(task_config, ClickHouse);
                 for _ in 0..parallel_size {
                     let url_info = Url::parse(&url)?;
                     let host = url_info.host_str().unwrap().to_string();
@@ -519,6 +621,7 @@ impl SinkerUtil {
                         username,
                         password,
                         batch_size,
+                        router: router.clone(),
                         monitor: monitor.clone(),
                         sync_timestamp: Utc::now().timestamp_millis(),
                     };
@@ -581,7 +684,11 @@ impl SinkerUtil {
                 s3_config,
                 engine,
             } => {
-                let router = create_router!(task_config, Mysql);
+                let router = RdbRouter::from_config(
+                    &task_config.router,
+                    // use the db_type of extractor
+                    &task_config.extractor_basic.db_type,
+                )?;
                 let reverse_router = router.reverse();
                 let conn_pool = TaskUtil::create_mysql_conn_pool(
                     &url,
@@ -606,7 +713,11 @@ impl SinkerUtil {
                     let pusher = FoxlakePusher {
                         url: url.to_string(),
                         extract_type: task_config.extractor_basic.extract_type.clone(),
-                        meta_manager: meta_manager.clone(),
+                        meta_manager: Self::create_foxlake_pusher_meta_manager(
+                            task_config,
+                            &meta_manager,
+                        )
+                        .await?,
                         batch_size,
                         batch_memory_bytes: batch_memory_mb * 1024 * 1024,
                         s3_config: s3_config.clone(),
@@ -656,7 +767,11 @@ impl SinkerUtil {
                 )
                 .await?;
                 let s3_client: S3Client = TaskUtil::create_s3_client(&s3_config);
-                let reverse_router = create_router!(task_config, Mysql).reverse();
+                let reverse_router = RdbRouter::from_config(
+                    &task_config.router,
+                    &task_config.extractor_basic.db_type,
+                )?
+                .reverse();
                 let orc_sequencer = Arc::new(Mutex::new(OrcSequencer::new()));
 
                 for _ in 0..parallel_size {
@@ -672,7 +787,11 @@ impl SinkerUtil {
                     let sinker = FoxlakePusher {
                         url: url.to_string(),
                         extract_type: task_config.extractor_basic.extract_type.clone(),
-                        meta_manager,
+                        meta_manager: Self::create_foxlake_pusher_meta_manager(
+                            task_config,
+                            &meta_manager,
+                        )
+                        .await?,
                         batch_size,
                         batch_memory_bytes: batch_memory_mb * 1024 * 1024,
                         s3_config: s3_config.clone(),
@@ -737,7 +856,71 @@ impl SinkerUtil {
                 };
                 sub_sinkers.push(Arc::new(async_mutex::Mutex::new(Box::new(sinker))));
             }
+
+            SinkerConfig::File {
+                root_dir,
+                file_format,
+                partition_by,
+                file_max_rows,
+                file_max_mb,
+                file_max_secs,
+                s3_config,
+            } => {
+                let router = RdbRouter::from_config(
+                    &task_config.router,
+                    // use the db_type of extractor
+                    &task_config.extractor_basic.db_type,
+                )?;
+                let reverse_router = router.reverse();
+                let s3_client = if s3_config.bucket.is_empty() {
+                    None
+                } else {
+                    Some(TaskUtil::create_s3_client(&s3_config))
+                };
+                let encoder = FileEncoder::new(file_format);
+
+                for _ in 0..parallel_size {
+                    // column orders of files are decided by tb_meta of RDB extractor
+                    let meta_manager =
+                        ExtractorUtil::get_extractor_meta_manager(task_config).await?;
+                    let sinker = FileSinker {
+                        root_dir: root_dir.clone(),
+                        encoder: encoder.clone(),
+                        partition_by: partition_by.clone(),
+                        file_max_rows,
+                        file_max_bytes: file_max_mb * 1024 * 1024,
+                        file_max_secs,
+                        s3_client: s3_client.clone(),
+                        s3_config: s3_config.clone(),
+                        router: router.clone(),
+                        reverse_router: reverse_router.clone(),
+                        meta_manager,
+                        monitor: monitor.clone(),
+                        open_files: HashMap::new(),
+                    };
+                    sub_sinkers.push(Arc::new(async_mutex::Mutex::new(Box::new(sinker))));
+                }
+            }
         };
         Ok(sub_sinkers)
     }
+
+    async fn create_foxlake_pusher_meta_manager(
+        task_config: &TaskConfig,
+        foxlake_meta_manager: &MysqlMetaManager,
+    ) -> anyhow::Result<RdbMetaManager> {
+        // for pg source, orc schemas are generated by source table metas
+        let meta_manager = match task_config.extractor_basic.db_type {
+            DbType::Pg => {
+                let pg_meta_manager = TaskUtil::create_pg_meta_manager(
+                    &task_config.extractor_basic.url,
+                    &task_config.runtime.log_level,
+                )
+                .await?;
+                RdbMetaManager::from_pg(pg_meta_manager)
+            }
+            _ => RdbMetaManager::from_mysql(foxlake_meta_manager.clone()),
+        };
+        Ok(meta_manager)
+    }
 }
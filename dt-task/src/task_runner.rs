@@ -1,5 +1,5 @@
 use std::{
-    collections::VecDeque,
+    collections::{HashMap, VecDeque},
     panic,
     sync::{
         atomic::{AtomicBool, Ordering},
@@ -9,24 +9,25 @@ use std::{
 
 use anyhow::{bail, Context};
 use log4rs::config::RawConfig;
-use ratelimit::Ratelimiter;
 use tokio::{
-    fs::metadata, fs::File, io::AsyncReadExt, sync::Mutex, sync::RwLock, task::JoinSet,
-    time::Duration, try_join,
+    fs::metadata, fs::File, io::AsyncReadExt, sync::Mutex, sync::RwLock, task::JoinSet, try_join,
 };
 
 use super::{
-    extractor_util::ExtractorUtil, parallelizer_util::ParallelizerUtil, sinker_util::SinkerUtil,
+    extractor_util::ExtractorUtil, parallelizer_util::ParallelizerUtil,
+    processor_util::ProcessorUtil, sinker_util::SinkerUtil,
 };
-use crate::task_util::TaskUtil;
+use crate::{json_log_encoder::JsonLogEncoder, task_util::TaskUtil};
 use dt_common::{
+    alert::alert_manager::AlertManager,
     config::{
-        config_enums::{build_task_type, DbType, PipelineType},
+        config_enums::{build_task_type, DbType, LogFormat, PipelineType},
         config_token_parser::ConfigTokenParser,
         extractor_config::ExtractorConfig,
         sinker_config::SinkerConfig,
         task_config::TaskConfig,
     },
+    control::{control_server::ControlServer, task_control::TaskControl},
     error::Error,
     log_error, log_finished, log_info,
     meta::{
@@ -50,10 +51,11 @@ use dt_connector::{
     Sinker,
 };
 use dt_pipeline::{
-    base_pipeline::BasePipeline, http_server_pipeline::HttpServerPipeline,
-    lua_processor::LuaProcessor, Pipeline,
+    base_pipeline::BasePipeline, http_server_pipeline::HttpServerPipeline, Pipeline,
 };
 
+#[cfg(feature = "otel")]
+use dt_common::monitor::otel_exporter::OtelExporter;
 #[cfg(feature = "metrics")]
 use dt_common::monitor::prometheus_metrics::PrometheusMetrics;
 
@@ -64,6 +66,8 @@ pub struct TaskRunner {
     pipeline_monitor: Arc<GroupMonitor>,
     sinker_monitor: Arc<GroupMonitor>,
     task_monitor: Arc<TaskMonitor>,
+    task_control: Arc<TaskControl>,
+    alert_manager: Arc<AlertManager>,
     #[cfg(feature = "metrics")]
     prometheus_metrics: Arc<PrometheusMetrics>,
 }
@@ -83,8 +87,18 @@ impl TaskRunner {
             &config.extractor_basic.extract_type,
             &config.sinker_basic.sink_type,
         );
+        let progress_file = config
+            .runtime
+            .write_progress_file
+            .then(|| config.runtime.progress_file.clone());
+        let alert_manager = Arc::new(AlertManager::new(&config.alert, &config.runtime.task_id)?);
         #[cfg(not(feature = "metrics"))]
-        let task_monitor = Arc::new(TaskMonitor::new(task_type.clone()));
+        let task_monitor = Arc::new(TaskMonitor::new(
+            task_type.clone(),
+            progress_file,
+            config.runtime.log_tb_metrics,
+            alert_manager.clone(),
+        ));
 
         #[cfg(feature = "metrics")]
         let prometheus_metrics = Arc::new(PrometheusMetrics::new(
@@ -95,15 +109,22 @@ impl TaskRunner {
         #[cfg(feature = "metrics")]
         let task_monitor = Arc::new(TaskMonitor::new(
             task_type.clone(),
+            progress_file,
+            config.runtime.log_tb_metrics,
+            alert_manager.clone(),
             prometheus_metrics.clone(),
         ));
 
+        let task_control = Arc::new(TaskControl::new(config.pipeline.max_rps));
+
         Ok(Self {
             config,
             extractor_monitor: Arc::new(GroupMonitor::new("extractor", "global")),
             pipeline_monitor: Arc::new(GroupMonitor::new("pipeline", "global")),
             sinker_monitor: Arc::new(GroupMonitor::new("sinker", "global")),
             task_monitor,
+            task_control,
+            alert_manager,
             #[cfg(feature = "metrics")]
             prometheus_metrics,
         })
@@ -130,7 +151,18 @@ impl TaskRunner {
             .start_metrics()
             .await;
 
-        match &self.config.extractor {
+        if self.config.control.http_port > 0 {
+            ControlServer::start(&self.config.control, self.task_control.clone())?;
+        }
+
+        #[cfg(feature = "otel")]
+        let otel_exporter = if self.config.otel.endpoint.is_empty() {
+            None
+        } else {
+            Some(OtelExporter::init(&self.config.otel)?)
+        };
+
+        let result = match &self.config.extractor {
             ExtractorConfig::MysqlStruct { url, .. }
             | ExtractorConfig::PgStruct { url, .. }
             | ExtractorConfig::MysqlSnapshot { url, .. }
@@ -138,7 +170,7 @@ impl TaskRunner {
             | ExtractorConfig::MongoSnapshot { url, .. }
             | ExtractorConfig::FoxlakeS3 { url, .. } => {
                 self.start_multi_task(url, &router, &snapshot_resumer, &cdc_resumer)
-                    .await?
+                    .await
             }
 
             _ => {
@@ -150,11 +182,26 @@ impl TaskRunner {
                         &cdc_resumer,
                         false,
                     )
-                    .await?
+                    .await
             }
         };
 
-        log_finished!("task finished");
+        // export the pending spans and metrics even if the task failed
+        #[cfg(feature = "otel")]
+        if let Some(otel_exporter) = &otel_exporter {
+            otel_exporter.shutdown();
+        }
+        // tasks stopped by the control api are not alerted
+        if result.is_err() || !self.task_control.is_stopping() {
+            self.alert_manager.notify_task_exit(&result).await;
+        }
+        result?;
+
+        if self.task_control.is_stopping() {
+            log_info!("task stopped");
+        } else {
+            log_finished!("task finished");
+        }
         Ok(())
     }
 
@@ -179,18 +226,24 @@ impl TaskRunner {
             .filter(|schema| !filter.filter_schema(schema))
             .map(|s| s.to_owned())
             .collect::<Vec<_>>();
+        let mut tb_record_counts = HashMap::new();
         if let Some(task_type) = task_type_option {
-            let record_count =
-                TaskUtil::estimate_record_count(&task_type, url, db_type, &schemas, &filter)
+            tb_record_counts =
+                TaskUtil::estimate_tb_record_counts(&task_type, url, db_type, &schemas, &filter)
                     .await?;
-            self.task_monitor
-                .add_no_window_metrics(TaskMetricsType::ExtractorPlanRecords, record_count);
+            self.task_monitor.add_no_window_metrics(
+                TaskMetricsType::ExtractorPlanRecords,
+                tb_record_counts.values().sum(),
+            );
         }
 
         // TODO: Need to limit resources when starting tasks concurrently at schema level.
         //       Currently connection count, rate limit, buffer size, etc. are controlled at single task level,
         //       which in multi-task mode will amplify these resources by at least schema count times
         for (flag, schema) in schemas.iter().enumerate() {
+            if self.task_control.is_stopping() {
+                break;
+            }
             // start a task for each schema
             let schema_extractor_config = match &self.config.extractor {
                 ExtractorConfig::MysqlStruct { url, .. } => Some(ExtractorConfig::MysqlStruct {
@@ -231,6 +284,16 @@ impl TaskRunner {
                     log_info!("schema: {}, tb: {}, insert events filtered", schema, tb);
                     continue;
                 }
+                let plan_records = tb_record_counts
+                    .get(&(schema.to_owned(), tb.to_owned()))
+                    .cloned()
+                    .unwrap_or_default();
+                self.task_monitor.init_tb_progress(
+                    &format!("{}.{}", schema, tb),
+                    schema,
+                    tb,
+                    plan_records,
+                );
                 pending_tbs.push_back((schema.to_owned(), tb.to_owned()));
             }
         }
@@ -279,6 +342,9 @@ impl TaskRunner {
         while let Some(result) = join_set.join_next().await {
             match result {
                 Ok((_, Ok(()))) => {
+                    if self.task_control.is_stopping() {
+                        continue;
+                    }
                     if let Some((schema, tb)) = pending_tbs.pop_front() {
                         self.clone()
                             .spawn_single_task(
@@ -294,6 +360,9 @@ impl TaskRunner {
                     }
                 }
                 Ok((single_task_id, Err(e))) => {
+                    // flush so the failed table is recorded in progress before the task exits
+                    self.task_monitor.fail_tb_progress(&single_task_id);
+                    self.task_monitor.flush().await;
                     bail!("single task: [{}] failed, error: {}", single_task_id, e)
                 }
                 Err(e) => {
@@ -346,14 +415,18 @@ impl TaskRunner {
                 batch_size: *batch_size,
             },
 
-            ExtractorConfig::MongoSnapshot { url, app_name, .. } => {
-                ExtractorConfig::MongoSnapshot {
-                    url: url.clone(),
-                    app_name: app_name.clone(),
-                    db: schema.into(),
-                    tb: tb.into(),
-                }
-            }
+            ExtractorConfig::MongoSnapshot {
+                url,
+                app_name,
+                parallel_size,
+                ..
+            } => ExtractorConfig::MongoSnapshot {
+                url: url.clone(),
+                app_name: app_name.clone(),
+                db: schema.into(),
+                tb: tb.into(),
+                parallel_size: *parallel_size,
+            },
 
             ExtractorConfig::FoxlakeS3 {
                 url,
@@ -459,6 +532,7 @@ impl TaskRunner {
             router.clone(),
             snapshot_resumer.clone(),
             cdc_resumer.clone(),
+            self.task_control.clone(),
         )
         .await?;
 
@@ -524,13 +598,38 @@ impl TaskRunner {
             }
         );
 
+        self.task_monitor.start_tb_progress(&single_task_id);
+        self.task_control
+            .register_syncer(&single_task_id, syncer.clone());
+
         // do pre operations before task starts
         self.pre_single_task(sinker_data_marker).await?;
 
         // start threads
+        let task_control = self.task_control.clone();
+        let extractor_shut_down = shut_down.clone();
         let f1 = tokio::spawn(async move {
-            extractor.extract().await.unwrap();
+            // if stopped by the control api, the extractor exits at the next push, or is dropped
+            // if it is waiting for the source (e.g. an idle binlog stream), then the pipeline
+            // sinks the buffered data and records the final checkpoint.
+            // dropping extract() mid-await is cancel safe: it only reads the source and pushes to
+            // the buffer, positions are checkpointed by the pipeline after data is sinked, so
+            // records not pushed yet are extracted again when resuming
+            let stopped = tokio::select! {
+                res = extractor.extract() => {
+                    let stopped = task_control.is_stopping();
+                    if !stopped {
+                        res.unwrap();
+                    }
+                    stopped
+                }
+                _ = task_control.wait_stopping() => true,
+            };
+            if stopped {
+                extractor_shut_down.store(true, Ordering::Release);
+            }
             extractor.close().await.unwrap();
+            stopped
         });
 
         let f2 = tokio::spawn(async move {
@@ -553,28 +652,36 @@ impl TaskRunner {
             )
             .await
         });
-        try_join!(f1, f2, f3)?;
+        let (stopped, _, _) = try_join!(f1, f2, f3)?;
+        self.task_control.unregister_syncer(&single_task_id);
 
         // finished log
         let (schema, tb) = match extractor_config {
             ExtractorConfig::MysqlSnapshot { db, tb, .. }
             | ExtractorConfig::MongoSnapshot { db, tb, .. } => (db.to_owned(), tb.to_owned()),
             ExtractorConfig::PgSnapshot { schema, tb, .. }
-            | ExtractorConfig::FoxlakeS3 { schema, tb, .. } => (schema.to_owned(), tb.to_owned()),
+            | ExtractorConfig::FoxlakeS3 { schema, tb, .. }
+            | ExtractorConfig::File { schema, tb, .. } => (schema.to_owned(), tb.to_owned()),
             _ => (String::new(), String::new()),
         };
-        if !tb.is_empty() {
+        // a stopped table is not finished and should be extracted again when resuming
+        if !tb.is_empty() && !stopped {
             log_finished!(
+                schema = schema.as_str(), tb = tb.as_str();
                 "{}",
                 Position::RdbSnapshotFinished {
                     db_type: self.config.extractor_basic.db_type.to_string(),
-                    schema,
-                    tb,
+                    schema: schema.clone(),
+                    tb: tb.clone(),
                 }
                 .to_string()
             );
         }
 
+        if !stopped {
+            self.task_monitor.finish_tb_progress(&single_task_id);
+        }
+
         // remove monitors from global monitors
         tokio::join!(
             async {
@@ -612,29 +719,12 @@ impl TaskRunner {
     ) -> anyhow::Result<Box<dyn Pipeline + Send>> {
         match self.config.pipeline.pipeline_type {
             PipelineType::Basic => {
-                let rps_limiter = if self.config.pipeline.max_rps > 0 {
-                    Some(
-                        Ratelimiter::builder(self.config.pipeline.max_rps, Duration::from_secs(1))
-                            .max_tokens(self.config.pipeline.max_rps)
-                            .initial_available(self.config.pipeline.max_rps)
-                            .build()?,
-                    )
-                } else {
-                    None
-                };
-
-                let lua_processor =
-                    self.config
-                        .processor
-                        .as_ref()
-                        .map(|processor_config| LuaProcessor {
-                            lua_code: processor_config.lua_code.clone(),
-                        });
+                let processor_chain = ProcessorUtil::create_processor_chain(&self.config)?;
 
                 let parallelizer = ParallelizerUtil::create_parallelizer(
                     &self.config,
                     monitor.clone(),
-                    rps_limiter,
+                    self.task_control.clone(),
                 )
                 .await?;
 
@@ -649,7 +739,8 @@ impl TaskRunner {
                     syncer,
                     monitor,
                     data_marker,
-                    lua_processor,
+                    processor_chain,
+                    task_control: self.task_control.clone(),
                 };
                 Ok(Box::new(pipeline))
             }
@@ -685,7 +776,8 @@ impl TaskRunner {
 
         match &self.config.sinker {
             SinkerConfig::MysqlCheck { check_log_dir, .. }
-            | SinkerConfig::PgCheck { check_log_dir, .. } => {
+            | SinkerConfig::PgCheck { check_log_dir, .. }
+            | SinkerConfig::RedisCheck { check_log_dir, .. } => {
                 if !check_log_dir.is_empty() {
                     config_str = config_str.replace(CHECK_LOG_DIR_PLACEHODLER, check_log_dir);
                 }
@@ -712,6 +804,10 @@ impl TaskRunner {
             .replace(LOG_DIR_PLACEHODLER, &self.config.runtime.log_dir)
             .replace(LOG_LEVEL_PLACEHODLER, &self.config.runtime.log_level);
 
+        if self.config.runtime.log_format == LogFormat::Json {
+            return JsonLogEncoder::init_log4rs(&config_str, &self.config.runtime.task_id);
+        }
+
         let config: RawConfig = serde_yaml::from_str(&config_str)?;
         log4rs::init_raw_config(config)?;
         Ok(())
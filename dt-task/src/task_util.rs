@@ -1,5 +1,6 @@
-use std::{str::FromStr, time::Duration};
+use std::{collections::HashMap, str::FromStr, time::Duration};
 
+use anyhow::bail;
 use dt_common::config::config_enums::TaskType;
 use dt_common::config::extractor_config::ExtractorConfig;
 use dt_common::config::s3_config::S3Config;
@@ -7,6 +8,7 @@ use dt_common::config::{
     config_enums::DbType, meta_center_config::MetaCenterConfig, sinker_config::SinkerConfig,
     task_config::TaskConfig,
 };
+use dt_common::error::Error;
 use dt_common::log_info;
 use dt_common::meta::mysql::mysql_dbengine_meta_center::MysqlDbEngineMetaCenter;
 use dt_common::meta::{
@@ -15,8 +17,14 @@ use dt_common::meta::{
 };
 use dt_common::rdb_filter::RdbFilter;
 use futures::TryStreamExt;
-use mongodb::bson::doc;
+use mongodb::bson::{doc, Document};
 use mongodb::options::ClientOptions;
+use rdkafka::{
+    admin::{AdminClient, AdminOptions, NewTopic, TopicReplication},
+    client::DefaultClientContext,
+    types::RDKafkaErrorCode,
+    ClientConfig,
+};
 use rusoto_core::Region;
 use rusoto_s3::S3Client;
 use sqlx::Executor;
@@ -213,20 +221,22 @@ impl TaskUtil {
         Ok(tbs)
     }
 
-    pub async fn estimate_record_count(
+    /// Estimated record count of each table, keyed by (schema, tb)
+    pub async fn estimate_tb_record_counts(
         task_type: &TaskType,
         url: &str,
         db_type: &DbType,
         schemas: &[String],
         filter: &RdbFilter,
-    ) -> anyhow::Result<u64> {
+    ) -> anyhow::Result<HashMap<(String, String), u64>> {
         match task_type {
             TaskType::Snapshot => match db_type {
                 DbType::Mysql => Self::estimate_mysql_snapshot(url, schemas, filter).await,
                 DbType::Pg => Self::estimate_pg_snapshot(url, schemas, filter).await,
-                _ => Ok(0),
+                DbType::Mongo => Self::estimate_mongo_snapshot(url, schemas, filter).await,
+                _ => Ok(HashMap::new()),
             },
-            _ => Ok(0),
+            _ => Ok(HashMap::new()),
         }
     }
 
@@ -234,7 +244,7 @@ impl TaskUtil {
         url: &str,
         schemas: &[String],
         filter: &RdbFilter,
-    ) -> anyhow::Result<u64> {
+    ) -> anyhow::Result<HashMap<(String, String), u64>> {
         let conn_pool = Self::create_mysql_conn_pool(url, 1, false, false).await?;
 
         let mut sql = String::from("select table_schema, table_name, TABLE_ROWS from information_schema.TABLES where table_type = 'BASE TABLE'");
@@ -252,7 +262,7 @@ impl TaskUtil {
             sql = sql_with_filter;
         }
 
-        let mut total_records = 0;
+        let mut tb_records = HashMap::new();
         let mut rows = sqlx::query(&sql).fetch(&conn_pool);
         while let Some(row) = rows.try_next().await.unwrap() {
             let schema: String = row.try_get(0)?;
@@ -261,18 +271,18 @@ impl TaskUtil {
             if filter.filter_tb(&schema, &tb) {
                 continue;
             }
-            total_records += if records < 0 { 0 } else { records as u64 };
+            tb_records.insert((schema, tb), if records < 0 { 0 } else { records as u64 });
         }
         conn_pool.close().await;
 
-        Ok(total_records)
+        Ok(tb_records)
     }
 
     async fn estimate_pg_snapshot(
         url: &str,
         schemas: &[String],
         filter: &RdbFilter,
-    ) -> anyhow::Result<u64> {
+    ) -> anyhow::Result<HashMap<(String, String), u64>> {
         let conn_pool = TaskUtil::create_pg_conn_pool(url, 1, false, false).await?;
 
         let mut sql = String::from(
@@ -302,7 +312,7 @@ WHERE
             sql = sql_with_filter;
         }
 
-        let mut total_length = 0;
+        let mut tb_records = HashMap::new();
         let mut rows = sqlx::query(&sql).fetch(&conn_pool);
         while let Some(row) = rows.try_next().await.unwrap() {
             let schema: String = row.try_get(0)?;
@@ -313,11 +323,42 @@ WHERE
             }
             // Convert to u64, handling negative values (which shouldn't happen but just in case)
             let row_count_u64 = if row_count < 0 { 0 } else { row_count as u64 };
-            total_length += row_count_u64;
+            tb_records.insert((schema, table_name), row_count_u64);
         }
         conn_pool.close().await;
 
-        Ok(total_length)
+        Ok(tb_records)
+    }
+
+    async fn estimate_mongo_snapshot(
+        url: &str,
+        schemas: &[String],
+        filter: &RdbFilter,
+    ) -> anyhow::Result<HashMap<(String, String), u64>> {
+        let client = Self::create_mongo_client(url, "").await?;
+
+        let mut tb_records = HashMap::new();
+        for db in schemas.iter() {
+            let tbs = client
+                .database(db)
+                .list_collection_names(Some(doc! { "type": "collection" }))
+                .await?;
+            for tb in tbs {
+                if tb.starts_with("system.") || filter.filter_tb(db, &tb) {
+                    continue;
+                }
+                // estimated by collection metadata, no collection scan
+                let records = client
+                    .database(db)
+                    .collection::<Document>(&tb)
+                    .estimated_document_count(None)
+                    .await?;
+                tb_records.insert((db.to_owned(), tb), records);
+            }
+        }
+        client.shutdown().await;
+
+        Ok(tb_records)
     }
 
     pub async fn check_tb_exist(
@@ -486,4 +527,77 @@ WHERE
 
         S3Client::new_with(rusoto_core::HttpClient::new().unwrap(), credentials, region)
     }
+
+    pub async fn create_kafka_topics(
+        url: &str,
+        topics: &[String],
+        partitions: i32,
+        replication_factor: i32,
+    ) -> anyhow::Result<()> {
+        let admin_client = Self::create_kafka_admin_client(url)?;
+        let new_topics: Vec<NewTopic> = topics
+            .iter()
+            .map(|topic| {
+                NewTopic::new(
+                    topic,
+                    partitions,
+                    TopicReplication::Fixed(replication_factor),
+                )
+            })
+            .collect();
+
+        let results = admin_client
+            .create_topics(&new_topics, &AdminOptions::new())
+            .await?;
+        for result in results {
+            match result {
+                Ok(topic) => log_info!("kafka topic created: {}", topic),
+                Err((_, RDKafkaErrorCode::TopicAlreadyExists)) => {}
+                Err((topic, code)) => {
+                    anyhow::bail!("failed to create kafka topic: {}, error: {}", topic, code)
+                }
+            }
+        }
+        Ok(())
+    }
+
+    pub async fn get_kafka_topic_partitions(
+        url: &str,
+        topics: &[String],
+    ) -> anyhow::Result<HashMap<String, i32>> {
+        let admin_client = Self::create_kafka_admin_client(url)?;
+        // fetch_metadata blocks the current thread
+        let metadata = tokio::task::spawn_blocking(move || {
+            admin_client
+                .inner()
+                .fetch_metadata(None, Duration::from_secs(10))
+        })
+        .await??;
+        let existing_partitions: HashMap<&str, i32> = metadata
+            .topics()
+            .iter()
+            .filter(|topic| topic.error().is_none())
+            .map(|topic| (topic.name(), topic.partitions().len() as i32))
+            .collect();
+
+        let mut topic_partitions = HashMap::new();
+        for topic in topics {
+            match existing_partitions.get(topic.as_str()) {
+                Some(count) if *count > 0 => {
+                    topic_partitions.insert(topic.clone(), *count);
+                }
+                _ => bail! {Error::ConfigError(format!(
+                    "kafka topic: {} does not exist, create it or set [sinker] create_topics=true",
+                    topic
+                ))},
+            }
+        }
+        Ok(topic_partitions)
+    }
+
+    fn create_kafka_admin_client(url: &str) -> anyhow::Result<AdminClient<DefaultClientContext>> {
+        let mut config = ClientConfig::new();
+        config.set("bootstrap.servers", url);
+        Ok(config.create()?)
+    }
 }
@@ -0,0 +1,7 @@
+SET str_key_1 val_1
+SET str_key_2 val_2000
+HSET hash_key_1 f_1 v_1 f_2 v_2000
+RPUSH list_key_1 v_1 v_3 v_2
+SADD set_key_1 m_2 m_1
+ZADD zset_key_1 1 m_1 3 m_2
+HSET type_key_1 f_1 v_1
@@ -0,0 +1,8 @@
+SET str_key_1 val_1
+SET str_key_2 val_2
+SET str_key_3 val_3
+HSET hash_key_1 f_1 v_1 f_2 v_2
+RPUSH list_key_1 v_1 v_2 v_3
+SADD set_key_1 m_1 m_2
+ZADD zset_key_1 1 m_1 2 m_2
+SET type_key_1 val_1
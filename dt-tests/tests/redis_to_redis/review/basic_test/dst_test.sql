@@ -0,0 +1,2 @@
+SET str_key_1 val_1000
+SET str_key_2 val_2000
\ No newline at end of file
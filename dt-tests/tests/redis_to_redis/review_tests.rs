@@ -0,0 +1,12 @@
+#[cfg(test)]
+mod test {
+    use serial_test::serial;
+
+    use crate::test_runner::test_base::TestBase;
+
+    #[tokio::test]
+    #[serial]
+    async fn review_basic_test() {
+        TestBase::run_redis_review_test("redis_to_redis/review/basic_test").await;
+    }
+}
@@ -0,0 +1,4 @@
+flushall
+SET str_key_1 val_1
+SET str_key_2 val_2000
+SET str_key_3 val_3
\ No newline at end of file
@@ -0,0 +1,3 @@
+SET str_key_1 val_1
+SET str_key_2 val_2
+HSET hash_key_1 f_1 v_1 f_2 v_2
@@ -123,7 +123,8 @@ impl TestConfigUtil {
         match config.extractor {
             ExtractorConfig::MysqlCheck { check_log_dir, .. }
             | ExtractorConfig::PgCheck { check_log_dir, .. }
-            | ExtractorConfig::MongoCheck { check_log_dir, .. } => {
+            | ExtractorConfig::MongoCheck { check_log_dir, .. }
+            | ExtractorConfig::RedisCheck { check_log_dir, .. } => {
                 let extractor_check_log_dir = format!("{}/{}", project_root, check_log_dir);
                 update_configs.push((
                     EXTRACTOR.to_string(),
@@ -144,7 +145,8 @@ impl TestConfigUtil {
             // sinker/check_log_dir
             SinkerConfig::MysqlCheck { check_log_dir, .. }
             | SinkerConfig::PgCheck { check_log_dir, .. }
-            | SinkerConfig::MongoCheck { check_log_dir, .. } => {
+            | SinkerConfig::MongoCheck { check_log_dir, .. }
+            | SinkerConfig::RedisCheck { check_log_dir, .. } => {
                 let sinker_check_log_dir = if !check_log_dir.is_empty() {
                     format!("{}/{}", project_root, check_log_dir)
                 } else {
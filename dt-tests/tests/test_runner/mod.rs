@@ -14,6 +14,7 @@ pub mod rdb_starrocks_test_runner;
 pub mod rdb_struct_test_runner;
 pub mod rdb_test_runner;
 pub mod rdb_util;
+pub mod redis_check_test_runner;
 pub mod redis_cluster_connection;
 pub mod redis_cycle_test_runner;
 pub mod redis_statistic_runner;
@@ -0,0 +1,45 @@
+use super::{check_util::CheckUtil, redis_test_runner::RedisTestRunner};
+
+pub struct RedisCheckTestRunner {
+    base: RedisTestRunner,
+    dst_check_log_dir: String,
+    expect_check_log_dir: String,
+}
+
+impl RedisCheckTestRunner {
+    pub async fn new(relative_test_dir: &str) -> anyhow::Result<Self> {
+        let base = RedisTestRunner::new_default(relative_test_dir)
+            .await
+            .unwrap();
+        let (expect_check_log_dir, dst_check_log_dir) =
+            CheckUtil::get_check_log_dir(&base.base, "");
+        Ok(Self {
+            base,
+            dst_check_log_dir,
+            expect_check_log_dir,
+        })
+    }
+
+    pub async fn run_check_test(&mut self) -> anyhow::Result<()> {
+        // clear existed check logs
+        CheckUtil::clear_check_log(&self.dst_check_log_dir);
+
+        // prepare src and dst data
+        self.base.execute_prepare_sqls()?;
+        self.base.execute_test_sqls()?;
+        self.base.execute_dst_test_sqls()?;
+
+        // start task
+        self.base.base.start_task().await?;
+        CheckUtil::validate_check_log(&self.expect_check_log_dir, &self.dst_check_log_dir)
+    }
+
+    pub async fn run_revise_test(&mut self) -> anyhow::Result<()> {
+        CheckUtil::clear_check_log(&self.dst_check_log_dir);
+        self.base.run_snapshot_test().await
+    }
+
+    pub async fn run_review_test(&mut self) -> anyhow::Result<()> {
+        self.run_check_test().await
+    }
+}
@@ -37,7 +37,8 @@ impl RedisTestRunner {
         let src_conn = match config.extractor {
             ExtractorConfig::RedisSnapshot { url, .. }
             | ExtractorConfig::RedisCdc { url, .. }
-            | ExtractorConfig::RedisSnapshotAndCdc { url, .. } => {
+            | ExtractorConfig::RedisSnapshotAndCdc { url, .. }
+            | ExtractorConfig::RedisCheck { url, .. } => {
                 RedisUtil::create_redis_conn(&url).await.unwrap()
             }
             _ => {
@@ -48,6 +49,9 @@ impl RedisTestRunner {
         let dst_conn = match config.sinker {
             SinkerConfig::Redis {
                 url, is_cluster, ..
+            }
+            | SinkerConfig::RedisCheck {
+                url, is_cluster, ..
             } => RedisClusterConnection::new(&url, is_cluster).await.unwrap(),
             _ => {
                 bail! {Error::ConfigError("unsupported sinker config".into())};
@@ -145,6 +149,12 @@ impl RedisTestRunner {
         Ok(())
     }
 
+    pub fn execute_dst_test_sqls(&mut self) -> anyhow::Result<()> {
+        self.redis_util
+            .execute_cmds_in_cluster(&mut self.dst_conn, &self.base.dst_test_sqls.clone());
+        Ok(())
+    }
+
     pub fn compare_all_data(&mut self) -> anyhow::Result<()> {
         let dbs = if self.dst_conn.is_cluster() {
             // a redis cluster strictly supports only database 0
@@ -12,8 +12,8 @@ use super::{
     rdb_kafka_rdb_test_runner::RdbKafkaRdbTestRunner, rdb_lua_test_runner::RdbLuaTestRunner,
     rdb_redis_test_runner::RdbRedisTestRunner, rdb_sql_test_runner::RdbSqlTestRunner,
     rdb_starrocks_test_runner::RdbStarRocksTestRunner, rdb_struct_test_runner::RdbStructTestRunner,
-    rdb_test_runner::RdbTestRunner, redis_statistic_runner::RedisStatisticTestRunner,
-    redis_test_runner::RedisTestRunner,
+    rdb_test_runner::RdbTestRunner, redis_check_test_runner::RedisCheckTestRunner,
+    redis_statistic_runner::RedisStatisticTestRunner, redis_test_runner::RedisTestRunner,
 };
 
 pub struct TestBase {}
@@ -194,6 +194,21 @@ impl TestBase {
         runner.run_review_test().await.unwrap();
     }
 
+    pub async fn run_redis_check_test(test_dir: &str) {
+        let mut runner = RedisCheckTestRunner::new(test_dir).await.unwrap();
+        runner.run_check_test().await.unwrap();
+    }
+
+    pub async fn run_redis_revise_test(test_dir: &str) {
+        let mut runner = RedisCheckTestRunner::new(test_dir).await.unwrap();
+        runner.run_revise_test().await.unwrap();
+    }
+
+    pub async fn run_redis_review_test(test_dir: &str) {
+        let mut runner = RedisCheckTestRunner::new(test_dir).await.unwrap();
+        runner.run_review_test().await.unwrap();
+    }
+
     pub async fn run_redis_snapshot_test(test_dir: &str) {
         let mut runner = RedisTestRunner::new_default(test_dir).await.unwrap();
         runner.run_snapshot_test().await.unwrap();